@@ -0,0 +1,529 @@
+//! WCAG-oriented audit of frames: text contrast, touch targets and reading order.
+//!
+//! Contrast is measured against the effective background of each text
+//! object: whatever is painted beneath it, composited bottom-up from the
+//! canvas color at several points across the text box, keeping the worst.
+//! Touch targets are checked in mobile frames only. Reading order compares
+//! the visual order (rows top to bottom, left to right within a row) with the
+//! layer order that generated code and screen readers follow.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::color::Rgba;
+use crate::fabric;
+use crate::geometry::Point;
+use crate::spatial::{Placed, Scene};
+use crate::text;
+
+/// Apple's 44pt; Material asks for 48dp.
+const DEFAULT_MIN_TARGET: f64 = 44.0;
+/// Frames at most this wide (and taller than wide) count as mobile.
+const MOBILE_MAX_WIDTH: f64 = 480.0;
+/// Where across the text box (as fractions of its size) the background is sampled.
+const SAMPLES: [(f64, f64); 5] = [(0.5, 0.5), (0.1, 0.5), (0.9, 0.5), (0.5, 0.15), (0.5, 0.85)];
+/// Whole words in a layer name that mark it as something to tap.
+const TARGET_WORDS: &[&str] = &[
+    "button", "btn", "link", "tab", "toggle", "switch", "checkbox", "radio", "input", "cta",
+    "chip", "slider",
+];
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
+pub enum WcagLevel {
+    #[default]
+    AA,
+    AAA,
+}
+
+impl WcagLevel {
+    /// Minimum contrast for normal and large text.
+    fn thresholds(self) -> (f64, f64) {
+        match self {
+            WcagLevel::AA => (4.5, 3.0),
+            WcagLevel::AAA => (7.0, 4.5),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct AuditOptions {
+    #[schemars(
+        description = "Frames to audit (default: every frame, plus objects outside frames)"
+    )]
+    pub frame_ids: Option<Vec<String>>,
+    #[schemars(description = "Conformance level for contrast: AA (default) or AAA")]
+    #[serde(default)]
+    pub level: WcagLevel,
+    #[schemars(description = "Smallest touch target side in px for mobile frames (default 44)")]
+    pub min_target_size: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessibilityReport {
+    pub level: WcagLevel,
+    pub summary: AuditSummary,
+    pub frames: Vec<FrameAudit>,
+    /// Findings per object id, passing checks included.
+    pub objects: BTreeMap<String, ObjectAudit>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditSummary {
+    pub frames: usize,
+    pub texts: usize,
+    pub contrast_failures: usize,
+    pub touch_targets: usize,
+    pub touch_target_failures: usize,
+    pub out_of_order: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameAudit {
+    /// `None` for objects that are in no frame.
+    pub frame_id: Option<String>,
+    pub name: Option<String>,
+    pub mobile: bool,
+    /// Text and touch targets in visual reading order.
+    pub reading_order: Vec<String>,
+    /// Whether the layer order reads the same way.
+    pub layer_order_matches: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectAudit {
+    pub frame_id: Option<String>,
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contrast: Option<ContrastCheck>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub touch_target: Option<TargetCheck>,
+    /// Absent for labels read as part of their touch target.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reading_order: Option<ReadingPosition>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContrastCheck {
+    pub foreground: String,
+    pub background: String,
+    pub ratio: f64,
+    pub large_text: bool,
+    pub required: f64,
+    /// Highest level met: `AAA`, `AA` or `fail`.
+    pub achieved: &'static str,
+    pub passes: bool,
+    /// The background includes an image or gradient, so the ratio is an estimate.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub approximate: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetCheck {
+    pub width: f64,
+    pub height: f64,
+    pub minimum: f64,
+    pub passes: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingPosition {
+    /// Position when reading the frame visually.
+    pub visual: usize,
+    /// Position in layer order.
+    pub layer: usize,
+}
+
+pub fn audit(canvas: &Value, options: &AuditOptions) -> Result<AccessibilityReport, String> {
+    let scene = Scene::new(canvas);
+    let items = scene.items();
+    let canvas_background = canvas
+        .get("background")
+        .and_then(|v| v.as_str())
+        .and_then(Rgba::parse)
+        .map(|c| c.over(Rgba::WHITE))
+        .unwrap_or(Rgba::WHITE);
+
+    let frames: Vec<Option<usize>> = match &options.frame_ids {
+        Some(ids) => ids
+            .iter()
+            .map(|id| match scene.find(id) {
+                Some(p) if fabric::is_frame(p.object) => Ok(Some(p.z)),
+                Some(_) => Err(format!("Not a frame: {}", id)),
+                None => Err(format!("Frame not found: {}", id)),
+            })
+            .collect::<Result<_, _>>()?,
+        None => {
+            let mut frames: Vec<Option<usize>> = items
+                .iter()
+                .filter(|p| fabric::is_frame(p.object))
+                .map(|p| Some(p.z))
+                .collect();
+            frames.push(None);
+            frames
+        }
+    };
+
+    let min_target = options.min_target_size.unwrap_or(DEFAULT_MIN_TARGET);
+    let (normal, large) = options.level.thresholds();
+    let mut report = AccessibilityReport {
+        level: options.level,
+        summary: AuditSummary::default(),
+        frames: Vec::new(),
+        objects: BTreeMap::new(),
+    };
+
+    for frame in frames {
+        let frame_item = frame.map(|f| &items[f]);
+        let mobile = frame_item.is_some_and(is_mobile_frame);
+
+        // Everything whose nearest frame is this one.
+        let members: Vec<&Placed> = items
+            .iter()
+            .filter(|p| Some(p.z) != frame && nearest_frame(&scene, p) == frame)
+            .collect();
+        let targets = touch_targets(&scene, &members);
+        let readable: Vec<&Placed> = members
+            .iter()
+            .copied()
+            .filter(|p| fabric::is_text(p.object) || targets.contains(&p.z))
+            // Labels inside a target are read as part of it.
+            .filter(|p| {
+                !targets
+                    .iter()
+                    .any(|&t| t != p.z && scene.is_ancestor(t, p.z))
+            })
+            .collect();
+        if frame.is_none() && readable.is_empty() {
+            continue;
+        }
+
+        let visual = visual_order(&readable);
+        let layer_order_matches = visual.windows(2).all(|w| w[0].z < w[1].z);
+        let mut by_layer: Vec<usize> = readable.iter().map(|p| p.z).collect();
+        by_layer.sort_unstable();
+
+        report.summary.frames += 1;
+        report.frames.push(FrameAudit {
+            frame_id: frame_item.and_then(|f| f.id()).map(str::to_string),
+            name: frame_item
+                .and_then(|f| fabric::str_prop(f.object, "name"))
+                .map(str::to_string),
+            mobile,
+            reading_order: visual
+                .iter()
+                .filter_map(|p| p.id())
+                .map(str::to_string)
+                .collect(),
+            layer_order_matches,
+        });
+
+        // Every text and target, in reading order first, then labels inside targets.
+        let audited = visual.iter().copied().chain(
+            members
+                .iter()
+                .copied()
+                .filter(|p| fabric::is_text(p.object) && !visual.iter().any(|v| v.z == p.z)),
+        );
+        for item in audited {
+            let Some(id) = item.id() else {
+                continue;
+            };
+            let reading_order = visual.iter().position(|v| v.z == item.z).map(|visual| {
+                let layer = by_layer.iter().position(|&z| z == item.z).unwrap_or(0);
+                if layer != visual {
+                    report.summary.out_of_order += 1;
+                }
+                ReadingPosition { visual, layer }
+            });
+
+            let contrast = fabric::is_text(item.object)
+                .then(|| contrast(&scene, item, canvas_background, normal, large))
+                .flatten();
+            if let Some(check) = &contrast {
+                report.summary.texts += 1;
+                if !check.passes {
+                    report.summary.contrast_failures += 1;
+                }
+            }
+
+            let touch_target = (mobile && targets.contains(&item.z)).then(|| {
+                let (width, height) = (round1(item.bounds.width), round1(item.bounds.height));
+                TargetCheck {
+                    width,
+                    height,
+                    minimum: min_target,
+                    passes: width >= min_target && height >= min_target,
+                }
+            });
+            if let Some(check) = &touch_target {
+                report.summary.touch_targets += 1;
+                if !check.passes {
+                    report.summary.touch_target_failures += 1;
+                }
+            }
+
+            report.objects.insert(
+                id.to_string(),
+                ObjectAudit {
+                    frame_id: frame_item.and_then(|f| f.id()).map(str::to_string),
+                    name: fabric::str_prop(item.object, "name").map(str::to_string),
+                    contrast,
+                    touch_target,
+                    reading_order,
+                },
+            );
+        }
+    }
+
+    Ok(report)
+}
+
+/// The frame preset the editor calls Mobile, or any narrow portrait frame.
+fn is_mobile_frame(frame: &Placed) -> bool {
+    let name = fabric::str_prop(frame.object, "name").unwrap_or("");
+    name.to_ascii_lowercase().ends_with("mobile")
+        || (frame.bounds.width <= MOBILE_MAX_WIDTH && frame.bounds.height > frame.bounds.width)
+}
+
+fn nearest_frame(scene: &Scene, item: &Placed) -> Option<usize> {
+    let items = scene.items();
+    let mut parent = item.parent;
+    for _ in 0..items.len() {
+        let p = parent?;
+        if fabric::is_frame(items[p].object) {
+            return Some(p);
+        }
+        parent = items[p].parent;
+    }
+    None
+}
+
+/// Components and layers named like controls ("Sign in button", "Tab 2").
+/// Only the outermost of nested candidates counts.
+fn touch_targets(scene: &Scene, members: &[&Placed]) -> HashSet<usize> {
+    let candidates: Vec<usize> = members
+        .iter()
+        .filter(|p| {
+            fabric::bool_prop(p.object, "isComponent")
+                || names_control(fabric::str_prop(p.object, "name").unwrap_or(""))
+        })
+        .map(|p| p.z)
+        .collect();
+    candidates
+        .iter()
+        .copied()
+        .filter(|&z| {
+            !candidates
+                .iter()
+                .any(|&c| c != z && scene.is_ancestor(c, z))
+        })
+        .collect()
+}
+
+fn names_control(name: &str) -> bool {
+    // Split camelCase as well as punctuation: "primaryButton", "nav-link".
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if (!c.is_alphanumeric() || (c.is_uppercase() && prev_lower)) && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+        if c.is_alphanumeric() {
+            word.extend(c.to_lowercase());
+        }
+        prev_lower = c.is_lowercase();
+    }
+    words.push(word);
+    words.iter().any(|w| TARGET_WORDS.contains(&w.as_str()))
+}
+
+/// Rows top to bottom, left to right within a row. An object joins the
+/// current row when its vertical centre falls inside the row's span.
+fn visual_order<'s, 'a>(items: &[&'s Placed<'a>]) -> Vec<&'s Placed<'a>> {
+    let mut sorted = items.to_vec();
+    sorted.sort_by(|a, b| a.bounds.top.total_cmp(&b.bounds.top).then(a.z.cmp(&b.z)));
+
+    let mut rows: Vec<(f64, Vec<&Placed>)> = Vec::new();
+    for item in sorted {
+        match rows.last_mut() {
+            Some((bottom, row)) if item.bounds.center_y() < *bottom => {
+                *bottom = bottom.max(item.bounds.bottom());
+                row.push(item);
+            }
+            _ => rows.push((item.bounds.bottom(), vec![item])),
+        }
+    }
+    rows.into_iter()
+        .flat_map(|(_, mut row)| {
+            row.sort_by(|a, b| a.bounds.left.total_cmp(&b.bounds.left).then(a.z.cmp(&b.z)));
+            row
+        })
+        .collect()
+}
+
+/// Worst contrast between the text's colors and what lies beneath it.
+fn contrast(
+    scene: &Scene,
+    item: &Placed,
+    canvas: Rgba,
+    normal: f64,
+    large: f64,
+) -> Option<ContrastCheck> {
+    let opacity = effective_opacity(scene, item);
+    let styles = text::char_styles(item.object);
+    let mut colors: Vec<Rgba> = Vec::new();
+    let mut approximate = false;
+    let fills = std::iter::once(item.object.get("fill"))
+        .chain(styles.iter().flatten().map(|style| style.get("fill")))
+        .flatten();
+    for fill in fills {
+        let Some((color, estimated)) = paint_color(fill) else {
+            continue;
+        };
+        approximate |= estimated;
+        if !colors.contains(&color) {
+            colors.push(color);
+        }
+    }
+    colors.retain(|c| c.a > 0.0);
+    if colors.is_empty() {
+        return None;
+    }
+
+    let (w, h) = (
+        fabric::base_width(item.object),
+        fabric::base_height(item.object),
+    );
+    let mut worst: Option<(f64, Rgba, Rgba)> = None;
+    for (fx, fy) in SAMPLES {
+        let point = item.matrix.apply(Point::new(w * fx, h * fy));
+        let (background, estimated) = background_at(scene, item, point, canvas);
+        approximate |= estimated;
+        for color in &colors {
+            let foreground = color.with_alpha(color.a * opacity).over(background);
+            let ratio = foreground.contrast_ratio(&background);
+            if worst.is_none_or(|(r, _, _)| ratio < r) {
+                worst = Some((ratio, foreground, background));
+            }
+        }
+    }
+    let (ratio, foreground, background) = worst?;
+
+    let size = fabric::num_prop(item.object, "fontSize").unwrap_or(40.0)
+        * item.matrix.decompose().scale_y.abs();
+    let large_text = size >= 24.0 || (size >= 18.66 && is_bold(item.object));
+    let required = if large_text { large } else { normal };
+    let (aa, aaa) = if large_text { (3.0, 4.5) } else { (4.5, 7.0) };
+    let ratio = (ratio * 100.0).round() / 100.0;
+    Some(ContrastCheck {
+        foreground: foreground.to_hex(),
+        background: background.to_hex(),
+        ratio,
+        large_text,
+        required,
+        achieved: if ratio >= aaa {
+            "AAA"
+        } else if ratio >= aa {
+            "AA"
+        } else {
+            "fail"
+        },
+        passes: ratio >= required,
+        approximate,
+    })
+}
+
+/// Opaque color at `point` from everything painted below `item`.
+fn background_at(scene: &Scene, item: &Placed, point: Point, canvas: Rgba) -> (Rgba, bool) {
+    let mut color = canvas;
+    let mut approximate = false;
+    for below in scene.fills_at(point).into_iter().filter(|p| p.z < item.z) {
+        if fabric::object_type(below.object) == "image" {
+            approximate = true;
+            continue;
+        }
+        let Some((fill, estimated)) = below.object.get("fill").and_then(paint_color) else {
+            continue;
+        };
+        approximate |= estimated;
+        let opacity = effective_opacity(scene, below);
+        color = fill.with_alpha(fill.a * opacity).over(color);
+    }
+    (color, approximate)
+}
+
+/// A paint as one color: solid colors as they are, gradients as the
+/// average of their stops (flagged as an estimate).
+fn paint_color(paint: &Value) -> Option<(Rgba, bool)> {
+    match paint {
+        Value::String(s) => Rgba::parse(s).map(|c| (c, false)),
+        Value::Object(gradient) => {
+            let stops: Vec<Rgba> = gradient
+                .get("colorStops")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|stop| {
+                    let color = Rgba::parse(stop.get("color")?.as_str()?)?;
+                    let opacity = stop.get("opacity").and_then(|v| v.as_f64()).unwrap_or(1.0);
+                    Some(color.with_alpha(color.a * opacity))
+                })
+                .collect();
+            if stops.is_empty() {
+                return None;
+            }
+            let n = stops.len() as f64;
+            let avg = |f: fn(&Rgba) -> f64| stops.iter().map(f).sum::<f64>() / n;
+            Some((
+                Rgba {
+                    r: avg(|c| c.r as f64).round() as u8,
+                    g: avg(|c| c.g as f64).round() as u8,
+                    b: avg(|c| c.b as f64).round() as u8,
+                    a: avg(|c| c.a),
+                },
+                true,
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The object's opacity times that of every group it is nested in.
+fn effective_opacity(scene: &Scene, item: &Placed) -> f64 {
+    let items = scene.items();
+    let mut opacity = fabric::num_prop(item.object, "opacity").unwrap_or(1.0);
+    let mut parent = item.parent;
+    for _ in 0..items.len() {
+        let Some(p) = parent.filter(|&p| fabric::object_type(items[p].object) == "group") else {
+            break;
+        };
+        opacity *= fabric::num_prop(items[p].object, "opacity").unwrap_or(1.0);
+        parent = items[p].parent;
+    }
+    opacity
+}
+
+fn is_bold(obj: &Value) -> bool {
+    match obj.get("fontWeight") {
+        Some(Value::String(w)) => {
+            w == "bold" || w == "bolder" || w.parse::<f64>().is_ok_and(|w| w >= 700.0)
+        }
+        Some(Value::Number(w)) => w.as_f64().is_some_and(|w| w >= 700.0),
+        _ => false,
+    }
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0 + 0.0
+}
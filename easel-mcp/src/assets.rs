@@ -0,0 +1,403 @@
+//! Content-addressed binary assets (images) stored next to a canvas.
+//!
+//! `canvases/abc.easel` keeps its assets in `canvases/abc.assets/`, each file
+//! named `<sha256>.<ext>`. Fabric Image objects reference an asset through
+//! `assetId` and carry a `src` relative to the canvas file. Fonts unpacked
+//! from a bundle live apart from images, in `abc.assets/fonts/`. When the
+//! canvas is a bundle, assets it embeds are read from it.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::bundle;
+use crate::import::new_object_id;
+use crate::oplog::{self, Batch};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Asset {
+    /// `<sha256>.<ext>`; also the file name inside the asset directory.
+    pub id: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+pub struct AssetStore {
+    dir: PathBuf,
+    /// The canvas, when it is a bundle, for assets not in `dir`.
+    bundle: Option<PathBuf>,
+}
+
+impl AssetStore {
+    /// The asset directory that belongs to a canvas file.
+    pub fn for_canvas(canvas_path: &Path) -> Self {
+        Self {
+            dir: canvas_path.with_extension("assets"),
+            bundle: bundle::is_bundle(canvas_path).then(|| canvas_path.to_path_buf()),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The font files a bundle carried, kept out of the image listing and
+    /// garbage collection.
+    pub fn fonts(&self) -> AssetStore {
+        Self {
+            dir: self.dir.join("fonts"),
+            bundle: None,
+        }
+    }
+
+    /// The font files the canvas's bundle embeds; none for a plain file.
+    pub fn embedded_fonts(&self) -> Result<Vec<Vec<u8>>, String> {
+        match &self.bundle {
+            Some(path) => bundle::read_fonts(path),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn path_of(&self, asset_id: &str) -> Result<PathBuf, String> {
+        validate_asset_id(asset_id)?;
+        Ok(self.dir.join(asset_id))
+    }
+
+    /// `src` value for Fabric: the asset path relative to the canvas file.
+    pub fn relative_src(&self, asset_id: &str) -> String {
+        let dir_name = self
+            .dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("assets");
+        format!("{}/{}", dir_name, asset_id)
+    }
+
+    /// Copy a local image into the store. Adding the same bytes twice is a no-op.
+    pub fn add_file(&self, source: &Path) -> Result<Asset, String> {
+        let bytes =
+            fs::read(source).map_err(|e| format!("Failed to read {}: {}", source.display(), e))?;
+        let ext_hint = source
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        self.add_bytes(&bytes, ext_hint.as_deref())
+    }
+
+    pub fn add_bytes(&self, bytes: &[u8], ext_hint: Option<&str>) -> Result<Asset, String> {
+        let (ext, mime_type) = sniff_format(bytes, ext_hint)?;
+        let hash = hex_digest(bytes);
+        let id = format!("{}.{}", hash, ext);
+
+        self.write_if_missing(&id, bytes)?;
+
+        let dims = imagesize::blob_size(bytes).ok();
+        Ok(Asset {
+            id,
+            mime_type: mime_type.to_string(),
+            size: bytes.len() as u64,
+            width: dims.as_ref().map(|d| d.width as u32),
+            height: dims.as_ref().map(|d| d.height as u32),
+        })
+    }
+
+    /// Store bytes under a known ID, e.g. when unpacking a bundle. The content
+    /// hash must match the ID.
+    pub fn insert(&self, asset_id: &str, bytes: &[u8]) -> Result<(), String> {
+        validate_asset_id(asset_id)?;
+        if !asset_id.starts_with(&hex_digest(bytes)) {
+            return Err(format!(
+                "Asset {} does not match its content hash",
+                asset_id
+            ));
+        }
+        self.write_if_missing(asset_id, bytes)
+    }
+
+    fn write_if_missing(&self, asset_id: &str, bytes: &[u8]) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create asset directory: {}", e))?;
+        let dest = self.dir.join(asset_id);
+        if !dest.exists() {
+            let tmp = self.dir.join(format!("{}.tmp", asset_id));
+            fs::write(&tmp, bytes).map_err(|e| format!("Failed to write asset: {}", e))?;
+            fs::rename(&tmp, &dest).map_err(|e| format!("Failed to store asset: {}", e))?;
+        }
+        Ok(())
+    }
+
+    pub fn read(&self, asset_id: &str) -> Result<Vec<u8>, String> {
+        let path = self.path_of(asset_id)?;
+        match &self.bundle {
+            Some(bundle) if !path.exists() => bundle::read_asset(bundle, asset_id)
+                .map_err(|e| format!("Failed to read asset {}: {}", asset_id, e)),
+            _ => fs::read(&path).map_err(|e| format!("Failed to read asset {}: {}", asset_id, e)),
+        }
+    }
+
+    /// Whether the store holds `asset_id`, on disk or in the bundle.
+    pub fn contains(&self, asset_id: &str) -> bool {
+        self.path_of(asset_id).is_ok_and(|p| p.exists())
+            || self
+                .embedded()
+                .is_ok_and(|ids| ids.iter().any(|id| id == asset_id))
+    }
+
+    /// Assets the canvas's bundle embeds; none for a plain file.
+    fn embedded(&self) -> Result<Vec<String>, String> {
+        match &self.bundle {
+            Some(path) => bundle::embedded_assets(path),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<Asset>, String> {
+        let mut assets = Vec::new();
+        for id in self.embedded()? {
+            if self.dir.join(&id).exists() {
+                continue;
+            }
+            let bytes = self.read(&id)?;
+            let ext = id.rsplit('.').next().unwrap_or("");
+            let dims = imagesize::blob_size(&bytes).ok();
+            assets.push(Asset {
+                mime_type: mime_for_extension(ext).to_string(),
+                size: bytes.len() as u64,
+                width: dims.as_ref().map(|d| d.width as u32),
+                height: dims.as_ref().map(|d| d.height as u32),
+                id,
+            });
+        }
+        if !self.dir.exists() {
+            assets.sort_by(|a, b| a.id.cmp(&b.id));
+            return Ok(assets);
+        }
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| format!("Failed to read asset directory: {}", e))?;
+
+        for entry in entries.flatten() {
+            let Some(id) = entry.file_name().to_str().map(String::from) else {
+                continue;
+            };
+            if validate_asset_id(&id).is_err() {
+                continue;
+            }
+            let path = entry.path();
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            let ext = id.rsplit('.').next().unwrap_or("");
+            let dims = imagesize::size(&path).ok();
+            assets.push(Asset {
+                mime_type: mime_for_extension(ext).to_string(),
+                size,
+                width: dims.as_ref().map(|d| d.width as u32),
+                height: dims.as_ref().map(|d| d.height as u32),
+                id,
+            });
+        }
+        assets.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(assets)
+    }
+
+    /// Delete assets no object in `canvas` references, keeping those an edit
+    /// in `history` that undo or redo can still reach does, since stepping
+    /// through it can bring them back.
+    /// Returns the removed IDs.
+    pub fn collect_garbage(
+        &self,
+        canvas: &Value,
+        history: &[Batch],
+    ) -> Result<Vec<String>, String> {
+        let mut referenced = referenced_assets(canvas);
+        referenced.extend(oplog::referenced_assets(history));
+        let mut removed = Vec::new();
+        for asset in self.list()? {
+            // Assets inside a bundle go when it is next saved without them
+            if !referenced.contains(&asset.id) && self.dir.join(&asset.id).exists() {
+                fs::remove_file(self.dir.join(&asset.id))
+                    .map_err(|e| format!("Failed to remove asset {}: {}", asset.id, e))?;
+                removed.push(asset.id);
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Copy assets (all of them when `ids` is empty) into `destination`.
+    pub fn export(&self, ids: &[String], destination: &Path) -> Result<Vec<PathBuf>, String> {
+        fs::create_dir_all(destination)
+            .map_err(|e| format!("Failed to create {}: {}", destination.display(), e))?;
+
+        let ids: Vec<String> = if ids.is_empty() {
+            self.list()?.into_iter().map(|a| a.id).collect()
+        } else {
+            ids.to_vec()
+        };
+
+        let mut written = Vec::new();
+        for id in ids {
+            let source = self.path_of(&id)?;
+            let dest = destination.join(&id);
+            if source.exists() {
+                fs::copy(&source, &dest).map_err(|e| format!("Failed to export {}: {}", id, e))?;
+            } else if self.contains(&id) {
+                fs::write(&dest, self.read(&id)?)
+                    .map_err(|e| format!("Failed to export {}: {}", id, e))?;
+            } else {
+                return Err(format!("Asset not found: {}", id));
+            }
+            written.push(dest);
+        }
+        Ok(written)
+    }
+
+    /// Remove the whole asset directory, e.g. when its canvas is deleted.
+    pub fn remove_all(&self) -> Result<(), String> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)
+                .map_err(|e| format!("Failed to remove asset directory: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Asset IDs referenced anywhere in the canvas, including inside groups.
+pub fn referenced_assets(canvas: &Value) -> HashSet<String> {
+    fn walk(objects: &[Value], out: &mut HashSet<String>) {
+        for obj in objects {
+            if let Some(id) = obj.get("assetId").and_then(|v| v.as_str()) {
+                out.insert(id.to_string());
+            }
+            if let Some(children) = obj.get("objects").and_then(|v| v.as_array()) {
+                walk(children, out);
+            }
+        }
+    }
+    let mut out = HashSet::new();
+    if let Some(objects) = canvas.get("objects").and_then(|v| v.as_array()) {
+        walk(objects, &mut out);
+    }
+    out
+}
+
+/// Build a Fabric Image object for an asset. The image keeps its natural
+/// pixel size as `width`/`height` and is scaled to the requested size.
+pub fn image_object(
+    store: &AssetStore,
+    asset: &Asset,
+    x: f64,
+    y: f64,
+    width: Option<f64>,
+    height: Option<f64>,
+    name: Option<String>,
+) -> Value {
+    let natural_w = asset.width.unwrap_or(100) as f64;
+    let natural_h = asset.height.unwrap_or(100) as f64;
+
+    // Preserve the aspect ratio when only one dimension is given.
+    let (scale_x, scale_y) = match (width, height) {
+        (Some(w), Some(h)) => (w / natural_w, h / natural_h),
+        (Some(w), None) => (w / natural_w, w / natural_w),
+        (None, Some(h)) => (h / natural_h, h / natural_h),
+        (None, None) => (1.0, 1.0),
+    };
+
+    json!({
+        "type": "Image",
+        "id": new_object_id(),
+        "name": name.unwrap_or_else(|| "Image".to_string()),
+        "assetId": asset.id,
+        "src": store.relative_src(&asset.id),
+        "left": x,
+        "top": y,
+        "width": natural_w,
+        "height": natural_h,
+        "scaleX": scale_x,
+        "scaleY": scale_y,
+        "cropX": 0,
+        "cropY": 0,
+        "strokeWidth": 0,
+        "filters": [],
+        "originX": "left",
+        "originY": "top",
+        "version": "7.0.0"
+    })
+}
+
+/// Bytes of a base64 `data:` URL.
+pub fn decode_data_url(src: &str) -> Result<Vec<u8>, String> {
+    use base64::Engine;
+    let (_, encoded) = src
+        .strip_prefix("data:")
+        .and_then(|data| data.split_once(";base64,"))
+        .ok_or("only base64 data URLs are supported")?;
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("invalid data URL: {}", e))
+}
+
+/// `<sha256>.<ext>` ID for arbitrary bytes, e.g. font files.
+pub fn content_id(bytes: &[u8], ext: &str) -> String {
+    format!("{}.{}", hex_digest(bytes), ext)
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Asset IDs are `<64 hex chars>.<ext>`; anything else could escape the directory.
+fn validate_asset_id(id: &str) -> Result<(), String> {
+    let valid = match id.split_once('.') {
+        Some((hash, ext)) => {
+            hash.len() == 64
+                && hash.chars().all(|c| c.is_ascii_hexdigit())
+                && !ext.is_empty()
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        }
+        None => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid asset id: {}", id))
+    }
+}
+
+fn sniff_format(
+    bytes: &[u8],
+    ext_hint: Option<&str>,
+) -> Result<(&'static str, &'static str), String> {
+    use imagesize::ImageType;
+    match imagesize::image_type(bytes) {
+        Ok(ImageType::Png) => Ok(("png", "image/png")),
+        Ok(ImageType::Jpeg) => Ok(("jpg", "image/jpeg")),
+        Ok(ImageType::Gif) => Ok(("gif", "image/gif")),
+        Ok(ImageType::Webp) => Ok(("webp", "image/webp")),
+        Ok(ImageType::Bmp) => Ok(("bmp", "image/bmp")),
+        Ok(ImageType::Ico) => Ok(("ico", "image/x-icon")),
+        _ if ext_hint == Some("svg") => Ok(("svg", "image/svg+xml")),
+        _ => {
+            Err("Unsupported image format (expected PNG, JPEG, GIF, WebP, BMP, ICO or SVG)".into())
+        }
+    }
+}
+
+fn mime_for_extension(ext: &str) -> &'static str {
+    match ext {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        "svg" => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,493 @@
+//! Who changed what on a canvas.
+//!
+//! An attributed save stamps every object it adds or changes with the actor
+//! and time (`editedBy`, `editedAt`), and its batch in the canvas's operation
+//! log (see [`oplog`](crate::oplog)) records the actor. The log answers "what
+//! did this agent change" and lets one actor's edits be reverted without
+//! touching anyone else's.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+use crate::fabric;
+use crate::oplog::{self, Batch, Op, OpKind};
+
+/// Actor id of the last edit to an object.
+pub const EDITED_BY: &str = "editedBy";
+/// When that edit was saved (RFC 3339).
+pub const EDITED_AT: &str = "editedAt";
+
+const OBJECTS: &str = "objects";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActorKind {
+    User,
+    Agent,
+}
+
+impl ActorKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActorKind::User => "user",
+            ActorKind::Agent => "agent",
+        }
+    }
+}
+
+/// Whoever made an edit: a person in the app or a labelled agent, plus the
+/// session (process) it came from, so parallel agents with the same label
+/// can still be told apart.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Actor {
+    pub kind: ActorKind,
+    pub label: String,
+    pub session: String,
+}
+
+impl Actor {
+    /// The person at this machine, in this process's session.
+    pub fn user() -> Self {
+        let label = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "user".to_string());
+        Self {
+            kind: ActorKind::User,
+            label,
+            session: session_id().to_string(),
+        }
+    }
+
+    /// An agent in this process's session.
+    pub fn agent(label: &str) -> Self {
+        Self {
+            kind: ActorKind::Agent,
+            label: label.to_string(),
+            session: session_id().to_string(),
+        }
+    }
+
+    /// `kind:label@session`, as stamped on objects.
+    pub fn id(&self) -> String {
+        format!("{}:{}@{}", self.kind.as_str(), self.label, self.session)
+    }
+
+    /// Whether `who` names this actor: its full id, label or session.
+    pub fn matches(&self, who: &str) -> bool {
+        who == self.label || who == self.session || who == self.id()
+    }
+}
+
+/// Random id for this process, shared by every actor it reports.
+pub fn session_id() -> &'static str {
+    static SESSION: OnceLock<String> = OnceLock::new();
+    SESSION.get_or_init(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Which edits to list. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct EditQuery {
+    /// Actor id, label or session id.
+    pub actor: Option<String>,
+    /// Only changes to this object.
+    pub object_id: Option<String>,
+    /// Only edits saved at or after this time.
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// How much one actor has edited.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActorSummary {
+    pub id: String,
+    #[serde(flatten)]
+    pub actor: Actor,
+    /// Attributed saves.
+    pub edits: usize,
+    /// Distinct objects touched.
+    pub objects: usize,
+    pub last_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevertReport {
+    /// Objects put back to how they were before the actor's edits.
+    pub reverted: Vec<String>,
+    /// Objects the actor edited that someone changed since; left alone.
+    pub skipped: Vec<String>,
+}
+
+/// Compare `canvas` with `base`, the state the edit started from (none for a
+/// new file), and stamp what was added or changed with `actor` and `at`.
+/// Unchanged objects keep the stamps they had in `base`, so writers that drop
+/// unknown properties don't erase them. Without an actor, changed objects are
+/// left as they are.
+pub fn stamp(base: Option<&Value>, canvas: &mut Value, actor: Option<&Actor>, at: DateTime<Utc>) {
+    fn visit(list: &mut [Value], prior: &HashMap<&str, &Value>, stamps: Option<&[Value; 2]>) {
+        for obj in list {
+            // Objects without an id aren't tracked
+            let before = fabric::object_id(obj).map(|id| prior.get(id).copied());
+            if let (Some(before), Some(props)) = (before, obj.as_object_mut()) {
+                match (before, stamps) {
+                    (Some(before), _) if oplog::same_props(before, props) => {
+                        // Unchanged: keep whoever last edited it
+                        for key in [EDITED_BY, EDITED_AT] {
+                            match before.get(key) {
+                                Some(value) => props.insert(key.into(), value.clone()),
+                                None => props.remove(key),
+                            };
+                        }
+                    }
+                    (_, Some([by, at])) => {
+                        props.insert(EDITED_BY.into(), by.clone());
+                        props.insert(EDITED_AT.into(), at.clone());
+                    }
+                    _ => {}
+                }
+            }
+            if let Some(children) = obj.get_mut(OBJECTS).and_then(Value::as_array_mut) {
+                visit(children, prior, stamps);
+            }
+        }
+    }
+
+    let nodes = base.map(oplog::nodes).unwrap_or_default();
+    let prior: HashMap<&str, &Value> = nodes.iter().map(|n| (n.id.as_str(), &n.object)).collect();
+    let stamps = actor.map(|a| [Value::String(a.id()), Value::String(at.to_rfc3339())]);
+    if let Some(objects) = canvas.get_mut(OBJECTS).and_then(Value::as_array_mut) {
+        visit(objects, &prior, stamps.as_ref());
+    }
+}
+
+/// `canvas` without the attribution stamps, to compare it with a copy from a
+/// writer that doesn't keep them.
+pub fn without_stamps(canvas: &Value) -> Value {
+    fn strip(list: &mut [Value]) {
+        for obj in list {
+            if let Some(props) = obj.as_object_mut() {
+                props.remove(EDITED_BY);
+                props.remove(EDITED_AT);
+            }
+            if let Some(children) = obj.get_mut(OBJECTS).and_then(Value::as_array_mut) {
+                strip(children);
+            }
+        }
+    }
+    let mut canvas = canvas.clone();
+    if let Some(objects) = canvas.get_mut(OBJECTS).and_then(Value::as_array_mut) {
+        strip(objects);
+    }
+    canvas
+}
+
+/// Batches of the operation log matching `query`, holding only the matching
+/// operations. Without `values` the before/after values are left out, keeping
+/// just which properties changed.
+pub fn query(batches: &[Batch], query: &EditQuery, values: bool) -> Vec<Batch> {
+    batches
+        .iter()
+        .filter(|b| {
+            query
+                .actor
+                .as_deref()
+                .is_none_or(|who| b.actor.as_ref().is_some_and(|a| a.matches(who)))
+        })
+        .filter(|b| query.since.is_none_or(|since| b.at >= since))
+        .filter_map(|b| {
+            let ops: Vec<Op> = b
+                .ops
+                .iter()
+                .filter(|op| {
+                    query
+                        .object_id
+                        .as_deref()
+                        .is_none_or(|id| op.object_id.as_deref() == Some(id))
+                })
+                .map(|op| {
+                    let mut op = op.clone();
+                    if !values {
+                        op.before = None;
+                        op.after = None;
+                    }
+                    op
+                })
+                .collect();
+            (!ops.is_empty()).then(|| Batch { ops, ..b.clone() })
+        })
+        .collect()
+}
+
+/// Everyone with attributed batches, in order of their first edit.
+pub fn actors(batches: &[Batch]) -> Vec<ActorSummary> {
+    let mut summaries: Vec<(ActorSummary, HashSet<&str>)> = Vec::new();
+    for batch in batches {
+        let Some(actor) = &batch.actor else {
+            continue;
+        };
+        let at = summaries.iter().position(|(s, _)| s.actor == *actor);
+        let (summary, objects) = match at {
+            Some(at) => &mut summaries[at],
+            None => {
+                summaries.push((
+                    ActorSummary {
+                        id: actor.id(),
+                        actor: actor.clone(),
+                        edits: 0,
+                        objects: 0,
+                        last_at: batch.at,
+                    },
+                    HashSet::new(),
+                ));
+                summaries.last_mut().expect("just pushed")
+            }
+        };
+        summary.edits += 1;
+        summary.last_at = batch.at;
+        objects.extend(batch.ops.iter().filter_map(|op| op.object_id.as_deref()));
+        summary.objects = objects.len();
+    }
+    summaries.into_iter().map(|(summary, _)| summary).collect()
+}
+
+/// Undo `who`'s edits in `canvas`. Each object goes back to how it was before
+/// the actor's last run of edits to it, provided nobody changed it since, in
+/// the log or outside it; otherwise it is skipped. Moves between groups and
+/// reorders are left as they are.
+pub fn revert(canvas: &mut Value, batches: &[Batch], who: &str) -> RevertReport {
+    let ours = |actor: Option<&Actor>| actor.is_some_and(|a| a.matches(who));
+    let mut order: Vec<&str> = Vec::new();
+    let mut history: HashMap<&str, Vec<(Option<&Actor>, &Op)>> = HashMap::new();
+    for batch in batches {
+        for op in &batch.ops {
+            let Some(id) = op.object_id.as_deref() else {
+                continue;
+            };
+            let edits = history.entry(id).or_default();
+            if edits.is_empty() {
+                order.push(id);
+            }
+            edits.push((batch.actor.as_ref(), op));
+        }
+    }
+
+    let mut report = RevertReport::default();
+    for id in order {
+        let edits = &history[id];
+        let run = edits
+            .iter()
+            .rev()
+            .take_while(|(actor, _)| ours(*actor))
+            .count();
+        if run == 0 {
+            if edits.iter().any(|(actor, _)| ours(*actor)) {
+                report.skipped.push(id.to_string());
+            }
+            continue;
+        }
+        let run = &edits[edits.len() - run..];
+        let (first, last) = (run[0].1, run[run.len() - 1].1);
+
+        let current = oplog::find(canvas, id);
+        let exists = current.is_some();
+        let untouched = match (current, &last.after) {
+            (Some(current), Some(after)) => oplog::same(current, after),
+            (None, None) => true,
+            _ => false,
+        };
+        if !untouched {
+            report.skipped.push(id.to_string());
+            continue;
+        }
+
+        match (&first.before, exists) {
+            (None, true) => oplog::remove(canvas, id),
+            (Some(before), true) => {
+                if let Some(target) = oplog::find_mut(canvas, id).and_then(Value::as_object_mut) {
+                    let children = target.remove(OBJECTS);
+                    *target = before.as_object().cloned().unwrap_or_default();
+                    if let Some(children) = children {
+                        target.insert(OBJECTS.into(), children);
+                    }
+                }
+            }
+            (Some(before), false) => {
+                // Back where the actor deleted it
+                let (parent, index) = run
+                    .iter()
+                    .rev()
+                    .find(|(_, op)| op.kind == OpKind::Delete)
+                    .map_or((None, None), |(_, op)| (op.parent.as_deref(), op.index));
+                oplog::insert(canvas, parent, index, before.clone());
+            }
+            (None, false) => {}
+        }
+        report.reverted.push(id.to_string());
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn actor(kind: ActorKind, label: &str) -> Actor {
+        Actor {
+            kind,
+            label: label.to_string(),
+            session: "session".to_string(),
+        }
+    }
+
+    /// Stamp `after` as `by` saved it over `before`, returning it and its batch.
+    fn save(id: i64, before: &Value, mut after: Value, by: &Actor) -> (Value, Batch) {
+        let at = Utc::now();
+        stamp(Some(before), &mut after, Some(by), at);
+        let batch = Batch {
+            id,
+            at,
+            revision: id as u64,
+            actor: Some(by.clone()),
+            step: None,
+            ops: oplog::diff(Some(before), &after),
+        };
+        (after, batch)
+    }
+
+    #[test]
+    fn stamps_added_and_changed_objects_only() {
+        let bot = actor(ActorKind::Agent, "bot");
+        let base = json!({"objects": [
+            {"id": "a", "left": 0, "editedBy": "user:me@s", "editedAt": "earlier"},
+            {"id": "b", "left": 0},
+        ]});
+        // A writer that drops the stamps it doesn't know about
+        let mut canvas = json!({"objects": [
+            {"id": "a", "left": 0},
+            {"id": "b", "left": 7},
+            {"id": "c", "objects": [{"id": "d"}]},
+        ]});
+        stamp(Some(&base), &mut canvas, Some(&bot), Utc::now());
+
+        let by = |i: usize| canvas["objects"][i][EDITED_BY].clone();
+        assert_eq!(by(0), "user:me@s");
+        assert_eq!(canvas["objects"][0][EDITED_AT], "earlier");
+        assert_eq!(by(1), bot.id());
+        assert_eq!(by(2), bot.id());
+        assert_eq!(canvas["objects"][2]["objects"][0][EDITED_BY], bot.id());
+        assert_eq!(
+            without_stamps(&canvas),
+            json!({"objects": [
+                {"id": "a", "left": 0},
+                {"id": "b", "left": 7},
+                {"id": "c", "objects": [{"id": "d"}]},
+            ]})
+        );
+    }
+
+    #[test]
+    fn unattributed_saves_leave_changes_unstamped() {
+        let base = json!({"objects": [{"id": "a", "left": 0}]});
+        let mut canvas = json!({"objects": [{"id": "a", "left": 3}, {"id": "b"}]});
+        stamp(Some(&base), &mut canvas, None, Utc::now());
+        assert_eq!(without_stamps(&canvas), canvas);
+    }
+
+    #[test]
+    fn revert_undoes_one_actors_edits() {
+        let (bot, me) = (actor(ActorKind::Agent, "bot"), actor(ActorKind::User, "me"));
+        let v0 = json!({"objects": [
+            {"id": "a", "left": 0},
+            {"id": "b", "left": 0},
+            {"id": "d", "left": 0},
+        ]});
+        let (v1, b1) = save(
+            1,
+            &v0,
+            json!({"objects": [
+                {"id": "a", "left": 5},
+                {"id": "b", "left": 5},
+                {"id": "x"},
+            ]}),
+            &bot,
+        );
+        // Someone else moves b after the agent did
+        let mut changed = v1.clone();
+        changed["objects"][1]["left"] = json!(9);
+        let (mut canvas, b2) = save(2, &v1, changed, &me);
+
+        let report = revert(&mut canvas, &[b1, b2], "bot");
+        let mut reverted = report.reverted.clone();
+        reverted.sort();
+        assert_eq!(reverted, ["a", "d", "x"]);
+        assert_eq!(report.skipped, ["b"]);
+        assert_eq!(
+            without_stamps(&canvas),
+            json!({"objects": [
+                {"id": "a", "left": 0},
+                {"id": "b", "left": 9},
+                {"id": "d", "left": 0},
+            ]})
+        );
+    }
+
+    #[test]
+    fn revert_skips_objects_changed_outside_the_log() {
+        let bot = actor(ActorKind::Agent, "bot");
+        let v0 = json!({"objects": [{"id": "a", "left": 0}]});
+        let (mut canvas, b1) = save(1, &v0, json!({"objects": [{"id": "a", "left": 5}]}), &bot);
+        canvas["objects"][0]["left"] = json!(6);
+
+        let report = revert(&mut canvas, &[b1], &bot.id());
+        assert!(report.reverted.is_empty());
+        assert_eq!(report.skipped, ["a"]);
+    }
+
+    #[test]
+    fn query_and_actors_summarize_the_log() {
+        let (bot, me) = (actor(ActorKind::Agent, "bot"), actor(ActorKind::User, "me"));
+        let v0 = json!({"objects": []});
+        let (v1, b1) = save(1, &v0, json!({"objects": [{"id": "a"}, {"id": "b"}]}), &bot);
+        let (_, b2) = save(
+            2,
+            &v1,
+            json!({"objects": [{"id": "a", "fill": "red"}, {"id": "b"}]}),
+            &me,
+        );
+        let batches = [b1, b2];
+
+        let edits = query(
+            &batches,
+            &EditQuery {
+                object_id: Some("a".to_string()),
+                ..EditQuery::default()
+            },
+            false,
+        );
+        assert_eq!(edits.len(), 2);
+        assert!(edits
+            .iter()
+            .all(|b| b.ops.iter().all(|op| op.after.is_none())));
+
+        let by_bot = query(
+            &batches,
+            &EditQuery {
+                actor: Some("bot".to_string()),
+                ..EditQuery::default()
+            },
+            true,
+        );
+        assert_eq!(by_bot.len(), 1);
+        assert_eq!(by_bot[0].ops.len(), 2);
+
+        let summaries = actors(&batches);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!((summaries[0].edits, summaries[0].objects), (1, 2));
+        assert_eq!(summaries[1].id, me.id());
+    }
+}
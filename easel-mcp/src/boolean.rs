@@ -0,0 +1,551 @@
+//! Boolean operations on filled shapes: union, subtract, intersect and
+//! exclude, as in Figma's boolean groups.
+//!
+//! Operands are flattened to polygons in canvas space. Every edge is split
+//! wherever it meets another one and kept when the result is filled on
+//! exactly one side of it; the kept edges, oriented with the filled side on
+//! their left, are chained back into closed contours. Curves come out as
+//! short straight segments.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+use crate::fabric;
+use crate::geometry::{self, Matrix, Point};
+use crate::import::{path_object, place};
+use crate::path::{self, Segment};
+use crate::render;
+
+/// Maximum distance between a curve and the segments that replace it.
+const FLATNESS: f64 = 0.25;
+/// How far either side of an edge its neighbourhood is sampled.
+const SIDE_OFFSET: f64 = 1e-4;
+/// Grid that contour endpoints are snapped to when chaining edges.
+const SNAP: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BooleanOp {
+    /// Area covered by any operand.
+    Union,
+    /// The bottom operand minus all the others.
+    Subtract,
+    /// Area covered by every operand.
+    Intersect,
+    /// Area covered by an odd number of operands.
+    Exclude,
+}
+
+impl BooleanOp {
+    fn keeps(self, inside: &[bool]) -> bool {
+        match self {
+            Self::Union => inside.iter().any(|&i| i),
+            Self::Subtract => inside[0] && !inside[1..].iter().any(|&i| i),
+            Self::Intersect => inside.iter().all(|&i| i),
+            Self::Exclude => inside.iter().filter(|&&i| i).count() % 2 == 1,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Union => "Union",
+            Self::Subtract => "Subtract",
+            Self::Intersect => "Intersect",
+            Self::Exclude => "Exclude",
+        }
+    }
+}
+
+/// One filled outline, as polygons in canvas space.
+struct Region {
+    contours: Vec<Vec<Point>>,
+    even_odd: bool,
+}
+
+/// A boolean operand: the filled area of an object, or of every shape in a group.
+pub struct Shape {
+    regions: Vec<Region>,
+}
+
+impl Shape {
+    /// The filled area of a shape, path or group of them, in canvas space.
+    pub fn from_object(obj: &Value) -> Result<Shape, String> {
+        let mut regions = Vec::new();
+        collect_regions(obj, &Matrix::IDENTITY, &mut regions)?;
+        if regions.iter().all(|r| r.contours.is_empty()) {
+            return Err(format!(
+                "{} has no area to combine",
+                fabric::str_prop(obj, "name").unwrap_or("Object")
+            ));
+        }
+        Ok(Shape { regions })
+    }
+
+    fn contains(&self, p: Point) -> bool {
+        self.regions.iter().any(|region| {
+            let winding: i32 = region.contours.iter().map(|c| winding(c, p)).sum();
+            if region.even_odd {
+                winding % 2 != 0
+            } else {
+                winding != 0
+            }
+        })
+    }
+}
+
+fn collect_regions(obj: &Value, parent: &Matrix, out: &mut Vec<Region>) -> Result<(), String> {
+    let matrix = parent.multiply(&geometry::object_matrix(obj));
+    if fabric::object_type(obj) == "group" {
+        let inner = matrix.multiply(&Matrix::translate(
+            fabric::base_width(obj) / 2.0,
+            fabric::base_height(obj) / 2.0,
+        ));
+        for child in obj
+            .get("objects")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+        {
+            collect_regions(child, &inner, out)?;
+        }
+        return Ok(());
+    }
+    let name = fabric::str_prop(obj, "name").unwrap_or("Object");
+    let segments = render::shape_segments(obj)
+        .map_err(|e| format!("{}: {}", name, e))?
+        .ok_or_else(|| format!("{} is not a shape", name))?;
+    let segments = path::transform(&segments, &matrix);
+    out.push(Region {
+        contours: flatten(&segments),
+        even_odd: fabric::str_prop(obj, "fillRule") == Some("evenodd"),
+    });
+    Ok(())
+}
+
+/// Closed polygons approximating the filled area of `segments`. Open
+/// subpaths are closed, as Fabric fills them.
+pub fn flatten(segments: &[Segment]) -> Vec<Vec<Point>> {
+    polylines(segments)
+        .into_iter()
+        .filter_map(|(mut contour, _)| {
+            if same_point(contour[0], contour[contour.len() - 1]) {
+                contour.pop();
+            }
+            (contour.len() > 2).then_some(contour)
+        })
+        .collect()
+}
+
+/// Each subpath of `segments` as a polyline of at least two points, with
+/// whether it was closed (`Z`).
+pub fn polylines(segments: &[Segment]) -> Vec<(Vec<Point>, bool)> {
+    let mut lines = Vec::new();
+    let mut current: Vec<Point> = Vec::new();
+    let mut finish = |current: &mut Vec<Point>, closed: bool| {
+        let line = std::mem::take(current);
+        if line.len() > 1 {
+            lines.push((line, closed));
+        }
+    };
+    for segment in segments {
+        let last = current.last().copied().unwrap_or(Point::new(0.0, 0.0));
+        match *segment {
+            Segment::Move(p) => {
+                finish(&mut current, false);
+                current.push(p);
+            }
+            Segment::Line(p) => current.push(p),
+            Segment::Quad(c, p) => {
+                let c1 = Point::new(
+                    last.x + (c.x - last.x) * 2.0 / 3.0,
+                    last.y + (c.y - last.y) * 2.0 / 3.0,
+                );
+                let c2 = Point::new(p.x + (c.x - p.x) * 2.0 / 3.0, p.y + (c.y - p.y) * 2.0 / 3.0);
+                flatten_cubic(last, c1, c2, p, &mut current);
+            }
+            Segment::Cubic(c1, c2, p) => flatten_cubic(last, c1, c2, p, &mut current),
+            Segment::Close => {
+                let start = current.first().copied();
+                finish(&mut current, true);
+                // Drawing may continue from the start of the closed subpath.
+                if let Some(start) = start {
+                    current.push(start);
+                }
+            }
+        }
+    }
+    finish(&mut current, false);
+    lines
+}
+
+fn flatten_cubic(p0: Point, c1: Point, c2: Point, p3: Point, out: &mut Vec<Point>) {
+    // Bound on the curve's deviation from its chord, from the second differences.
+    let dd = |a: Point, b: Point, c: Point| {
+        ((a.x - 2.0 * b.x + c.x).powi(2) + (a.y - 2.0 * b.y + c.y).powi(2)).sqrt()
+    };
+    let deviation = dd(p0, c1, c2).max(dd(c1, c2, p3));
+    let steps = ((0.75 * deviation / FLATNESS).sqrt().ceil() as usize).clamp(1, 256);
+    for i in 1..=steps {
+        let t = i as f64 / steps as f64;
+        let mt = 1.0 - t;
+        let (a, b, c, d) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+        out.push(Point::new(
+            a * p0.x + b * c1.x + c * c2.x + d * p3.x,
+            a * p0.y + b * c1.y + c * c2.y + d * p3.y,
+        ));
+    }
+}
+
+/// Winding number of a closed polygon around `p`.
+pub(crate) fn winding(contour: &[Point], p: Point) -> i32 {
+    let mut winding = 0;
+    for (i, &a) in contour.iter().enumerate() {
+        let b = contour[(i + 1) % contour.len()];
+        let side = (b.x - a.x) * (p.y - a.y) - (p.x - a.x) * (b.y - a.y);
+        if a.y <= p.y {
+            if b.y > p.y && side > 0.0 {
+                winding += 1;
+            }
+        } else if b.y <= p.y && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+fn same_point(a: Point, b: Point) -> bool {
+    (a.x - b.x).abs() < SNAP && (a.y - b.y).abs() < SNAP
+}
+
+/// Combine the operands, bottom-most first. Returns the result's contours.
+pub fn combine(op: BooleanOp, shapes: &[Shape]) -> Vec<Vec<Point>> {
+    if shapes.is_empty() {
+        return Vec::new();
+    }
+
+    // Every operand edge, with the points where other edges meet it.
+    let mut edges: Vec<(Point, Point)> = Vec::new();
+    for shape in shapes {
+        for region in &shape.regions {
+            for contour in &region.contours {
+                for (i, &a) in contour.iter().enumerate() {
+                    let b = contour[(i + 1) % contour.len()];
+                    if !same_point(a, b) {
+                        edges.push((a, b));
+                    }
+                }
+            }
+        }
+    }
+    let mut splits: Vec<Vec<(f64, Point)>> = vec![Vec::new(); edges.len()];
+    let boxes: Vec<[f64; 4]> = edges
+        .iter()
+        .map(|(a, b)| [a.x.min(b.x), a.y.min(b.y), a.x.max(b.x), a.y.max(b.y)])
+        .collect();
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            let (bi, bj) = (boxes[i], boxes[j]);
+            if bi[0] > bj[2] + SNAP
+                || bj[0] > bi[2] + SNAP
+                || bi[1] > bj[3] + SNAP
+                || bj[1] > bi[3] + SNAP
+            {
+                continue;
+            }
+            for (t, u, p) in crossings(edges[i], edges[j]) {
+                splits[i].push((t, p));
+                splits[j].push((u, p));
+            }
+        }
+    }
+
+    // Keep each piece whose two sides differ, filled side on the left.
+    let mut kept: Vec<(Point, Point)> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut inside = vec![false; shapes.len()];
+    for ((a, b), mut points) in edges.into_iter().zip(splits) {
+        points.sort_by(|x, y| x.0.total_cmp(&y.0));
+        let mut chain = vec![a];
+        chain.extend(
+            points
+                .into_iter()
+                .filter(|(t, _)| *t > 0.0 && *t < 1.0)
+                .map(|(_, p)| p),
+        );
+        chain.push(b);
+        for pair in chain.windows(2) {
+            let (p, q) = (pair[0], pair[1]);
+            let length = ((q.x - p.x).powi(2) + (q.y - p.y).powi(2)).sqrt();
+            if length < SNAP {
+                continue;
+            }
+            let mid = Point::new((p.x + q.x) / 2.0, (p.y + q.y) / 2.0);
+            let normal = Point::new(
+                -(q.y - p.y) / length * SIDE_OFFSET,
+                (q.x - p.x) / length * SIDE_OFFSET,
+            );
+            let mut filled = |side: Point| {
+                for (flag, shape) in inside.iter_mut().zip(shapes) {
+                    *flag = shape.contains(side);
+                }
+                op.keeps(&inside)
+            };
+            let left = filled(Point::new(mid.x + normal.x, mid.y + normal.y));
+            let right = filled(Point::new(mid.x - normal.x, mid.y - normal.y));
+            let edge = match (left, right) {
+                (true, false) => (p, q),
+                (false, true) => (q, p),
+                _ => continue,
+            };
+            // Operands sharing an edge contribute it twice.
+            if seen.insert((snap(edge.0), snap(edge.1))) {
+                kept.push(edge);
+            }
+        }
+    }
+    chain_contours(&kept)
+}
+
+/// Where two edges meet: parameters along each and the shared point.
+/// Overlapping collinear edges meet at each other's endpoints.
+fn crossings((p, p2): (Point, Point), (q, q2): (Point, Point)) -> Vec<(f64, f64, Point)> {
+    let r = Point::new(p2.x - p.x, p2.y - p.y);
+    let s = Point::new(q2.x - q.x, q2.y - q.y);
+    let cross = |a: Point, b: Point| a.x * b.y - a.y * b.x;
+    let qp = Point::new(q.x - p.x, q.y - p.y);
+    let denom = cross(r, s);
+    let (rr, ss) = (r.x * r.x + r.y * r.y, s.x * s.x + s.y * s.y);
+
+    if denom.abs() <= 1e-12 * rr.sqrt() * ss.sqrt() {
+        // Parallel: only collinear overlaps matter.
+        if cross(qp, r).abs() > 1e-9 * rr.sqrt().max(1.0) {
+            return Vec::new();
+        }
+        let along_p = |x: Point| ((x.x - p.x) * r.x + (x.y - p.y) * r.y) / rr;
+        let along_q = |x: Point| ((x.x - q.x) * s.x + (x.y - q.y) * s.y) / ss;
+        let mut out = Vec::new();
+        for x in [q, q2] {
+            let t = along_p(x);
+            if t > 0.0 && t < 1.0 {
+                out.push((t, along_q(x), x));
+            }
+        }
+        for x in [p, p2] {
+            let u = along_q(x);
+            if u > 0.0 && u < 1.0 {
+                out.push((along_p(x), u, x));
+            }
+        }
+        return out;
+    }
+
+    let t = cross(qp, s) / denom;
+    let u = cross(qp, r) / denom;
+    let eps = 1e-9;
+    if !(-eps..=1.0 + eps).contains(&t) || !(-eps..=1.0 + eps).contains(&u) {
+        return Vec::new();
+    }
+    // Reuse exact endpoints so touching contours share their vertices.
+    let point = if u <= eps {
+        q
+    } else if u >= 1.0 - eps {
+        q2
+    } else if t <= eps {
+        p
+    } else if t >= 1.0 - eps {
+        p2
+    } else {
+        Point::new(p.x + t * r.x, p.y + t * r.y)
+    };
+    vec![(t.clamp(0.0, 1.0), u.clamp(0.0, 1.0), point)]
+}
+
+fn snap(p: Point) -> (i64, i64) {
+    ((p.x / SNAP).round() as i64, (p.y / SNAP).round() as i64)
+}
+
+/// Link directed edges end to start into closed loops. Where several edges
+/// leave one vertex (regions touching at a point), turn hardest towards the
+/// filled side so each loop stays around one region.
+fn chain_contours(edges: &[(Point, Point)]) -> Vec<Vec<Point>> {
+    let mut outgoing: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, (a, _)) in edges.iter().enumerate() {
+        outgoing.entry(snap(*a)).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut contours = Vec::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut contour = vec![edges[start].0];
+        let mut current = start;
+        let closed = loop {
+            let (from, to) = edges[current];
+            if snap(to) == snap(edges[start].0) {
+                break true;
+            }
+            contour.push(to);
+            let heading = (to.y - from.y).atan2(to.x - from.x);
+            let next = outgoing.get(&snap(to)).and_then(|candidates| {
+                candidates
+                    .iter()
+                    .filter(|&&i| !used[i])
+                    .min_by(|&&x, &&y| {
+                        let turn = |i: usize| {
+                            let (a, b) = edges[i];
+                            let mut d = (b.y - a.y).atan2(b.x - a.x) - heading;
+                            while d <= -std::f64::consts::PI {
+                                d += std::f64::consts::TAU;
+                            }
+                            while d > std::f64::consts::PI {
+                                d -= std::f64::consts::TAU;
+                            }
+                            -d
+                        };
+                        turn(x).total_cmp(&turn(y))
+                    })
+                    .copied()
+            });
+            match next {
+                Some(i) => {
+                    used[i] = true;
+                    current = i;
+                }
+                None => break false,
+            }
+        };
+        if closed {
+            let contour = simplify(contour);
+            if contour.len() > 2 {
+                contours.push(contour);
+            }
+        }
+    }
+    contours
+}
+
+/// Drop points that lie on the line through their neighbours.
+fn simplify(mut contour: Vec<Point>) -> Vec<Point> {
+    let mut i = 0;
+    while contour.len() > 2 && i < contour.len() {
+        let n = contour.len();
+        let (a, b, c) = (contour[(i + n - 1) % n], contour[i], contour[(i + 1) % n]);
+        let cross = (b.x - a.x) * (c.y - b.y) - (b.y - a.y) * (c.x - b.x);
+        let dot = (b.x - a.x) * (c.x - b.x) + (b.y - a.y) * (c.y - b.y);
+        let scale = ((b.x - a.x).hypot(b.y - a.y)) * ((c.x - b.x).hypot(c.y - b.y));
+        if cross.abs() <= 1e-9 * scale.max(1e-12) && dot >= 0.0 {
+            contour.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+    contour
+}
+
+/// Copied from the bottom operand onto the result, as Figma does.
+const STYLE_KEYS: &[&str] = &[
+    "fill",
+    "stroke",
+    "strokeWidth",
+    "strokeUniform",
+    "strokeDashArray",
+    "strokeLineCap",
+    "strokeLineJoin",
+    "strokeMiterLimit",
+    "opacity",
+    "shadow",
+    "parentId",
+];
+
+/// Build a Path object from the operands (bottom-most first), styled like the
+/// bottom one. Errors when the result is empty.
+pub fn boolean_object(
+    op: BooleanOp,
+    operands: &[&Value],
+    name: Option<&str>,
+) -> Result<Value, String> {
+    if operands.len() < 2 {
+        return Err("A boolean operation needs at least two objects".into());
+    }
+    let shapes = operands
+        .iter()
+        .map(|obj| Shape::from_object(obj))
+        .collect::<Result<Vec<_>, _>>()?;
+    let contours = combine(op, &shapes);
+    if contours.is_empty() {
+        return Err(format!("{} of these objects is empty", op.name()));
+    }
+
+    let mut segments = Vec::new();
+    for contour in &contours {
+        segments.push(Segment::Move(contour[0]));
+        segments.extend(contour[1..].iter().map(|p| Segment::Line(*p)));
+        segments.push(Segment::Close);
+    }
+    let (mut obj, origin) =
+        path_object(&segments, name.unwrap_or(op.name())).ok_or("The result has no area")?;
+    let base = operands[0];
+    for key in STYLE_KEYS {
+        if let Some(value) = base.get(*key) {
+            obj[*key] = value.clone();
+        }
+    }
+    // The contours are plain lines in canvas space, so the Path keeps no transform.
+    obj["path"] = round_path(&obj["path"]);
+    place(&mut obj, &Matrix::IDENTITY, origin);
+    Ok(obj)
+}
+
+/// Replace top-level objects of `canvas` with their boolean result, placed
+/// where the bottom-most of them was. With `keep_originals` the operands stay.
+pub fn apply_to_canvas(
+    canvas: &mut Value,
+    ids: &[String],
+    op: BooleanOp,
+    name: Option<&str>,
+    keep_originals: bool,
+) -> Result<Value, String> {
+    let objects = canvas
+        .get_mut("objects")
+        .and_then(|v| v.as_array_mut())
+        .ok_or("Canvas has no objects")?;
+    let mut indices = Vec::with_capacity(ids.len());
+    for id in ids {
+        let index = objects
+            .iter()
+            .position(|o| fabric::object_id(o) == Some(id.as_str()))
+            .ok_or_else(|| format!("Object not found at the top level: {}", id))?;
+        if !indices.contains(&index) {
+            indices.push(index);
+        }
+    }
+    indices.sort_unstable();
+
+    let operands: Vec<&Value> = indices.iter().map(|&i| &objects[i]).collect();
+    let mut result = boolean_object(op, &operands, name)?;
+    result["id"] = json!(crate::import::new_object_id());
+
+    let at = indices[0];
+    if keep_originals {
+        objects.insert(indices[indices.len() - 1] + 1, result.clone());
+    } else {
+        for &i in indices.iter().rev() {
+            objects.remove(i);
+        }
+        objects.insert(at, result.clone());
+    }
+    Ok(result)
+}
+
+fn round_path(path: &Value) -> Value {
+    match path {
+        Value::Array(items) => Value::Array(items.iter().map(round_path).collect()),
+        Value::Number(n) => n
+            .as_f64()
+            .map_or(path.clone(), |v| json!(geometry::round(v))),
+        other => other.clone(),
+    }
+}
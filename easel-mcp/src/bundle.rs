@@ -0,0 +1,433 @@
+//! Packaged `.easel` bundles: a zip archive holding the document JSON, the
+//! assets it references and an optional thumbnail.
+//!
+//! ```text
+//! manifest.json
+//! document.json
+//! thumbnail.png        (optional)
+//! assets/<sha256>.<ext>
+//! fonts/<sha256>.<ext>
+//! ```
+//!
+//! A bundle is detected by its zip signature, so both layouts share the
+//! `.easel` extension. Inside a bundle an image's `src` is its entry,
+//! `assets/<id>`; on load it points at the canvas's asset directory again,
+//! as in a plain file. Loading writes nothing: [`AssetStore::for_canvas`]
+//! reads embedded assets and fonts straight from the bundle, and
+//! [`unpack_assets`] extracts them. On save, the referenced assets and the
+//! fonts the text names are packed back in.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::assets::{self, AssetStore};
+use crate::fonts::{self, FontBook};
+
+pub const MANIFEST_ENTRY: &str = "manifest.json";
+pub const DOCUMENT_ENTRY: &str = "document.json";
+pub const THUMBNAIL_ENTRY: &str = "thumbnail.png";
+const ASSET_PREFIX: &str = "assets/";
+const FONT_PREFIX: &str = "fonts/";
+const BUNDLE_FORMAT: &str = "easel-bundle";
+const BUNDLE_VERSION: u32 = 1;
+/// Largest entry a bundle may hold once unpacked.
+const MAX_ENTRY_SIZE: u64 = 512 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub format: String,
+    pub bundle_version: u32,
+    pub document: String,
+    #[serde(default)]
+    pub assets: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fonts: Vec<String>,
+    /// The id in `fonts` of each embedded face, by PostScript name, so a
+    /// rewrite can keep a face without loading and hashing it again.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub font_faces: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+}
+
+/// Whether the file at `path` is a zipped bundle rather than plain JSON.
+pub fn is_bundle(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut magic))
+        .map(|_| magic == *b"PK\x03\x04")
+        .unwrap_or(false)
+}
+
+/// Read the document JSON from a `.easel` file of either layout, with image
+/// sources relative to the canvas file.
+pub fn read_document(path: &Path) -> Result<String, String> {
+    if !is_bundle(path) {
+        return fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e));
+    }
+
+    let mut archive = open_archive(path)?;
+    let manifest = read_manifest(&mut archive, path)?;
+    let document = read_entry(&mut archive, &manifest.document)?;
+    let document = String::from_utf8(document)
+        .map_err(|e| format!("Bundle document in {} is not UTF-8: {}", path.display(), e))?;
+    let store = AssetStore::for_canvas(path);
+    rewrite_sources(&document, |id| store.relative_src(id))
+}
+
+/// Extract the assets and fonts embedded in the bundle at `path` into
+/// `store`, skipping those it already holds.
+pub fn unpack_assets(path: &Path, store: &AssetStore) -> Result<(), String> {
+    let mut archive = open_archive(path)?;
+    let manifest = read_manifest(&mut archive, path)?;
+    for id in &manifest.assets {
+        if store.path_of(id)?.exists() {
+            continue;
+        }
+        let bytes = read_entry(&mut archive, &format!("{}{}", ASSET_PREFIX, id))?;
+        store.insert(id, &bytes)?;
+    }
+    let font_store = store.fonts();
+    for id in &manifest.fonts {
+        if font_store.path_of(id)?.exists() {
+            continue;
+        }
+        let bytes = read_entry(&mut archive, &format!("{}{}", FONT_PREFIX, id))?;
+        font_store.insert(id, &bytes)?;
+    }
+    Ok(())
+}
+
+/// IDs of the assets embedded in the bundle at `path`.
+pub fn embedded_assets(path: &Path) -> Result<Vec<String>, String> {
+    let mut archive = open_archive(path)?;
+    Ok(read_manifest(&mut archive, path)?.assets)
+}
+
+/// One asset embedded in the bundle at `path`.
+pub fn read_asset(path: &Path, asset_id: &str) -> Result<Vec<u8>, String> {
+    let mut archive = open_archive(path)?;
+    read_entry(&mut archive, &format!("{}{}", ASSET_PREFIX, asset_id))
+}
+
+/// The font files embedded in the bundle at `path`.
+pub fn read_fonts(path: &Path) -> Result<Vec<Vec<u8>>, String> {
+    let mut archive = open_archive(path)?;
+    let manifest = read_manifest(&mut archive, path)?;
+    manifest
+        .fonts
+        .iter()
+        .map(|id| read_entry(&mut archive, &format!("{}{}", FONT_PREFIX, id)))
+        .collect()
+}
+
+/// The thumbnail stored in a bundle, if any.
+pub fn read_thumbnail(path: &Path) -> Result<Option<Vec<u8>>, String> {
+    if !is_bundle(path) {
+        return Ok(None);
+    }
+    let mut archive = open_archive(path)?;
+    if archive.index_for_name(THUMBNAIL_ENTRY).is_none() {
+        return Ok(None);
+    }
+    read_entry(&mut archive, THUMBNAIL_ENTRY).map(Some)
+}
+
+/// Write the document JSON, keeping the layout already on disk: an existing
+/// bundle is rewritten as a bundle, anything else as JSON. The bundle's
+/// thumbnail and the assets and fonts it already holds are copied across
+/// as they are, so a save only packs what is new.
+pub fn write_document(path: &Path, json: &str, canvas: &Value) -> Result<(), String> {
+    if !is_bundle(path) {
+        return write_json(path, json);
+    }
+    let mut archive = open_archive(path)?;
+    let manifest = read_manifest(&mut archive, path)?;
+    let previous = Previous { archive, manifest };
+    let store = AssetStore::for_canvas(path);
+    pack(path, json, canvas, &store, None, Some(previous))
+}
+
+/// Write the document as a plain JSON `.easel` file.
+pub fn write_json(path: &Path, json: &str) -> Result<(), String> {
+    // Atomic write: write to .tmp then rename
+    let tmp_path = path.with_extension("easel.tmp");
+    fs::write(&tmp_path, json).map_err(|e| format!("Failed to write temp file: {}", e))?;
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to rename temp file: {}", e))?;
+    Ok(())
+}
+
+/// Write a bundle containing the document, every asset `canvas` references
+/// (read from `store`), the fonts its text names and an optional PNG thumbnail.
+pub fn write_bundle(
+    path: &Path,
+    json: &str,
+    canvas: &Value,
+    store: &AssetStore,
+    thumbnail: Option<&[u8]>,
+) -> Result<(), String> {
+    pack(path, json, canvas, store, thumbnail, None)
+}
+
+/// The bundle a save replaces, whose entries are reused where they still fit.
+struct Previous {
+    archive: ZipArchive<File>,
+    manifest: Manifest,
+}
+
+impl Previous {
+    fn has(&self, name: &str) -> bool {
+        self.archive.index_for_name(name).is_some()
+    }
+
+    /// Copy an entry into `zip` still compressed.
+    fn copy(&mut self, zip: &mut ZipWriter<File>, name: &str) -> Result<(), String> {
+        let entry = self
+            .archive
+            .by_name(name)
+            .map_err(|e| format!("Bundle entry {}: {}", name, e))?;
+        zip.raw_copy_file(entry)
+            .map_err(|e| format!("Failed to add {} to bundle: {}", name, e))
+    }
+}
+
+/// A font to embed: its bytes, or none to copy it from the previous bundle.
+struct FontEntry {
+    id: String,
+    post_script_name: String,
+    bytes: Option<Vec<u8>>,
+}
+
+fn pack(
+    path: &Path,
+    json: &str,
+    canvas: &Value,
+    store: &AssetStore,
+    thumbnail: Option<&[u8]>,
+    mut previous: Option<Previous>,
+) -> Result<(), String> {
+    let mut asset_ids: Vec<String> = assets::referenced_assets(canvas).into_iter().collect();
+    asset_ids.sort();
+
+    let bundled = FontBook::with_bundled(store);
+    let font_book = bundled.as_ref().unwrap_or_else(|| FontBook::system());
+    let mut font_files: Vec<FontEntry> = fonts::used_faces(canvas, font_book)
+        .into_iter()
+        .filter_map(|face| {
+            let post_script_name = font_book.face(face)?.post_script_name;
+            let kept = previous.as_ref().and_then(|p| {
+                let id = p.manifest.font_faces.get(&post_script_name)?;
+                p.has(&format!("{}{}", FONT_PREFIX, id)).then(|| id.clone())
+            });
+            if let Some(id) = kept {
+                return Some(FontEntry {
+                    id,
+                    post_script_name,
+                    bytes: None,
+                });
+            }
+            let font = font_book.load(face)?;
+            Some(FontEntry {
+                id: assets::content_id(&font.bytes, fonts::font_extension(&font.bytes)),
+                post_script_name,
+                bytes: Some(font.bytes.clone()),
+            })
+        })
+        .collect();
+    font_files.sort_by(|a, b| a.id.cmp(&b.id));
+    font_files.dedup_by(|a, b| a.id == b.id);
+
+    let keep_thumbnail = thumbnail.is_none()
+        && previous
+            .as_ref()
+            .is_some_and(|p| p.manifest.thumbnail.is_some() && p.has(THUMBNAIL_ENTRY));
+    let manifest = Manifest {
+        format: BUNDLE_FORMAT.to_string(),
+        bundle_version: BUNDLE_VERSION,
+        document: DOCUMENT_ENTRY.to_string(),
+        assets: asset_ids.clone(),
+        fonts: font_files.iter().map(|f| f.id.clone()).collect(),
+        font_faces: font_files
+            .iter()
+            .map(|f| (f.post_script_name.clone(), f.id.clone()))
+            .collect(),
+        thumbnail: (thumbnail.is_some() || keep_thumbnail).then(|| THUMBNAIL_ENTRY.to_string()),
+    };
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    let json = rewrite_sources(json, |id| format!("{}{}", ASSET_PREFIX, id))?;
+
+    let tmp_path = path.with_extension("easel.tmp");
+    let file = File::create(&tmp_path).map_err(|e| format!("Failed to write temp file: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let deflated = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    // Images are already compressed; storing them keeps saves fast.
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    write_entry(&mut zip, MANIFEST_ENTRY, manifest_json.as_bytes(), deflated)?;
+    write_entry(&mut zip, DOCUMENT_ENTRY, json.as_bytes(), deflated)?;
+    if let Some(png) = thumbnail {
+        write_entry(&mut zip, THUMBNAIL_ENTRY, png, stored)?;
+    } else if let Some(previous) = previous.as_mut().filter(|_| keep_thumbnail) {
+        previous.copy(&mut zip, THUMBNAIL_ENTRY)?;
+    }
+    for id in &asset_ids {
+        let name = format!("{}{}", ASSET_PREFIX, id);
+        match previous.as_mut().filter(|p| p.has(&name)) {
+            Some(previous) => previous.copy(&mut zip, &name)?,
+            None => write_entry(&mut zip, &name, &store.read(id)?, stored)?,
+        }
+    }
+    for font in &font_files {
+        let name = format!("{}{}", FONT_PREFIX, font.id);
+        match (&font.bytes, previous.as_mut()) {
+            (Some(bytes), _) => write_entry(&mut zip, &name, bytes, deflated)?,
+            (None, Some(previous)) => previous.copy(&mut zip, &name)?,
+            (None, None) => unreachable!("kept fonts come from a previous bundle"),
+        }
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish bundle: {}", e))?;
+    // Close the old bundle before replacing it
+    drop(previous);
+
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to rename temp file: {}", e))?;
+    Ok(())
+}
+
+/// Point the `src` of every image in the document JSON `json` at `src` of
+/// its asset ID.
+fn rewrite_sources(json: &str, src: impl Fn(&str) -> String) -> Result<String, String> {
+    fn walk(objects: &mut [Value], src: &impl Fn(&str) -> String) {
+        for obj in objects {
+            if let Some(id) = obj.get("assetId").and_then(Value::as_str) {
+                obj["src"] = Value::String(src(id));
+            }
+            if let Some(children) = obj.get_mut("objects").and_then(Value::as_array_mut) {
+                walk(children, src);
+            }
+        }
+    }
+
+    let mut document: Value =
+        serde_json::from_str(json).map_err(|e| format!("Invalid document JSON: {}", e))?;
+    if let Some(objects) = document
+        .pointer_mut("/canvas/objects")
+        .and_then(Value::as_array_mut)
+    {
+        walk(objects, &src);
+    }
+    serde_json::to_string_pretty(&document)
+        .map_err(|e| format!("Failed to serialize document: {}", e))
+}
+
+fn open_archive(path: &Path) -> Result<ZipArchive<File>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    ZipArchive::new(file).map_err(|e| format!("Invalid bundle {}: {}", path.display(), e))
+}
+
+fn read_manifest(archive: &mut ZipArchive<File>, path: &Path) -> Result<Manifest, String> {
+    let manifest: Manifest = serde_json::from_slice(&read_entry(archive, MANIFEST_ENTRY)?)
+        .map_err(|e| format!("Invalid bundle manifest in {}: {}", path.display(), e))?;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err(format!("{} is not an Easel bundle", path.display()));
+    }
+    if manifest.bundle_version > BUNDLE_VERSION {
+        return Err(format!(
+            "{} uses bundle version {}, newer than supported ({})",
+            path.display(),
+            manifest.bundle_version,
+            BUNDLE_VERSION
+        ));
+    }
+    Ok(manifest)
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>, String> {
+    let entry = archive
+        .by_name(name)
+        .map_err(|e| format!("Bundle entry {}: {}", name, e))?;
+    // The size in the header can't be trusted, so read one byte past the limit
+    let mut bytes = Vec::new();
+    entry
+        .take(MAX_ENTRY_SIZE + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read bundle entry {}: {}", name, e))?;
+    if bytes.len() as u64 > MAX_ENTRY_SIZE {
+        return Err(format!(
+            "Bundle entry {} is larger than {} MB",
+            name,
+            MAX_ENTRY_SIZE / (1024 * 1024)
+        ));
+    }
+    Ok(bytes)
+}
+
+fn write_entry(
+    zip: &mut ZipWriter<File>,
+    name: &str,
+    bytes: &[u8],
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    zip.start_file(name, options)
+        .map_err(|e| format!("Failed to add {} to bundle: {}", name, e))?;
+    zip.write_all(bytes)
+        .map_err(|e| format!("Failed to add {} to bundle: {}", name, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::easel::EaselFile;
+    use serde_json::json;
+
+    #[test]
+    fn bundles_point_images_at_their_entries_and_load_without_unpacking() {
+        let dir = std::env::temp_dir().join(format!("easel-bundle-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let plain = dir.join("plain.easel");
+        let packed = dir.join("packed.easel");
+        let store = AssetStore::for_canvas(&plain);
+        let asset = store.add_bytes(b"<svg/>", Some("svg")).unwrap();
+
+        let mut easel = EaselFile::new("Design");
+        easel.objects_mut().push(json!({
+            "id": "g",
+            "type": "Group",
+            "objects": [{
+                "id": "i",
+                "type": "Image",
+                "assetId": asset.id,
+                "src": store.relative_src(&asset.id),
+            }],
+        }));
+        easel.save_bundle(&packed, &store, None).unwrap();
+
+        let mut archive = open_archive(&packed).unwrap();
+        let document: Value =
+            serde_json::from_slice(&read_entry(&mut archive, DOCUMENT_ENTRY).unwrap()).unwrap();
+        let entry = format!("{}{}", ASSET_PREFIX, asset.id);
+        assert_eq!(document["canvas"]["objects"][0]["objects"][0]["src"], entry);
+
+        let loaded = EaselFile::load(&packed).unwrap();
+        let packed_store = AssetStore::for_canvas(&packed);
+        assert_eq!(
+            loaded.objects()[0]["objects"][0]["src"],
+            packed_store.relative_src(&asset.id)
+        );
+        assert!(!packed_store.dir().exists());
+        assert_eq!(packed_store.read(&asset.id).unwrap(), b"<svg/>");
+
+        unpack_assets(&packed, &packed_store).unwrap();
+        assert!(packed_store.dir().join(&asset.id).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
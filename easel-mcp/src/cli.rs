@@ -0,0 +1,398 @@
+//! Command-line subcommands. Without a subcommand the binary serves MCP on stdio.
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use easel_mcp::assets::AssetStore;
+use easel_mcp::attribution::{self, Actor, EditQuery};
+use easel_mcp::diff;
+use easel_mcp::easel::EaselFile;
+use easel_mcp::export::pdf::{self, PdfOptions};
+use easel_mcp::export::ExportScope;
+use easel_mcp::lint::{self, LintConfig, LintRule, Severity};
+use easel_mcp::merge::{self, Side};
+use easel_mcp::oplog::{self, OpKind};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(
+    name = "easel-mcp",
+    version,
+    about = "MCP server and tools for .easel canvas files"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Serve the MCP protocol over stdio (the default).
+    Serve,
+    /// Export a canvas to PDF.
+    ExportPdf {
+        /// The .easel file to export.
+        file: PathBuf,
+        /// Where to write the PDF.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// What to put on the pages.
+        #[arg(long, value_enum, default_value_t = Scope::Canvas)]
+        scope: Scope,
+        /// Frame IDs (scope=frames) or object IDs (scope=selection). Repeatable.
+        #[arg(long = "id")]
+        ids: Vec<String>,
+    },
+    /// Check a canvas against the design lint rules. Exits non-zero on errors.
+    Lint {
+        /// The .easel file to check.
+        file: PathBuf,
+        /// JSON lint config (rules, disable, min_font_size, min_contrast, grid, palette).
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// Only run this rule, overriding the config. Repeatable.
+        #[arg(long = "rule")]
+        rules: Vec<LintRule>,
+        /// Print the full report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show what changed between two versions of a canvas.
+    Diff {
+        /// The earlier version.
+        before: PathBuf,
+        /// The later version.
+        after: PathBuf,
+        /// Print the diff as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// List who changed what in a canvas, from its operation log.
+    Edits {
+        /// The .easel file.
+        file: PathBuf,
+        /// Only edits by this actor: a label, session id or full actor id.
+        #[arg(long)]
+        actor: Option<String>,
+        /// Only changes to this object.
+        #[arg(long)]
+        object: Option<String>,
+        /// Print the batches as JSON, with each object before and after.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Revert one actor's edits to a canvas, leaving later edits by others.
+    Revert {
+        /// The .easel file.
+        file: PathBuf,
+        /// Whose edits to revert: a label, session id or full actor id.
+        actor: String,
+    },
+    /// Rebuild a canvas from its operation log, as of a revision.
+    Replay {
+        /// The .easel file.
+        file: PathBuf,
+        /// Stop after the save that produced this revision (default: the latest).
+        #[arg(long)]
+        revision: Option<u64>,
+        /// Write a new .easel file here instead of printing the canvas JSON.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Three-way merge two edits of a canvas by object id. Exits non-zero
+    /// when both sides changed the same thing.
+    Merge {
+        /// The common ancestor.
+        base: PathBuf,
+        /// Our edit.
+        ours: PathBuf,
+        /// Their edit.
+        theirs: PathBuf,
+        /// Where to write the result (defaults to overwriting ours).
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Which side wins a conflict.
+        #[arg(long, value_enum, default_value_t = Prefer::Ours)]
+        prefer: Prefer,
+        /// Print the conflicts as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Scope {
+    Canvas,
+    Frames,
+    Selection,
+}
+
+impl From<Scope> for ExportScope {
+    fn from(scope: Scope) -> Self {
+        match scope {
+            Scope::Canvas => ExportScope::Canvas,
+            Scope::Frames => ExportScope::Frames,
+            Scope::Selection => ExportScope::Selection,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Prefer {
+    Ours,
+    Theirs,
+}
+
+impl From<Prefer> for Side {
+    fn from(prefer: Prefer) -> Self {
+        match prefer {
+            Prefer::Ours => Side::Ours,
+            Prefer::Theirs => Side::Theirs,
+        }
+    }
+}
+
+/// Run a one-shot subcommand. `Serve` is handled by `main`.
+pub fn run(command: Command) -> Result<()> {
+    match command {
+        Command::Serve => Ok(()),
+        Command::ExportPdf {
+            file,
+            output,
+            scope,
+            ids,
+        } => {
+            let easel = EaselFile::load(&file).map_err(|e| anyhow!(e))?;
+            let options = PdfOptions {
+                scope: scope.into(),
+                ids,
+            };
+            let summary = pdf::write_pdf(
+                &easel.canvas,
+                &options,
+                Some(&AssetStore::for_canvas(&file)),
+                &output,
+            )
+            .map_err(|e| anyhow!(e))?;
+            for warning in &summary.warnings {
+                eprintln!("warning: {}", warning);
+            }
+            println!(
+                "Wrote {} ({} page{})",
+                output.display(),
+                summary.pages,
+                if summary.pages == 1 { "" } else { "s" }
+            );
+            Ok(())
+        }
+        Command::Lint {
+            file,
+            config,
+            rules,
+            json,
+        } => {
+            let easel = EaselFile::load(&file).map_err(|e| anyhow!(e))?;
+            let mut config: LintConfig = match config {
+                Some(path) => serde_json::from_str(&std::fs::read_to_string(&path)?)
+                    .map_err(|e| anyhow!("Invalid lint config {}: {}", path.display(), e))?,
+                None => LintConfig::default(),
+            };
+            if !rules.is_empty() {
+                config.rules = Some(rules);
+            }
+            let report = lint::lint(&easel.canvas, &config).map_err(|e| anyhow!(e))?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                for issue in &report.issues {
+                    println!(
+                        "{:7} {:20} {}  {}",
+                        issue.severity.as_str(),
+                        issue.rule.as_str(),
+                        issue.object_id,
+                        issue.message
+                    );
+                }
+                println!(
+                    "{} issue{} in {} objects",
+                    report.issues.len(),
+                    if report.issues.len() == 1 { "" } else { "s" },
+                    report.checked
+                );
+            }
+
+            let errors = report
+                .issues
+                .iter()
+                .filter(|issue| issue.severity == Severity::Error)
+                .count();
+            if errors > 0 {
+                return Err(anyhow!(
+                    "{} lint error{}",
+                    errors,
+                    if errors == 1 { "" } else { "s" }
+                ));
+            }
+            Ok(())
+        }
+        Command::Diff {
+            before,
+            after,
+            json,
+        } => {
+            let load = |path: &PathBuf| EaselFile::load(path).map_err(|e| anyhow!(e));
+            let diff = diff::diff_files(&load(&before)?, &load(&after)?);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print!("{}", diff::report(&diff));
+            }
+            Ok(())
+        }
+        Command::Edits {
+            file,
+            actor,
+            object,
+            json,
+        } => {
+            let batches = oplog::read(&file).map_err(|e| anyhow!(e))?;
+            let query = EditQuery {
+                actor,
+                object_id: object,
+                since: None,
+            };
+            let edits = attribution::query(&batches, &query, json);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&edits)?);
+                return Ok(());
+            }
+            for batch in &edits {
+                let actor = batch.actor.as_ref().map_or("-".to_string(), Actor::id);
+                for op in &batch.ops {
+                    let target = match (op.kind, &op.object_id, &op.parent) {
+                        (_, Some(id), _) => id.clone(),
+                        (OpKind::Reorder, None, Some(parent)) => format!("children of {}", parent),
+                        (OpKind::Reorder, None, None) => "top level".to_string(),
+                        (_, None, _) => "canvas".to_string(),
+                    };
+                    let properties = if op.properties.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" ({})", op.properties.join(", "))
+                    };
+                    println!(
+                        "{}  {}  r{}  {} {}{}",
+                        batch.at.format("%Y-%m-%d %H:%M:%S"),
+                        actor,
+                        batch.revision,
+                        op.kind.as_str(),
+                        target,
+                        properties
+                    );
+                }
+            }
+            Ok(())
+        }
+        Command::Revert { file, actor } => {
+            let mut easel = EaselFile::load(&file).map_err(|e| anyhow!(e))?;
+            let batches = oplog::read(&file).map_err(|e| anyhow!(e))?;
+            if !batches
+                .iter()
+                .any(|b| b.actor.as_ref().is_some_and(|a| a.matches(&actor)))
+            {
+                return Err(anyhow!("No edits by {} in the operation log", actor));
+            }
+            let report = attribution::revert(&mut easel.canvas, &batches, &actor);
+            if !report.reverted.is_empty() {
+                easel.updated_at = chrono::Utc::now();
+                easel
+                    .save_as(&file, &Actor::user())
+                    .map_err(|e| anyhow!(e))?;
+            }
+            let count = report.reverted.len();
+            println!(
+                "Reverted {} object{}",
+                count,
+                if count == 1 { "" } else { "s" }
+            );
+            for id in &report.skipped {
+                println!("skipped {} (changed by someone else since)", id);
+            }
+            Ok(())
+        }
+        Command::Replay {
+            file,
+            revision,
+            output,
+        } => {
+            let canvas = oplog::replay(&file, revision).map_err(|e| anyhow!(e))?;
+            let Some(output) = output else {
+                println!("{}", serde_json::to_string_pretty(&canvas)?);
+                return Ok(());
+            };
+            if output.exists() {
+                return Err(anyhow!("{} already exists", output.display()));
+            }
+            let name = EaselFile::load(&file)
+                .map(|easel| easel.name)
+                .unwrap_or_else(|_| "Untitled".to_string());
+            let mut easel = EaselFile::new(&name);
+            easel.canvas = canvas;
+            easel.save(&output).map_err(|e| anyhow!(e))?;
+            println!("Wrote {}", output.display());
+            Ok(())
+        }
+        Command::Merge {
+            base,
+            ours,
+            theirs,
+            output,
+            prefer,
+            json,
+        } => {
+            let load = |path: &PathBuf| EaselFile::load(path).map_err(|e| anyhow!(e));
+            let result =
+                merge::merge_files(&load(&base)?, &load(&ours)?, &load(&theirs)?, prefer.into());
+
+            let output = output.unwrap_or(ours);
+            let mut merged = result.merged;
+            merged.revision = EaselFile::load(&output).map_or(0, |existing| existing.revision);
+            merged.save(&output).map_err(|e| anyhow!(e))?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&result.conflicts)?);
+            } else {
+                for conflict in &result.conflicts {
+                    let target = match (&conflict.object_id, &conflict.property) {
+                        (Some(id), Some(property)) => format!("{}.{}", id, property),
+                        (Some(id), None) => id.clone(),
+                        (None, Some(property)) => property.clone(),
+                        (None, None) => "canvas".to_string(),
+                    };
+                    let show = |v: &Option<serde_json::Value>| {
+                        v.as_ref().map_or("(none)".to_string(), |v| v.to_string())
+                    };
+                    println!(
+                        "conflict {} {}: ours={} theirs={} (kept {})",
+                        conflict.kind.as_str(),
+                        target,
+                        show(&conflict.ours),
+                        show(&conflict.theirs),
+                        conflict.resolved.as_str()
+                    );
+                }
+                println!("Wrote {}", output.display());
+            }
+
+            let count = result.conflicts.len();
+            if count > 0 {
+                return Err(anyhow!(
+                    "{} conflict{}",
+                    count,
+                    if count == 1 { "" } else { "s" }
+                ));
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,861 @@
+//! Code generation from a frame's object tree to HTML/CSS or a React component.
+//!
+//! A frame and everything parented to it (via `parentId`, recursively through
+//! nested frames and into group `objects`) is turned into a tree of semantic
+//! elements. Children are placed either absolutely, mirroring the canvas, or in
+//! an inferred flex column/row when they don't overlap along one axis.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+
+use crate::fabric::{self, Bounds};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeTarget {
+    #[default]
+    Html,
+    React,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutMode {
+    #[default]
+    Absolute,
+    Flex,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CodegenOptions {
+    pub target: CodeTarget,
+    pub layout: LayoutMode,
+    /// Emit Tailwind utility classes instead of a separate stylesheet.
+    pub tailwind: bool,
+    /// Component name for React output, in PascalCase. Defaults to the frame name.
+    pub component_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedFile {
+    pub file_name: String,
+    pub contents: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GeneratedCode {
+    pub frame_id: String,
+    pub files: Vec<GeneratedFile>,
+}
+
+/// Generate code for the frame with `frame_id` from a Fabric canvas JSON value.
+pub fn generate(
+    canvas: &Value,
+    frame_id: &str,
+    options: &CodegenOptions,
+) -> Result<GeneratedCode, String> {
+    let objects = canvas
+        .get("objects")
+        .and_then(|v| v.as_array())
+        .map(|v| v.as_slice())
+        .unwrap_or(&[]);
+
+    let frame =
+        find_object(objects, frame_id).ok_or_else(|| format!("Object not found: {}", frame_id))?;
+    if !fabric::is_frame(frame) {
+        return Err(format!("Object {} is not a frame", frame_id));
+    }
+
+    let mut builder = TreeBuilder {
+        objects,
+        layout: options.layout,
+        class_names: HashSet::new(),
+        visited: HashSet::new(),
+    };
+    let root_bounds = fabric::local_bounds(frame);
+    let root = builder.build(frame, root_bounds, true);
+
+    let title = fabric::str_prop(frame, "name").unwrap_or("Frame");
+    // Also names the output files, so keep it to an identifier
+    let component = pascal_case(options.component_name.as_deref().unwrap_or(title));
+
+    let mut emitter = Emitter {
+        jsx: options.target == CodeTarget::React,
+        tailwind: options.tailwind,
+        markup: String::new(),
+        css: String::new(),
+    };
+    let markup_indent = if emitter.jsx { 2 } else { 1 };
+    emitter.emit(&root, Placement::Root, markup_indent);
+
+    let files = match options.target {
+        CodeTarget::Html => {
+            let head = if options.tailwind {
+                "  <script src=\"https://cdn.tailwindcss.com\"></script>\n".to_string()
+            } else {
+                "  <link rel=\"stylesheet\" href=\"styles.css\" />\n".to_string()
+            };
+            let mut html = String::new();
+            html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n");
+            html.push_str("  <meta charset=\"utf-8\" />\n");
+            html.push_str(
+                "  <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\" />\n",
+            );
+            let _ = writeln!(html, "  <title>{}</title>", escape_html(title));
+            html.push_str(&head);
+            html.push_str("</head>\n<body>\n");
+            html.push_str(&emitter.markup);
+            html.push_str("</body>\n</html>\n");
+
+            let mut files = vec![GeneratedFile {
+                file_name: "index.html".to_string(),
+                contents: html,
+            }];
+            if !options.tailwind {
+                files.push(GeneratedFile {
+                    file_name: "styles.css".to_string(),
+                    contents: emitter.css,
+                });
+            }
+            files
+        }
+        CodeTarget::React => {
+            let mut tsx = String::new();
+            if !options.tailwind {
+                let _ = writeln!(tsx, "import \"./{}.css\";\n", component);
+            }
+            let _ = writeln!(tsx, "export default function {}() {{", component);
+            tsx.push_str("  return (\n");
+            tsx.push_str(&emitter.markup);
+            tsx.push_str("  );\n}\n");
+
+            let mut files = vec![GeneratedFile {
+                file_name: format!("{}.tsx", component),
+                contents: tsx,
+            }];
+            if !options.tailwind {
+                files.push(GeneratedFile {
+                    file_name: format!("{}.css", component),
+                    contents: emitter.css,
+                });
+            }
+            files
+        }
+    };
+
+    Ok(GeneratedCode {
+        frame_id: frame_id.to_string(),
+        files,
+    })
+}
+
+/// The object with `id`, searching into groups.
+fn find_object<'a>(objects: &'a [Value], id: &str) -> Option<&'a Value> {
+    objects.iter().find_map(|obj| {
+        if fabric::object_id(obj) == Some(id) {
+            return Some(obj);
+        }
+        let children = obj.get("objects").and_then(|v| v.as_array())?;
+        find_object(children, id)
+    })
+}
+
+// ── Tree building ───────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Flow {
+    Absolute,
+    Column,
+    Row,
+}
+
+struct Node<'a> {
+    obj: &'a Value,
+    tag: &'static str,
+    class_name: String,
+    /// Bounds relative to the parent's top-left corner.
+    bounds: Bounds,
+    flow: Flow,
+    padding: (f64, f64),
+    gap: f64,
+    /// Offsets applied as margins when the parent is a flex container:
+    /// (main axis, cross axis).
+    margins: (f64, f64),
+    children: Vec<Node<'a>>,
+}
+
+struct TreeBuilder<'a> {
+    objects: &'a [Value],
+    layout: LayoutMode,
+    class_names: HashSet<String>,
+    visited: HashSet<String>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    fn build(&mut self, obj: &'a Value, bounds: Bounds, root: bool) -> Node<'a> {
+        let mut children = Vec::new();
+
+        let id = fabric::object_id(obj).unwrap_or("");
+        let first_visit = id.is_empty() || self.visited.insert(id.to_string());
+
+        if first_visit && fabric::is_frame(obj) && !id.is_empty() {
+            let frame_abs = fabric::local_bounds(obj);
+            for child in fabric::children_of(self.objects, id) {
+                if !is_visible(child) {
+                    continue;
+                }
+                let child_bounds = fabric::local_bounds(child).relative_to(&frame_abs);
+                children.push(self.build(child, child_bounds, false));
+            }
+        } else if let Some(group_children) = obj.get("objects").and_then(|v| v.as_array()) {
+            // Group children are positioned relative to the group's center.
+            let group_box = fabric::local_bounds(obj);
+            let center = Bounds {
+                left: -group_box.width / 2.0,
+                top: -group_box.height / 2.0,
+                width: group_box.width,
+                height: group_box.height,
+            };
+            for child in group_children {
+                if !is_visible(child) {
+                    continue;
+                }
+                let child_bounds = fabric::local_bounds(child).relative_to(&center);
+                children.push(self.build(child, child_bounds, false));
+            }
+        }
+
+        let class_name = self.unique_class(obj);
+        let mut node = Node {
+            obj,
+            tag: semantic_tag(obj, root),
+            class_name,
+            bounds,
+            flow: Flow::Absolute,
+            padding: (0.0, 0.0),
+            gap: 0.0,
+            margins: (0.0, 0.0),
+            children,
+        };
+
+        if self.layout == LayoutMode::Flex && !node.children.is_empty() {
+            infer_flow(&mut node);
+        }
+
+        node
+    }
+
+    fn unique_class(&mut self, obj: &Value) -> String {
+        let base_name = fabric::str_prop(obj, "name")
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| fabric::object_type(obj));
+        let mut base = kebab_case(&base_name);
+        if base.is_empty() || !base.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            base = format!("el-{}", base).trim_end_matches('-').to_string();
+        }
+
+        let mut candidate = base.clone();
+        let mut n = 2;
+        while !self.class_names.insert(candidate.clone()) {
+            candidate = format!("{}-{}", base, n);
+            n += 1;
+        }
+        candidate
+    }
+}
+
+/// Decide whether a container's children form a column or a row. Children
+/// must not overlap along the main axis; any irregular spacing is preserved
+/// through per-child margins so the result matches the canvas exactly.
+fn infer_flow(node: &mut Node<'_>) {
+    const EPS: f64 = 0.5;
+
+    let fits = |children: &[Node<'_>], main: fn(&Bounds) -> (f64, f64)| {
+        let mut spans: Vec<(f64, f64)> = children.iter().map(|c| main(&c.bounds)).collect();
+        spans.sort_by(|a, b| a.0.total_cmp(&b.0));
+        spans.windows(2).all(|w| w[1].0 >= w[0].1 - EPS)
+    };
+
+    let vertical = |b: &Bounds| (b.top, b.bottom());
+    let horizontal = |b: &Bounds| (b.left, b.right());
+
+    let flow = if node.children.len() == 1 || fits(&node.children, vertical) {
+        Flow::Column
+    } else if fits(&node.children, horizontal) {
+        Flow::Row
+    } else {
+        return;
+    };
+
+    let main_of = |b: &Bounds| match flow {
+        Flow::Row => (b.left, b.right()),
+        _ => (b.top, b.bottom()),
+    };
+    let cross_of = |b: &Bounds| match flow {
+        Flow::Row => b.top,
+        _ => b.left,
+    };
+
+    node.children
+        .sort_by(|a, b| main_of(&a.bounds).0.total_cmp(&main_of(&b.bounds).0));
+
+    let first_main = main_of(&node.children[0].bounds).0;
+    let min_cross = node
+        .children
+        .iter()
+        .map(|c| cross_of(&c.bounds))
+        .fold(f64::INFINITY, f64::min);
+
+    let gaps: Vec<f64> = node
+        .children
+        .windows(2)
+        .map(|w| main_of(&w[1].bounds).0 - main_of(&w[0].bounds).1)
+        .collect();
+    let uniform_gap = match gaps.first() {
+        Some(&g) if gaps.iter().all(|x| (x - g).abs() <= EPS) => g,
+        _ => 0.0,
+    };
+
+    let mut prev_end = first_main;
+    for (i, child) in node.children.iter_mut().enumerate() {
+        let (start, end) = main_of(&child.bounds);
+        let main_margin = if i == 0 {
+            0.0
+        } else {
+            start - prev_end - uniform_gap
+        };
+        child.margins = (main_margin, cross_of(&child.bounds) - min_cross);
+        prev_end = end;
+    }
+
+    node.flow = flow;
+    node.gap = uniform_gap;
+    node.padding = match flow {
+        Flow::Row => (min_cross, first_main),
+        _ => (first_main, min_cross),
+    };
+}
+
+fn is_visible(obj: &Value) -> bool {
+    obj.get("visible").and_then(|v| v.as_bool()).unwrap_or(true)
+}
+
+fn semantic_tag(obj: &Value, root: bool) -> &'static str {
+    let name = fabric::str_prop(obj, "name")
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    if root {
+        return "section";
+    }
+    if fabric::is_text(obj) {
+        let size = fabric::num_prop(obj, "fontSize").unwrap_or(16.0)
+            * fabric::num_prop(obj, "scaleY").unwrap_or(1.0);
+        return if size >= 32.0 {
+            "h1"
+        } else if size >= 24.0 {
+            "h2"
+        } else if size >= 20.0 {
+            "h3"
+        } else {
+            "p"
+        };
+    }
+    if fabric::object_type(obj) == "image" {
+        return "img";
+    }
+    if name.contains("button") || name.starts_with("btn") {
+        return "button";
+    }
+    if fabric::is_frame(obj) {
+        if name.contains("header") {
+            return "header";
+        }
+        if name.contains("footer") {
+            return "footer";
+        }
+        if name.contains("nav") {
+            return "nav";
+        }
+        return "div";
+    }
+    "div"
+}
+
+// ── Emitting ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Placement {
+    Root,
+    Absolute,
+    Flex(Flow),
+}
+
+struct Emitter {
+    jsx: bool,
+    tailwind: bool,
+    markup: String,
+    css: String,
+}
+
+impl Emitter {
+    fn emit(&mut self, node: &Node<'_>, placement: Placement, indent: usize) {
+        let decls = declarations(node, placement);
+        let pad = "  ".repeat(indent);
+        let class_attr = if self.jsx { "className" } else { "class" };
+
+        let classes = if self.tailwind {
+            decls
+                .iter()
+                .map(|(p, v)| tailwind_class(p, v))
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            if !decls.is_empty() {
+                let _ = writeln!(self.css, ".{} {{", node.class_name);
+                for (prop, value) in &decls {
+                    let _ = writeln!(self.css, "  {}: {};", prop, value);
+                }
+                self.css.push_str("}\n\n");
+            }
+            node.class_name.clone()
+        };
+
+        let mut attrs = format!(" {}=\"{}\"", class_attr, classes);
+        if let Some(id) = fabric::object_id(node.obj) {
+            let _ = write!(attrs, " data-easel-id=\"{}\"", escape_html(id));
+        }
+
+        if node.tag == "img" {
+            let src = fabric::str_prop(node.obj, "src").unwrap_or("");
+            let alt = fabric::str_prop(node.obj, "name").unwrap_or("");
+            let close = if self.jsx { " />" } else { ">" };
+            let _ = writeln!(
+                self.markup,
+                "{}<img{} src=\"{}\" alt=\"{}\"{}",
+                pad,
+                attrs,
+                escape_html(src),
+                escape_html(alt),
+                close
+            );
+            return;
+        }
+
+        if fabric::is_text(node.obj) {
+            let text = fabric::str_prop(node.obj, "text").unwrap_or("");
+            let br = if self.jsx { "<br />" } else { "<br>" };
+            let body = text
+                .split('\n')
+                .map(|line| self.escape_text(line))
+                .collect::<Vec<_>>()
+                .join(br);
+            let _ = writeln!(
+                self.markup,
+                "{}<{}{}>{}</{}>",
+                pad, node.tag, attrs, body, node.tag
+            );
+            return;
+        }
+
+        if node.children.is_empty() {
+            let _ = writeln!(self.markup, "{}<{}{}></{}>", pad, node.tag, attrs, node.tag);
+            return;
+        }
+
+        let _ = writeln!(self.markup, "{}<{}{}>", pad, node.tag, attrs);
+        let child_placement = match node.flow {
+            Flow::Absolute => Placement::Absolute,
+            flow => Placement::Flex(flow),
+        };
+        for child in &node.children {
+            self.emit(child, child_placement, indent + 1);
+        }
+        let _ = writeln!(self.markup, "{}</{}>", pad, node.tag);
+    }
+
+    fn escape_text(&self, text: &str) -> String {
+        if self.jsx && text.contains(['{', '}', '<', '>']) {
+            return format!("{{{}}}", Value::String(text.to_string()));
+        }
+        escape_html(text)
+    }
+}
+
+fn declarations(node: &Node<'_>, placement: Placement) -> Vec<(&'static str, String)> {
+    let obj = node.obj;
+    let b = &node.bounds;
+    let mut d: Vec<(&'static str, String)> = Vec::new();
+    let text = fabric::is_text(obj);
+
+    match placement {
+        Placement::Root => {
+            d.push(("position", "relative".into()));
+        }
+        Placement::Absolute => {
+            d.push(("position", "absolute".into()));
+            d.push(("left", px(b.left)));
+            d.push(("top", px(b.top)));
+        }
+        Placement::Flex(flow) => {
+            let (main, cross) = node.margins;
+            let (main_prop, cross_prop) = match flow {
+                Flow::Row => ("margin-left", "margin-top"),
+                _ => ("margin-top", "margin-left"),
+            };
+            if main.abs() > 0.005 {
+                d.push((main_prop, px(main)));
+            }
+            if cross.abs() > 0.005 {
+                d.push((cross_prop, px(cross)));
+            }
+            d.push(("flex-shrink", "0".into()));
+        }
+    }
+
+    let textbox = fabric::object_type(obj) == "textbox";
+    if !text || textbox {
+        d.push(("width", px(b.width)));
+    }
+    if !text {
+        d.push(("height", px(b.height)));
+    }
+
+    if !node.children.is_empty() {
+        match node.flow {
+            Flow::Absolute => {
+                if placement != Placement::Root && placement != Placement::Absolute {
+                    d.push(("position", "relative".into()));
+                }
+            }
+            flow => {
+                d.push(("display", "flex".into()));
+                let dir = if flow == Flow::Row { "row" } else { "column" };
+                d.push(("flex-direction", dir.into()));
+                d.push(("align-items", "flex-start".into()));
+                if node.gap > 0.005 {
+                    d.push(("gap", px(node.gap)));
+                }
+                if node.padding.0 > 0.005 {
+                    d.push(("padding-top", px(node.padding.0)));
+                }
+                if node.padding.1 > 0.005 {
+                    d.push(("padding-left", px(node.padding.1)));
+                }
+                d.push(("box-sizing", "border-box".into()));
+            }
+        }
+        if fabric::is_frame(obj) {
+            d.push(("overflow", "hidden".into()));
+        }
+    }
+
+    let fill = fabric::str_prop(obj, "fill").filter(|f| !f.is_empty());
+    if text {
+        if let Some(fill) = fill {
+            d.push(("color", fill.to_string()));
+        }
+        if let Some(size) = fabric::num_prop(obj, "fontSize") {
+            d.push((
+                "font-size",
+                px(size * fabric::num_prop(obj, "scaleY").unwrap_or(1.0)),
+            ));
+        }
+        if let Some(family) = fabric::str_prop(obj, "fontFamily") {
+            d.push(("font-family", family.to_string()));
+        }
+        match obj.get("fontWeight") {
+            Some(Value::String(w)) if w != "normal" && w != "400" => {
+                d.push(("font-weight", w.clone()))
+            }
+            Some(Value::Number(w)) if w.as_f64() != Some(400.0) => {
+                d.push(("font-weight", w.to_string()))
+            }
+            _ => {}
+        }
+        if fabric::str_prop(obj, "fontStyle") == Some("italic") {
+            d.push(("font-style", "italic".into()));
+        }
+        if let Some(align) = fabric::str_prop(obj, "textAlign").filter(|a| *a != "left") {
+            d.push(("text-align", align.to_string()));
+        }
+        if let Some(lh) = fabric::num_prop(obj, "lineHeight") {
+            d.push(("line-height", num(lh)));
+        }
+        if let Some(cs) = fabric::num_prop(obj, "charSpacing").filter(|c| *c != 0.0) {
+            d.push(("letter-spacing", format!("{}em", num(cs / 1000.0))));
+        }
+        if fabric::bool_prop(obj, "underline") {
+            d.push(("text-decoration", "underline".into()));
+        }
+        d.push(("margin", "0".into()));
+        if !textbox {
+            d.push(("white-space", "pre".into()));
+        }
+    } else {
+        if let Some(fill) = fill {
+            d.push(("background-color", fill.to_string()));
+        }
+        let stroke_width = fabric::num_prop(obj, "strokeWidth").unwrap_or(0.0);
+        if let Some(stroke) = fabric::str_prop(obj, "stroke").filter(|s| !s.is_empty()) {
+            if stroke_width > 0.0 {
+                d.push(("border", format!("{} solid {}", px(stroke_width), stroke)));
+                d.push(("box-sizing", "border-box".into()));
+            }
+        }
+        match fabric::object_type(obj).as_str() {
+            "ellipse" | "circle" => d.push(("border-radius", "50%".into())),
+            _ => {
+                if let Some(rx) = fabric::num_prop(obj, "rx").filter(|r| *r > 0.0) {
+                    d.push(("border-radius", px(rx)));
+                }
+            }
+        }
+    }
+
+    if let Some(opacity) = fabric::num_prop(obj, "opacity").filter(|o| *o < 1.0) {
+        d.push(("opacity", num(opacity)));
+    }
+    if let Some(angle) = fabric::num_prop(obj, "angle").filter(|a| a.abs() > 0.005) {
+        let ox = fabric::origin_factor(obj.get("originX")) * 100.0;
+        let oy = fabric::origin_factor(obj.get("originY")) * 100.0;
+        d.push(("transform", format!("rotate({}deg)", num(angle))));
+        d.push(("transform-origin", format!("{}% {}%", num(ox), num(oy))));
+    }
+
+    // Later declarations win in CSS; keep only the last occurrence of each property.
+    let mut seen = HashSet::new();
+    let mut deduped: Vec<(&'static str, String)> = d
+        .into_iter()
+        .rev()
+        .filter(|(p, _)| seen.insert(*p))
+        .collect();
+    deduped.reverse();
+    deduped
+}
+
+fn tailwind_class(prop: &str, value: &str) -> String {
+    let arb = value.replace(' ', "_");
+    match (prop, value) {
+        ("position", v) => v.to_string(),
+        ("display", "flex") => "flex".to_string(),
+        ("flex-direction", "column") => "flex-col".to_string(),
+        ("flex-direction", "row") => "flex-row".to_string(),
+        ("align-items", "flex-start") => "items-start".to_string(),
+        ("flex-shrink", "0") => "shrink-0".to_string(),
+        ("overflow", "hidden") => "overflow-hidden".to_string(),
+        ("box-sizing", "border-box") => "box-border".to_string(),
+        ("font-style", "italic") => "italic".to_string(),
+        ("text-decoration", "underline") => "underline".to_string(),
+        ("white-space", "pre") => "whitespace-pre".to_string(),
+        ("margin", "0") => "m-0".to_string(),
+        ("text-align", v) => format!("text-{}", v),
+        ("border-radius", "50%") => "rounded-full".to_string(),
+        ("left", _) => format!("left-[{}]", arb),
+        ("top", _) => format!("top-[{}]", arb),
+        ("width", _) => format!("w-[{}]", arb),
+        ("height", _) => format!("h-[{}]", arb),
+        ("gap", _) => format!("gap-[{}]", arb),
+        ("padding-top", _) => format!("pt-[{}]", arb),
+        ("padding-left", _) => format!("pl-[{}]", arb),
+        ("margin-top", _) => format!("mt-[{}]", arb),
+        ("margin-left", _) => format!("ml-[{}]", arb),
+        ("background-color", _) => format!("bg-[{}]", arb),
+        ("color", _) => format!("text-[color:{}]", arb),
+        ("font-size", _) => format!("text-[length:{}]", arb),
+        ("font-weight", _) => format!("font-[{}]", arb),
+        ("line-height", _) => format!("leading-[{}]", arb),
+        ("letter-spacing", _) => format!("tracking-[{}]", arb),
+        ("border-radius", _) => format!("rounded-[{}]", arb),
+        ("opacity", _) => format!("opacity-[{}]", arb),
+        _ => format!("[{}:{}]", prop, arb),
+    }
+}
+
+// ── Formatting helpers ──────────────────────────────────────────────────────
+
+fn num(v: f64) -> String {
+    let rounded = (v * 100.0).round() / 100.0;
+    // Avoid printing "-0"
+    format!("{}", rounded + 0.0)
+}
+
+fn px(v: f64) -> String {
+    let n = num(v);
+    if n == "0" {
+        n
+    } else {
+        format!("{}px", n)
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn words(s: &str) -> Vec<String> {
+    s.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_string())
+        .collect()
+}
+
+fn kebab_case(s: &str) -> String {
+    words(s)
+        .iter()
+        .map(|w| w.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn pascal_case(s: &str) -> String {
+    let name: String = words(s)
+        .iter()
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("Frame{}", name)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn frame(children: Vec<Value>) -> Value {
+        let mut objects = vec![json!({
+            "id": "f", "type": "Rect", "isFrame": true, "name": "Card",
+            "left": 0, "top": 0, "width": 200, "height": 200,
+        })];
+        objects.extend(children);
+        json!({ "objects": objects })
+    }
+
+    fn rect(id: &str, left: f64, top: f64) -> Value {
+        json!({
+            "id": id, "type": "Rect", "parentId": "f",
+            "left": left, "top": top, "width": 40, "height": 20,
+        })
+    }
+
+    fn file(canvas: &Value, options: &CodegenOptions, name: &str) -> String {
+        let code = generate(canvas, "f", options).unwrap();
+        code.files
+            .into_iter()
+            .find(|f| f.file_name == name)
+            .unwrap()
+            .contents
+    }
+
+    fn flex() -> CodegenOptions {
+        CodegenOptions {
+            layout: LayoutMode::Flex,
+            ..CodegenOptions::default()
+        }
+    }
+
+    #[test]
+    fn stacked_children_form_a_column() {
+        let canvas = frame(vec![rect("b", 10.0, 50.0), rect("a", 10.0, 10.0)]);
+        let css = file(&canvas, &flex(), "styles.css");
+        assert!(css.contains("flex-direction: column;"), "{}", css);
+        assert!(css.contains("gap: 20px;"), "{}", css);
+        assert!(css.contains("padding-top: 10px;"), "{}", css);
+        // Children follow the main axis, whatever their order in the canvas
+        let html = file(&canvas, &flex(), "index.html");
+        assert!(html.find("\"a\"").unwrap() < html.find("\"b\"").unwrap());
+    }
+
+    #[test]
+    fn side_by_side_children_form_a_row() {
+        let canvas = frame(vec![rect("a", 10.0, 10.0), rect("b", 80.0, 16.0)]);
+        let css = file(&canvas, &flex(), "styles.css");
+        assert!(css.contains("flex-direction: row;"), "{}", css);
+        // The lower child keeps its offset as a cross-axis margin
+        assert!(css.contains("margin-top: 6px;"), "{}", css);
+    }
+
+    #[test]
+    fn overlapping_children_stay_absolute() {
+        let canvas = frame(vec![rect("a", 10.0, 10.0), rect("b", 30.0, 20.0)]);
+        let css = file(&canvas, &flex(), "styles.css");
+        assert!(!css.contains("display: flex"), "{}", css);
+        assert!(css.contains("position: absolute;"), "{}", css);
+    }
+
+    #[test]
+    fn maps_declarations_to_tailwind_classes() {
+        assert_eq!(tailwind_class("display", "flex"), "flex");
+        assert_eq!(tailwind_class("flex-direction", "column"), "flex-col");
+        assert_eq!(tailwind_class("width", "120px"), "w-[120px]");
+        assert_eq!(tailwind_class("color", "#333"), "text-[color:#333]");
+        assert_eq!(tailwind_class("border-radius", "50%"), "rounded-full");
+        assert_eq!(
+            tailwind_class("border", "1px solid #000"),
+            "[border:1px_solid_#000]"
+        );
+
+        let options = CodegenOptions {
+            tailwind: true,
+            ..flex()
+        };
+        let code = generate(&frame(vec![rect("a", 0.0, 0.0)]), "f", &options).unwrap();
+        assert_eq!(code.files.len(), 1);
+        assert!(code.files[0]
+            .contents
+            .contains("class=\"relative w-[200px]"));
+    }
+
+    #[test]
+    fn escapes_text_and_attributes() {
+        let canvas = frame(vec![
+            json!({
+                "id": "t", "type": "Textbox", "parentId": "f",
+                "left": 0, "top": 0, "width": 100, "text": "a < b & {c}",
+            }),
+            json!({
+                "id": "i\"d", "type": "Image", "parentId": "f", "name": "Say \"hi\"",
+                "src": "a.png?x=1&y=2", "left": 0, "top": 50, "width": 10, "height": 10,
+            }),
+        ]);
+
+        let html = file(&canvas, &CodegenOptions::default(), "index.html");
+        assert!(html.contains(">a &lt; b &amp; {c}</p>"), "{}", html);
+        assert!(html.contains("data-easel-id=\"i&quot;d\""), "{}", html);
+        assert!(html.contains("src=\"a.png?x=1&amp;y=2\" alt=\"Say &quot;hi&quot;\">"));
+
+        let react = CodegenOptions {
+            target: CodeTarget::React,
+            ..CodegenOptions::default()
+        };
+        let tsx = file(&canvas, &react, "Card.tsx");
+        // Braces and angle brackets would be read as JSX; quote the line instead
+        assert!(tsx.contains(">{\"a < b & {c}\"}</p>"), "{}", tsx);
+        assert!(tsx.contains("alt=\"Say &quot;hi&quot;\" />"), "{}", tsx);
+    }
+}
@@ -0,0 +1,213 @@
+//! CSS color parsing and formatting for Fabric `fill`/`stroke` strings.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    /// Alpha in `0.0..=1.0`.
+    pub a: f64,
+}
+
+impl Rgba {
+    pub const BLACK: Rgba = Rgba::rgb(0, 0, 0);
+    pub const WHITE: Rgba = Rgba::rgb(255, 255, 255);
+    pub const TRANSPARENT: Rgba = Rgba {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 0.0,
+    };
+
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 1.0 }
+    }
+
+    /// Parse hex (`#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`), `rgb()`/`rgba()`,
+    /// `transparent` and the common named colors.
+    pub fn parse(input: &str) -> Option<Rgba> {
+        let s = input.trim().to_ascii_lowercase();
+        if let Some(hex) = s.strip_prefix('#') {
+            return parse_hex(hex);
+        }
+        if let Some(args) = s
+            .strip_prefix("rgba(")
+            .or_else(|| s.strip_prefix("rgb("))
+            .and_then(|rest| rest.strip_suffix(')'))
+        {
+            return parse_rgb_args(args);
+        }
+        named(&s)
+    }
+
+    pub fn with_alpha(self, a: f64) -> Rgba {
+        Rgba {
+            a: a.clamp(0.0, 1.0),
+            ..self
+        }
+    }
+
+    /// `#rrggbb` when opaque, `rgba(...)` otherwise.
+    pub fn to_css(&self) -> String {
+        if self.a >= 1.0 {
+            self.to_hex()
+        } else {
+            format!(
+                "rgba({},{},{},{})",
+                self.r,
+                self.g,
+                self.b,
+                (self.a * 1000.0).round() / 1000.0
+            )
+        }
+    }
+
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Source-over composite of `self` on top of an opaque `background`.
+    pub fn over(&self, background: Rgba) -> Rgba {
+        let blend = |fg: u8, bg: u8| -> u8 {
+            (fg as f64 * self.a + bg as f64 * (1.0 - self.a)).round() as u8
+        };
+        Rgba::rgb(
+            blend(self.r, background.r),
+            blend(self.g, background.g),
+            blend(self.b, background.b),
+        )
+    }
+
+    /// WCAG 2.x relative luminance.
+    pub fn relative_luminance(&self) -> f64 {
+        let channel = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * channel(self.r) + 0.7152 * channel(self.g) + 0.0722 * channel(self.b)
+    }
+
+    /// WCAG 2.x contrast ratio between two opaque colors (1.0 to 21.0).
+    pub fn contrast_ratio(&self, other: &Rgba) -> f64 {
+        let l1 = self.relative_luminance();
+        let l2 = other.relative_luminance();
+        let (hi, lo) = if l1 > l2 { (l1, l2) } else { (l2, l1) };
+        (hi + 0.05) / (lo + 0.05)
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Rgba> {
+    let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+    let pair = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    match hex.len() {
+        3 | 4 => {
+            let r = digit(0)? * 17;
+            let g = digit(1)? * 17;
+            let b = digit(2)? * 17;
+            let a = if hex.len() == 4 {
+                digit(3)? as f64 * 17.0 / 255.0
+            } else {
+                1.0
+            };
+            Some(Rgba { r, g, b, a })
+        }
+        6 | 8 => {
+            let a = if hex.len() == 8 {
+                pair(6)? as f64 / 255.0
+            } else {
+                1.0
+            };
+            Some(Rgba {
+                r: pair(0)?,
+                g: pair(2)?,
+                b: pair(4)?,
+                a,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn parse_rgb_args(args: &str) -> Option<Rgba> {
+    let parts: Vec<&str> = args
+        .split([',', ' ', '/'])
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if parts.len() < 3 {
+        return None;
+    }
+    let channel = |p: &str| -> Option<u8> {
+        if let Some(pct) = p.strip_suffix('%') {
+            Some((pct.parse::<f64>().ok()? * 2.55).round().clamp(0.0, 255.0) as u8)
+        } else {
+            Some(p.parse::<f64>().ok()?.round().clamp(0.0, 255.0) as u8)
+        }
+    };
+    let a = match parts.get(3) {
+        Some(p) => match p.strip_suffix('%') {
+            Some(pct) => pct.parse::<f64>().ok()? / 100.0,
+            None => p.parse::<f64>().ok()?,
+        },
+        None => 1.0,
+    };
+    Some(Rgba {
+        r: channel(parts[0])?,
+        g: channel(parts[1])?,
+        b: channel(parts[2])?,
+        a: a.clamp(0.0, 1.0),
+    })
+}
+
+fn named(name: &str) -> Option<Rgba> {
+    let rgb = match name {
+        "transparent" => return Some(Rgba::TRANSPARENT),
+        "black" => (0, 0, 0),
+        "white" => (255, 255, 255),
+        "red" => (255, 0, 0),
+        "green" => (0, 128, 0),
+        "lime" => (0, 255, 0),
+        "blue" => (0, 0, 255),
+        "yellow" => (255, 255, 0),
+        "cyan" | "aqua" => (0, 255, 255),
+        "magenta" | "fuchsia" => (255, 0, 255),
+        "gray" | "grey" => (128, 128, 128),
+        "silver" => (192, 192, 192),
+        "lightgray" | "lightgrey" => (211, 211, 211),
+        "darkgray" | "darkgrey" => (169, 169, 169),
+        "maroon" => (128, 0, 0),
+        "olive" => (128, 128, 0),
+        "navy" => (0, 0, 128),
+        "purple" => (128, 0, 128),
+        "teal" => (0, 128, 128),
+        "orange" => (255, 165, 0),
+        "pink" => (255, 192, 203),
+        "brown" => (165, 42, 42),
+        "gold" => (255, 215, 0),
+        "indigo" => (75, 0, 130),
+        "violet" => (238, 130, 238),
+        "coral" => (255, 127, 80),
+        "salmon" => (250, 128, 114),
+        "tomato" => (255, 99, 71),
+        "crimson" => (220, 20, 60),
+        "khaki" => (240, 230, 140),
+        "beige" => (245, 245, 220),
+        "ivory" => (255, 255, 240),
+        "whitesmoke" => (245, 245, 245),
+        "gainsboro" => (220, 220, 220),
+        "skyblue" => (135, 206, 235),
+        "steelblue" => (70, 130, 180),
+        "royalblue" => (65, 105, 225),
+        "dodgerblue" => (30, 144, 255),
+        "darkblue" => (0, 0, 139),
+        "darkgreen" => (0, 100, 0),
+        "darkred" => (139, 0, 0),
+        "slategray" | "slategrey" => (112, 128, 144),
+        _ => return None,
+    };
+    Some(Rgba::rgb(rgb.0, rgb.1, rgb.2))
+}
@@ -0,0 +1,817 @@
+//! CRDT backing for canvas documents, so concurrent edits converge.
+//!
+//! Every save of an `.easel` file is also recorded as changes in an Automerge
+//! log kept next to it (`<file>.crdt`). A writer that says which state its
+//! edit started from (the `heads` stored in the file) has the edit merged with
+//! whatever other writers committed since, instead of overwriting them. The
+//! log is the local channel the app and easel-mcp processes share; a
+//! [`CanvasDoc`] can also exchange changes with a peer over the Automerge sync
+//! protocol.
+//!
+//! Layout: the document name at `name`; canvas-level properties in the
+//! `canvas` map; every object, at any depth, in the `objects` map keyed by id;
+//! the top-level stacking order in the `order` list of ids, and a group's
+//! children in an `objects` list of ids inside the group. Property values are
+//! scalars; arrays and maps (path data, gradients, ...) are stored as JSON
+//! bytes so concurrent edits replace them whole rather than interleaving.
+
+use automerge::sync::SyncDoc;
+use automerge::transaction::Transactable;
+use automerge::{AutoCommit, ChangeHash, ObjId, ObjType, ReadDoc, ScalarValue, ROOT};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+pub use automerge::sync::State as SyncState;
+
+use crate::bundle;
+use crate::diff::longest_increasing;
+use crate::fabric;
+
+const NAME: &str = "name";
+const CANVAS: &str = "canvas";
+const OBJECTS: &str = "objects";
+const ORDER: &str = "order";
+
+/// Past this size a save rewrites the log as one encoded document rather than
+/// appending its changes. That only packs the encoding tighter; every change
+/// is still kept.
+const REWRITE_BYTES: usize = 1 << 20;
+
+/// Object in a flattened canvas: id, properties, and child ids for groups.
+type Flat<'a> = (&'a str, &'a Map<String, Value>, Option<Vec<String>>);
+
+pub struct CanvasDoc {
+    doc: AutoCommit,
+}
+
+impl Default for CanvasDoc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CanvasDoc {
+    /// An empty document. Replicas of one canvas must all descend from the
+    /// same empty document, so create it once and pass it on with
+    /// [`save`](Self::save) rather than calling this on each side.
+    pub fn new() -> Self {
+        let mut doc = AutoCommit::new();
+        doc.put(ROOT, NAME, "").expect("fresh document");
+        for (key, kind) in [
+            (CANVAS, ObjType::Map),
+            (OBJECTS, ObjType::Map),
+            (ORDER, ObjType::List),
+        ] {
+            doc.put_object(ROOT, key, kind).expect("fresh document");
+        }
+        Self { doc }
+    }
+
+    pub fn load(bytes: &[u8]) -> Result<Self, String> {
+        AutoCommit::load(bytes)
+            .map(|doc| Self { doc })
+            .map_err(crdt_error)
+    }
+
+    pub fn save(&mut self) -> Vec<u8> {
+        self.doc.save()
+    }
+
+    /// Changes made since `heads`, in the form [`apply_changes`](Self::apply_changes) takes.
+    pub fn changes_since(&mut self, heads: &[ChangeHash]) -> Vec<u8> {
+        self.doc.save_after(heads)
+    }
+
+    pub fn apply_changes(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.doc
+            .load_incremental(bytes)
+            .map(|_| ())
+            .map_err(crdt_error)
+    }
+
+    pub fn heads(&mut self) -> Vec<ChangeHash> {
+        self.doc.get_heads()
+    }
+
+    /// Whether every change in `heads` is known here.
+    pub fn has_heads(&mut self, heads: &[ChangeHash]) -> bool {
+        heads
+            .iter()
+            .all(|h| self.doc.get_change_by_hash(h).is_some())
+    }
+
+    /// A copy to edit separately and [`merge`](Self::merge) back.
+    pub fn fork(&mut self) -> Self {
+        Self {
+            doc: self.doc.fork(),
+        }
+    }
+
+    /// Like [`fork`](Self::fork), but of the document as it was at `heads`.
+    pub fn fork_at(&mut self, heads: &[ChangeHash]) -> Result<Self, String> {
+        self.doc
+            .fork_at(heads)
+            .map(|doc| Self { doc })
+            .map_err(crdt_error)
+    }
+
+    pub fn merge(&mut self, other: &mut CanvasDoc) -> Result<(), String> {
+        self.doc
+            .merge(&mut other.doc)
+            .map(|_| ())
+            .map_err(crdt_error)
+    }
+
+    /// Next sync message for `peer`, or `None` when it is up to date.
+    pub fn sync_message(&mut self, peer: &mut SyncState) -> Option<Vec<u8>> {
+        self.doc
+            .sync()
+            .generate_sync_message(peer)
+            .map(|m| m.encode())
+    }
+
+    pub fn receive_sync_message(
+        &mut self,
+        peer: &mut SyncState,
+        message: &[u8],
+    ) -> Result<(), String> {
+        let message = automerge::sync::Message::decode(message).map_err(crdt_error)?;
+        self.doc
+            .sync()
+            .receive_sync_message(peer, message)
+            .map_err(crdt_error)
+    }
+
+    pub fn name(&self) -> String {
+        match self.scalar(&ROOT, NAME) {
+            Some(ScalarValue::Str(s)) => s.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// The Fabric canvas JSON, with group children nested back under `objects`.
+    pub fn canvas(&self) -> Value {
+        let mut canvas = self
+            .child(&ROOT, CANVAS)
+            .map(|c| self.props(&c))
+            .unwrap_or_default();
+        let mut objects = Vec::new();
+        if let Some(objs) = self.child(&ROOT, OBJECTS) {
+            let mut placed = HashSet::new();
+            let order = self.child(&ROOT, ORDER);
+            for id in order.map(|o| self.ids(&o)).unwrap_or_default() {
+                objects.extend(self.build(&objs, &id, &mut placed));
+            }
+            // An object no list reaches any more, say one moved into a group
+            // another writer deleted, goes on top rather than vanishing.
+            for id in self.doc.keys(&objs).collect::<Vec<_>>() {
+                objects.extend(self.build(&objs, &id, &mut placed));
+            }
+        }
+        canvas.insert(OBJECTS.into(), Value::Array(objects));
+        Value::Object(canvas)
+    }
+
+    /// Record the edits that turn this document into `name` and `canvas`, as
+    /// property-level changes so they merge with other writers'. Objects
+    /// without an id are given one.
+    pub fn update(&mut self, name: &str, canvas: &mut Value) -> Result<(), String> {
+        assign_ids(canvas);
+        let empty = Map::new();
+        let props = canvas.as_object().unwrap_or(&empty);
+
+        self.put(&ROOT, NAME, ScalarValue::Str(name.into()))?;
+        let canvas_obj = self.child_or_insert(&ROOT, CANVAS, ObjType::Map)?;
+        self.update_props(&canvas_obj, props)?;
+
+        let mut seen = HashSet::new();
+        let mut flat = Vec::new();
+        let top = match props.get(OBJECTS).and_then(Value::as_array) {
+            Some(list) => flatten(list, &mut seen, &mut flat),
+            None => Vec::new(),
+        };
+
+        let objs = self.child_or_insert(&ROOT, OBJECTS, ObjType::Map)?;
+        let stale: Vec<String> = self
+            .doc
+            .keys(&objs)
+            .filter(|id| !seen.contains(id.as_str()))
+            .collect();
+        for id in stale {
+            self.doc.delete(&objs, id.as_str()).map_err(crdt_error)?;
+        }
+        for (id, props, children) in flat {
+            let obj = self.child_or_insert(&objs, id, ObjType::Map)?;
+            self.update_props(&obj, props)?;
+            match children {
+                Some(children) => {
+                    let list = self.child_or_insert(&obj, OBJECTS, ObjType::List)?;
+                    self.update_list(&list, &children)?;
+                }
+                None if self.child(&obj, OBJECTS).is_some() => {
+                    self.doc.delete(&obj, OBJECTS).map_err(crdt_error)?;
+                }
+                None => {}
+            }
+        }
+
+        let order = self.child_or_insert(&ROOT, ORDER, ObjType::List)?;
+        self.update_list(&order, &top)
+    }
+
+    fn child(&self, obj: &ObjId, key: &str) -> Option<ObjId> {
+        match self.doc.get(obj, key) {
+            Ok(Some((automerge::Value::Object(_), id))) => Some(id),
+            _ => None,
+        }
+    }
+
+    fn child_or_insert(&mut self, obj: &ObjId, key: &str, kind: ObjType) -> Result<ObjId, String> {
+        match self.child(obj, key) {
+            Some(id) => Ok(id),
+            None => self.doc.put_object(obj, key, kind).map_err(crdt_error),
+        }
+    }
+
+    fn scalar(&self, obj: &ObjId, key: &str) -> Option<ScalarValue> {
+        match self.doc.get(obj, key) {
+            Ok(Some((automerge::Value::Scalar(s), _))) => Some(s.into_owned()),
+            _ => None,
+        }
+    }
+
+    /// Put `value` unless it is already there, so unchanged properties don't
+    /// become changes that could clash with someone else's.
+    fn put(&mut self, obj: &ObjId, key: &str, value: ScalarValue) -> Result<(), String> {
+        if self.scalar(obj, key).as_ref() != Some(&value) {
+            self.doc.put(obj, key, value).map_err(crdt_error)?;
+        }
+        Ok(())
+    }
+
+    /// Scalar properties of a map; nested lists (group children) are skipped.
+    fn props(&self, obj: &ObjId) -> Map<String, Value> {
+        self.doc
+            .keys(obj)
+            .filter_map(|key| {
+                let value = from_scalar(&self.scalar(obj, &key)?);
+                Some((key, value))
+            })
+            .collect()
+    }
+
+    fn update_props(&mut self, obj: &ObjId, props: &Map<String, Value>) -> Result<(), String> {
+        let stale: Vec<String> = self
+            .doc
+            .keys(obj)
+            .filter(|key| key != OBJECTS && !props.contains_key(key))
+            .collect();
+        for key in stale {
+            self.doc.delete(obj, key.as_str()).map_err(crdt_error)?;
+        }
+        for (key, value) in props {
+            if key != OBJECTS {
+                self.put(obj, key, to_scalar(value))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn ids(&self, list: &ObjId) -> Vec<String> {
+        self.doc
+            .list_range(list, ..)
+            .filter_map(|item| match item.value {
+                automerge::Value::Scalar(s) => match s.as_ref() {
+                    ScalarValue::Str(id) => Some(id.to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Edit the list of ids at `list` into `target`, leaving in place the
+    /// longest run that is already in the right order.
+    fn update_list(&mut self, list: &ObjId, target: &[String]) -> Result<(), String> {
+        let current = self.ids(list);
+        let at: HashMap<&str, usize> = target
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.as_str(), i))
+            .collect();
+        let mut seen = HashSet::new();
+        let candidates: Vec<(usize, usize)> = current
+            .iter()
+            .enumerate()
+            .filter_map(|(i, id)| {
+                let t = *at.get(id.as_str())?;
+                seen.insert(t).then_some((i, t))
+            })
+            .collect();
+        let keep = longest_increasing(&candidates.iter().map(|(_, t)| *t).collect::<Vec<_>>());
+        let kept: HashSet<usize> = candidates
+            .iter()
+            .zip(keep)
+            .filter(|(_, k)| *k)
+            .map(|((i, _), _)| *i)
+            .collect();
+
+        for i in (0..current.len()).rev() {
+            if !kept.contains(&i) {
+                self.doc.delete(list, i).map_err(crdt_error)?;
+            }
+        }
+        let remaining: Vec<&String> = current
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| kept.contains(i))
+            .map(|(_, id)| id)
+            .collect();
+        let mut next = 0;
+        for (pos, id) in target.iter().enumerate() {
+            if remaining.get(next) == Some(&id) {
+                next += 1;
+            } else {
+                self.doc
+                    .insert(list, pos, id.as_str())
+                    .map_err(crdt_error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Materialize object `id` and its children, each object once.
+    fn build(&self, objs: &ObjId, id: &str, placed: &mut HashSet<String>) -> Option<Value> {
+        let obj = self.child(objs, id)?;
+        if !placed.insert(id.to_string()) {
+            return None;
+        }
+        let mut props = self.props(&obj);
+        if let Some(list) = self.child(&obj, OBJECTS) {
+            let children = self
+                .ids(&list)
+                .iter()
+                .filter_map(|child| self.build(objs, child, placed))
+                .collect();
+            props.insert(OBJECTS.into(), Value::Array(children));
+        }
+        Some(Value::Object(props))
+    }
+}
+
+/// What [`commit`] reads and updates in an `.easel` document.
+pub struct Tracked<'a> {
+    pub name: &'a mut String,
+    pub canvas: &'a mut Value,
+    /// The state the edit started from; replaced by the merged state's.
+    pub heads: &'a mut Vec<String>,
+}
+
+/// Record an edit to the canvas at `path` in its log, merged with everything
+/// committed there since the edit's heads. Without heads the edit applies over
+/// the latest state. On return the name, canvas and heads describe the merged
+/// document, ready to be written; the name and canvas are left as given unless
+/// the edit had to be merged. The result is the revision currently on disk.
+///
+/// Call with the [`CanvasLock`](crate::easel::CanvasLock) held.
+pub fn commit(path: &Path, doc: Tracked) -> Result<u64, String> {
+    let log = log_path(path);
+    let existing = match fs::read(&log) {
+        Ok(bytes) => Some(bytes),
+        Err(e) if e.kind() == ErrorKind::NotFound => None,
+        Err(e) => return Err(format!("Failed to read {}: {}", log.display(), e)),
+    };
+    let (mut crdt, append) = match existing.as_deref().map(CanvasDoc::load) {
+        Some(Ok(crdt)) => (
+            crdt,
+            existing.as_ref().is_some_and(|b| b.len() < REWRITE_BYTES),
+        ),
+        Some(Err(e)) => {
+            tracing::warn!("Starting a new log for {}: {}", path.display(), e);
+            (CanvasDoc::new(), false)
+        }
+        None => (CanvasDoc::new(), false),
+    };
+    let start = crdt.heads();
+
+    // Writers that bypass the log (or predate it) still count, as edits
+    // made on top of whatever state their file last recorded.
+    let mut revision = 0;
+    if let Some(mut disk) = read_on_disk(path)? {
+        revision = disk.revision;
+        let current = disk.heads == heads_to_strings(&start)
+            && disk.name == crdt.name()
+            && disk.canvas == crdt.canvas();
+        if !current {
+            edit(&mut crdt, &disk.heads, &disk.name, &mut disk.canvas)?;
+        }
+    }
+    let merged = edit(&mut crdt, doc.heads, doc.name, doc.canvas)?;
+
+    if append {
+        let changes = crdt.changes_since(&start);
+        if !changes.is_empty() {
+            OpenOptions::new()
+                .append(true)
+                .open(&log)
+                .and_then(|mut f| f.write_all(&changes))
+                .map_err(|e| format!("Failed to write {}: {}", log.display(), e))?;
+        }
+    } else {
+        let tmp = log.with_extension("crdt.tmp");
+        fs::write(&tmp, crdt.save()).map_err(|e| format!("Failed to write temp file: {}", e))?;
+        fs::rename(&tmp, &log).map_err(|e| format!("Failed to rename temp file: {}", e))?;
+    }
+
+    // Rebuilding the canvas from the document loses its key order, so only
+    // do it when other writers' edits have to be taken in
+    if merged {
+        *doc.name = crdt.name();
+        *doc.canvas = crdt.canvas();
+    }
+    *doc.heads = heads_to_strings(&crdt.heads());
+    Ok(revision)
+}
+
+/// Apply `name` and `canvas` as an edit made at `heads`. Returns whether it
+/// was merged with changes committed since, so the document now differs
+/// from `name` and `canvas`.
+fn edit(
+    crdt: &mut CanvasDoc,
+    heads: &[String],
+    name: &str,
+    canvas: &mut Value,
+) -> Result<bool, String> {
+    let base = parse_heads(heads).filter(|h| !h.is_empty() && crdt.has_heads(h));
+    match base {
+        Some(base) if base != crdt.heads() => {
+            let mut fork = crdt.fork_at(&base)?;
+            fork.update(name, canvas)?;
+            crdt.merge(&mut fork)?;
+            Ok(true)
+        }
+        _ => crdt.update(name, canvas).map(|_| false),
+    }
+}
+
+/// The canvas at `path` as of `heads`, the state an edit made at them started
+/// from. Falls back to the file on disk when the log doesn't know `heads`.
+pub fn canvas_at(path: &Path, heads: &[String]) -> Result<Option<Value>, String> {
+    match load_with(path, heads) {
+        Some((mut crdt, heads)) => crdt.fork_at(&heads).map(|fork| Some(fork.canvas())),
+        None => Ok(read_on_disk(path)?.map(|disk| disk.canvas)),
+    }
+}
+
+/// Whether the log at `path` records `heads`, so [`commit`] can merge an edit
+/// made at them with whatever was committed since rather than apply it over
+/// the latest state.
+pub fn knows_heads(path: &Path, heads: &[String]) -> bool {
+    load_with(path, heads).is_some()
+}
+
+/// The log at `path` with `heads` parsed, if it records them.
+fn load_with(path: &Path, heads: &[String]) -> Option<(CanvasDoc, Vec<ChangeHash>)> {
+    let heads = parse_heads(heads).filter(|h| !h.is_empty())?;
+    let bytes = fs::read(log_path(path)).ok()?;
+    let mut crdt = CanvasDoc::load(&bytes).ok()?;
+    crdt.has_heads(&heads).then_some((crdt, heads))
+}
+
+/// The CRDT log kept beside the canvas at `path`.
+pub fn log_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".crdt");
+    PathBuf::from(name)
+}
+
+pub fn heads_to_strings(heads: &[ChangeHash]) -> Vec<String> {
+    heads.iter().map(ToString::to_string).collect()
+}
+
+/// Parse hex change hashes, or `None` if any is malformed.
+pub fn parse_heads(heads: &[String]) -> Option<Vec<ChangeHash>> {
+    heads.iter().map(|h| h.parse().ok()).collect()
+}
+
+#[derive(Deserialize)]
+struct OnDisk {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    canvas: Value,
+    #[serde(default)]
+    revision: u64,
+    #[serde(default)]
+    heads: Vec<String>,
+}
+
+fn read_on_disk(path: &Path) -> Result<Option<OnDisk>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = bundle::read_document(path)?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Give every object an id; the document is keyed by them.
+fn assign_ids(canvas: &mut Value) {
+    let Some(objects) = canvas.get_mut(OBJECTS).and_then(Value::as_array_mut) else {
+        return;
+    };
+    for obj in objects {
+        if let Some(props) = obj.as_object_mut() {
+            if !props.get("id").is_some_and(Value::is_string) {
+                props.insert("id".into(), Value::String(uuid::Uuid::new_v4().to_string()));
+            }
+        }
+        assign_ids(obj);
+    }
+}
+
+/// Collect every object under `list` into `out`, returning this level's ids.
+/// Later duplicates of an id are dropped.
+fn flatten<'a>(
+    list: &'a [Value],
+    seen: &mut HashSet<&'a str>,
+    out: &mut Vec<Flat<'a>>,
+) -> Vec<String> {
+    let mut ids = Vec::new();
+    for obj in list {
+        let (Some(id), Some(props)) = (fabric::object_id(obj), obj.as_object()) else {
+            continue;
+        };
+        if !seen.insert(id) {
+            continue;
+        }
+        let slot = out.len();
+        out.push((id, props, None));
+        if let Some(children) = props.get(OBJECTS).and_then(Value::as_array) {
+            out[slot].2 = Some(flatten(children, seen, out));
+        }
+        ids.push(id.to_string());
+    }
+    ids
+}
+
+fn to_scalar(value: &Value) -> ScalarValue {
+    match value {
+        Value::Null => ScalarValue::Null,
+        Value::Bool(b) => ScalarValue::Boolean(*b),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => ScalarValue::Int(i),
+            (None, Some(u)) => ScalarValue::Uint(u),
+            _ => ScalarValue::F64(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => ScalarValue::Str(s.as_str().into()),
+        Value::Array(_) | Value::Object(_) => {
+            ScalarValue::Bytes(serde_json::to_vec(value).unwrap_or_default())
+        }
+    }
+}
+
+fn from_scalar(value: &ScalarValue) -> Value {
+    match value {
+        ScalarValue::Str(s) => Value::String(s.to_string()),
+        ScalarValue::Int(i) => Value::from(*i),
+        ScalarValue::Uint(u) => Value::from(*u),
+        ScalarValue::F64(f) => serde_json::Number::from_f64(*f).map_or(Value::Null, Value::Number),
+        ScalarValue::Boolean(b) => Value::Bool(*b),
+        ScalarValue::Bytes(b) => serde_json::from_slice(b).unwrap_or(Value::Null),
+        ScalarValue::Counter(c) => Value::from(i64::from(c)),
+        ScalarValue::Timestamp(t) => Value::from(*t),
+        ScalarValue::Null | ScalarValue::Unknown { .. } => Value::Null,
+    }
+}
+
+fn crdt_error(e: impl std::fmt::Display) -> String {
+    format!("CRDT error: {}", e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::easel::{is_conflict, EaselFile};
+    use serde_json::json;
+
+    fn doc_with(canvas: Value) -> CanvasDoc {
+        let mut doc = CanvasDoc::new();
+        doc.update("Design", &mut canvas.clone()).unwrap();
+        doc
+    }
+
+    fn ids(canvas: &Value) -> Vec<&str> {
+        canvas["objects"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(fabric::object_id)
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_nested_groups() {
+        let canvas = json!({
+            "background": "#fff",
+            "objects": [
+                {"id": "a", "type": "Rect", "left": 1.5, "path": [["M", 0, 0]]},
+                {"id": "g", "type": "Group", "objects": [
+                    {"id": "b", "type": "Circle", "radius": 4},
+                ]},
+            ],
+        });
+        let doc = doc_with(canvas.clone());
+        assert_eq!(doc.name(), "Design");
+        assert_eq!(doc.canvas(), canvas);
+    }
+
+    #[test]
+    fn assigns_missing_ids() {
+        let mut canvas = json!({"objects": [{"type": "Rect"}]});
+        CanvasDoc::new().update("", &mut canvas).unwrap();
+        assert!(canvas["objects"][0]["id"].is_string());
+    }
+
+    #[test]
+    fn concurrent_property_edits_both_survive() {
+        let mut doc = doc_with(json!({"objects": [{"id": "a", "left": 0, "top": 0}]}));
+        let mut fork = doc.fork();
+
+        doc.update(
+            "Design",
+            &mut json!({"objects": [{"id": "a", "left": 10, "top": 0}]}),
+        )
+        .unwrap();
+        fork.update(
+            "Design",
+            &mut json!({"objects": [{"id": "a", "left": 0, "top": 20}]}),
+        )
+        .unwrap();
+        doc.merge(&mut fork).unwrap();
+
+        assert_eq!(
+            doc.canvas()["objects"][0],
+            json!({"id": "a", "left": 10, "top": 20})
+        );
+    }
+
+    #[test]
+    fn concurrent_additions_keep_both_objects() {
+        let mut doc = doc_with(json!({"objects": [{"id": "a"}]}));
+        let mut fork = doc.fork();
+
+        doc.update(
+            "Design",
+            &mut json!({"objects": [{"id": "a"}, {"id": "b"}]}),
+        )
+        .unwrap();
+        fork.update(
+            "Design",
+            &mut json!({"objects": [{"id": "a"}, {"id": "c"}]}),
+        )
+        .unwrap();
+        doc.merge(&mut fork).unwrap();
+
+        let canvas = doc.canvas();
+        let mut merged = ids(&canvas);
+        merged.sort_unstable();
+        assert_eq!(merged, ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn object_moved_into_a_deleted_group_stays_on_the_canvas() {
+        let mut doc = doc_with(json!({"objects": [{"id": "g", "objects": []}, {"id": "a"}]}));
+        let mut fork = doc.fork();
+
+        doc.update("Design", &mut json!({"objects": [{"id": "a"}]}))
+            .unwrap();
+        fork.update(
+            "Design",
+            &mut json!({"objects": [{"id": "g", "objects": [{"id": "a"}]}]}),
+        )
+        .unwrap();
+        doc.merge(&mut fork).unwrap();
+
+        assert_eq!(ids(&doc.canvas()), ["a"]);
+    }
+
+    #[test]
+    fn sync_brings_peers_to_the_same_state() {
+        let mut owner = doc_with(json!({"objects": [{"id": "a", "fill": "red"}]}));
+        let mut peer = CanvasDoc::load(&owner.save()).unwrap();
+        peer.update(
+            "Design",
+            &mut json!({"objects": [{"id": "a", "fill": "blue"}]}),
+        )
+        .unwrap();
+
+        let (mut to_peer, mut to_owner) = (SyncState::new(), SyncState::new());
+        for _ in 0..10 {
+            let a = owner.sync_message(&mut to_peer);
+            let b = peer.sync_message(&mut to_owner);
+            if a.is_none() && b.is_none() {
+                break;
+            }
+            if let Some(message) = a {
+                peer.receive_sync_message(&mut to_owner, &message).unwrap();
+            }
+            if let Some(message) = b {
+                owner.receive_sync_message(&mut to_peer, &message).unwrap();
+            }
+        }
+        assert_eq!(owner.heads(), peer.heads());
+        assert_eq!(owner.canvas()["objects"][0]["fill"], "blue");
+    }
+
+    #[test]
+    fn commit_merges_an_edit_made_at_older_heads() {
+        let dir = std::env::temp_dir().join(format!("easel-crdt-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("canvas.easel");
+        let write = |name: &str, canvas: &Value, heads: &[String]| {
+            let file = json!({"name": name, "canvas": canvas, "heads": heads, "revision": 1});
+            fs::write(&path, file.to_string()).unwrap();
+        };
+
+        let (mut name, mut canvas, mut heads) = (
+            "Design".to_string(),
+            json!({"objects": [{"id": "a", "left": 0, "top": 0}]}),
+            Vec::new(),
+        );
+        commit(
+            &path,
+            Tracked {
+                name: &mut name,
+                canvas: &mut canvas,
+                heads: &mut heads,
+            },
+        )
+        .unwrap();
+        write(&name, &canvas, &heads);
+        let started = heads.clone();
+
+        // One writer moves the object and saves
+        let mut first = json!({"objects": [{"id": "a", "left": 10, "top": 0}]});
+        let mut first_heads = started.clone();
+        commit(
+            &path,
+            Tracked {
+                name: &mut name,
+                canvas: &mut first,
+                heads: &mut first_heads,
+            },
+        )
+        .unwrap();
+        write(&name, &first, &first_heads);
+
+        // Another, still at the old heads, changes a different property
+        let mut second = json!({"objects": [{"id": "a", "left": 0, "top": 20}]});
+        let mut second_heads = started;
+        commit(
+            &path,
+            Tracked {
+                name: &mut name,
+                canvas: &mut second,
+                heads: &mut second_heads,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            second["objects"][0],
+            json!({"id": "a", "left": 10, "top": 20})
+        );
+        assert_eq!(canvas_at(&path, &first_heads).unwrap().unwrap(), first);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn save_checks_the_revision_without_heads_the_log_records() {
+        let dir = std::env::temp_dir().join(format!("easel-crdt-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("canvas.easel");
+        EaselFile::new("Design").save(&path).unwrap();
+
+        let mut first = EaselFile::load(&path).unwrap();
+        let mut merged = EaselFile::load(&path).unwrap();
+        let mut stale = EaselFile::load(&path).unwrap();
+        assert!(knows_heads(&path, &first.heads));
+        first.canvas["background"] = json!("red");
+        first.save(&path).unwrap();
+
+        merged.canvas["objects"] = json!([{"id": "a"}]);
+        merged.save(&path).unwrap();
+        assert_eq!(merged.canvas["background"], "red");
+
+        stale.heads.clear();
+        let error = stale.save(&path).unwrap_err();
+        assert!(is_conflict(&error), "{}", error);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,545 @@
+//! Structural diff between two versions of a canvas, for reviewing edits.
+//!
+//! Objects are matched by id wherever they sit (top level or inside groups).
+//! Position changes and moves between frames or groups are reported as moves,
+//! everything else as per-property changes, and objects that changed their
+//! place in the stacking order (beyond what additions and removals shift) as
+//! reorderings.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+use crate::easel::EaselFile;
+use crate::fabric;
+use crate::summary;
+
+/// Longest property value shown in the text report, in characters.
+const VALUE_EXCERPT: usize = 48;
+
+/// Properties reported as moves rather than changes, or not at all.
+const POSITION_KEYS: [&str; 4] = ["left", "top", "parentId", "objects"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffFormat {
+    /// Human-readable report, one line per change.
+    #[default]
+    Text,
+    /// The diff as structured JSON.
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Change<T> {
+    pub from: T,
+    pub to: T,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PropertyChange {
+    pub property: String,
+    /// `None` when the property was added.
+    pub from: Option<Value>,
+    /// `None` when the property was removed.
+    pub to: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectRef {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Frame (`parentId`) or group the object belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectMove {
+    #[serde(flatten)]
+    pub object: ObjectRef,
+    pub from: [f64; 2],
+    pub to: [f64; 2],
+    /// Set when the object changed frame or group.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<Change<Option<String>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectChange {
+    #[serde(flatten)]
+    pub object: ObjectRef,
+    pub properties: Vec<PropertyChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reordering {
+    #[serde(flatten)]
+    pub object: ObjectRef,
+    /// Stacking index within its parent list (0 = bottom).
+    pub from: usize,
+    pub to: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasDiff {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<Change<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub viewport: Option<Change<Value>>,
+    /// Canvas-level properties such as the background.
+    pub canvas: Vec<PropertyChange>,
+    pub added: Vec<ObjectRef>,
+    pub removed: Vec<ObjectRef>,
+    pub moved: Vec<ObjectMove>,
+    pub changed: Vec<ObjectChange>,
+    pub reordered: Vec<Reordering>,
+}
+
+impl CanvasDiff {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.viewport.is_none()
+            && self.canvas.is_empty()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.moved.is_empty()
+            && self.changed.is_empty()
+            && self.reordered.is_empty()
+    }
+}
+
+/// An object with where it sits: its container list and index in it.
+struct Entry<'a> {
+    object: &'a Value,
+    /// Group holding it, `None` for the top level.
+    group: Option<&'a str>,
+    index: usize,
+}
+
+fn collect<'a>(objects: &'a [Value], group: Option<&'a str>, out: &mut Vec<(&'a str, Entry<'a>)>) {
+    for (index, object) in objects.iter().enumerate() {
+        if let Some(id) = fabric::object_id(object) {
+            out.push((
+                id,
+                Entry {
+                    object,
+                    group,
+                    index,
+                },
+            ));
+        }
+        if let Some(children) = object.get("objects").and_then(|v| v.as_array()) {
+            collect(children, fabric::object_id(object), out);
+        }
+    }
+}
+
+fn entries(canvas: &Value) -> Vec<(&str, Entry<'_>)> {
+    let mut out = Vec::new();
+    let objects = canvas.get("objects").and_then(|v| v.as_array());
+    collect(objects.map_or(&[][..], |v| v), None, &mut out);
+    out
+}
+
+impl Entry<'_> {
+    fn parent(&self) -> Option<String> {
+        self.group
+            .or_else(|| fabric::str_prop(self.object, "parentId"))
+            .map(String::from)
+    }
+
+    fn reference(&self, id: &str) -> ObjectRef {
+        ObjectRef {
+            id: id.to_string(),
+            kind: summary::kind(self.object),
+            name: fabric::str_prop(self.object, "name").map(String::from),
+            parent_id: self.parent(),
+        }
+    }
+
+    fn position(&self) -> [f64; 2] {
+        [
+            fabric::num_prop(self.object, "left").unwrap_or(0.0),
+            fabric::num_prop(self.object, "top").unwrap_or(0.0),
+        ]
+    }
+}
+
+/// Changed properties of two JSON objects, skipping `skip`.
+fn properties(before: &Value, after: &Value, skip: &[&str]) -> Vec<PropertyChange> {
+    let empty = serde_json::Map::new();
+    let b = before.as_object().unwrap_or(&empty);
+    let a = after.as_object().unwrap_or(&empty);
+    let keys: BTreeSet<&String> = b.keys().chain(a.keys()).collect();
+    keys.into_iter()
+        .filter(|key| !skip.contains(&key.as_str()))
+        .filter(|key| b.get(*key) != a.get(*key))
+        .map(|key| PropertyChange {
+            property: key.clone(),
+            from: b.get(key).cloned(),
+            to: a.get(key).cloned(),
+        })
+        .collect()
+}
+
+/// Compare two Fabric canvases.
+pub fn diff_canvases(before: &Value, after: &Value) -> CanvasDiff {
+    let old = entries(before);
+    let new = entries(after);
+    let old_by_id: HashMap<&str, &Entry> = old.iter().map(|(id, e)| (*id, e)).collect();
+    let new_by_id: HashMap<&str, &Entry> = new.iter().map(|(id, e)| (*id, e)).collect();
+
+    let mut diff = CanvasDiff {
+        canvas: properties(before, after, &["objects"]),
+        ..Default::default()
+    };
+
+    for (id, entry) in &old {
+        if !new_by_id.contains_key(id) {
+            diff.removed.push(entry.reference(id));
+        }
+    }
+    for (id, entry) in &new {
+        let Some(was) = old_by_id.get(id) else {
+            diff.added.push(entry.reference(id));
+            continue;
+        };
+        let (from, to) = (was.position(), entry.position());
+        let parent = Some(Change {
+            from: was.parent(),
+            to: entry.parent(),
+        })
+        .filter(|p| p.from != p.to);
+        if from != to || parent.is_some() {
+            diff.moved.push(ObjectMove {
+                object: entry.reference(id),
+                from,
+                to,
+                parent,
+            });
+        }
+        let changed = properties(was.object, entry.object, &POSITION_KEYS);
+        if !changed.is_empty() {
+            diff.changed.push(ObjectChange {
+                object: entry.reference(id),
+                properties: changed,
+            });
+        }
+    }
+
+    diff.reordered = reorderings(&old, &new_by_id);
+    diff
+}
+
+/// An object's id and its index in its list before and after.
+type Slot<'a> = (&'a str, usize, usize);
+
+/// Objects whose order relative to the others in the same list changed. The
+/// objects kept in place are a longest run already in order, so a single
+/// object brought to front is the only one reported.
+fn reorderings(old: &[(&str, Entry)], new_by_id: &HashMap<&str, &Entry>) -> Vec<Reordering> {
+    // Objects still in the same container, grouped by container in old order.
+    let mut lists: Vec<(Option<&str>, Vec<Slot>)> = Vec::new();
+    for (id, was) in old {
+        let Some(now) = new_by_id.get(id).filter(|now| now.group == was.group) else {
+            continue;
+        };
+        match lists.iter_mut().find(|(group, _)| *group == was.group) {
+            Some((_, list)) => list.push((id, was.index, now.index)),
+            None => lists.push((was.group, vec![(id, was.index, now.index)])),
+        }
+    }
+
+    let mut out = Vec::new();
+    for (_, list) in lists {
+        let keep = longest_increasing(&list.iter().map(|(_, _, to)| *to).collect::<Vec<_>>());
+        for (i, (id, from, to)) in list.into_iter().enumerate() {
+            if !keep[i] {
+                out.push(Reordering {
+                    object: new_by_id[id].reference(id),
+                    from,
+                    to,
+                });
+            }
+        }
+    }
+    out
+}
+
+/// Which elements of `seq` belong to one longest strictly increasing subsequence.
+pub(crate) fn longest_increasing(seq: &[usize]) -> Vec<bool> {
+    // tails[k]: index in seq of the smallest tail of an increasing run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev: Vec<Option<usize>> = vec![None; seq.len()];
+    for (i, &v) in seq.iter().enumerate() {
+        let k = tails.partition_point(|&t| seq[t] < v);
+        prev[i] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+    let mut keep = vec![false; seq.len()];
+    let mut at = tails.last().copied();
+    while let Some(i) = at {
+        keep[i] = true;
+        at = prev[i];
+    }
+    keep
+}
+
+/// Compare two documents: the canvas plus its name and viewport.
+pub fn diff_files(before: &EaselFile, after: &EaselFile) -> CanvasDiff {
+    let mut diff = diff_canvases(&before.canvas, &after.canvas);
+    if before.name != after.name {
+        diff.name = Some(Change {
+            from: before.name.clone(),
+            to: after.name.clone(),
+        });
+    }
+    let viewport = |f: &EaselFile| serde_json::to_value(&f.viewport).unwrap_or(Value::Null);
+    let (from, to) = (viewport(before), viewport(after));
+    if from != to {
+        diff.viewport = Some(Change { from, to });
+    }
+    diff
+}
+
+/// The diff as a human-readable report.
+pub fn report(diff: &CanvasDiff) -> String {
+    let mut out = String::new();
+    if diff.is_empty() {
+        out.push_str("No changes\n");
+        return out;
+    }
+    if let Some(name) = &diff.name {
+        let _ = writeln!(out, "renamed {:?} -> {:?}", name.from, name.to);
+    }
+    if let Some(viewport) = &diff.viewport {
+        let _ = writeln!(out, "viewport {} -> {}", viewport.from, viewport.to);
+    }
+    for change in &diff.canvas {
+        let _ = writeln!(out, "canvas {}", property(change));
+    }
+    for object in &diff.added {
+        let _ = writeln!(out, "+ {}", label(object));
+    }
+    for object in &diff.removed {
+        let _ = writeln!(out, "- {}", label(object));
+    }
+    for m in &diff.moved {
+        let _ = write!(
+            out,
+            "> {} moved ({}, {}) -> ({}, {})",
+            label(&m.object),
+            number(m.from[0]),
+            number(m.from[1]),
+            number(m.to[0]),
+            number(m.to[1])
+        );
+        if let Some(parent) = &m.parent {
+            let show = |p: &Option<String>| {
+                p.as_deref()
+                    .map_or("top level".to_string(), |p| format!("#{}", p))
+            };
+            let _ = write!(out, ", {} -> {}", show(&parent.from), show(&parent.to));
+        }
+        out.push('\n');
+    }
+    for c in &diff.changed {
+        let properties: Vec<String> = c.properties.iter().map(property).collect();
+        let _ = writeln!(out, "~ {} {}", label(&c.object), properties.join(", "));
+    }
+    for r in &diff.reordered {
+        let _ = writeln!(out, "^ {} z {} -> {}", label(&r.object), r.from, r.to);
+    }
+    let _ = writeln!(
+        out,
+        "{} added, {} removed, {} moved, {} changed, {} reordered",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.moved.len(),
+        diff.changed.len(),
+        diff.reordered.len()
+    );
+    out
+}
+
+fn label(object: &ObjectRef) -> String {
+    match &object.name {
+        Some(name) => format!("{} {:?} #{}", object.kind, name, object.id),
+        None => format!("{} #{}", object.kind, object.id),
+    }
+}
+
+fn property(change: &PropertyChange) -> String {
+    let show = |v: &Option<Value>| v.as_ref().map_or("(unset)".to_string(), excerpt);
+    format!(
+        "{} {} -> {}",
+        change.property,
+        show(&change.from),
+        show(&change.to)
+    )
+}
+
+fn excerpt(value: &Value) -> String {
+    let text = value.to_string();
+    if text.chars().count() > VALUE_EXCERPT {
+        format!("{}…", text.chars().take(VALUE_EXCERPT).collect::<String>())
+    } else {
+        text
+    }
+}
+
+fn number(v: f64) -> String {
+    let rounded = (v * 100.0).round() / 100.0 + 0.0;
+    rounded.to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ids(objects: &[ObjectRef]) -> Vec<&str> {
+        objects.iter().map(|o| o.id.as_str()).collect()
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_objects() {
+        let before = json!({"background": "#fff", "objects": [
+            {"id": "a", "type": "Rect", "fill": "red"},
+            {"id": "b", "type": "Rect"},
+        ]});
+        let after = json!({"background": "#000", "objects": [
+            {"id": "a", "type": "Rect", "fill": "blue", "stroke": "black"},
+            {"id": "c", "type": "Circle"},
+        ]});
+        let diff = diff_canvases(&before, &after);
+
+        assert_eq!(ids(&diff.added), ["c"]);
+        assert_eq!(ids(&diff.removed), ["b"]);
+        assert!(diff.moved.is_empty() && diff.reordered.is_empty());
+        assert_eq!(diff.canvas.len(), 1);
+        assert_eq!(diff.canvas[0].property, "background");
+
+        let [change] = diff.changed.as_slice() else {
+            panic!("{:?}", diff.changed);
+        };
+        assert_eq!(change.object.id, "a");
+        let properties: Vec<(&str, Option<&Value>, Option<&Value>)> = change
+            .properties
+            .iter()
+            .map(|p| (p.property.as_str(), p.from.as_ref(), p.to.as_ref()))
+            .collect();
+        assert_eq!(
+            properties,
+            [
+                ("fill", Some(&json!("red")), Some(&json!("blue"))),
+                ("stroke", None, Some(&json!("black"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn position_changes_are_moves_not_changes() {
+        let before = json!({"objects": [{"id": "a", "left": 0, "top": 0, "parentId": "f1"}]});
+        let after = json!({"objects": [{"id": "a", "left": 10, "top": 5, "parentId": "f2"}]});
+        let diff = diff_canvases(&before, &after);
+
+        assert!(diff.changed.is_empty());
+        let [moved] = diff.moved.as_slice() else {
+            panic!("{:?}", diff.moved);
+        };
+        assert_eq!((moved.from, moved.to), ([0.0, 0.0], [10.0, 5.0]));
+        let parent = moved.parent.as_ref().unwrap();
+        assert_eq!(
+            (parent.from.as_deref(), parent.to.as_deref()),
+            (Some("f1"), Some("f2"))
+        );
+    }
+
+    #[test]
+    fn matches_objects_inside_nested_groups() {
+        let before = json!({"objects": [
+            {"id": "g", "objects": [
+                {"id": "inner", "objects": [{"id": "a", "fill": "red"}, {"id": "b"}]},
+            ]},
+            {"id": "c"},
+        ]});
+        let after = json!({"objects": [
+            {"id": "g", "objects": [
+                {"id": "inner", "objects": [{"id": "a", "fill": "blue"}, {"id": "d"}]},
+                {"id": "c"},
+            ]},
+        ]});
+        let diff = diff_canvases(&before, &after);
+
+        assert_eq!(ids(&diff.added), ["d"]);
+        assert_eq!(diff.added[0].parent_id.as_deref(), Some("inner"));
+        assert_eq!(ids(&diff.removed), ["b"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].object.id, "a");
+        assert_eq!(diff.changed[0].object.parent_id.as_deref(), Some("inner"));
+        // A child's change doesn't show up as a change of its groups
+        let [moved] = diff.moved.as_slice() else {
+            panic!("{:?}", diff.moved);
+        };
+        assert_eq!(moved.object.id, "c");
+        let parent = moved.parent.as_ref().unwrap();
+        assert_eq!(
+            (parent.from.as_deref(), parent.to.as_deref()),
+            (None, Some("g"))
+        );
+    }
+
+    #[test]
+    fn reports_only_the_objects_that_changed_order() {
+        let before = json!({"objects": [{"id": "a"}, {"id": "b"}, {"id": "c"}, {"id": "d"}]});
+        let after = json!({"objects": [{"id": "b"}, {"id": "c"}, {"id": "d"}, {"id": "a"}]});
+        let diff = diff_canvases(&before, &after);
+        let [reordered] = diff.reordered.as_slice() else {
+            panic!("{:?}", diff.reordered);
+        };
+        assert_eq!(
+            (reordered.object.id.as_str(), reordered.from, reordered.to),
+            ("a", 0, 3)
+        );
+
+        // Removing an object shifts the rest without reordering them
+        let after = json!({"objects": [{"id": "b"}, {"id": "c"}, {"id": "d"}]});
+        assert!(diff_canvases(&before, &after).reordered.is_empty());
+    }
+
+    #[test]
+    fn report_lists_each_change() {
+        let before = json!({"objects": [{"id": "a", "left": 0}, {"id": "b"}]});
+        let after = json!({"objects": [{"id": "a", "left": 4}, {"id": "c"}]});
+        let text = report(&diff_canvases(&before, &after));
+        assert!(text.contains("+ "), "{}", text);
+        assert!(text.contains("- "), "{}", text);
+        assert!(text.contains("> "), "{}", text);
+        assert!(
+            text.ends_with("1 added, 1 removed, 1 moved, 0 changed, 0 reordered\n"),
+            "{}",
+            text
+        );
+        assert_eq!(
+            report(&diff_canvases(&before, &before)),
+            report(&CanvasDiff::default())
+        );
+    }
+}
@@ -1,8 +1,16 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
-use std::fs;
-use std::path::Path;
+use std::fs::{self, File, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::assets::AssetStore;
+use crate::attribution::Actor;
+use crate::bundle;
+use crate::crdt;
+use crate::oplog::{self, Step};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -11,8 +19,18 @@ pub struct EaselFile {
     pub name: String,
     pub canvas: Value,
     pub viewport: ViewportState,
+    #[serde(deserialize_with = "deserialize_timestamp")]
     pub created_at: DateTime<Utc>,
+    #[serde(deserialize_with = "deserialize_timestamp")]
     pub updated_at: DateTime<Utc>,
+    /// Bumped on every save; a save without usable `heads` is rejected when
+    /// the file on disk has moved past the revision this copy was loaded at.
+    #[serde(default)]
+    pub revision: u64,
+    /// State of the canvas's CRDT log this copy was loaded at; a save is
+    /// merged with whatever other writers committed since.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub heads: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,33 +53,113 @@ impl EaselFile {
             },
             created_at: now,
             updated_at: now,
+            revision: 0,
+            heads: Vec::new(),
         }
     }
 
+    /// Load a `.easel` file, either plain JSON or a zipped bundle.
     pub fn load(path: &Path) -> Result<Self, String> {
-        let content = fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let content = bundle::read_document(path)?;
         serde_json::from_str(&content)
             .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
     }
 
-    pub fn save(&self, path: &Path) -> Result<(), String> {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create directory: {}", e))?;
+    /// Save in the layout already on disk (new files are plain JSON), and
+    /// record what changed in the canvas's operation log. A save the log
+    /// fails to record is still a save; the failure is only logged.
+    ///
+    /// Saves made since this copy was loaded are merged with it through the
+    /// CRDT log. Without heads the log records, fails with a conflict (see
+    /// [`is_conflict`]) if another writer saved `path` since; reload and
+    /// reapply the change.
+    pub fn save(&mut self, path: &Path) -> Result<(), String> {
+        self.save_attributed(path, None, None)
+    }
+
+    /// Like [`save`](Self::save), also stamping the objects this copy changed
+    /// with `actor` and recording them as its edit in the operation log.
+    pub fn save_as(&mut self, path: &Path, actor: &Actor) -> Result<(), String> {
+        self.save_attributed(path, Some(actor), None)
+    }
+
+    /// Like [`save_as`](Self::save_as), recording the save as `step` through
+    /// the canvas's history (see [`oplog::undo`]).
+    pub fn save_step(&mut self, path: &Path, actor: &Actor, step: Step) -> Result<(), String> {
+        self.save_attributed(path, Some(actor), Some(step))
+    }
+
+    fn save_attributed(
+        &mut self,
+        path: &Path,
+        actor: Option<&Actor>,
+        step: Option<Step>,
+    ) -> Result<(), String> {
+        ensure_parent_dir(path)?;
+        let _lock = CanvasLock::acquire(path)?;
+        // Without heads the log records there is no common state to merge
+        // from, and the edit would overwrite whatever was saved since.
+        if !crdt::knows_heads(path, &self.heads) {
+            check_revision(path, self.revision)?;
+        }
+        let pending = oplog::begin(path, &self.heads, &mut self.canvas, actor)?;
+        self.revision = crdt::commit(
+            path,
+            crdt::Tracked {
+                name: &mut self.name,
+                canvas: &mut self.canvas,
+                heads: &mut self.heads,
+            },
+        )?;
+        self.write(|json, canvas| bundle::write_document(path, json, canvas))?;
+
+        // The file is saved; failing now would only get the edit repeated
+        if let Err(e) = pending.finish(path, &self.canvas, self.revision, step) {
+            tracing::warn!("Failed to record the save of {}: {}", path.display(), e);
         }
-        let json = serde_json::to_string_pretty(self)
-            .map_err(|e| format!("Failed to serialize: {}", e))?;
-
-        // Atomic write: write to .tmp then rename
-        let tmp_path = path.with_extension("easel.tmp");
-        fs::write(&tmp_path, &json)
-            .map_err(|e| format!("Failed to write temp file: {}", e))?;
-        fs::rename(&tmp_path, path)
-            .map_err(|e| format!("Failed to rename temp file: {}", e))?;
         Ok(())
     }
 
+    /// Save as plain JSON, even if `path` currently holds a bundle. Meant for
+    /// conversions, so the revision on disk is not checked.
+    pub fn save_json(&mut self, path: &Path) -> Result<(), String> {
+        ensure_parent_dir(path)?;
+        let _lock = CanvasLock::acquire(path)?;
+        self.write(|json, _| bundle::write_json(path, json))
+    }
+
+    /// Save as a self-contained bundle, embedding the referenced assets from
+    /// `store`. Like [`save_json`](Self::save_json), this doesn't check the revision.
+    pub fn save_bundle(
+        &mut self,
+        path: &Path,
+        store: &AssetStore,
+        thumbnail: Option<&[u8]>,
+    ) -> Result<(), String> {
+        ensure_parent_dir(path)?;
+        let _lock = CanvasLock::acquire(path)?;
+        self.write(|json, canvas| bundle::write_bundle(path, json, canvas, store, thumbnail))
+    }
+
+    /// Serialize at the next revision and hand the JSON to `write`.
+    fn write(
+        &mut self,
+        write: impl FnOnce(&str, &Value) -> Result<(), String>,
+    ) -> Result<(), String> {
+        self.revision += 1;
+        let result = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize: {}", e))
+            .and_then(|json| write(&json, &self.canvas));
+        if result.is_err() {
+            self.revision -= 1;
+        }
+        result
+    }
+
+    pub fn touch(&mut self) {
+        self.updated_at = Utc::now();
+    }
+
     pub fn objects(&self) -> &Vec<Value> {
         static EMPTY: Vec<Value> = Vec::new();
         self.canvas
@@ -118,6 +216,7 @@ impl EaselFile {
 
         // Second: find (parent_idx, child_idx) inside groups
         let mut found: Option<(usize, usize)> = None;
+        #[allow(clippy::needless_range_loop)]
         'outer: for i in 0..objects.len() {
             if let Some(children) = objects[i].get("objects").and_then(|v| v.as_array()) {
                 for j in 0..children.len() {
@@ -165,3 +264,120 @@ impl EaselFile {
         removed
     }
 }
+
+/// Parse a file timestamp: RFC 3339, or the `YYYY-MM-DD HH:MM:SS` (UTC) that
+/// files migrated from the app's database were written with.
+pub fn parse_timestamp(s: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").map(|t| t.and_utc()))
+        .map_err(|e| format!("Invalid timestamp {:?}: {}", s, e))
+}
+
+fn deserialize_timestamp<'de, D: Deserializer<'de>>(d: D) -> Result<DateTime<Utc>, D::Error> {
+    parse_timestamp(&String::deserialize(d)?).map_err(serde::de::Error::custom)
+}
+
+/// Remove the files kept beside a canvas file: its lock, CRDT and operation
+/// logs, and asset directory. The canvas file itself is left to the caller.
+pub fn remove_sidecars(path: &Path) -> Result<(), String> {
+    for sidecar in [lock_path(path), crdt::log_path(path), oplog::log_path(path)] {
+        if sidecar.exists() {
+            fs::remove_file(&sidecar)
+                .map_err(|e| format!("Failed to remove {}: {}", sidecar.display(), e))?;
+        }
+    }
+    AssetStore::for_canvas(path).remove_all()
+}
+
+fn ensure_parent_dir(path: &Path) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    Ok(())
+}
+
+/// How long a save waits for another writer to release a canvas.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_RETRY: Duration = Duration::from_millis(20);
+
+/// Start of every conflict error, so callers can tell them from I/O failures.
+pub const CONFLICT: &str = "Conflict";
+
+pub fn is_conflict(error: &str) -> bool {
+    error.starts_with(CONFLICT)
+}
+
+/// Advisory exclusive lock on a canvas file, released on drop.
+///
+/// The lock is taken on a sidecar `<file>.lock` rather than the file itself,
+/// because saves replace the file by renaming a temporary over it. Only
+/// writers that take the lock are kept out; both easel-mcp and the app do.
+pub struct CanvasLock {
+    _file: File,
+}
+
+impl CanvasLock {
+    /// Wait up to a few seconds for the lock on `path`.
+    pub fn acquire(path: &Path) -> Result<Self, String> {
+        let lock_path = lock_path(path);
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .map_err(|e| format!("Failed to open {}: {}", lock_path.display(), e))?;
+
+        let started = Instant::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { _file: file }),
+                Err(TryLockError::WouldBlock) if started.elapsed() < LOCK_TIMEOUT => {
+                    thread::sleep(LOCK_RETRY)
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(format!(
+                        "{} is locked by another writer; try again",
+                        path.display()
+                    ))
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(format!("Failed to lock {}: {}", path.display(), e))
+                }
+            }
+        }
+    }
+}
+
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// Fail with a conflict unless `path` is missing or still at `expected`.
+/// Call with the [`CanvasLock`] held so nobody writes between check and save.
+pub fn check_revision(path: &Path, expected: u64) -> Result<(), String> {
+    #[derive(Deserialize)]
+    struct Head {
+        #[serde(default)]
+        revision: u64,
+    }
+
+    if !path.exists() {
+        return Ok(());
+    }
+    let content = bundle::read_document(path)?;
+    let head: Head = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    if head.revision != expected {
+        return Err(format!(
+            "{}: {} was saved by another writer (revision {}, expected {}); reload it and try again",
+            CONFLICT,
+            path.display(),
+            head.revision,
+            expected
+        ));
+    }
+    Ok(())
+}
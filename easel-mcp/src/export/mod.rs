@@ -0,0 +1,128 @@
+//! Exporters that turn canvas content into other document formats.
+
+pub mod pdf;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::color::Rgba;
+use crate::fabric::{self, Bounds};
+use crate::geometry;
+use crate::render::{self, Renderer, Scene};
+
+/// Which part of the canvas to export.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportScope {
+    /// Everything on the canvas, on a single page.
+    #[default]
+    Canvas,
+    /// One page per frame: the given frame IDs, or every top-level frame.
+    Frames,
+    /// The given object IDs (and the contents of selected frames) on one page.
+    Selection,
+}
+
+/// Build one scene per page for `scope`.
+pub fn scenes(
+    canvas: &Value,
+    scope: ExportScope,
+    ids: &[String],
+    renderer: &mut Renderer,
+) -> Result<Vec<Scene>, String> {
+    let empty = Vec::new();
+    let objects = canvas
+        .get("objects")
+        .and_then(|v| v.as_array())
+        .unwrap_or(&empty);
+
+    match scope {
+        ExportScope::Canvas => {
+            let all: Vec<&Value> = objects.iter().collect();
+            let bounds = union_bounds(&all).ok_or("Canvas is empty")?;
+            let mut scene = renderer.scene("Canvas", bounds, &all);
+            scene.background = canvas
+                .get("background")
+                .and_then(|v| v.as_str())
+                .and_then(Rgba::parse);
+            Ok(vec![scene])
+        }
+        ExportScope::Frames => {
+            let frames: Vec<&Value> = if ids.is_empty() {
+                objects
+                    .iter()
+                    .filter(|o| fabric::is_frame(o) && fabric::str_prop(o, "parentId").is_none())
+                    .collect()
+            } else {
+                ids.iter()
+                    .map(|id| {
+                        objects
+                            .iter()
+                            .find(|o| {
+                                fabric::object_id(o) == Some(id.as_str()) && fabric::is_frame(o)
+                            })
+                            .ok_or_else(|| format!("Frame not found: {}", id))
+                    })
+                    .collect::<Result<_, _>>()?
+            };
+            if frames.is_empty() {
+                return Err("Canvas has no frames".to_string());
+            }
+            Ok(frames
+                .into_iter()
+                .map(|frame| {
+                    let id = fabric::object_id(frame).unwrap_or_default();
+                    let contents = with_descendants(objects, &[id.to_string()]);
+                    let title = fabric::str_prop(frame, "name").unwrap_or("Frame");
+                    renderer.scene(title, render::box_bounds(frame), &contents)
+                })
+                .collect())
+        }
+        ExportScope::Selection => {
+            if ids.is_empty() {
+                return Err("Selection export needs at least one object ID".to_string());
+            }
+            for id in ids {
+                if !objects
+                    .iter()
+                    .any(|o| fabric::object_id(o) == Some(id.as_str()))
+                {
+                    return Err(format!("Object not found: {}", id));
+                }
+            }
+            let selected = with_descendants(objects, ids);
+            let bounds = union_bounds(&selected).ok_or("Selection is empty")?;
+            Ok(vec![renderer.scene("Selection", bounds, &selected)])
+        }
+    }
+}
+
+/// `roots` plus every object nested under them via `parentId`, in canvas order.
+fn with_descendants<'a>(objects: &'a [Value], roots: &[String]) -> Vec<&'a Value> {
+    let mut included: HashSet<&str> = roots.iter().map(String::as_str).collect();
+    // Parents can appear after their children in the list, so iterate to a fixed point.
+    loop {
+        let before = included.len();
+        for obj in objects {
+            if let (Some(id), Some(parent)) =
+                (fabric::object_id(obj), fabric::str_prop(obj, "parentId"))
+            {
+                if included.contains(parent) {
+                    included.insert(id);
+                }
+            }
+        }
+        if included.len() == before {
+            break;
+        }
+    }
+    objects
+        .iter()
+        .filter(|o| fabric::object_id(o).is_some_and(|id| included.contains(id)))
+        .collect()
+}
+
+fn union_bounds(objects: &[&Value]) -> Option<Bounds> {
+    Bounds::union_all(objects.iter().map(|o| geometry::object_bounds(o)))
+}
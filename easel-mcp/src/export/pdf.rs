@@ -0,0 +1,853 @@
+//! PDF export: vector shapes, gradients, images and real text with embedded,
+//! subsetted fonts. One canvas pixel maps to one PDF point.
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use pdf_writer::types::{
+    CidFontType, ColorSpaceOperand, FontFlags, FunctionShadingType, LineCapStyle, LineJoinStyle,
+    SystemInfo, UnicodeCmap,
+};
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{Cursor, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use super::ExportScope;
+use crate::assets::AssetStore;
+use crate::color::Rgba;
+use crate::fonts::{FontBook, FontData};
+use crate::geometry::Matrix;
+use crate::path::Segment;
+use crate::render::{
+    DrawItem, DrawKind, Gradient, GradientKind, LineCap, LineJoin, Paint, Renderer, Scene,
+    StrokeStyle,
+};
+use crate::text::TextLayout;
+
+#[derive(Debug, Clone, Default)]
+pub struct PdfOptions {
+    pub scope: ExportScope,
+    /// Frame IDs for [`ExportScope::Frames`] (all top-level frames when empty),
+    /// object IDs for [`ExportScope::Selection`].
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PdfSummary {
+    pub pages: usize,
+    pub bytes: usize,
+    /// Objects that were skipped or simplified.
+    pub warnings: Vec<String>,
+}
+
+/// Render the canvas to PDF bytes.
+pub fn export_pdf(
+    canvas: &Value,
+    options: &PdfOptions,
+    assets: Option<&AssetStore>,
+) -> Result<(Vec<u8>, PdfSummary), String> {
+    // Fonts a bundle carries stand in for ones this machine lacks.
+    let bundled = assets.and_then(FontBook::with_bundled);
+    let fonts = bundled.as_ref().unwrap_or_else(|| FontBook::system());
+    let mut renderer = Renderer::new(fonts, assets);
+    let scenes = super::scenes(canvas, options.scope, &options.ids, &mut renderer)?;
+    let mut warnings = renderer.into_warnings();
+
+    let mut writer = PdfWriter::new();
+    for scene in &scenes {
+        writer.page(scene, &mut warnings);
+    }
+    let bytes = writer.finish(&mut warnings);
+
+    let summary = PdfSummary {
+        pages: scenes.len(),
+        bytes: bytes.len(),
+        warnings,
+    };
+    Ok((bytes, summary))
+}
+
+/// Render the canvas and write the PDF to `output`.
+pub fn write_pdf(
+    canvas: &Value,
+    options: &PdfOptions,
+    assets: Option<&AssetStore>,
+    output: &Path,
+) -> Result<PdfSummary, String> {
+    let (bytes, summary) = export_pdf(canvas, options, assets)?;
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    std::fs::write(output, bytes)
+        .map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+    Ok(summary)
+}
+
+struct EmbeddedFont {
+    name: String,
+    font_ref: Ref,
+    data: Arc<FontData>,
+    /// Glyph ID → the character it was used for (for ToUnicode).
+    glyphs: BTreeMap<u16, char>,
+}
+
+struct PdfWriter {
+    pdf: Pdf,
+    next_ref: Ref,
+    catalog_ref: Ref,
+    pages_ref: Ref,
+    resources_ref: Ref,
+    page_refs: Vec<Ref>,
+    fonts: HashMap<fontdb::ID, EmbeddedFont>,
+    /// Quantised (fill, stroke) alpha → graphics state.
+    alphas: HashMap<(u16, u16), (String, Ref)>,
+    patterns: Vec<(String, Ref)>,
+    images: HashMap<String, (String, Ref)>,
+}
+
+impl PdfWriter {
+    fn new() -> Self {
+        let mut next_ref = Ref::new(1);
+        let catalog_ref = next_ref.bump();
+        let pages_ref = next_ref.bump();
+        let resources_ref = next_ref.bump();
+        Self {
+            pdf: Pdf::new(),
+            next_ref,
+            catalog_ref,
+            pages_ref,
+            resources_ref,
+            page_refs: Vec::new(),
+            fonts: HashMap::new(),
+            alphas: HashMap::new(),
+            patterns: Vec::new(),
+            images: HashMap::new(),
+        }
+    }
+
+    fn alloc(&mut self) -> Ref {
+        self.next_ref.bump()
+    }
+
+    fn page(&mut self, scene: &Scene, warnings: &mut Vec<String>) {
+        let b = scene.bounds;
+        // Canvas space is y-down with an arbitrary origin; PDF is y-up from the page corner.
+        let base = Matrix([1.0, 0.0, 0.0, -1.0, -b.left, b.top + b.height]);
+
+        let mut content = Content::new();
+        content.transform(to_f32(base.0));
+        if let Some(bg) = scene.background {
+            content.save_state();
+            self.set_alpha(&mut content, bg.a, 1.0);
+            content.set_fill_rgb(channel(bg.r), channel(bg.g), channel(bg.b));
+            content.rect(b.left as f32, b.top as f32, b.width as f32, b.height as f32);
+            content.fill_nonzero();
+            content.restore_state();
+        }
+        for item in &scene.items {
+            content.save_state();
+            content.transform(to_f32(item.transform.0));
+            match &item.kind {
+                DrawKind::Shape { segments, even_odd } => {
+                    self.shape(&mut content, &base, item, segments, *even_odd)
+                }
+                DrawKind::Text(layout) => self.text(&mut content, &base, item, layout),
+                DrawKind::Image { .. } => {
+                    if let Err(e) = self.image(&mut content, item) {
+                        let who = item.id.as_deref().unwrap_or("image");
+                        warnings.push(format!("{}: {}", who, e));
+                    }
+                }
+            }
+            content.restore_state();
+        }
+
+        let content_ref = self.alloc();
+        let page_ref = self.alloc();
+        let data = deflate(&content.finish());
+        self.pdf
+            .stream(content_ref, &data)
+            .filter(Filter::FlateDecode);
+
+        let mut page = self.pdf.page(page_ref);
+        page.media_box(Rect::new(0.0, 0.0, b.width as f32, b.height as f32));
+        page.parent(self.pages_ref);
+        page.contents(content_ref);
+        page.pair(Name(b"Resources"), self.resources_ref);
+        page.finish();
+        self.page_refs.push(page_ref);
+    }
+
+    fn shape(
+        &mut self,
+        content: &mut Content,
+        base: &Matrix,
+        item: &DrawItem,
+        segments: &[Segment],
+        even_odd: bool,
+    ) {
+        if segments.is_empty() {
+            return;
+        }
+        let paint_fill = |w: &mut Self, content: &mut Content| {
+            if let Some(fill) = &item.fill {
+                w.set_paint(content, base, item, fill, false);
+                write_path(content, segments);
+                if even_odd {
+                    content.fill_even_odd();
+                } else {
+                    content.fill_nonzero();
+                }
+            }
+        };
+        let paint_stroke = |w: &mut Self, content: &mut Content| {
+            if let Some(stroke) = &item.stroke {
+                w.set_stroke_style(content, base, item, stroke);
+                write_path(content, segments);
+                content.stroke();
+            }
+        };
+        if item.stroke_first {
+            paint_stroke(self, content);
+            paint_fill(self, content);
+        } else {
+            paint_fill(self, content);
+            paint_stroke(self, content);
+        }
+    }
+
+    /// Register a font for embedding and return its resource name.
+    fn font_resource(&mut self, font: &Arc<FontData>) -> String {
+        if !self.fonts.contains_key(&font.id) {
+            let embedded = EmbeddedFont {
+                name: format!("F{}", self.fonts.len() + 1),
+                font_ref: self.alloc(),
+                data: font.clone(),
+                glyphs: BTreeMap::new(),
+            };
+            self.fonts.insert(font.id, embedded);
+        }
+        self.fonts[&font.id].name.clone()
+    }
+
+    fn text(&mut self, content: &mut Content, base: &Matrix, item: &DrawItem, layout: &TextLayout) {
+        let mode = match (&item.fill, &item.stroke) {
+            (Some(_), Some(_)) => 2,
+            (None, Some(_)) => 1,
+            (Some(_), None) => 0,
+            (None, None) => return,
+        };
+        if let Some(stroke) = &item.stroke {
+            self.set_stroke_style(content, base, item, stroke);
+        }
+
+        content.begin_text();
+        content.set_char_spacing(layout.char_spacing as f32);
+        content.set_text_rendering_mode(match mode {
+            2 => pdf_writer::types::TextRenderingMode::FillStroke,
+            1 => pdf_writer::types::TextRenderingMode::Stroke,
+            _ => pdf_writer::types::TextRenderingMode::Fill,
+        });
+        for line in &layout.lines {
+            for run in &line.runs {
+                let Some(font) = run.font.as_ref().or(layout.font.as_ref()) else {
+                    continue;
+                };
+                let Some(face) = font.face() else {
+                    continue;
+                };
+                let font_name = self.font_resource(font);
+                let entry = self.fonts.get_mut(&font.id).expect("embedded above");
+                let mut bytes = Vec::with_capacity(run.text.len() * 2);
+                for ch in run.text.chars() {
+                    let glyph = font.glyph_id(&face, ch);
+                    entry.glyphs.entry(glyph).or_insert(ch);
+                    bytes.extend(glyph.to_be_bytes());
+                }
+
+                // A per-character fill replaces the object's paint for this run.
+                let fill = match run.style.get("fill").and_then(|v| v.as_str()) {
+                    Some(color) => Rgba::parse(color).map(Paint::Solid),
+                    None => item.fill.clone(),
+                };
+                if let Some(fill) = &fill {
+                    self.set_paint(content, base, item, fill, false);
+                }
+                content.set_font(Name(font_name.as_bytes()), run.font_size as f32);
+                // Flip the glyphs back upright inside the y-down object space.
+                let x = line.left + run.left;
+                content.set_text_matrix([1.0, 0.0, 0.0, -1.0, x as f32, line.baseline as f32]);
+                content.show(Str(&bytes));
+            }
+        }
+        content.end_text();
+    }
+
+    fn image(&mut self, content: &mut Content, item: &DrawItem) -> Result<(), String> {
+        let DrawKind::Image {
+            key,
+            bytes,
+            width,
+            height,
+            crop_x,
+            crop_y,
+            natural_width,
+            natural_height,
+        } = &item.kind
+        else {
+            return Ok(());
+        };
+
+        let name = match self.images.get(key) {
+            Some((name, _)) => name.clone(),
+            None => {
+                let image_ref = self.embed_image(bytes)?;
+                let name = format!("Im{}", self.images.len() + 1);
+                self.images.insert(key.clone(), (name.clone(), image_ref));
+                name
+            }
+        };
+
+        self.set_alpha(content, item.opacity, item.opacity);
+        // Clip to the visible (cropped) region, then place the full image.
+        content.rect(0.0, 0.0, *width as f32, *height as f32);
+        content.clip_nonzero();
+        content.end_path();
+        content.transform([
+            *natural_width as f32,
+            0.0,
+            0.0,
+            -*natural_height as f32,
+            -*crop_x as f32,
+            (*natural_height - *crop_y) as f32,
+        ]);
+        content.x_object(Name(name.as_bytes()));
+        Ok(())
+    }
+
+    fn embed_image(&mut self, bytes: &[u8]) -> Result<Ref, String> {
+        let image_ref = self.alloc();
+        match imagesize::image_type(bytes) {
+            Ok(imagesize::ImageType::Jpeg) => {
+                let size = imagesize::blob_size(bytes).map_err(|e| e.to_string())?;
+                let mut image = self.pdf.image_xobject(image_ref, bytes);
+                image.filter(Filter::DctDecode);
+                image.width(size.width as i32);
+                image.height(size.height as i32);
+                match jpeg_components(bytes) {
+                    Some(1) => image.color_space().device_gray(),
+                    Some(4) => image.color_space().device_cmyk(),
+                    _ => image.color_space().device_rgb(),
+                }
+                image.bits_per_component(8);
+            }
+            Ok(imagesize::ImageType::Png) => {
+                let decoded = decode_png(bytes)?;
+                let mask_ref = if let Some(alpha) = &decoded.alpha {
+                    let mask_ref = self.alloc();
+                    let data = deflate(alpha);
+                    let mut mask = self.pdf.image_xobject(mask_ref, &data);
+                    mask.filter(Filter::FlateDecode);
+                    mask.width(decoded.width as i32);
+                    mask.height(decoded.height as i32);
+                    mask.color_space().device_gray();
+                    mask.bits_per_component(8);
+                    Some(mask_ref)
+                } else {
+                    None
+                };
+                let data = deflate(&decoded.color);
+                let mut image = self.pdf.image_xobject(image_ref, &data);
+                image.filter(Filter::FlateDecode);
+                image.width(decoded.width as i32);
+                image.height(decoded.height as i32);
+                if decoded.gray {
+                    image.color_space().device_gray();
+                } else {
+                    image.color_space().device_rgb();
+                }
+                image.bits_per_component(8);
+                if let Some(mask_ref) = mask_ref {
+                    image.s_mask(mask_ref);
+                }
+            }
+            _ => return Err("only PNG and JPEG images can be embedded in PDF".to_string()),
+        }
+        Ok(image_ref)
+    }
+
+    fn set_stroke_style(
+        &mut self,
+        content: &mut Content,
+        base: &Matrix,
+        item: &DrawItem,
+        stroke: &StrokeStyle,
+    ) {
+        self.set_paint(content, base, item, &stroke.paint, true);
+        content.set_line_width(stroke.width as f32);
+        content.set_line_cap(match stroke.cap {
+            LineCap::Butt => LineCapStyle::ButtCap,
+            LineCap::Round => LineCapStyle::RoundCap,
+            LineCap::Square => LineCapStyle::ProjectingSquareCap,
+        });
+        content.set_line_join(match stroke.join {
+            LineJoin::Miter => LineJoinStyle::MiterJoin,
+            LineJoin::Round => LineJoinStyle::RoundJoin,
+            LineJoin::Bevel => LineJoinStyle::BevelJoin,
+        });
+        content.set_miter_limit(stroke.miter_limit as f32);
+        if !stroke.dash.is_empty() && stroke.dash.iter().any(|d| *d > 0.0) {
+            content.set_dash_pattern(
+                stroke.dash.iter().map(|d| *d as f32),
+                stroke.dash_offset as f32,
+            );
+        }
+    }
+
+    fn set_paint(
+        &mut self,
+        content: &mut Content,
+        base: &Matrix,
+        item: &DrawItem,
+        paint: &Paint,
+        stroke: bool,
+    ) {
+        match paint {
+            Paint::Solid(color) => {
+                let alpha = color.a * item.opacity;
+                if stroke {
+                    self.set_alpha(content, self.current_fill_alpha(item), alpha);
+                    content.set_stroke_rgb(channel(color.r), channel(color.g), channel(color.b));
+                } else {
+                    self.set_alpha(content, alpha, item.opacity);
+                    content.set_fill_rgb(channel(color.r), channel(color.g), channel(color.b));
+                }
+            }
+            Paint::Gradient(gradient) => {
+                let alpha = item.opacity * average_alpha(gradient);
+                let (fill_alpha, stroke_alpha) = if stroke {
+                    (self.current_fill_alpha(item), alpha)
+                } else {
+                    (alpha, item.opacity)
+                };
+                self.set_alpha(content, fill_alpha, stroke_alpha);
+                let matrix = base.multiply(&item.transform).multiply(&gradient.transform);
+                let name = self.gradient_pattern(gradient, &matrix);
+                if stroke {
+                    content.set_stroke_color_space(ColorSpaceOperand::Pattern);
+                    content.set_stroke_pattern(None, Name(name.as_bytes()));
+                } else {
+                    content.set_fill_color_space(ColorSpaceOperand::Pattern);
+                    content.set_fill_pattern(None, Name(name.as_bytes()));
+                }
+            }
+        }
+    }
+
+    /// Fill alpha to keep when only the stroke paint changes.
+    fn current_fill_alpha(&self, item: &DrawItem) -> f64 {
+        match &item.fill {
+            Some(Paint::Solid(c)) => c.a * item.opacity,
+            Some(Paint::Gradient(g)) => average_alpha(g) * item.opacity,
+            None => item.opacity,
+        }
+    }
+
+    fn set_alpha(&mut self, content: &mut Content, fill: f64, stroke: f64) {
+        let key = (quantise(fill), quantise(stroke));
+        if key == (1000, 1000) {
+            // Every item starts from an opaque graphics state.
+            return;
+        }
+        let name = match self.alphas.get(&key) {
+            Some((name, _)) => name.clone(),
+            None => {
+                let gs_ref = self.alloc();
+                let name = format!("GS{}", self.alphas.len() + 1);
+                self.pdf
+                    .ext_graphics(gs_ref)
+                    .non_stroking_alpha(key.0 as f32 / 1000.0)
+                    .stroking_alpha(key.1 as f32 / 1000.0);
+                self.alphas.insert(key, (name.clone(), gs_ref));
+                name
+            }
+        };
+        content.set_parameters(Name(name.as_bytes()));
+    }
+
+    fn gradient_pattern(&mut self, gradient: &Gradient, matrix: &Matrix) -> String {
+        let function_ref = self.gradient_function(&gradient.stops);
+        let pattern_ref = self.alloc();
+        let name = format!("P{}", self.patterns.len() + 1);
+
+        let mut pattern = self.pdf.shading_pattern(pattern_ref);
+        pattern.matrix(to_f32(matrix.0));
+        let mut shading = pattern.function_shading();
+        let c = gradient.coords.map(|v| v as f32);
+        match gradient.kind {
+            GradientKind::Linear => {
+                shading.shading_type(FunctionShadingType::Axial);
+                shading.coords([c[0], c[1], c[2], c[3]]);
+            }
+            GradientKind::Radial => {
+                shading.shading_type(FunctionShadingType::Radial);
+                shading.coords(c);
+            }
+        }
+        shading.color_space().device_rgb();
+        shading.function(function_ref);
+        shading.extend([true, true]);
+        shading.finish();
+        pattern.finish();
+
+        self.patterns.push((name.clone(), pattern_ref));
+        name
+    }
+
+    /// Piecewise-linear RGB function over `[0, 1]` through the gradient stops.
+    fn gradient_function(&mut self, stops: &[(f64, Rgba)]) -> Ref {
+        let rgb = |c: &Rgba| [channel(c.r), channel(c.g), channel(c.b)];
+        let mut stops = stops.to_vec();
+        // Canvas pads with the end colors outside the first and last stop.
+        if stops[0].0 > 0.0 {
+            stops.insert(0, (0.0, stops[0].1));
+        }
+        if stops[stops.len() - 1].0 < 1.0 {
+            stops.push((1.0, stops[stops.len() - 1].1));
+        }
+        if stops.len() == 1 {
+            stops.push((1.0, stops[0].1));
+        }
+
+        let pieces: Vec<Ref> = stops
+            .windows(2)
+            .map(|pair| {
+                let piece = self.alloc();
+                self.pdf
+                    .exponential_function(piece)
+                    .domain([0.0, 1.0])
+                    .c0(rgb(&pair[0].1))
+                    .c1(rgb(&pair[1].1))
+                    .n(1.0);
+                piece
+            })
+            .collect();
+        if pieces.len() == 1 {
+            return pieces[0];
+        }
+
+        let function_ref = self.alloc();
+        let bounds: Vec<f32> = stops[1..stops.len() - 1]
+            .iter()
+            .map(|s| s.0 as f32)
+            .collect();
+        self.pdf
+            .stitching_function(function_ref)
+            .domain([0.0, 1.0])
+            .functions(pieces.iter().copied())
+            .bounds(bounds)
+            .encode(pieces.iter().flat_map(|_| [0.0, 1.0]));
+        function_ref
+    }
+
+    fn finish(mut self, warnings: &mut Vec<String>) -> Vec<u8> {
+        let fonts: Vec<EmbeddedFont> = std::mem::take(&mut self.fonts).into_values().collect();
+        for (index, font) in fonts.iter().enumerate() {
+            if let Err(e) = self.embed_font(font, index) {
+                warnings.push(format!("font {}: {}", font.data.post_script_name, e));
+            }
+        }
+
+        let mut resources = self
+            .pdf
+            .indirect(self.resources_ref)
+            .start::<pdf_writer::writers::Resources>();
+        let mut font_dict = resources.fonts();
+        for font in &fonts {
+            font_dict.pair(Name(font.name.as_bytes()), font.font_ref);
+        }
+        font_dict.finish();
+        let mut states = resources.ext_g_states();
+        for (name, gs_ref) in self.alphas.values() {
+            states.pair(Name(name.as_bytes()), *gs_ref);
+        }
+        states.finish();
+        let mut patterns = resources.patterns();
+        for (name, pattern_ref) in &self.patterns {
+            patterns.pair(Name(name.as_bytes()), *pattern_ref);
+        }
+        patterns.finish();
+        let mut x_objects = resources.x_objects();
+        for (name, image_ref) in self.images.values() {
+            x_objects.pair(Name(name.as_bytes()), *image_ref);
+        }
+        x_objects.finish();
+        resources.finish();
+
+        self.pdf
+            .pages(self.pages_ref)
+            .kids(self.page_refs.iter().copied())
+            .count(self.page_refs.len() as i32);
+        self.pdf.catalog(self.catalog_ref).pages(self.pages_ref);
+        self.pdf.finish()
+    }
+
+    fn embed_font(&mut self, font: &EmbeddedFont, index: usize) -> Result<(), String> {
+        let data = &font.data;
+        let face = data.face().ok_or("unreadable font")?;
+        let glyphs: Vec<u16> = std::iter::once(0)
+            .chain(font.glyphs.keys().copied())
+            .collect();
+        let subset = subsetter::subset(&data.bytes, data.index, subsetter::Profile::pdf(&glyphs))
+            .map_err(|e| format!("subsetting failed: {}", e))?;
+        let is_cff = face.tables().cff.is_some();
+
+        let base_font = format!("{}+{}", subset_tag(index), data.post_script_name);
+        let cid_ref = self.alloc();
+        let descriptor_ref = self.alloc();
+        let cmap_ref = self.alloc();
+        let file_ref = self.alloc();
+
+        self.pdf
+            .type0_font(font.font_ref)
+            .base_font(Name(base_font.as_bytes()))
+            .encoding_predefined(Name(b"Identity-H"))
+            .descendant_font(cid_ref)
+            .to_unicode(cmap_ref);
+
+        let units = face.units_per_em() as f32;
+        let to_pdf = |v: f32| v * 1000.0 / units;
+        let mut cid = self.pdf.cid_font(cid_ref);
+        cid.subtype(if is_cff {
+            CidFontType::Type0
+        } else {
+            CidFontType::Type2
+        });
+        cid.base_font(Name(base_font.as_bytes()));
+        cid.system_info(SystemInfo {
+            registry: Str(b"Adobe"),
+            ordering: Str(b"Identity"),
+            supplement: 0,
+        });
+        cid.font_descriptor(descriptor_ref);
+        cid.default_width(0.0);
+        if !is_cff {
+            cid.cid_to_gid_map_predefined(Name(b"Identity"));
+        }
+        let mut widths = cid.widths();
+        for &glyph in font.glyphs.keys() {
+            let advance = data.advance(&face, glyph) as f32 * 1000.0;
+            widths.consecutive(glyph, [advance]);
+        }
+        widths.finish();
+        cid.finish();
+
+        let bbox = face.global_bounding_box();
+        let mut flags = FontFlags::SYMBOLIC;
+        if face.is_italic() {
+            flags |= FontFlags::ITALIC;
+        }
+        if face.is_monospaced() {
+            flags |= FontFlags::FIXED_PITCH;
+        }
+        let mut descriptor = self.pdf.font_descriptor(descriptor_ref);
+        descriptor
+            .name(Name(base_font.as_bytes()))
+            .flags(flags)
+            .bbox(Rect::new(
+                to_pdf(bbox.x_min as f32),
+                to_pdf(bbox.y_min as f32),
+                to_pdf(bbox.x_max as f32),
+                to_pdf(bbox.y_max as f32),
+            ))
+            .italic_angle(face.italic_angle())
+            .ascent(to_pdf(face.ascender() as f32))
+            .descent(to_pdf(face.descender() as f32))
+            .cap_height(to_pdf(
+                face.capital_height().unwrap_or(face.ascender()) as f32
+            ))
+            .stem_v(10.0 + 0.244 * (face.weight().to_number() as f32 - 50.0));
+        if is_cff {
+            descriptor.font_file3(file_ref);
+        } else {
+            descriptor.font_file2(file_ref);
+        }
+        descriptor.finish();
+
+        let mut cmap = UnicodeCmap::new(
+            Name(b"Custom"),
+            SystemInfo {
+                registry: Str(b"Adobe"),
+                ordering: Str(b"UCS"),
+                supplement: 0,
+            },
+        );
+        for (&glyph, &ch) in &font.glyphs {
+            cmap.pair(glyph, ch);
+        }
+        let cmap_data = deflate(&cmap.finish());
+        self.pdf
+            .cmap(cmap_ref, &cmap_data)
+            .filter(Filter::FlateDecode);
+
+        let file_data = deflate(&subset);
+        let mut stream = self.pdf.stream(file_ref, &file_data);
+        stream.filter(Filter::FlateDecode);
+        if is_cff {
+            stream.pair(Name(b"Subtype"), Name(b"OpenType"));
+        }
+        stream.finish();
+        Ok(())
+    }
+}
+
+fn write_path(content: &mut Content, segments: &[Segment]) {
+    let mut current = (0.0_f32, 0.0_f32);
+    for seg in segments {
+        match *seg {
+            Segment::Move(p) => {
+                current = (p.x as f32, p.y as f32);
+                content.move_to(current.0, current.1);
+            }
+            Segment::Line(p) => {
+                current = (p.x as f32, p.y as f32);
+                content.line_to(current.0, current.1);
+            }
+            Segment::Quad(c, p) => {
+                // Elevate to a cubic; PDF has no quadratic curves.
+                let (x0, y0) = current;
+                let (cx, cy) = (c.x as f32, c.y as f32);
+                let (x, y) = (p.x as f32, p.y as f32);
+                content.cubic_to(
+                    x0 + 2.0 / 3.0 * (cx - x0),
+                    y0 + 2.0 / 3.0 * (cy - y0),
+                    x + 2.0 / 3.0 * (cx - x),
+                    y + 2.0 / 3.0 * (cy - y),
+                    x,
+                    y,
+                );
+                current = (x, y);
+            }
+            Segment::Cubic(c1, c2, p) => {
+                content.cubic_to(
+                    c1.x as f32,
+                    c1.y as f32,
+                    c2.x as f32,
+                    c2.y as f32,
+                    p.x as f32,
+                    p.y as f32,
+                );
+                current = (p.x as f32, p.y as f32);
+            }
+            Segment::Close => {
+                content.close_path();
+            }
+        }
+    }
+}
+
+struct DecodedPng {
+    width: u32,
+    height: u32,
+    gray: bool,
+    color: Vec<u8>,
+    alpha: Option<Vec<u8>>,
+}
+
+fn decode_png(bytes: &[u8]) -> Result<DecodedPng, String> {
+    let mut decoder = png::Decoder::new(Cursor::new(bytes));
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|e| format!("invalid PNG: {}", e))?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buf)
+        .map_err(|e| format!("invalid PNG: {}", e))?;
+    buf.truncate(info.buffer_size());
+
+    let (channels, gray, has_alpha) = match info.color_type {
+        png::ColorType::Grayscale => (1, true, false),
+        png::ColorType::GrayscaleAlpha => (2, true, true),
+        png::ColorType::Rgb => (3, false, false),
+        png::ColorType::Rgba => (4, false, true),
+        png::ColorType::Indexed => return Err("unexpanded palette PNG".to_string()),
+    };
+    let color_channels = if gray { 1 } else { 3 };
+    let mut color = Vec::with_capacity(buf.len());
+    let mut alpha = has_alpha.then(|| Vec::with_capacity(buf.len() / channels));
+    for px in buf.chunks_exact(channels) {
+        color.extend_from_slice(&px[..color_channels]);
+        if let Some(alpha) = alpha.as_mut() {
+            alpha.push(px[channels - 1]);
+        }
+    }
+    Ok(DecodedPng {
+        width: info.width,
+        height: info.height,
+        gray,
+        color,
+        alpha,
+    })
+}
+
+/// Number of color components declared in a JPEG's start-of-frame marker.
+fn jpeg_components(bytes: &[u8]) -> Option<u8> {
+    let mut i = 2;
+    while i + 9 < bytes.len() {
+        if bytes[i] != 0xFF {
+            i += 1;
+            continue;
+        }
+        let marker = bytes[i + 1];
+        let length = u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]) as usize;
+        if (0xC0..=0xCF).contains(&marker) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            return Some(bytes[i + 9]);
+        }
+        i += 2 + length;
+    }
+    None
+}
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Writing into a Vec cannot fail.
+    encoder.write_all(data).expect("in-memory write");
+    encoder.finish().expect("in-memory write")
+}
+
+/// Six-letter subset prefix required by the PDF spec, unique per font.
+fn subset_tag(index: usize) -> String {
+    let mut n = index;
+    (0..6)
+        .map(|_| {
+            let c = (b'A' + (n % 26) as u8) as char;
+            n /= 26;
+            c
+        })
+        .collect::<String>()
+        .chars()
+        .rev()
+        .collect()
+}
+
+fn average_alpha(gradient: &Gradient) -> f64 {
+    gradient.stops.iter().map(|s| s.1.a).sum::<f64>() / gradient.stops.len() as f64
+}
+
+fn quantise(alpha: f64) -> u16 {
+    (alpha.clamp(0.0, 1.0) * 1000.0).round() as u16
+}
+
+fn channel(c: u8) -> f32 {
+    c as f32 / 255.0
+}
+
+fn to_f32(m: [f64; 6]) -> [f32; 6] {
+    m.map(|v| v as f32)
+}
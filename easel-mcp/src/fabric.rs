@@ -0,0 +1,143 @@
+//! Helpers for reading Fabric.js v7 object JSON as stored in `.easel` files.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Axis-aligned box in canvas coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Bounds {
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Bounds {
+    pub fn right(&self) -> f64 {
+        self.left + self.width
+    }
+
+    pub fn bottom(&self) -> f64 {
+        self.top + self.height
+    }
+
+    pub fn center_x(&self) -> f64 {
+        self.left + self.width / 2.0
+    }
+
+    pub fn center_y(&self) -> f64 {
+        self.top + self.height / 2.0
+    }
+
+    /// Smallest box containing all of `bounds`, or `None` when empty.
+    pub fn union_all(mut bounds: impl Iterator<Item = Bounds>) -> Option<Bounds> {
+        let first = bounds.next()?;
+        let (mut l, mut t, mut r, mut b) = (first.left, first.top, first.right(), first.bottom());
+        for next in bounds {
+            l = l.min(next.left);
+            t = t.min(next.top);
+            r = r.max(next.right());
+            b = b.max(next.bottom());
+        }
+        Some(Bounds {
+            left: l,
+            top: t,
+            width: r - l,
+            height: b - t,
+        })
+    }
+
+    /// Bounds relative to another box's top-left corner.
+    pub fn relative_to(&self, parent: &Bounds) -> Bounds {
+        Bounds {
+            left: self.left - parent.left,
+            top: self.top - parent.top,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+pub fn str_prop<'a>(obj: &'a Value, key: &str) -> Option<&'a str> {
+    obj.get(key).and_then(|v| v.as_str())
+}
+
+pub fn num_prop(obj: &Value, key: &str) -> Option<f64> {
+    obj.get(key).and_then(|v| v.as_f64())
+}
+
+pub fn bool_prop(obj: &Value, key: &str) -> bool {
+    obj.get(key).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+pub fn object_id(obj: &Value) -> Option<&str> {
+    str_prop(obj, "id")
+}
+
+/// Fabric type name normalised to lowercase (v7 writes `Rect`, older files `rect`).
+pub fn object_type(obj: &Value) -> String {
+    str_prop(obj, "type").unwrap_or("").to_ascii_lowercase()
+}
+
+pub fn is_frame(obj: &Value) -> bool {
+    bool_prop(obj, "isFrame")
+}
+
+pub fn is_text(obj: &Value) -> bool {
+    matches!(
+        object_type(obj).as_str(),
+        "itext" | "i-text" | "text" | "textbox"
+    )
+}
+
+/// Direct children of a frame. Easel keeps objects flat and links them by `parentId`.
+pub fn children_of<'a>(objects: &'a [Value], parent_id: &str) -> Vec<&'a Value> {
+    objects
+        .iter()
+        .filter(|obj| str_prop(obj, "parentId") == Some(parent_id))
+        .collect()
+}
+
+/// Unscaled width, falling back to ellipse/circle radii when `width` is absent.
+pub fn base_width(obj: &Value) -> f64 {
+    num_prop(obj, "width")
+        .or_else(|| num_prop(obj, "rx").map(|rx| rx * 2.0))
+        .or_else(|| num_prop(obj, "radius").map(|r| r * 2.0))
+        .unwrap_or(0.0)
+}
+
+/// Unscaled height, falling back to ellipse/circle radii when `height` is absent.
+pub fn base_height(obj: &Value) -> f64 {
+    num_prop(obj, "height")
+        .or_else(|| num_prop(obj, "ry").map(|ry| ry * 2.0))
+        .or_else(|| num_prop(obj, "radius").map(|r| r * 2.0))
+        .unwrap_or(0.0)
+}
+
+/// Fraction of the object's size that `originX`/`originY` sits at.
+pub fn origin_factor(value: Option<&Value>) -> f64 {
+    match value {
+        Some(Value::String(s)) => match s.as_str() {
+            "center" => 0.5,
+            "right" | "bottom" => 1.0,
+            _ => 0.0,
+        },
+        Some(Value::Number(n)) => n.as_f64().unwrap_or(0.0),
+        _ => 0.0,
+    }
+}
+
+/// Unrotated bounds of an object in its parent's coordinate space,
+/// honouring `originX`/`originY` and `scaleX`/`scaleY`.
+pub fn local_bounds(obj: &Value) -> Bounds {
+    let width = base_width(obj) * num_prop(obj, "scaleX").unwrap_or(1.0).abs();
+    let height = base_height(obj) * num_prop(obj, "scaleY").unwrap_or(1.0).abs();
+    let left = num_prop(obj, "left").unwrap_or(0.0) - width * origin_factor(obj.get("originX"));
+    let top = num_prop(obj, "top").unwrap_or(0.0) - height * origin_factor(obj.get("originY"));
+    Bounds {
+        left,
+        top,
+        width,
+        height,
+    }
+}
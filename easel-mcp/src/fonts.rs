@@ -0,0 +1,344 @@
+//! System font lookup and glyph metrics for text layout and export.
+//!
+//! [`FontBook`] enumerates installed font files and resolves CSS
+//! `font-family` lists the way a browser would, so headless exports and text
+//! measurement use the same faces the app shows. Fonts a bundle carried are
+//! layered on top with [`FontBook::with_bundled`].
+
+use fontdb::{Database, Family, Query, Source, Stretch, Style, Weight, ID};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::assets::AssetStore;
+use crate::fabric;
+use crate::text;
+
+/// Families tried, in order, when none of the requested ones are installed.
+const FALLBACK_FAMILIES: &[&str] = &[
+    "Inter",
+    "Helvetica",
+    "Arial",
+    "Liberation Sans",
+    "DejaVu Sans",
+    "Noto Sans",
+];
+
+/// Raw bytes of one font face, shared between layout and export.
+pub struct FontData {
+    pub id: ID,
+    pub bytes: Vec<u8>,
+    /// Face index inside a collection (`.ttc`); 0 for single fonts.
+    pub index: u32,
+    pub post_script_name: String,
+    pub family: String,
+}
+
+impl FontData {
+    pub fn face(&self) -> Option<ttf_parser::Face<'_>> {
+        ttf_parser::Face::parse(&self.bytes, self.index).ok()
+    }
+
+    pub fn glyph_id(&self, face: &ttf_parser::Face, ch: char) -> u16 {
+        face.glyph_index(ch).map(|g| g.0).unwrap_or(0)
+    }
+
+    /// Advance of a glyph as a fraction of the em.
+    pub fn advance(&self, face: &ttf_parser::Face, glyph: u16) -> f64 {
+        let units = face.units_per_em() as f64;
+        face.glyph_hor_advance(ttf_parser::GlyphId(glyph))
+            .map(|a| a as f64 / units)
+            .unwrap_or(0.0)
+    }
+
+    /// Width of `text` at `size`, with `char_spacing` pixels added after each character.
+    pub fn measure(&self, text: &str, size: f64, char_spacing: f64) -> f64 {
+        let Some(face) = self.face() else {
+            return approximate_width(text, size, char_spacing);
+        };
+        text.chars()
+            .map(|ch| self.advance(&face, self.glyph_id(&face, ch)) * size + char_spacing)
+            .sum()
+    }
+}
+
+/// Width estimate used when no font file is available at all.
+pub fn approximate_width(text: &str, size: f64, char_spacing: f64) -> f64 {
+    text.chars().count() as f64 * (size * 0.55 + char_spacing)
+}
+
+/// File extension for a font's bytes, from its signature.
+pub fn font_extension(bytes: &[u8]) -> &'static str {
+    match bytes.get(..4) {
+        Some(b"OTTO") => "otf",
+        Some(b"ttcf") => "ttc",
+        Some(b"wOFF") => "woff",
+        Some(b"wOF2") => "woff2",
+        _ => "ttf",
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontFace {
+    pub family: String,
+    pub post_script_name: String,
+    pub weight: u16,
+    pub italic: bool,
+    pub monospaced: bool,
+    /// Font file on disk; `None` for fonts loaded from memory.
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FontFamily {
+    pub family: String,
+    pub faces: Vec<FontFace>,
+}
+
+/// How a CSS `font-family` list resolved on this machine.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolvedFont {
+    pub requested: String,
+    pub face: FontFace,
+    /// None of the named families is installed; a generic or fallback face stands in.
+    pub fallback: bool,
+}
+
+pub struct FontBook {
+    db: Database,
+    loaded: Mutex<HashMap<ID, Arc<FontData>>>,
+}
+
+impl FontBook {
+    /// Fonts installed on this machine, loaded once per process.
+    pub fn system() -> &'static FontBook {
+        static BOOK: OnceLock<FontBook> = OnceLock::new();
+        BOOK.get_or_init(|| {
+            let mut db = Database::new();
+            db.load_system_fonts();
+            FontBook::new(db)
+        })
+    }
+
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            loaded: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The system fonts plus those a canvas bundle embeds or had unpacked,
+    /// or `None` when the canvas carries no fonts of its own.
+    pub fn with_bundled(store: &AssetStore) -> Option<FontBook> {
+        let dir = store.fonts().dir().to_path_buf();
+        let embedded = store.embedded_fonts().unwrap_or_default();
+        if !dir.is_dir() && embedded.is_empty() {
+            return None;
+        }
+        let mut db = FontBook::system().db.clone();
+        if dir.is_dir() {
+            db.load_fonts_dir(dir);
+        }
+        for bytes in embedded {
+            db.load_font_data(bytes);
+        }
+        Some(FontBook::new(db))
+    }
+
+    pub fn database(&self) -> &Database {
+        &self.db
+    }
+
+    pub fn face(&self, id: ID) -> Option<FontFace> {
+        let info = self.db.face(id)?;
+        Some(FontFace {
+            family: info
+                .families
+                .first()
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default(),
+            post_script_name: info.post_script_name.clone(),
+            weight: info.weight.0,
+            italic: info.style != Style::Normal,
+            monospaced: info.monospaced,
+            path: match &info.source {
+                Source::File(path) | Source::SharedFile(path, _) => {
+                    Some(path.display().to_string())
+                }
+                Source::Binary(_) => None,
+            },
+        })
+    }
+
+    /// Resolve a `font-family` list and report whether a named family matched.
+    pub fn describe(&self, family: &str, weight: u16, italic: bool) -> Option<ResolvedFont> {
+        let face = self.face(self.resolve(family, weight, italic)?)?;
+        Some(ResolvedFont {
+            requested: family.to_string(),
+            fallback: !names_family(family, &face.family),
+            face,
+        })
+    }
+
+    /// Installed families in name order, each with its faces by weight.
+    pub fn families(&self) -> Vec<FontFamily> {
+        let mut families: BTreeMap<String, Vec<FontFace>> = BTreeMap::new();
+        for info in self.db.faces() {
+            if let Some(face) = self.face(info.id) {
+                families.entry(face.family.clone()).or_default().push(face);
+            }
+        }
+        families
+            .into_iter()
+            .map(|(family, mut faces)| {
+                faces.sort_by_key(|f| (f.weight, f.italic));
+                faces.dedup_by(|a, b| a.post_script_name == b.post_script_name);
+                FontFamily { family, faces }
+            })
+            .collect()
+    }
+
+    /// Best match for a CSS `font-family` list, weight and style, falling back
+    /// to a common sans-serif and finally to any installed face.
+    pub fn resolve(&self, family: &str, weight: u16, italic: bool) -> Option<ID> {
+        let names: Vec<String> = family
+            .split(',')
+            .map(|f| f.trim().trim_matches(['"', '\'']).to_string())
+            .filter(|f| !f.is_empty())
+            .collect();
+
+        let mut families: Vec<Family> = names
+            .iter()
+            .map(|name| match name.to_ascii_lowercase().as_str() {
+                "serif" => Family::Serif,
+                "sans-serif" | "system-ui" => Family::SansSerif,
+                "monospace" => Family::Monospace,
+                "cursive" => Family::Cursive,
+                "fantasy" => Family::Fantasy,
+                _ => Family::Name(name),
+            })
+            .collect();
+        families.extend(FALLBACK_FAMILIES.iter().map(|name| Family::Name(name)));
+
+        let query = Query {
+            families: &families,
+            weight: Weight(weight),
+            stretch: Stretch::Normal,
+            style: if italic { Style::Italic } else { Style::Normal },
+        };
+        self.db
+            .query(&query)
+            .or_else(|| self.db.faces().next().map(|face| face.id))
+    }
+
+    /// Resolve the font a Fabric text object asks for.
+    pub fn resolve_for(&self, obj: &Value) -> Option<ID> {
+        let family = fabric::str_prop(obj, "fontFamily").unwrap_or("sans-serif");
+        self.resolve(family, font_weight(obj), is_italic(obj))
+    }
+
+    pub fn load(&self, id: ID) -> Option<Arc<FontData>> {
+        let mut loaded = self.loaded.lock().ok()?;
+        if let Some(data) = loaded.get(&id) {
+            return Some(data.clone());
+        }
+        let info = self.db.face(id)?;
+        let (bytes, index) = self
+            .db
+            .with_face_data(id, |data, index| (data.to_vec(), index))?;
+        let data = Arc::new(FontData {
+            id,
+            bytes,
+            index,
+            post_script_name: info.post_script_name.clone(),
+            family: info
+                .families
+                .first()
+                .map(|(name, _)| name.clone())
+                .unwrap_or_default(),
+        });
+        loaded.insert(id, data.clone());
+        Some(data)
+    }
+}
+
+/// Numeric CSS weight of a text object (`fontWeight` may be a keyword or number).
+pub fn font_weight(obj: &Value) -> u16 {
+    match obj.get("fontWeight") {
+        Some(Value::Number(n)) => n.as_f64().unwrap_or(400.0) as u16,
+        Some(Value::String(s)) => parse_weight(s),
+        _ => 400,
+    }
+}
+
+/// Numeric CSS weight for a keyword (`bold`) or number string (`600`).
+pub fn parse_weight(weight: &str) -> u16 {
+    match weight {
+        "bold" | "bolder" => 700,
+        "lighter" => 300,
+        "normal" | "" => 400,
+        other => other.parse().unwrap_or(400),
+    }
+}
+
+pub fn is_italic(obj: &Value) -> bool {
+    matches!(
+        fabric::str_prop(obj, "fontStyle"),
+        Some("italic" | "oblique")
+    )
+}
+
+/// Whether a CSS `font-family` list names `family` itself (not just a generic).
+fn names_family(list: &str, family: &str) -> bool {
+    list.split(',')
+        .map(|f| f.trim().trim_matches(['"', '\'']))
+        .any(|name| name.eq_ignore_ascii_case(family))
+}
+
+/// Fonts the text in `canvas` asks for by name, including per-character
+/// styles. Generic families and fallbacks are left out: those are whatever
+/// the machine has, not part of the design.
+pub fn used_faces(canvas: &Value, fonts: &FontBook) -> Vec<ID> {
+    fn named_face(props: &Value, fonts: &FontBook) -> Option<ID> {
+        let family = fabric::str_prop(props, "fontFamily").unwrap_or("sans-serif");
+        let id = fonts.resolve_for(props)?;
+        names_family(family, &fonts.face(id)?.family).then_some(id)
+    }
+
+    fn walk(objects: &[Value], fonts: &FontBook, out: &mut BTreeSet<ID>) {
+        for obj in objects {
+            if fabric::is_text(obj) {
+                let styles = text::char_styles(obj);
+                let mut seen = Vec::new();
+                let mut props = vec![obj.clone()];
+                for style in styles.iter().flatten() {
+                    let changes_font = ["fontFamily", "fontWeight", "fontStyle"]
+                        .iter()
+                        .any(|key| style.contains_key(*key));
+                    if changes_font && !seen.contains(&style) {
+                        seen.push(style);
+                        let mut merged = obj.clone();
+                        for (key, value) in style {
+                            merged[key.as_str()] = value.clone();
+                        }
+                        props.push(merged);
+                    }
+                }
+                out.extend(props.iter().filter_map(|p| named_face(p, fonts)));
+            }
+            if let Some(children) = obj.get("objects").and_then(|v| v.as_array()) {
+                walk(children, fonts, out);
+            }
+        }
+    }
+
+    let mut out = BTreeSet::new();
+    if let Some(objects) = canvas.get("objects").and_then(|v| v.as_array()) {
+        walk(objects, fonts, &mut out);
+    }
+    out.into_iter().collect()
+}
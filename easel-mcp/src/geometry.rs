@@ -0,0 +1,266 @@
+//! 2D affine transforms in Fabric's `[a, b, c, d, e, f]` matrix layout.
+
+use serde_json::Value;
+
+use crate::fabric::{self, Bounds};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+}
+
+/// Affine matrix mapping `(x, y)` to `(a*x + c*y + e, b*x + d*y + f)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix(pub [f64; 6]);
+
+/// Result of splitting a matrix into Fabric object transform properties.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decomposed {
+    pub translate_x: f64,
+    pub translate_y: f64,
+    pub angle: f64,
+    pub scale_x: f64,
+    pub scale_y: f64,
+    pub skew_x: f64,
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    pub fn translate(x: f64, y: f64) -> Self {
+        Matrix([1.0, 0.0, 0.0, 1.0, x, y])
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Matrix([sx, 0.0, 0.0, sy, 0.0, 0.0])
+    }
+
+    pub fn rotate_degrees(angle: f64) -> Self {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Matrix([cos, sin, -sin, cos, 0.0, 0.0])
+    }
+
+    pub fn skew_x_degrees(angle: f64) -> Self {
+        Matrix([1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0])
+    }
+
+    pub fn skew_y_degrees(angle: f64) -> Self {
+        Matrix([1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0])
+    }
+
+    /// `self · other`: applies `other` first, then `self`.
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        let [a1, b1, c1, d1, e1, f1] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = other.0;
+        Matrix([
+            a1 * a2 + c1 * b2,
+            b1 * a2 + d1 * b2,
+            a1 * c2 + c1 * d2,
+            b1 * c2 + d1 * d2,
+            a1 * e2 + c1 * f2 + e1,
+            b1 * e2 + d1 * f2 + f1,
+        ])
+    }
+
+    pub fn invert(&self) -> Option<Matrix> {
+        let [a, b, c, d, e, f] = self.0;
+        let det = a * d - b * c;
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        Some(Matrix([
+            d / det,
+            -b / det,
+            -c / det,
+            a / det,
+            (c * f - d * e) / det,
+            (b * e - a * f) / det,
+        ]))
+    }
+
+    pub fn apply(&self, p: Point) -> Point {
+        let [a, b, c, d, e, f] = self.0;
+        Point::new(a * p.x + c * p.y + e, b * p.x + d * p.y + f)
+    }
+
+    /// Same decomposition as Fabric's `qrDecompose`.
+    pub fn decompose(&self) -> Decomposed {
+        let [a, b, c, d, e, f] = self.0;
+        let denom = a * a + b * b;
+        let scale_x = denom.sqrt();
+        let (scale_y, skew_x) = if scale_x > 0.0 {
+            (
+                (a * d - c * b) / scale_x,
+                (a * c + b * d).atan2(denom).to_degrees(),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        Decomposed {
+            translate_x: e,
+            translate_y: f,
+            angle: b.atan2(a).to_degrees(),
+            scale_x,
+            scale_y,
+            skew_x,
+        }
+    }
+
+    /// Axis-aligned bounds of `bounds` after this transform.
+    pub fn transform_bounds(&self, bounds: &Bounds) -> Bounds {
+        let corners = [
+            Point::new(bounds.left, bounds.top),
+            Point::new(bounds.right(), bounds.top),
+            Point::new(bounds.right(), bounds.bottom()),
+            Point::new(bounds.left, bounds.bottom()),
+        ]
+        .map(|p| self.apply(p));
+        bounds_of_points(&corners).unwrap_or(*bounds)
+    }
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Matrix::IDENTITY
+    }
+}
+
+pub fn bounds_of_points(points: &[Point]) -> Option<Bounds> {
+    let first = points.first()?;
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (first.x, first.y, first.x, first.y);
+    for p in &points[1..] {
+        min_x = min_x.min(p.x);
+        min_y = min_y.min(p.y);
+        max_x = max_x.max(p.x);
+        max_y = max_y.max(p.y);
+    }
+    Some(Bounds {
+        left: min_x,
+        top: min_y,
+        width: max_x - min_x,
+        height: max_y - min_y,
+    })
+}
+
+/// Write a matrix onto a Fabric object. `matrix` maps the object's unscaled
+/// local box (top-left at the origin, `width`×`height` already set on `obj`)
+/// into its parent's space; `left`/`top` are derived the way Fabric positions
+/// a left/top origin, including the stroke.
+pub fn apply_to_object(obj: &mut Value, matrix: &Matrix) {
+    let width = fabric::base_width(obj);
+    let height = fabric::base_height(obj);
+    let t = matrix.decompose();
+    let center = matrix.apply(Point::new(width / 2.0, height / 2.0));
+
+    let (dim_x, dim_y) = transformed_dimensions(obj, t.scale_x, t.scale_y);
+    let corner = Matrix::rotate_degrees(t.angle).apply(Point::new(-dim_x / 2.0, -dim_y / 2.0));
+
+    if let Some(map) = obj.as_object_mut() {
+        map.insert("left".into(), round(center.x + corner.x).into());
+        map.insert("top".into(), round(center.y + corner.y).into());
+        map.insert("scaleX".into(), round(t.scale_x).into());
+        map.insert("scaleY".into(), round(t.scale_y).into());
+        map.insert("angle".into(), round(t.angle).into());
+        if t.skew_x.abs() > 1e-6 {
+            map.insert("skewX".into(), round(t.skew_x).into());
+        }
+        map.insert("originX".into(), "left".into());
+        map.insert("originY".into(), "top".into());
+    }
+}
+
+/// Scaled size of an object's box including its stroke, as Fabric's
+/// `_getTransformedDimensions` computes it (ignoring skew).
+pub fn transformed_dimensions(obj: &Value, scale_x: f64, scale_y: f64) -> (f64, f64) {
+    let stroke_width = fabric::num_prop(obj, "strokeWidth").unwrap_or(0.0);
+    let (pre, post) = if fabric::bool_prop(obj, "strokeUniform") {
+        (0.0, stroke_width)
+    } else {
+        (stroke_width, 0.0)
+    };
+    (
+        (fabric::base_width(obj) + pre) * scale_x.abs() + post,
+        (fabric::base_height(obj) + pre) * scale_y.abs() + post,
+    )
+}
+
+/// The object's own transform: maps its unscaled local box (top-left at the
+/// origin) into its parent's coordinate space, as Fabric's `calcOwnMatrix` does.
+pub fn object_matrix(obj: &Value) -> Matrix {
+    let width = fabric::base_width(obj);
+    let height = fabric::base_height(obj);
+    let sx = fabric::num_prop(obj, "scaleX").unwrap_or(1.0);
+    let sy = fabric::num_prop(obj, "scaleY").unwrap_or(1.0);
+    let angle = fabric::num_prop(obj, "angle").unwrap_or(0.0);
+    let skew_x = fabric::num_prop(obj, "skewX").unwrap_or(0.0);
+    let skew_y = fabric::num_prop(obj, "skewY").unwrap_or(0.0);
+    let flip_x = if fabric::bool_prop(obj, "flipX") {
+        -1.0
+    } else {
+        1.0
+    };
+    let flip_y = if fabric::bool_prop(obj, "flipY") {
+        -1.0
+    } else {
+        1.0
+    };
+
+    // left/top locate the origin point of the stroke-inclusive box; find the center.
+    let (dim_x, dim_y) = transformed_dimensions(obj, sx, sy);
+    let ox = fabric::origin_factor(obj.get("originX"));
+    let oy = fabric::origin_factor(obj.get("originY"));
+    let offset =
+        Matrix::rotate_degrees(angle).apply(Point::new((0.5 - ox) * dim_x, (0.5 - oy) * dim_y));
+    let center_x = fabric::num_prop(obj, "left").unwrap_or(0.0) + offset.x;
+    let center_y = fabric::num_prop(obj, "top").unwrap_or(0.0) + offset.y;
+
+    Matrix::translate(center_x, center_y)
+        .multiply(&Matrix::rotate_degrees(angle))
+        .multiply(&Matrix::scale(sx * flip_x, sy * flip_y))
+        .multiply(&Matrix::skew_x_degrees(skew_x))
+        .multiply(&Matrix::skew_y_degrees(skew_y))
+        .multiply(&Matrix::translate(-width / 2.0, -height / 2.0))
+}
+
+/// Axis-aligned bounds of an object (stroke included) in its parent's space.
+pub fn object_bounds(obj: &Value) -> Bounds {
+    object_bounds_with(obj, &Matrix::IDENTITY)
+}
+
+/// Axis-aligned bounds of an object after `parent` is applied on top of its own
+/// transform, e.g. a group's matrix for objects nested inside it.
+pub fn object_bounds_with(obj: &Value, parent: &Matrix) -> Bounds {
+    let stroke_width = fabric::num_prop(obj, "strokeWidth").unwrap_or(0.0);
+    let (pre, post) = if fabric::bool_prop(obj, "strokeUniform") {
+        (0.0, stroke_width)
+    } else {
+        (stroke_width, 0.0)
+    };
+    let local = Bounds {
+        left: -pre / 2.0,
+        top: -pre / 2.0,
+        width: fabric::base_width(obj) + pre,
+        height: fabric::base_height(obj) + pre,
+    };
+    let b = parent
+        .multiply(&object_matrix(obj))
+        .transform_bounds(&local);
+    Bounds {
+        left: b.left - post / 2.0,
+        top: b.top - post / 2.0,
+        width: b.width + post,
+        height: b.height + post,
+    }
+}
+
+pub fn round(v: f64) -> f64 {
+    let r = (v * 1000.0).round() / 1000.0;
+    r + 0.0
+}
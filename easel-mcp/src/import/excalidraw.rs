@@ -0,0 +1,527 @@
+//! Excalidraw (`.excalidraw`) scene → Fabric v7 object conversion.
+//!
+//! Rectangles, ellipses and text keep their Fabric types; diamonds, lines,
+//! arrows and freehand strokes become Paths. Excalidraw groups (`groupIds`)
+//! become nested Groups, and frames become Easel frames with their elements
+//! parented to them. Strokes are drawn clean rather than hand-drawn, and
+//! hachure and cross-hatch fills become solid fills.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::assets::{self, AssetStore};
+use crate::fabric;
+use crate::geometry::{self, Matrix, Point};
+
+use crate::shapes::{
+    end_tangent, linear_object, paint, polyline, polyline_length, start_tangent, ArrowEnd,
+    Arrowhead, LineStyle,
+};
+
+use super::whiteboard::{freehand, label_object, smooth_curve, Label};
+use super::{base_object, group_objects, path_object, place};
+use super::{import_whiteboard, ImportedScene, WhiteboardFormat};
+
+/// Excalidraw's default text line height, in font sizes.
+const DEFAULT_LINE_HEIGHT: f64 = 1.25;
+
+/// Convert an Excalidraw scene. Embedded images are copied into `assets`.
+pub fn import_excalidraw(
+    source: &str,
+    assets: Option<&AssetStore>,
+) -> Result<ImportedScene, String> {
+    import_whiteboard(source, Some(WhiteboardFormat::Excalidraw), assets)
+}
+
+pub(super) fn convert(doc: &Value, assets: Option<&AssetStore>) -> Result<ImportedScene, String> {
+    let elements = doc
+        .get("elements")
+        .and_then(|v| v.as_array())
+        .ok_or("Excalidraw file has no elements")?;
+
+    let mut scene = ImportedScene {
+        name: doc
+            .pointer("/appState/name")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .map(String::from),
+        background: doc
+            .pointer("/appState/viewBackgroundColor")
+            .and_then(|v| v.as_str())
+            .map(String::from),
+        ..Default::default()
+    };
+
+    let live: Vec<&Value> = elements
+        .iter()
+        .filter(|el| {
+            !el.get("isDeleted")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+        })
+        .collect();
+
+    // Frames go first so their contents stack above them.
+    let mut frame_ids: HashMap<&str, String> = HashMap::new();
+    for el in live.iter().filter(|el| is_frame(el)) {
+        let frame = frame_object(el);
+        if let (Some(source_id), Some(id)) = (fabric::str_prop(el, "id"), frame["id"].as_str()) {
+            frame_ids.insert(source_id, id.to_string());
+        }
+        scene.objects.push(frame);
+    }
+
+    let mut converter = Converter {
+        files: doc.get("files"),
+        assets,
+        warnings: Vec::new(),
+    };
+    let mut items = Vec::new();
+    for el in live.iter().filter(|el| !is_frame(el)) {
+        let Some(mut obj) = converter.element(el) else {
+            continue;
+        };
+        if let Some(frame_id) = fabric::str_prop(el, "frameId").and_then(|id| frame_ids.get(id)) {
+            obj["parentId"] = json!(frame_id);
+        }
+        // groupIds run from innermost to outermost.
+        let mut path: Vec<String> = el
+            .get("groupIds")
+            .and_then(|v| v.as_array())
+            .map(|ids| {
+                ids.iter()
+                    .filter_map(|id| id.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+        path.reverse();
+        items.push((path, obj));
+    }
+    scene.elements = scene.objects.len() + items.len();
+    scene.objects.extend(nest(items, 0));
+    scene.warnings = converter.warnings;
+    Ok(scene)
+}
+
+/// Wrap objects that share a group ID at `depth` (outermost first) into
+/// Groups, each placed where its first member was in the stacking order.
+fn nest(items: Vec<(Vec<String>, Value)>, depth: usize) -> Vec<Value> {
+    enum Slot {
+        Object(Value),
+        Group(Vec<(Vec<String>, Value)>),
+    }
+
+    let mut slots: Vec<Slot> = Vec::new();
+    let mut groups: HashMap<String, usize> = HashMap::new();
+    for (path, obj) in items {
+        let Some(group_id) = path.get(depth).cloned() else {
+            slots.push(Slot::Object(obj));
+            continue;
+        };
+        let index = *groups.entry(group_id).or_insert_with(|| {
+            slots.push(Slot::Group(Vec::new()));
+            slots.len() - 1
+        });
+        if let Slot::Group(members) = &mut slots[index] {
+            members.push((path, obj));
+        }
+    }
+
+    let mut out = Vec::new();
+    for slot in slots {
+        match slot {
+            Slot::Object(obj) => out.push(obj),
+            Slot::Group(members) => {
+                let mut children = nest(members, depth + 1);
+                if children.len() <= 1 {
+                    out.append(&mut children);
+                    continue;
+                }
+                // The group takes over its members' frame.
+                let parent = children[0].get("parentId").cloned();
+                for child in &mut children {
+                    if let Some(map) = child.as_object_mut() {
+                        map.remove("parentId");
+                    }
+                }
+                let mut group = group_objects(children, "Group");
+                if let Some(parent) = parent {
+                    group["parentId"] = parent;
+                }
+                out.push(group);
+            }
+        }
+    }
+    out
+}
+
+struct Converter<'a> {
+    files: Option<&'a Value>,
+    assets: Option<&'a AssetStore>,
+    warnings: Vec<String>,
+}
+
+impl Converter<'_> {
+    fn element(&mut self, el: &Value) -> Option<Value> {
+        let kind = fabric::str_prop(el, "type").unwrap_or("");
+        let (x, y, w, h) = element_box(el);
+        let angle = fabric::num_prop(el, "angle").unwrap_or(0.0);
+        let fill = paint(fabric::str_prop(el, "backgroundColor"));
+        let mut style = LineStyle {
+            color: paint(fabric::str_prop(el, "strokeColor")),
+            width: fabric::num_prop(el, "strokeWidth").unwrap_or(2.0),
+            dash: None,
+            round: false,
+        };
+        style.dash = match fabric::str_prop(el, "strokeStyle") {
+            Some("dashed") => Some(vec![8.0, 8.0 + style.width]),
+            Some("dotted") => Some(vec![1.5, 6.0 + style.width]),
+            _ => None,
+        };
+        // Excalidraw rotates every element about the center of its box.
+        let box_matrix = rotation_about(Point::new(x + w / 2.0, y + h / 2.0), angle)
+            .multiply(&Matrix::translate(x, y));
+
+        let mut obj = match kind {
+            "rectangle" => {
+                let radius = corner_radius(el, w.min(h));
+                let mut obj = base_object("Rect", "Rectangle");
+                obj["width"] = json!(w);
+                obj["height"] = json!(h);
+                obj["rx"] = json!(radius);
+                obj["ry"] = json!(radius);
+                style.apply(&mut obj, fill);
+                place(&mut obj, &box_matrix, Point::new(0.0, 0.0));
+                obj
+            }
+            "ellipse" => {
+                let mut obj = base_object("Ellipse", "Ellipse");
+                obj["rx"] = json!(w / 2.0);
+                obj["ry"] = json!(h / 2.0);
+                obj["width"] = json!(w);
+                obj["height"] = json!(h);
+                style.apply(&mut obj, fill);
+                place(&mut obj, &box_matrix, Point::new(0.0, 0.0));
+                obj
+            }
+            "diamond" => {
+                let corners = [
+                    Point::new(w / 2.0, 0.0),
+                    Point::new(w, h / 2.0),
+                    Point::new(w / 2.0, h),
+                    Point::new(0.0, h / 2.0),
+                ];
+                let (mut obj, origin) = path_object(&polyline(&corners, true), "Diamond")?;
+                style.apply(&mut obj, fill);
+                place(&mut obj, &box_matrix, origin);
+                obj
+            }
+            "text" => {
+                let text = fabric::str_prop(el, "text").unwrap_or("");
+                if text.trim().is_empty() {
+                    return None;
+                }
+                let label = Label {
+                    text,
+                    font_size: fabric::num_prop(el, "fontSize").unwrap_or(20.0),
+                    font_family: font_family(el.get("fontFamily").and_then(|v| v.as_u64())),
+                    color: fabric::str_prop(el, "strokeColor")
+                        .filter(|c| *c != "transparent")
+                        .unwrap_or("#1e1e1e"),
+                    align: fabric::str_prop(el, "textAlign").unwrap_or("left"),
+                    line_spacing: fabric::num_prop(el, "lineHeight").unwrap_or(DEFAULT_LINE_HEIGHT),
+                };
+                label_object(&label, &box_matrix, 0.0, 0.0, w)
+            }
+            "line" | "arrow" | "freedraw" => {
+                let points = points(el);
+                let bbox = geometry::bounds_of_points(&points)?;
+                // Points are relative to (x, y); the rotation center is the middle of their bounds.
+                let center = Point::new(x + bbox.center_x(), y + bbox.center_y());
+                let matrix = rotation_about(center, angle).multiply(&Matrix::translate(x, y));
+                style.round = true;
+                if kind == "freedraw" {
+                    let (mut obj, origin) = path_object(&freehand(&points, false), "Drawing")?;
+                    style.apply(&mut obj, Value::Null);
+                    place(&mut obj, &matrix, origin);
+                    obj
+                } else {
+                    self.linear(el, kind, &points, &style, fill, &matrix)?
+                }
+            }
+            "image" => self.image(el, w, h, &box_matrix)?,
+            other => {
+                self.warnings.push(format!(
+                    "Skipped unsupported {} element {}",
+                    other,
+                    fabric::str_prop(el, "id").unwrap_or("?")
+                ));
+                return None;
+            }
+        };
+
+        let opacity = fabric::num_prop(el, "opacity").unwrap_or(100.0) / 100.0;
+        if opacity < 1.0 {
+            obj["opacity"] = json!(geometry::round(opacity));
+        }
+        Some(obj)
+    }
+
+    fn linear(
+        &mut self,
+        el: &Value,
+        kind: &str,
+        points: &[Point],
+        style: &LineStyle,
+        fill: Value,
+        matrix: &Matrix,
+    ) -> Option<Value> {
+        let first = *points.first()?;
+        let last = *points.last()?;
+        let closed = kind == "line"
+            && points.len() > 3
+            && (first.x - last.x).abs() < 1e-6
+            && (first.y - last.y).abs() < 1e-6;
+        let curved = !el.get("roundness").is_none_or(|v| v.is_null())
+            && !el.get("elbowed").and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let unique = if closed {
+            &points[..points.len() - 1]
+        } else {
+            points
+        };
+        let shaft = if curved {
+            smooth_curve(unique, closed)
+        } else {
+            polyline(unique, closed)
+        };
+
+        let mut ends = Vec::new();
+        if closed || points.len() < 2 {
+            // A closed shape has no ends to put heads on
+            let what = if closed {
+                "closed line"
+            } else {
+                "single point"
+            };
+            for key in ["startArrowhead", "endArrowhead"] {
+                if let Some(name) = fabric::str_prop(el, key) {
+                    self.warnings
+                        .push(format!("Dropped {} '{}' of a {}", key, name, what));
+                }
+            }
+        } else {
+            let n = points.len();
+            // Heads follow the curve's tangent but are sized by the last straight segment.
+            let ends_of_shaft = [
+                (
+                    "startArrowhead",
+                    start_tangent(&shaft),
+                    polyline_length(&points[..2]),
+                ),
+                (
+                    "endArrowhead",
+                    end_tangent(&shaft),
+                    polyline_length(&points[n - 2..]),
+                ),
+            ];
+            for (key, tangent, segment) in ends_of_shaft {
+                let (Some(name), Some((tip, from))) = (fabric::str_prop(el, key), tangent) else {
+                    continue;
+                };
+                let head = self.arrowhead(name);
+                ends.push(ArrowEnd {
+                    head,
+                    tip,
+                    from,
+                    size: arrowhead_size(head, segment),
+                });
+            }
+        }
+
+        let fill = if closed { fill } else { Value::Null };
+        let name = if kind == "arrow" { "Arrow" } else { "Line" };
+        linear_object(shaft, &ends, style, fill, matrix, name)
+    }
+
+    fn arrowhead(&mut self, name: &str) -> Arrowhead {
+        Arrowhead::from_name(name).unwrap_or_else(|| {
+            self.warnings
+                .push(format!("Arrowhead '{}' is drawn as a plain arrow", name));
+            Arrowhead::Arrow
+        })
+    }
+
+    fn image(&mut self, el: &Value, w: f64, h: f64, matrix: &Matrix) -> Option<Value> {
+        let id = fabric::str_prop(el, "id").unwrap_or("?");
+        let Some(store) = self.assets else {
+            self.warnings.push(format!(
+                "Skipped image {}: no asset store to copy it into",
+                id
+            ));
+            return None;
+        };
+        let file = fabric::str_prop(el, "fileId").and_then(|file_id| self.files?.get(file_id));
+        let Some(data_url) = file.and_then(|f| fabric::str_prop(f, "dataURL")) else {
+            self.warnings
+                .push(format!("Skipped image {}: file data is missing", id));
+            return None;
+        };
+        let ext = file
+            .and_then(|f| fabric::str_prop(f, "mimeType"))
+            .and_then(|mime| mime.split('/').nth(1));
+        let asset = match assets::decode_data_url(data_url)
+            .and_then(|bytes| store.add_bytes(&bytes, ext))
+        {
+            Ok(asset) => asset,
+            Err(e) => {
+                self.warnings.push(format!("Skipped image {}: {}", id, e));
+                return None;
+            }
+        };
+
+        let mut obj = assets::image_object(store, &asset, 0.0, 0.0, None, None, None);
+        let natural_w = obj["width"].as_f64().unwrap_or(w).max(1.0);
+        let natural_h = obj["height"].as_f64().unwrap_or(h).max(1.0);
+        // `scale` holds ±1 per axis for flipped images.
+        let flip = el.get("scale").and_then(|v| v.as_array());
+        let flip_x = flip
+            .and_then(|s| s.first())
+            .and_then(|v| v.as_f64())
+            .unwrap_or(1.0);
+        let flip_y = flip
+            .and_then(|s| s.get(1))
+            .and_then(|v| v.as_f64())
+            .unwrap_or(1.0);
+        let image_matrix = matrix
+            .multiply(&Matrix::translate(w / 2.0, h / 2.0))
+            .multiply(&Matrix::scale(flip_x, flip_y))
+            .multiply(&Matrix::translate(-w / 2.0, -h / 2.0))
+            .multiply(&Matrix::scale(w / natural_w, h / natural_h));
+        geometry::apply_to_object(&mut obj, &image_matrix);
+        Some(obj)
+    }
+}
+
+fn frame_object(el: &Value) -> Value {
+    let name = fabric::str_prop(el, "name").unwrap_or("Frame");
+    let (x, y, w, h) = element_box(el);
+    let mut obj = base_object("Rect", name);
+    obj["isFrame"] = json!(true);
+    obj["width"] = json!(w);
+    obj["height"] = json!(h);
+    obj["fill"] = json!("#ffffff");
+    obj["stroke"] = json!("#e0e0e0");
+    obj["strokeWidth"] = json!(1);
+    obj["strokeUniform"] = json!(true);
+    let matrix = rotation_about(
+        Point::new(x + w / 2.0, y + h / 2.0),
+        fabric::num_prop(el, "angle").unwrap_or(0.0),
+    )
+    .multiply(&Matrix::translate(x, y));
+    place(&mut obj, &matrix, Point::new(0.0, 0.0));
+    obj
+}
+
+fn element_box(el: &Value) -> (f64, f64, f64, f64) {
+    let field = |key| fabric::num_prop(el, key).unwrap_or(0.0);
+    (field("x"), field("y"), field("width"), field("height"))
+}
+
+fn is_frame(el: &Value) -> bool {
+    matches!(fabric::str_prop(el, "type"), Some("frame" | "magicframe"))
+}
+
+fn points(el: &Value) -> Vec<Point> {
+    el.get("points")
+        .and_then(|v| v.as_array())
+        .map(|points| {
+            points
+                .iter()
+                .filter_map(|p| {
+                    let p = p.as_array()?;
+                    Some(Point::new(p.first()?.as_f64()?, p.get(1)?.as_f64()?))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn rotation_about(center: Point, radians: f64) -> Matrix {
+    Matrix::translate(center.x, center.y)
+        .multiply(&Matrix::rotate_degrees(radians.to_degrees()))
+        .multiply(&Matrix::translate(-center.x, -center.y))
+}
+
+/// Excalidraw's `getCornerRadius`: a quarter of the shorter side, capped at a
+/// fixed radius for adaptive rounding.
+fn corner_radius(el: &Value, size: f64) -> f64 {
+    let Some(roundness) = el.get("roundness").filter(|v| !v.is_null()) else {
+        return 0.0;
+    };
+    match roundness.get("type").and_then(|v| v.as_u64()) {
+        Some(1 | 2) => size * 0.25,
+        Some(3) => {
+            let fixed = fabric::num_prop(roundness, "value").unwrap_or(32.0);
+            if size <= fixed / 0.25 {
+                size * 0.25
+            } else {
+                fixed
+            }
+        }
+        _ => 0.0,
+    }
+}
+
+/// Excalidraw's arrowhead sizes, shrunk on short segments.
+fn arrowhead_size(head: Arrowhead, segment: f64) -> f64 {
+    let (size, max_share) = match head {
+        Arrowhead::Arrow => (25.0, 0.5),
+        Arrowhead::Diamond | Arrowhead::DiamondOutline => (12.0, 0.25),
+        _ => (15.0, 0.5),
+    };
+    f64::min(size, segment * max_share)
+}
+
+fn font_family(id: Option<u64>) -> &'static str {
+    match id {
+        Some(2) => "Helvetica, Arial, sans-serif",
+        Some(3) => "Cascadia Code, monospace",
+        Some(5) => "Excalifont, cursive",
+        Some(6) => "Nunito, sans-serif",
+        Some(7) => "Lilita One, sans-serif",
+        Some(8) => "Comic Shanns, cursive",
+        Some(9) => "Liberation Sans, sans-serif",
+        _ => "Virgil, cursive",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn imports_the_grouped_fixture() {
+        let scene = import_excalidraw(include_str!("fixtures/grouped.excalidraw"), None).unwrap();
+
+        assert_eq!(scene.name.as_deref(), Some("Fixture"));
+        assert_eq!(scene.elements, 5);
+        let types: Vec<&str> = scene
+            .objects
+            .iter()
+            .map(|o| o["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(types, ["Group", "IText", "Group", "Path"]);
+        assert_eq!(scene.objects[0]["objects"].as_array().unwrap().len(), 2);
+
+        // The arrow's filled triangle head is drawn beside its shaft
+        let arrow = &scene.objects[2];
+        assert_eq!(arrow["name"], "Arrow");
+        assert_eq!(arrow["objects"][1]["name"], "Arrowhead");
+
+        assert_eq!(
+            scene.warnings,
+            vec!["Dropped endArrowhead 'arrow' of a closed line".to_string()]
+        );
+    }
+}
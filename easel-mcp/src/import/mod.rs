@@ -0,0 +1,78 @@
+//! Converters from other vector formats into Fabric v7 object JSON.
+
+pub mod excalidraw;
+pub mod svg;
+pub mod tldraw;
+mod whiteboard;
+
+pub use whiteboard::{import_whiteboard, ImportedScene, WhiteboardFormat};
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::fabric::{self, Bounds};
+use crate::geometry::{self, Matrix, Point};
+use crate::path::{self, Segment};
+
+pub(crate) fn new_object_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+pub(crate) fn base_object(fabric_type: &str, name: &str) -> Value {
+    json!({
+        "type": fabric_type,
+        "id": new_object_id(),
+        "name": name,
+        "originX": "left",
+        "originY": "top",
+        "version": "7.0.0"
+    })
+}
+
+/// Wrap objects positioned in the parent's space into a Group. Group
+/// children are stored relative to the group's center.
+pub(crate) fn group_objects(mut children: Vec<Value>, name: &str) -> Value {
+    let bbox = Bounds::union_all(children.iter().map(geometry::object_bounds)).unwrap_or(Bounds {
+        left: 0.0,
+        top: 0.0,
+        width: 0.0,
+        height: 0.0,
+    });
+    let (cx, cy) = (bbox.center_x(), bbox.center_y());
+    for child in &mut children {
+        let left = fabric::num_prop(child, "left").unwrap_or(0.0);
+        let top = fabric::num_prop(child, "top").unwrap_or(0.0);
+        child["left"] = json!(geometry::round(left - cx));
+        child["top"] = json!(geometry::round(top - cy));
+    }
+
+    let mut group = base_object("Group", name);
+    group["left"] = json!(geometry::round(bbox.left));
+    group["top"] = json!(geometry::round(bbox.top));
+    group["width"] = json!(geometry::round(bbox.width));
+    group["height"] = json!(geometry::round(bbox.height));
+    group["objects"] = Value::Array(children);
+    group
+}
+
+/// A Path object for segments in local coordinates, returned with the local
+/// point its box starts at (pass both to [`place`]).
+pub(crate) fn path_object(segments: &[Segment], name: &str) -> Option<(Value, Point)> {
+    let bbox = path::bounds(segments)?;
+    let mut obj = base_object("Path", name);
+    obj["path"] = path::to_fabric(segments);
+    obj["width"] = json!(bbox.width);
+    obj["height"] = json!(bbox.height);
+    obj["pathOffset"] = json!({"x": bbox.center_x(), "y": bbox.center_y()});
+    Some((obj, Point::new(bbox.left, bbox.top)))
+}
+
+/// Position an object whose unscaled box starts at `origin` in the local
+/// space that `matrix` maps into the parent. Set the stroke first: it
+/// affects where Fabric puts `left`/`top`.
+pub(crate) fn place(obj: &mut Value, matrix: &Matrix, origin: Point) {
+    geometry::apply_to_object(
+        obj,
+        &matrix.multiply(&Matrix::translate(origin.x, origin.y)),
+    );
+}
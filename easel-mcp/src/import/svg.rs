@@ -0,0 +1,1016 @@
+//! SVG → Fabric v7 object conversion.
+//!
+//! Shapes keep their Fabric type where one exists (Rect, Circle, Ellipse,
+//! Line, Polyline, Polygon, Path, IText); `<g>` elements with more than one
+//! drawable child become Groups. Element and ancestor transforms are composed
+//! and decomposed back into `left`/`top`/`scaleX`/`scaleY`/`angle`/`skewX`.
+
+use roxmltree::{Document, Node, ParsingOptions};
+use serde_json::{json, Map, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use crate::color::Rgba;
+use crate::fabric::{self, Bounds};
+use crate::geometry::{self, Matrix, Point};
+use crate::path;
+
+use super::{base_object, group_objects};
+
+const XLINK_NS: &str = "http://www.w3.org/1999/xlink";
+
+/// Distance from the top of a Fabric text line to its baseline, in font sizes
+/// (`_fontSizeMult` × (1 − `_fontSizeFraction`)).
+const BASELINE_RATIO: f64 = 1.13 * (1.0 - 0.222);
+
+#[derive(Debug, Clone, Default)]
+pub struct SvgImportOptions {
+    /// Translation applied to everything after the SVG's own viewport mapping.
+    pub offset_x: f64,
+    pub offset_y: f64,
+}
+
+/// Objects converted from SVG markup.
+#[derive(Debug, Default)]
+pub struct ImportedSvg {
+    pub objects: Vec<Value>,
+    /// Elements that were skipped, such as `<use>` of a missing id.
+    pub warnings: Vec<String>,
+}
+
+/// Convert SVG markup into top-level Fabric objects.
+pub fn import_svg(source: &str, options: &SvgImportOptions) -> Result<ImportedSvg, String> {
+    let doc = Document::parse_with_options(
+        source,
+        ParsingOptions {
+            allow_dtd: true,
+            ..Default::default()
+        },
+    )
+    .map_err(|e| format!("Failed to parse SVG: {}", e))?;
+
+    let root = doc.root_element();
+    if root.tag_name().name() != "svg" {
+        return Err("Root element is not <svg>".to_string());
+    }
+
+    let importer = Importer {
+        doc: &doc,
+        gradients: collect_gradients(&doc),
+        warnings: RefCell::new(Vec::new()),
+    };
+
+    let viewport = viewport_matrix(&root);
+    let base = Matrix::translate(options.offset_x, options.offset_y).multiply(&viewport);
+
+    let mut objects = Vec::new();
+    for child in root.children().filter(|n| n.is_element()) {
+        objects.extend(importer.convert(&child, &Style::default(), &base, 0));
+    }
+    Ok(ImportedSvg {
+        objects,
+        warnings: importer.warnings.into_inner(),
+    })
+}
+
+// ── Style resolution ────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Paint {
+    None,
+    Color(String),
+    Url(String),
+    CurrentColor,
+}
+
+/// Inherited presentation properties.
+#[derive(Debug, Clone)]
+struct Style {
+    fill: Paint,
+    stroke: Paint,
+    color: String,
+    stroke_width: f64,
+    fill_opacity: f64,
+    stroke_opacity: f64,
+    stroke_linecap: Option<String>,
+    stroke_linejoin: Option<String>,
+    stroke_miterlimit: Option<f64>,
+    stroke_dasharray: Option<Vec<f64>>,
+    font_family: String,
+    font_size: f64,
+    font_weight: String,
+    font_style: String,
+    text_anchor: String,
+    display_none: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Self {
+            fill: Paint::Color("#000000".to_string()),
+            stroke: Paint::None,
+            color: "#000000".to_string(),
+            stroke_width: 1.0,
+            fill_opacity: 1.0,
+            stroke_opacity: 1.0,
+            stroke_linecap: None,
+            stroke_linejoin: None,
+            stroke_miterlimit: None,
+            stroke_dasharray: None,
+            font_family: "sans-serif".to_string(),
+            font_size: 16.0,
+            font_weight: "normal".to_string(),
+            font_style: "normal".to_string(),
+            text_anchor: "start".to_string(),
+            display_none: false,
+        }
+    }
+}
+
+/// Presentation attributes plus declarations from the `style` attribute,
+/// which take precedence.
+fn declared_properties<'a>(node: &Node<'a, '_>) -> HashMap<String, String> {
+    let mut props = HashMap::new();
+    for attr in node.attributes() {
+        props.insert(attr.name().to_string(), attr.value().trim().to_string());
+    }
+    if let Some(style) = node.attribute("style") {
+        for decl in style.split(';') {
+            if let Some((k, v)) = decl.split_once(':') {
+                props.insert(
+                    k.trim().to_string(),
+                    v.trim().trim_end_matches("!important").trim().to_string(),
+                );
+            }
+        }
+    }
+    props
+}
+
+fn parse_paint(value: &str) -> Paint {
+    let v = value.trim();
+    if v == "none" || v == "transparent" {
+        Paint::None
+    } else if v == "currentColor" {
+        Paint::CurrentColor
+    } else if let Some(rest) = v.strip_prefix("url(") {
+        let id = rest
+            .split(')')
+            .next()
+            .unwrap_or("")
+            .trim()
+            .trim_matches(|c| c == '\'' || c == '"')
+            .trim_start_matches('#');
+        Paint::Url(id.to_string())
+    } else {
+        Paint::Color(v.to_string())
+    }
+}
+
+impl Style {
+    fn inherit(&self, props: &HashMap<String, String>) -> Style {
+        let mut s = self.clone();
+        s.display_none = false;
+        for (key, value) in props {
+            if value == "inherit" {
+                continue;
+            }
+            match key.as_str() {
+                "fill" => s.fill = parse_paint(value),
+                "stroke" => s.stroke = parse_paint(value),
+                "color" => s.color = value.clone(),
+                "stroke-width" => {
+                    if let Some(w) = parse_length(value, 1.0) {
+                        s.stroke_width = w;
+                    }
+                }
+                "fill-opacity" => s.fill_opacity = parse_opacity(value).unwrap_or(1.0),
+                "stroke-opacity" => s.stroke_opacity = parse_opacity(value).unwrap_or(1.0),
+                "stroke-linecap" => s.stroke_linecap = Some(value.clone()),
+                "stroke-linejoin" => s.stroke_linejoin = Some(value.clone()),
+                "stroke-miterlimit" => s.stroke_miterlimit = value.parse().ok(),
+                "stroke-dasharray" => {
+                    s.stroke_dasharray = if value == "none" {
+                        None
+                    } else {
+                        Some(parse_number_list(value))
+                    }
+                }
+                "font-family" => s.font_family = value.clone(),
+                "font-size" => {
+                    if let Some(size) = parse_length(value, s.font_size) {
+                        s.font_size = size;
+                    }
+                }
+                "font-weight" => s.font_weight = value.clone(),
+                "font-style" => s.font_style = value.clone(),
+                "text-anchor" => s.text_anchor = value.clone(),
+                "display" => s.display_none = value == "none",
+                "visibility" if value == "hidden" || value == "collapse" => {
+                    s.display_none = true;
+                }
+                _ => {}
+            }
+        }
+        s
+    }
+}
+
+// ── Conversion ──────────────────────────────────────────────────────────────
+
+struct Importer<'d, 'input> {
+    doc: &'d Document<'input>,
+    gradients: HashMap<String, Gradient>,
+    warnings: RefCell<Vec<String>>,
+}
+
+impl<'d, 'input> Importer<'d, 'input> {
+    fn convert(
+        &self,
+        node: &Node<'d, 'input>,
+        parent_style: &Style,
+        parent_matrix: &Matrix,
+        depth: usize,
+    ) -> Vec<Value> {
+        // Guard against `<use>` cycles.
+        if depth > 64 {
+            return Vec::new();
+        }
+
+        let props = declared_properties(node);
+        let style = parent_style.inherit(&props);
+        if style.display_none {
+            return Vec::new();
+        }
+
+        let matrix = match props.get("transform") {
+            Some(t) => parent_matrix.multiply(&parse_transform(t)),
+            None => *parent_matrix,
+        };
+        let opacity = props
+            .get("opacity")
+            .and_then(|v| parse_opacity(v))
+            .unwrap_or(1.0);
+        let name = props.get("id").cloned();
+
+        let attr = |key: &str| {
+            props
+                .get(key)
+                .and_then(|v| parse_length(v, 0.0))
+                .unwrap_or(0.0)
+        };
+
+        let mut object = match node.tag_name().name() {
+            "g" | "a" | "switch" => {
+                return self.convert_group(node, &style, &matrix, opacity, name, depth);
+            }
+            "svg" => {
+                let x = attr("x");
+                let y = attr("y");
+                let inner = matrix
+                    .multiply(&Matrix::translate(x, y))
+                    .multiply(&viewport_matrix(node));
+                return self.convert_group(node, &style, &inner, opacity, name, depth);
+            }
+            "use" => {
+                let href = node
+                    .attribute((XLINK_NS, "href"))
+                    .or_else(|| node.attribute("href"))
+                    .unwrap_or("")
+                    .trim_start_matches('#');
+                let Some(target) = self
+                    .doc
+                    .descendants()
+                    .find(|n| n.is_element() && n.attribute("id") == Some(href))
+                else {
+                    self.warnings
+                        .borrow_mut()
+                        .push(format!("Skipped <use> of missing element #{}", href));
+                    return Vec::new();
+                };
+                let inner = matrix.multiply(&Matrix::translate(attr("x"), attr("y")));
+                let mut out = self.convert(&target, &style, &inner, depth + 1);
+                if opacity < 1.0 {
+                    for obj in &mut out {
+                        multiply_opacity(obj, opacity);
+                    }
+                }
+                return out;
+            }
+            "rect" => {
+                let (w, h) = (attr("width"), attr("height"));
+                if w <= 0.0 || h <= 0.0 {
+                    return Vec::new();
+                }
+                let rx = props.get("rx").and_then(|v| parse_length(v, 0.0));
+                let ry = props.get("ry").and_then(|v| parse_length(v, 0.0));
+                let (rx, ry) = match (rx, ry) {
+                    (Some(rx), Some(ry)) => (rx, ry),
+                    (Some(r), None) | (None, Some(r)) => (r, r),
+                    (None, None) => (0.0, 0.0),
+                };
+                let mut obj = base_object("Rect", name.as_deref().unwrap_or("Rectangle"));
+                obj["width"] = json!(w);
+                obj["height"] = json!(h);
+                obj["rx"] = json!(rx.min(w / 2.0));
+                obj["ry"] = json!(ry.min(h / 2.0));
+                self.finish_shape(obj, &style, &matrix, Point::new(attr("x"), attr("y")))
+            }
+            "circle" => {
+                let r = attr("r");
+                if r <= 0.0 {
+                    return Vec::new();
+                }
+                let mut obj = base_object("Circle", name.as_deref().unwrap_or("Ellipse"));
+                obj["radius"] = json!(r);
+                obj["width"] = json!(2.0 * r);
+                obj["height"] = json!(2.0 * r);
+                obj["startAngle"] = json!(0);
+                obj["endAngle"] = json!(360);
+                let origin = Point::new(attr("cx") - r, attr("cy") - r);
+                self.finish_shape(obj, &style, &matrix, origin)
+            }
+            "ellipse" => {
+                let (rx, ry) = (attr("rx"), attr("ry"));
+                if rx <= 0.0 || ry <= 0.0 {
+                    return Vec::new();
+                }
+                let mut obj = base_object("Ellipse", name.as_deref().unwrap_or("Ellipse"));
+                obj["rx"] = json!(rx);
+                obj["ry"] = json!(ry);
+                obj["width"] = json!(2.0 * rx);
+                obj["height"] = json!(2.0 * ry);
+                let origin = Point::new(attr("cx") - rx, attr("cy") - ry);
+                self.finish_shape(obj, &style, &matrix, origin)
+            }
+            "line" => {
+                let (x1, y1, x2, y2) = (attr("x1"), attr("y1"), attr("x2"), attr("y2"));
+                let mut obj = base_object("Line", name.as_deref().unwrap_or("Line"));
+                obj["x1"] = json!(x1);
+                obj["y1"] = json!(y1);
+                obj["x2"] = json!(x2);
+                obj["y2"] = json!(y2);
+                obj["width"] = json!((x2 - x1).abs());
+                obj["height"] = json!((y2 - y1).abs());
+                let origin = Point::new(x1.min(x2), y1.min(y2));
+                // Lines have no fill in SVG regardless of the inherited value.
+                let line_style = Style {
+                    fill: Paint::None,
+                    ..style.clone()
+                };
+                self.finish_shape(obj, &line_style, &matrix, origin)
+            }
+            tag @ ("polyline" | "polygon") => {
+                let numbers =
+                    parse_number_list(props.get("points").map(|s| s.as_str()).unwrap_or(""));
+                let points: Vec<Point> = numbers
+                    .chunks_exact(2)
+                    .map(|p| Point::new(p[0], p[1]))
+                    .collect();
+                let Some(bbox) = geometry::bounds_of_points(&points) else {
+                    return Vec::new();
+                };
+                let (fabric_type, default_name) = if tag == "polygon" {
+                    ("Polygon", "Polygon")
+                } else {
+                    ("Polyline", "Polyline")
+                };
+                let mut obj = base_object(fabric_type, name.as_deref().unwrap_or(default_name));
+                obj["points"] = Value::Array(
+                    points
+                        .iter()
+                        .map(|p| json!({"x": geometry::round(p.x), "y": geometry::round(p.y)}))
+                        .collect(),
+                );
+                obj["width"] = json!(bbox.width);
+                obj["height"] = json!(bbox.height);
+                obj["pathOffset"] = json!({"x": bbox.center_x(), "y": bbox.center_y()});
+                self.finish_shape(obj, &style, &matrix, Point::new(bbox.left, bbox.top))
+            }
+            "path" => {
+                let Some(d) = props.get("d") else {
+                    return Vec::new();
+                };
+                let Ok(segments) = path::parse(d) else {
+                    return Vec::new();
+                };
+                let Some(bbox) = path::bounds(&segments) else {
+                    return Vec::new();
+                };
+                let mut obj = base_object("Path", name.as_deref().unwrap_or("Path"));
+                obj["path"] = path::to_fabric(&segments);
+                obj["width"] = json!(bbox.width);
+                obj["height"] = json!(bbox.height);
+                obj["pathOffset"] = json!({"x": bbox.center_x(), "y": bbox.center_y()});
+                if let Some(rule) = props.get("fill-rule") {
+                    obj["fillRule"] = json!(rule);
+                }
+                self.finish_shape(obj, &style, &matrix, Point::new(bbox.left, bbox.top))
+            }
+            "text" => match self.convert_text(node, &style, &matrix, name) {
+                Some(obj) => obj,
+                None => return Vec::new(),
+            },
+            _ => return Vec::new(),
+        };
+
+        multiply_opacity(&mut object, opacity);
+        vec![object]
+    }
+
+    fn convert_group(
+        &self,
+        node: &Node<'d, 'input>,
+        style: &Style,
+        matrix: &Matrix,
+        opacity: f64,
+        name: Option<String>,
+        depth: usize,
+    ) -> Vec<Value> {
+        let mut children = Vec::new();
+        for child in node.children().filter(|n| n.is_element()) {
+            children.extend(self.convert(&child, style, matrix, depth + 1));
+        }
+
+        if children.len() <= 1 {
+            for child in &mut children {
+                multiply_opacity(child, opacity);
+            }
+            return children;
+        }
+
+        let mut group = group_objects(children, name.as_deref().unwrap_or("Group"));
+        if opacity < 1.0 {
+            group["opacity"] = json!(opacity);
+        }
+        vec![group]
+    }
+
+    fn convert_text(
+        &self,
+        node: &Node<'d, 'input>,
+        style: &Style,
+        matrix: &Matrix,
+        name: Option<String>,
+    ) -> Option<Value> {
+        let first_number = |n: &Node, key: &str| {
+            n.attribute(key)
+                .map(parse_number_list)
+                .and_then(|v| v.first().copied())
+        };
+        let x = first_number(node, "x").unwrap_or(0.0);
+        let y = first_number(node, "y").unwrap_or(0.0);
+
+        // A <tspan> that repositions (x/y/dy) starts a new line.
+        let mut lines: Vec<String> = vec![String::new()];
+        for child in node.children() {
+            if child.is_text() {
+                lines
+                    .last_mut()?
+                    .push_str(&collapse_whitespace(child.text().unwrap_or("")));
+            } else if child.is_element() && child.tag_name().name() == "tspan" {
+                let breaks = child.attribute("y").is_some() || child.attribute("dy").is_some();
+                if breaks && !lines.last()?.trim().is_empty() {
+                    lines.push(String::new());
+                }
+                let text: String = child
+                    .descendants()
+                    .filter(|n| n.is_text())
+                    .filter_map(|n| n.text())
+                    .collect();
+                lines.last_mut()?.push_str(&collapse_whitespace(&text));
+            }
+        }
+        let text = lines
+            .iter()
+            .map(|l| l.trim())
+            .collect::<Vec<_>>()
+            .join("\n");
+        if text.trim().is_empty() {
+            return None;
+        }
+
+        let t = matrix.decompose();
+        let anchor = matrix.apply(Point::new(x, y - style.font_size * BASELINE_RATIO));
+        let origin_x = match style.text_anchor.as_str() {
+            "middle" => "center",
+            "end" => "right",
+            _ => "left",
+        };
+
+        let fill = self.resolve_paint(&style.fill, style, style.fill_opacity, None);
+        let mut obj = base_object("IText", name.as_deref().unwrap_or("Text"));
+        let longest = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        obj["text"] = json!(text);
+        obj["left"] = json!(geometry::round(anchor.x));
+        obj["top"] = json!(geometry::round(anchor.y));
+        // Rough size until Fabric measures the text on load.
+        obj["width"] = json!(geometry::round(longest as f64 * style.font_size * 0.55));
+        obj["height"] = json!(geometry::round(
+            text.lines().count() as f64 * style.font_size * 1.16 * 1.13
+        ));
+        obj["originX"] = json!(origin_x);
+        obj["originY"] = json!("top");
+        obj["scaleX"] = json!(geometry::round(t.scale_x));
+        obj["scaleY"] = json!(geometry::round(t.scale_y));
+        obj["angle"] = json!(geometry::round(t.angle));
+        obj["fontSize"] = json!(style.font_size);
+        obj["fontFamily"] = json!(style.font_family);
+        obj["fontWeight"] = json!(style.font_weight);
+        obj["fontStyle"] = json!(style.font_style);
+        obj["textAlign"] = json!(match origin_x {
+            "center" => "center",
+            "right" => "right",
+            _ => "left",
+        });
+        obj["fill"] = fill;
+        obj["strokeWidth"] = json!(0);
+        Some(obj)
+    }
+
+    /// Apply paint, stroke properties and the transform to a shape whose
+    /// unscaled local box starts at `origin` in its own user space.
+    fn finish_shape(&self, mut obj: Value, style: &Style, matrix: &Matrix, origin: Point) -> Value {
+        let bbox = Bounds {
+            left: origin.x,
+            top: origin.y,
+            width: fabric::base_width(&obj),
+            height: fabric::base_height(&obj),
+        };
+
+        obj["fill"] = self.resolve_paint(&style.fill, style, style.fill_opacity, Some(&bbox));
+        let stroke = self.resolve_paint(&style.stroke, style, style.stroke_opacity, Some(&bbox));
+        let has_stroke = !stroke.is_null();
+        obj["stroke"] = stroke;
+        obj["strokeWidth"] = json!(if has_stroke { style.stroke_width } else { 0.0 });
+        if let Some(cap) = &style.stroke_linecap {
+            obj["strokeLineCap"] = json!(cap);
+        }
+        if let Some(join) = &style.stroke_linejoin {
+            obj["strokeLineJoin"] = json!(join);
+        }
+        if let Some(limit) = style.stroke_miterlimit {
+            obj["strokeMiterLimit"] = json!(limit);
+        }
+        if let Some(dash) = &style.stroke_dasharray {
+            obj["strokeDashArray"] = json!(dash);
+        }
+
+        let local = matrix.multiply(&Matrix::translate(origin.x, origin.y));
+        geometry::apply_to_object(&mut obj, &local);
+        obj
+    }
+
+    fn resolve_paint(
+        &self,
+        paint: &Paint,
+        style: &Style,
+        opacity: f64,
+        bbox: Option<&Bounds>,
+    ) -> Value {
+        let with_opacity = |color: &str| match Rgba::parse(color) {
+            Some(c) if opacity < 1.0 => json!(c.with_alpha(c.a * opacity).to_css()),
+            _ => json!(color),
+        };
+        match paint {
+            Paint::None => Value::Null,
+            Paint::Color(c) => with_opacity(c),
+            Paint::CurrentColor => with_opacity(&style.color),
+            Paint::Url(id) => match self.gradients.get(id) {
+                Some(gradient) => gradient.to_fabric(bbox, opacity),
+                None => Value::Null,
+            },
+        }
+    }
+}
+
+fn multiply_opacity(obj: &mut Value, opacity: f64) {
+    if opacity >= 1.0 {
+        return;
+    }
+    let current = fabric::num_prop(obj, "opacity").unwrap_or(1.0);
+    obj["opacity"] = json!(geometry::round(current * opacity));
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut last_space = false;
+    for c in s.chars() {
+        if c.is_whitespace() {
+            if !last_space {
+                out.push(' ');
+            }
+            last_space = true;
+        } else {
+            out.push(c);
+            last_space = false;
+        }
+    }
+    out
+}
+
+// ── Gradients ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+struct Gradient {
+    radial: bool,
+    user_space: bool,
+    coords: HashMap<&'static str, f64>,
+    stops: Vec<(f64, String, f64)>,
+    transform: Option<Matrix>,
+}
+
+impl Gradient {
+    fn to_fabric(&self, bbox: Option<&Bounds>, opacity: f64) -> Value {
+        let mut coords = Map::new();
+        for (k, v) in &self.coords {
+            // Fabric pixel coordinates are relative to the object's top-left.
+            let shifted = match (self.user_space, bbox, *k) {
+                (true, Some(b), "x1" | "x2") => v - b.left,
+                (true, Some(b), "y1" | "y2") => v - b.top,
+                _ => *v,
+            };
+            coords.insert(k.to_string(), json!(geometry::round(shifted)));
+        }
+        let stops: Vec<Value> = self
+            .stops
+            .iter()
+            .map(|(offset, color, stop_opacity)| {
+                json!({"offset": offset, "color": color, "opacity": stop_opacity * opacity})
+            })
+            .collect();
+
+        let mut gradient = json!({
+            "type": if self.radial { "radial" } else { "linear" },
+            "gradientUnits": if self.user_space { "pixels" } else { "percentage" },
+            "coords": coords,
+            "colorStops": stops,
+            "offsetX": 0,
+            "offsetY": 0
+        });
+        if let Some(m) = self.transform {
+            gradient["gradientTransform"] = json!(m.0.to_vec());
+        }
+        gradient
+    }
+}
+
+fn collect_gradients(doc: &Document) -> HashMap<String, Gradient> {
+    let by_id: HashMap<&str, Node> = doc
+        .descendants()
+        .filter(|n| {
+            n.is_element() && matches!(n.tag_name().name(), "linearGradient" | "radialGradient")
+        })
+        .filter_map(|n| Some((n.attribute("id")?, n)))
+        .collect();
+
+    let mut out = HashMap::new();
+    for (id, node) in &by_id {
+        // Walk the href chain for stops and inherited attributes.
+        let mut chain = vec![*node];
+        let mut current = *node;
+        while let Some(href) = current
+            .attribute((XLINK_NS, "href"))
+            .or_else(|| current.attribute("href"))
+        {
+            match by_id.get(href.trim_start_matches('#')) {
+                Some(next) if chain.len() < 16 && !chain.contains(next) => {
+                    chain.push(*next);
+                    current = *next;
+                }
+                _ => break,
+            }
+        }
+        let lookup = |key: &str| chain.iter().find_map(|n| n.attribute(key));
+
+        let radial = node.tag_name().name() == "radialGradient";
+        let user_space = lookup("gradientUnits") == Some("userSpaceOnUse");
+        let coord = |key: &str, default: f64| {
+            lookup(key)
+                .and_then(|v| parse_fraction(v, user_space))
+                .unwrap_or(default)
+        };
+
+        let mut coords = HashMap::new();
+        if radial {
+            let cx = coord("cx", 0.5);
+            let cy = coord("cy", 0.5);
+            coords.insert("x1", coord("fx", cx));
+            coords.insert("y1", coord("fy", cy));
+            coords.insert("r1", coord("fr", 0.0));
+            coords.insert("x2", cx);
+            coords.insert("y2", cy);
+            coords.insert("r2", coord("r", 0.5));
+        } else {
+            coords.insert("x1", coord("x1", 0.0));
+            coords.insert("y1", coord("y1", 0.0));
+            coords.insert("x2", coord("x2", 1.0));
+            coords.insert("y2", coord("y2", 0.0));
+        }
+
+        let stops = chain
+            .iter()
+            .map(|n| {
+                n.children()
+                    .filter(|c| c.is_element() && c.tag_name().name() == "stop")
+                    .map(|stop| {
+                        let props = declared_properties(&stop);
+                        let offset = props
+                            .get("offset")
+                            .and_then(|v| parse_fraction(v, false))
+                            .unwrap_or(0.0)
+                            .clamp(0.0, 1.0);
+                        let color = props
+                            .get("stop-color")
+                            .cloned()
+                            .unwrap_or_else(|| "#000000".to_string());
+                        let opacity = props
+                            .get("stop-opacity")
+                            .and_then(|v| parse_opacity(v))
+                            .unwrap_or(1.0);
+                        (offset, color, opacity)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .find(|stops| !stops.is_empty())
+            .unwrap_or_default();
+
+        out.insert(
+            id.to_string(),
+            Gradient {
+                radial,
+                user_space,
+                coords,
+                stops,
+                transform: lookup("gradientTransform").map(parse_transform),
+            },
+        );
+    }
+    out
+}
+
+// ── Attribute parsing ───────────────────────────────────────────────────────
+
+/// Map the root viewBox onto the viewport (`preserveAspectRatio` xMidYMid meet).
+fn viewport_matrix(node: &Node) -> Matrix {
+    let view_box = node.attribute("viewBox").map(parse_number_list);
+    let Some([vx, vy, vw, vh]) = view_box.and_then(|v| <[f64; 4]>::try_from(v).ok()) else {
+        return Matrix::IDENTITY;
+    };
+    if vw <= 0.0 || vh <= 0.0 {
+        return Matrix::IDENTITY;
+    }
+    let width = node
+        .attribute("width")
+        .and_then(|v| parse_length(v, vw))
+        .unwrap_or(vw);
+    let height = node
+        .attribute("height")
+        .and_then(|v| parse_length(v, vh))
+        .unwrap_or(vh);
+
+    let (sx, sy) = (width / vw, height / vh);
+    let aspect = node.attribute("preserveAspectRatio").unwrap_or("xMidYMid");
+    if aspect.starts_with("none") {
+        return Matrix::scale(sx, sy).multiply(&Matrix::translate(-vx, -vy));
+    }
+    let s = if aspect.contains("slice") {
+        sx.max(sy)
+    } else {
+        sx.min(sy)
+    };
+    let align = |axis_min: &str, axis_mid: &str, free: f64| {
+        if aspect.contains(axis_min) {
+            0.0
+        } else if aspect.contains(axis_mid) {
+            free / 2.0
+        } else {
+            free
+        }
+    };
+    let tx = align("xMin", "xMid", width - vw * s);
+    let ty = align("YMin", "YMid", height - vh * s);
+    Matrix::translate(tx, ty)
+        .multiply(&Matrix::scale(s, s))
+        .multiply(&Matrix::translate(-vx, -vy))
+}
+
+fn parse_transform(value: &str) -> Matrix {
+    let mut result = Matrix::IDENTITY;
+    for part in value.split(')') {
+        let Some((name, args)) = part.split_once('(') else {
+            continue;
+        };
+        let name = name.trim().trim_start_matches(',').trim();
+        let a = parse_number_list(args);
+        let arg = |i: usize, default: f64| a.get(i).copied().unwrap_or(default);
+        let m = match name {
+            "matrix" if a.len() == 6 => Matrix([a[0], a[1], a[2], a[3], a[4], a[5]]),
+            "translate" => Matrix::translate(arg(0, 0.0), arg(1, 0.0)),
+            "scale" => Matrix::scale(arg(0, 1.0), arg(1, arg(0, 1.0))),
+            "rotate" => {
+                let (cx, cy) = (arg(1, 0.0), arg(2, 0.0));
+                Matrix::translate(cx, cy)
+                    .multiply(&Matrix::rotate_degrees(arg(0, 0.0)))
+                    .multiply(&Matrix::translate(-cx, -cy))
+            }
+            "skewX" => Matrix::skew_x_degrees(arg(0, 0.0)),
+            "skewY" => Matrix::skew_y_degrees(arg(0, 0.0)),
+            _ => continue,
+        };
+        result = result.multiply(&m);
+    }
+    result
+}
+
+fn parse_number_list(value: &str) -> Vec<f64> {
+    value
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| parse_length(s, 0.0))
+        .collect()
+}
+
+/// Parse a length with an optional unit. `reference` resolves percentages and `em`.
+fn parse_length(value: &str, reference: f64) -> Option<f64> {
+    let v = value.trim();
+    let units: [(&str, f64); 8] = [
+        ("px", 1.0),
+        ("pt", 4.0 / 3.0),
+        ("pc", 16.0),
+        ("mm", 96.0 / 25.4),
+        ("cm", 96.0 / 2.54),
+        ("in", 96.0),
+        ("em", reference),
+        ("%", reference / 100.0),
+    ];
+    for (unit, factor) in units {
+        if let Some(n) = v.strip_suffix(unit) {
+            return n.trim().parse::<f64>().ok().map(|n| n * factor);
+        }
+    }
+    v.parse::<f64>().ok()
+}
+
+/// Gradient offsets and coordinates: `50%` or `0.5` (or plain numbers in user space).
+fn parse_fraction(value: &str, user_space: bool) -> Option<f64> {
+    let v = value.trim();
+    match v.strip_suffix('%') {
+        Some(pct) => pct.trim().parse::<f64>().ok().map(|p| p / 100.0),
+        None if user_space => parse_length(v, 0.0),
+        None => v.parse::<f64>().ok(),
+    }
+}
+
+fn parse_opacity(value: &str) -> Option<f64> {
+    parse_fraction(value, false).map(|o| o.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn import(source: &str) -> Vec<Value> {
+        import_svg(source, &SvgImportOptions::default())
+            .unwrap()
+            .objects
+    }
+
+    fn num(obj: &Value, key: &str) -> f64 {
+        obj[key].as_f64().unwrap()
+    }
+
+    #[test]
+    fn composes_element_and_ancestor_transforms() {
+        let objects = import(
+            r#"<svg xmlns="http://www.w3.org/2000/svg">
+                <g transform="translate(5 5)">
+                    <rect x="10" y="10" width="20" height="10" transform="scale(2)"/>
+                </g>
+            </svg>"#,
+        );
+        let rect = &objects[0];
+        assert_eq!(rect["type"], "Rect");
+        assert_eq!((num(rect, "left"), num(rect, "top")), (25.0, 25.0));
+        assert_eq!((num(rect, "width"), num(rect, "height")), (20.0, 10.0));
+        assert_eq!((num(rect, "scaleX"), num(rect, "scaleY")), (2.0, 2.0));
+    }
+
+    #[test]
+    fn decomposes_rotation_into_an_angle() {
+        let objects = import(
+            r#"<svg xmlns="http://www.w3.org/2000/svg">
+                <rect width="10" height="10" transform="translate(50 0) rotate(90)"/>
+            </svg>"#,
+        );
+        assert_eq!(num(&objects[0], "angle"), 90.0);
+        assert_eq!(num(&objects[0], "left"), 50.0);
+    }
+
+    #[test]
+    fn scales_the_view_box_onto_the_viewport() {
+        let objects = import(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="10 10 50 50" width="100" height="100">
+                <circle cx="35" cy="35" r="5"/>
+            </svg>"#,
+        );
+        let circle = &objects[0];
+        assert_eq!((num(circle, "left"), num(circle, "top")), (40.0, 40.0));
+        assert_eq!(num(circle, "scaleX"), 2.0);
+
+        // meet: the wide view box is centred vertically at its own scale
+        let objects = import(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 50" width="100" height="100">
+                <rect width="100" height="50"/>
+            </svg>"#,
+        );
+        assert_eq!(
+            (num(&objects[0], "left"), num(&objects[0], "top")),
+            (0.0, 25.0)
+        );
+    }
+
+    #[test]
+    fn converts_gradients_and_follows_href_chains() {
+        let objects = import(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink">
+                <defs>
+                    <linearGradient id="base" x2="0" y2="1">
+                        <stop offset="0" stop-color="#fff"/>
+                        <stop offset="100%" stop-color="#000" stop-opacity="0.5"/>
+                    </linearGradient>
+                    <linearGradient id="pixels" xlink:href="#base"
+                        gradientUnits="userSpaceOnUse" x1="10" x2="110"/>
+                    <radialGradient id="glow" r="0.25">
+                        <stop offset="0.5" stop-color="red"/>
+                    </radialGradient>
+                </defs>
+                <rect width="10" height="10" fill="url(#base)"/>
+                <rect x="10" width="100" height="10" fill="url(#pixels)"/>
+                <circle cx="50" cy="50" r="10" fill="url(#glow)"/>
+            </svg>"##,
+        );
+        assert_eq!(objects.len(), 3);
+
+        let base = &objects[0]["fill"];
+        assert_eq!(base["type"], "linear");
+        assert_eq!(base["gradientUnits"], "percentage");
+        assert_eq!(
+            base["coords"],
+            json!({"x1": 0.0, "y1": 0.0, "x2": 0.0, "y2": 1.0})
+        );
+        assert_eq!(base["colorStops"][1]["color"], "#000");
+        assert_eq!(base["colorStops"][1]["opacity"], 0.5);
+
+        // Stops come from the referenced gradient; pixel coordinates are
+        // made relative to the shape
+        let pixels = &objects[1]["fill"];
+        assert_eq!(pixels["gradientUnits"], "pixels");
+        assert_eq!(pixels["coords"]["x1"], 0.0);
+        assert_eq!(pixels["coords"]["x2"], 100.0);
+        assert_eq!(pixels["colorStops"].as_array().unwrap().len(), 2);
+
+        let glow = &objects[2]["fill"];
+        assert_eq!(glow["type"], "radial");
+        assert_eq!(glow["coords"]["r2"], 0.25);
+        assert_eq!(glow["coords"]["x1"], 0.5);
+    }
+
+    #[test]
+    fn groups_several_children_and_unwraps_single_ones() {
+        let objects = import(
+            r#"<svg xmlns="http://www.w3.org/2000/svg">
+                <g id="pair" opacity="0.5">
+                    <rect width="10" height="10"/>
+                    <rect x="20" width="10" height="10"/>
+                </g>
+                <g opacity="0.5"><rect width="10" height="10"/></g>
+            </svg>"#,
+        );
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0]["type"], "Group");
+        assert_eq!(objects[0]["name"], "pair");
+        assert_eq!(objects[0]["objects"].as_array().unwrap().len(), 2);
+        assert_eq!(objects[0]["opacity"], 0.5);
+        assert_eq!(objects[1]["type"], "Rect");
+        assert_eq!(objects[1]["opacity"], 0.5);
+    }
+
+    #[test]
+    fn warns_about_use_of_a_missing_element() {
+        let imported = import_svg(
+            r##"<svg xmlns="http://www.w3.org/2000/svg">
+                <rect id="r" width="10" height="10"/>
+                <use href="#r" x="20"/>
+                <use href="#missing"/>
+            </svg>"##,
+            &SvgImportOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(imported.objects.len(), 2);
+        assert_eq!(
+            imported.warnings,
+            vec!["Skipped <use> of missing element #missing".to_string()]
+        );
+    }
+}
@@ -0,0 +1,993 @@
+//! tldraw (`.tldr` and store snapshot JSON) → Fabric v7 object conversion.
+//!
+//! Only the first page is imported. Geo shapes keep Fabric Rect/Ellipse types
+//! where they match and become Paths otherwise; labels, notes and arrow text
+//! are grouped with their shape. tldraw groups become Groups and frames become
+//! Easel frames. Named colors, sizes and fonts resolve through tldraw's
+//! default light theme.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::assets::{self, AssetStore};
+use crate::color::Rgba;
+use crate::fabric;
+use crate::fonts;
+use crate::geometry::{self, Matrix, Point};
+use crate::path::{self, Segment};
+
+use crate::shapes::{
+    end_tangent, linear_object, paint, polyline, polyline_length, start_tangent, ArrowEnd,
+    Arrowhead, LineStyle,
+};
+
+use super::whiteboard::{freehand, label_object, smooth_curve, Label};
+use super::{base_object, group_objects, path_object, place};
+use super::{import_whiteboard, ImportedScene, WhiteboardFormat};
+
+/// Line height tldraw uses for all text.
+const LINE_HEIGHT: f64 = 1.35;
+/// Space between a shape's edge and its label.
+const LABEL_PADDING: f64 = 16.0;
+const NOTE_SIZE: f64 = 200.0;
+/// tldraw's `theme.solid`, used by the "semi" fill.
+const SEMI_FILL: &str = "#fcfffe";
+
+/// Named colors of the default light theme: (name, solid, semi).
+const PALETTE: &[(&str, &str, &str)] = &[
+    ("black", "#1d1d1d", "#e8e8e8"),
+    ("grey", "#9fa8b2", "#eceef0"),
+    ("light-violet", "#e085f4", "#f5eafa"),
+    ("violet", "#ae3ec9", "#ecdcf2"),
+    ("blue", "#4465e9", "#dce1f8"),
+    ("light-blue", "#4ba1f1", "#ddedfa"),
+    ("yellow", "#f1ac4b", "#f9f0e6"),
+    ("orange", "#e16919", "#f8e2d4"),
+    ("green", "#099268", "#d3e9e3"),
+    ("light-green", "#4cb05e", "#dbf0e0"),
+    ("light-red", "#f87777", "#f4dadb"),
+    ("red", "#e03131", "#f4dadb"),
+    ("white", "#ffffff", "#f5f5f5"),
+];
+
+/// Convert a tldraw file. Embedded images are copied into `assets`.
+pub fn import_tldraw(source: &str, assets: Option<&AssetStore>) -> Result<ImportedScene, String> {
+    import_whiteboard(source, Some(WhiteboardFormat::Tldraw), assets)
+}
+
+pub(super) fn convert(doc: &Value, assets: Option<&AssetStore>) -> Result<ImportedScene, String> {
+    let records = records(doc)?;
+    let of_type = |name: &str| -> Vec<&Value> {
+        records
+            .iter()
+            .copied()
+            .filter(|r| fabric::str_prop(r, "typeName") == Some(name))
+            .collect()
+    };
+
+    let mut scene = ImportedScene {
+        name: of_type("document")
+            .first()
+            .and_then(|d| fabric::str_prop(d, "name"))
+            .filter(|s| !s.is_empty())
+            .map(String::from),
+        ..Default::default()
+    };
+
+    let mut pages = of_type("page");
+    pages.sort_by(|a, b| index_of(a).cmp(index_of(b)));
+    let page = pages.first().ok_or("tldraw file has no pages")?;
+    let page_id = fabric::str_prop(page, "id").unwrap_or("");
+    if pages.len() > 1 {
+        scene.warnings.push(format!(
+            "Imported the first page ({}); skipped {} other page(s)",
+            fabric::str_prop(page, "name").unwrap_or(page_id),
+            pages.len() - 1
+        ));
+    }
+
+    let shapes = of_type("shape");
+    let mut children: HashMap<&str, Vec<&Value>> = HashMap::new();
+    for shape in &shapes {
+        if let Some(parent) = fabric::str_prop(shape, "parentId") {
+            children.entry(parent).or_default().push(shape);
+        }
+    }
+    // Fractional indices sort as plain strings.
+    for list in children.values_mut() {
+        list.sort_by(|a, b| index_of(a).cmp(index_of(b)));
+    }
+
+    let mut bindings = HashMap::new();
+    for binding in of_type("binding") {
+        if let (Some(from), Some(terminal)) = (
+            fabric::str_prop(binding, "fromId"),
+            binding.pointer("/props/terminal").and_then(|v| v.as_str()),
+        ) {
+            bindings.insert((from, terminal), binding);
+        }
+    }
+
+    let mut converter = Converter {
+        shapes: shapes
+            .iter()
+            .filter_map(|s| Some((fabric::str_prop(s, "id")?, *s)))
+            .collect(),
+        children,
+        assets_by_id: of_type("asset")
+            .into_iter()
+            .filter_map(|a| Some((fabric::str_prop(a, "id")?, a)))
+            .collect(),
+        bindings,
+        store: assets,
+        converted: 0,
+        warnings: Vec::new(),
+    };
+    scene.objects = converter.children_of(page_id, &Matrix::IDENTITY);
+    scene.elements = converter.converted;
+    scene.warnings.append(&mut converter.warnings);
+    Ok(scene)
+}
+
+/// Records from a `.tldr` file (`records`) or a store snapshot (`store`).
+fn records(doc: &Value) -> Result<Vec<&Value>, String> {
+    if let Some(records) = doc.get("records").and_then(|v| v.as_array()) {
+        return Ok(records.iter().collect());
+    }
+    let store = doc
+        .get("store")
+        .or_else(|| doc.pointer("/document/store"))
+        .and_then(|v| v.as_object());
+    match store {
+        Some(store) => Ok(store.values().collect()),
+        None if doc.pointer("/document/pages").is_some() => Err(
+            "tldraw v1 files are not supported; open and save the file in current tldraw first"
+                .to_string(),
+        ),
+        None => Err("tldraw file has no records".to_string()),
+    }
+}
+
+fn index_of(record: &Value) -> &str {
+    fabric::str_prop(record, "index").unwrap_or("")
+}
+
+struct Converter<'a> {
+    shapes: HashMap<&'a str, &'a Value>,
+    children: HashMap<&'a str, Vec<&'a Value>>,
+    assets_by_id: HashMap<&'a str, &'a Value>,
+    /// Arrow bindings keyed by (arrow ID, "start" | "end").
+    bindings: HashMap<(&'a str, &'a str), &'a Value>,
+    store: Option<&'a AssetStore>,
+    /// Shapes converted so far, frames included.
+    converted: usize,
+    warnings: Vec<String>,
+}
+
+impl<'a> Converter<'a> {
+    /// Convert the shapes under `parent_id`, whose space `matrix` maps to the page.
+    fn children_of(&mut self, parent_id: &str, matrix: &Matrix) -> Vec<Value> {
+        let shapes = self.children.get(parent_id).cloned().unwrap_or_default();
+        let mut out = Vec::new();
+        for shape in shapes {
+            let id = fabric::str_prop(shape, "id").unwrap_or("");
+            let m = matrix.multiply(&local_matrix(shape));
+            match fabric::str_prop(shape, "type").unwrap_or("") {
+                "group" => {
+                    let mut members = self.children_of(id, &m);
+                    if members.len() > 1 {
+                        out.push(group_objects(members, "Group"));
+                    } else {
+                        out.append(&mut members);
+                    }
+                }
+                "frame" => {
+                    let props = props(shape);
+                    let name = fabric::str_prop(props, "name").filter(|n| !n.is_empty());
+                    let mut frame = base_object("Rect", name.unwrap_or("Frame"));
+                    frame["isFrame"] = json!(true);
+                    frame["width"] = json!(prop_num(props, "w", 160.0));
+                    frame["height"] = json!(prop_num(props, "h", 90.0));
+                    frame["fill"] = json!("#ffffff");
+                    frame["stroke"] = json!("#e0e0e0");
+                    frame["strokeWidth"] = json!(1);
+                    frame["strokeUniform"] = json!(true);
+                    place(&mut frame, &m, Point::new(0.0, 0.0));
+                    let frame_id = frame["id"].clone();
+                    out.push(frame);
+                    self.converted += 1;
+                    // Frame contents stay top-level, parented by ID.
+                    for mut member in self.children_of(id, &m) {
+                        if member.get("parentId").is_none() {
+                            member["parentId"] = frame_id.clone();
+                        }
+                        out.push(member);
+                    }
+                }
+                _ => {
+                    if let Some(mut obj) = self.shape(shape, &m) {
+                        let opacity = fabric::num_prop(shape, "opacity").unwrap_or(1.0);
+                        if opacity < 1.0 {
+                            obj["opacity"] = json!(geometry::round(opacity));
+                        }
+                        out.push(obj);
+                        self.converted += 1;
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn shape(&mut self, shape: &Value, m: &Matrix) -> Option<Value> {
+        let props = props(shape);
+        let kind = fabric::str_prop(shape, "type").unwrap_or("");
+        match kind {
+            "geo" => self.geo(props, m),
+            "text" => {
+                let text = text_of(props);
+                if text.trim().is_empty() {
+                    return None;
+                }
+                let size = prop_num(props, "scale", 1.0) * font_size(props);
+                let family = font_family(props);
+                let width = props
+                    .get("w")
+                    .and_then(|v| v.as_f64())
+                    .unwrap_or_else(|| longest_line(&text, size));
+                let align = fabric::str_prop(props, "textAlign")
+                    .or_else(|| fabric::str_prop(props, "align"));
+                let label = Label {
+                    text: &text,
+                    font_size: size,
+                    font_family: family,
+                    color: palette(props).0,
+                    align: text_align(align),
+                    line_spacing: LINE_HEIGHT,
+                };
+                Some(label_object(&label, m, 0.0, 0.0, width))
+            }
+            "note" => {
+                let (solid, _) = palette(props);
+                let size = NOTE_SIZE * prop_num(props, "scale", 1.0);
+                let height = size + prop_num(props, "growY", 0.0);
+                let note_fill = Rgba::parse(solid)
+                    .map(|c| {
+                        c.with_alpha(0.5)
+                            .over(Rgba::parse("#ffffff").unwrap_or(c))
+                            .to_hex()
+                    })
+                    .unwrap_or_else(|| solid.to_string());
+                let mut card = base_object("Rect", "Note");
+                card["width"] = json!(size);
+                card["height"] = json!(height);
+                card["fill"] = json!(note_fill);
+                card["strokeWidth"] = json!(0);
+                place(&mut card, m, Point::new(0.0, 0.0));
+                self.with_label(card, props, m, size, height, "Note")
+            }
+            "arrow" => self.arrow(shape, props, m),
+            "line" => {
+                let points = line_points(props);
+                if points.len() < 2 {
+                    return None;
+                }
+                let segments = if fabric::str_prop(props, "spline") == Some("cubic") {
+                    smooth_curve(&points, false)
+                } else {
+                    polyline(&points, false)
+                };
+                let style = line_style(props);
+                linear_object(segments, &[], &style, Value::Null, m, "Line")
+            }
+            "draw" | "highlight" => self.draw(kind, props, m),
+            "image" => self.image(shape, props, m),
+            other => {
+                self.warnings.push(format!(
+                    "Skipped unsupported {} shape {}",
+                    other,
+                    fabric::str_prop(shape, "id").unwrap_or("?")
+                ));
+                None
+            }
+        }
+    }
+
+    fn geo(&mut self, props: &Value, m: &Matrix) -> Option<Value> {
+        let w = prop_num(props, "w", 100.0);
+        let h = prop_num(props, "h", 100.0) + prop_num(props, "growY", 0.0);
+        let geo = fabric::str_prop(props, "geo").unwrap_or("rectangle");
+        let style = line_style(props);
+        let fill = fill_paint(props);
+        let name = geo_name(geo);
+
+        let mut obj = match geo {
+            "rectangle" => {
+                let mut obj = base_object("Rect", name);
+                obj["width"] = json!(w);
+                obj["height"] = json!(h);
+                obj
+            }
+            "oval" => {
+                let mut obj = base_object("Rect", name);
+                let radius = w.min(h) / 2.0;
+                obj["width"] = json!(w);
+                obj["height"] = json!(h);
+                obj["rx"] = json!(radius);
+                obj["ry"] = json!(radius);
+                obj
+            }
+            "ellipse" | "cloud" | "heart" => {
+                if geo != "ellipse" {
+                    self.warnings
+                        .push(format!("Geo shape '{}' is drawn as an ellipse", geo));
+                }
+                let mut obj = base_object("Ellipse", name);
+                obj["rx"] = json!(w / 2.0);
+                obj["ry"] = json!(h / 2.0);
+                obj["width"] = json!(w);
+                obj["height"] = json!(h);
+                obj
+            }
+            _ => {
+                let segments = match geo_outline(geo, w, h) {
+                    Some(segments) => segments,
+                    None => {
+                        self.warnings
+                            .push(format!("Geo shape '{}' is drawn as a rectangle", geo));
+                        geo_outline("rectangle", w, h)?
+                    }
+                };
+                let (mut obj, origin) = path_object(&segments, name)?;
+                style.apply(&mut obj, fill);
+                place(&mut obj, m, origin);
+                return self.with_label(obj, props, m, w, h, name);
+            }
+        };
+        style.apply(&mut obj, fill);
+        place(&mut obj, m, Point::new(0.0, 0.0));
+        self.with_label(obj, props, m, w, h, name)
+    }
+
+    /// Group a shape with its text label, laid out inside its `w`×`h` box.
+    fn with_label(
+        &self,
+        shape: Value,
+        props: &Value,
+        m: &Matrix,
+        w: f64,
+        h: f64,
+        name: &str,
+    ) -> Option<Value> {
+        let text = text_of(props);
+        if text.trim().is_empty() {
+            return Some(shape);
+        }
+        let size = label_font_size(props) * prop_num(props, "scale", 1.0);
+        let text_height = text.split('\n').count() as f64 * size * LINE_HEIGHT;
+        let top = match fabric::str_prop(props, "verticalAlign") {
+            Some("start") => LABEL_PADDING,
+            Some("end") => h - LABEL_PADDING - text_height,
+            _ => (h - text_height) / 2.0,
+        };
+        let label = Label {
+            text: &text,
+            font_size: size,
+            font_family: font_family(props),
+            color: label_color(props),
+            align: text_align(fabric::str_prop(props, "align").or(Some("middle"))),
+            line_spacing: LINE_HEIGHT,
+        };
+        let width = (w - 2.0 * LABEL_PADDING).max(0.0);
+        let text_obj = label_object(&label, m, LABEL_PADDING, top, width);
+        Some(group_objects(vec![shape, text_obj], name))
+    }
+
+    fn arrow(&mut self, shape: &Value, props: &Value, m: &Matrix) -> Option<Value> {
+        let id = fabric::str_prop(shape, "id").unwrap_or("");
+        let mut start = terminal_point(props.get("start"));
+        let mut end = terminal_point(props.get("end"));
+
+        // Bound terminals point at their shape, clipped to its edge.
+        let inverse = m.invert()?;
+        let page_start = self.bound_point(id, "start", props.get("start"));
+        let page_end = self.bound_point(id, "end", props.get("end"));
+        let other_start = page_start.map(|(p, _)| p).unwrap_or_else(|| m.apply(start));
+        let other_end = page_end.map(|(p, _)| p).unwrap_or_else(|| m.apply(end));
+        if let Some((target, bounds)) = page_start {
+            start = inverse.apply(clip_to_bounds(other_end, target, &bounds));
+        }
+        if let Some((target, bounds)) = page_end {
+            end = inverse.apply(clip_to_bounds(other_start, target, &bounds));
+        }
+
+        let bend = prop_num(props, "bend", 0.0);
+        let shaft = arc_between(start, end, bend);
+        let style = line_style(props);
+        let length = polyline_length(&[start, end]);
+        let head_size = f64::min(length / 3.0, style.width * 4.0 + 10.0);
+
+        let mut ends = Vec::new();
+        let tangents = [
+            ("arrowheadStart", start_tangent(&shaft)),
+            ("arrowheadEnd", end_tangent(&shaft)),
+        ];
+        for (key, tangent) in tangents {
+            let Some((tip, from)) = tangent else {
+                continue;
+            };
+            let name = fabric::str_prop(props, key).unwrap_or(if key == "arrowheadEnd" {
+                "arrow"
+            } else {
+                "none"
+            });
+            let head = match name {
+                "none" => continue,
+                "arrow" => Arrowhead::Arrow,
+                "triangle" => Arrowhead::Triangle,
+                "square" => Arrowhead::Square,
+                "dot" => Arrowhead::Dot,
+                "diamond" => Arrowhead::Diamond,
+                "bar" | "pipe" => Arrowhead::Bar,
+                "inverted" => {
+                    // Base on the terminal, point back along the shaft.
+                    let len = (tip.x - from.x).hypot(tip.y - from.y).max(1e-9);
+                    let back = Point::new(
+                        tip.x - (tip.x - from.x) / len * head_size * 0.9,
+                        tip.y - (tip.y - from.y) / len * head_size * 0.9,
+                    );
+                    ends.push(ArrowEnd {
+                        head: Arrowhead::Triangle,
+                        tip: back,
+                        from: tip,
+                        size: head_size,
+                    });
+                    continue;
+                }
+                other => {
+                    self.warnings
+                        .push(format!("Arrowhead '{}' is drawn as a plain arrow", other));
+                    Arrowhead::Arrow
+                }
+            };
+            ends.push(ArrowEnd {
+                head,
+                tip,
+                from,
+                size: head_size,
+            });
+        }
+
+        let arrow = linear_object(shaft, &ends, &style, Value::Null, m, "Arrow")?;
+        let text = text_of(props);
+        if text.trim().is_empty() {
+            return Some(arrow);
+        }
+        // The label sits on the middle of the arc.
+        let middle = arc_middle(start, end, bend);
+        let size = label_font_size(props) * prop_num(props, "scale", 1.0);
+        let width = longest_line(&text, size);
+        let height = text.split('\n').count() as f64 * size * LINE_HEIGHT;
+        let label = Label {
+            text: &text,
+            font_size: size,
+            font_family: font_family(props),
+            color: label_color(props),
+            align: "center",
+            line_spacing: LINE_HEIGHT,
+        };
+        let text_obj = label_object(
+            &label,
+            m,
+            middle.x - width / 2.0,
+            middle.y - height / 2.0,
+            width,
+        );
+        Some(group_objects(vec![arrow, text_obj], "Arrow"))
+    }
+
+    /// Page-space target of a bound arrow terminal, with the bound shape's page bounds.
+    fn bound_point(
+        &self,
+        arrow_id: &str,
+        terminal: &str,
+        inline: Option<&Value>,
+    ) -> Option<(Point, crate::fabric::Bounds)> {
+        // Older files store the binding on the terminal itself.
+        let (target_id, binding_props) =
+            match inline.filter(|t| fabric::str_prop(t, "type") == Some("binding")) {
+                Some(t) => (fabric::str_prop(t, "boundShapeId")?, t),
+                None => {
+                    let binding = self.bindings.get(&(arrow_id, terminal))?;
+                    (fabric::str_prop(binding, "toId")?, binding.get("props")?)
+                }
+            };
+        let target = self.shapes.get(target_id)?;
+        let matrix = self.page_matrix(target);
+        let (w, h) = shape_size(target);
+        let precise = binding_props
+            .get("isPrecise")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let anchor = binding_props.get("normalizedAnchor");
+        let (nx, ny) = match anchor {
+            Some(a) if precise => (prop_num(a, "x", 0.5), prop_num(a, "y", 0.5)),
+            _ => (0.5, 0.5),
+        };
+        let corners = [
+            Point::new(0.0, 0.0),
+            Point::new(w, 0.0),
+            Point::new(w, h),
+            Point::new(0.0, h),
+        ]
+        .map(|p| matrix.apply(p));
+        let bounds = geometry::bounds_of_points(&corners)?;
+        Some((matrix.apply(Point::new(w * nx, h * ny)), bounds))
+    }
+
+    fn page_matrix(&self, shape: &Value) -> Matrix {
+        let own = local_matrix(shape);
+        match fabric::str_prop(shape, "parentId").and_then(|p| self.shapes.get(p)) {
+            Some(parent) => self.page_matrix(parent).multiply(&own),
+            None => own,
+        }
+    }
+
+    fn draw(&mut self, kind: &str, props: &Value, m: &Matrix) -> Option<Value> {
+        let mut points = Vec::new();
+        for segment in props.get("segments").and_then(|v| v.as_array())? {
+            match segment.get("points").and_then(|v| v.as_array()) {
+                Some(list) => points.extend(
+                    list.iter()
+                        .map(|p| Point::new(prop_num(p, "x", 0.0), prop_num(p, "y", 0.0))),
+                ),
+                None => {
+                    self.warnings.push(
+                        "Skipped a drawing stored in the compressed point format".to_string(),
+                    );
+                    return None;
+                }
+            }
+        }
+        if points.len() < 2 {
+            return None;
+        }
+        let closed = props
+            .get("isClosed")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let mut style = line_style(props);
+        style.dash = None;
+        style.width *= prop_num(props, "scale", 1.0);
+        let mut fill = if closed {
+            fill_paint(props)
+        } else {
+            Value::Null
+        };
+        if kind == "highlight" {
+            // Highlighter strokes are wide and translucent.
+            style.width *= 4.0;
+            fill = Value::Null;
+        }
+        let name = if kind == "highlight" {
+            "Highlight"
+        } else {
+            "Drawing"
+        };
+        let (mut obj, origin) = path_object(&freehand(&points, closed), name)?;
+        style.apply(&mut obj, fill);
+        place(&mut obj, m, origin);
+        if kind == "highlight" {
+            obj["opacity"] = json!(0.5);
+        }
+        Some(obj)
+    }
+
+    fn image(&mut self, shape: &Value, props: &Value, m: &Matrix) -> Option<Value> {
+        let id = fabric::str_prop(shape, "id").unwrap_or("?");
+        let Some(store) = self.store else {
+            self.warnings.push(format!(
+                "Skipped image {}: no asset store to copy it into",
+                id
+            ));
+            return None;
+        };
+        let asset = fabric::str_prop(props, "assetId").and_then(|a| self.assets_by_id.get(a));
+        let src = asset
+            .and_then(|a| a.pointer("/props/src"))
+            .and_then(|v| v.as_str());
+        let Some(src) = src.filter(|s| s.starts_with("data:")) else {
+            self.warnings.push(format!(
+                "Skipped image {}: only embedded images can be imported",
+                id
+            ));
+            return None;
+        };
+        let ext = asset
+            .and_then(|a| a.pointer("/props/mimeType"))
+            .and_then(|v| v.as_str())
+            .and_then(|mime| mime.split('/').nth(1));
+        let stored =
+            match assets::decode_data_url(src).and_then(|bytes| store.add_bytes(&bytes, ext)) {
+                Ok(stored) => stored,
+                Err(e) => {
+                    self.warnings.push(format!("Skipped image {}: {}", id, e));
+                    return None;
+                }
+            };
+
+        let (w, h) = (prop_num(props, "w", 100.0), prop_num(props, "h", 100.0));
+        let mut obj = assets::image_object(store, &stored, 0.0, 0.0, None, None, None);
+        let natural_w = obj["width"].as_f64().unwrap_or(w).max(1.0);
+        let natural_h = obj["height"].as_f64().unwrap_or(h).max(1.0);
+        let flip = |key| {
+            if fabric::bool_prop(props, key) {
+                -1.0
+            } else {
+                1.0
+            }
+        };
+        let matrix = m
+            .multiply(&Matrix::translate(w / 2.0, h / 2.0))
+            .multiply(&Matrix::scale(flip("flipX"), flip("flipY")))
+            .multiply(&Matrix::translate(-w / 2.0, -h / 2.0))
+            .multiply(&Matrix::scale(w / natural_w, h / natural_h));
+        geometry::apply_to_object(&mut obj, &matrix);
+        Some(obj)
+    }
+}
+
+// ── Geometry ────────────────────────────────────────────────────────────────
+
+/// tldraw positions shapes by their top-left corner and rotates about it.
+fn local_matrix(shape: &Value) -> Matrix {
+    Matrix::translate(
+        fabric::num_prop(shape, "x").unwrap_or(0.0),
+        fabric::num_prop(shape, "y").unwrap_or(0.0),
+    )
+    .multiply(&Matrix::rotate_degrees(
+        fabric::num_prop(shape, "rotation")
+            .unwrap_or(0.0)
+            .to_degrees(),
+    ))
+}
+
+fn shape_size(shape: &Value) -> (f64, f64) {
+    let props = props(shape);
+    let scale = prop_num(props, "scale", 1.0);
+    match fabric::str_prop(shape, "type") {
+        Some("note") => (
+            NOTE_SIZE * scale,
+            NOTE_SIZE * scale + prop_num(props, "growY", 0.0),
+        ),
+        Some("text") => {
+            let text = text_of(props);
+            let size = font_size(props) * scale;
+            (
+                prop_num(props, "w", longest_line(&text, size)),
+                text.split('\n').count() as f64 * size * LINE_HEIGHT,
+            )
+        }
+        _ => (
+            prop_num(props, "w", 100.0),
+            prop_num(props, "h", 100.0) + prop_num(props, "growY", 0.0),
+        ),
+    }
+}
+
+/// Outline of a geo shape in its `w`×`h` box, for the kinds that are not a
+/// plain Rect or Ellipse.
+fn geo_outline(geo: &str, w: f64, h: f64) -> Option<Vec<Segment>> {
+    let p = Point::new;
+    let offset = f64::min(w * 0.38, h * 0.38);
+    let corners = match geo {
+        "rectangle" => vec![p(0.0, 0.0), p(w, 0.0), p(w, h), p(0.0, h)],
+        "triangle" => vec![p(w / 2.0, 0.0), p(w, h), p(0.0, h)],
+        "diamond" => vec![
+            p(w / 2.0, 0.0),
+            p(w, h / 2.0),
+            p(w / 2.0, h),
+            p(0.0, h / 2.0),
+        ],
+        "rhombus" => vec![p(offset, 0.0), p(w, 0.0), p(w - offset, h), p(0.0, h)],
+        "rhombus-2" => vec![p(0.0, 0.0), p(w - offset, 0.0), p(w, h), p(offset, h)],
+        "trapezoid" => vec![p(offset, 0.0), p(w - offset, 0.0), p(w, h), p(0.0, h)],
+        "pentagon" => regular_polygon(5, w, h, 1.0),
+        "hexagon" => regular_polygon(6, w, h, 1.0),
+        "octagon" => regular_polygon(8, w, h, 1.0),
+        "star" => regular_polygon(10, w, h, 0.4),
+        "arrow-right" | "arrow-left" | "arrow-up" | "arrow-down" => {
+            // Drawn pointing right in a unit square, then mapped into the box.
+            let unit = [
+                p(0.0, 0.25),
+                p(0.6, 0.25),
+                p(0.6, 0.0),
+                p(1.0, 0.5),
+                p(0.6, 1.0),
+                p(0.6, 0.75),
+                p(0.0, 0.75),
+            ];
+            unit.iter()
+                .map(|q| match geo {
+                    "arrow-left" => p((1.0 - q.x) * w, q.y * h),
+                    "arrow-up" => p(q.y * w, (1.0 - q.x) * h),
+                    "arrow-down" => p(q.y * w, q.x * h),
+                    _ => p(q.x * w, q.y * h),
+                })
+                .collect()
+        }
+        "x-box" | "check-box" => {
+            let mut segments = polyline(&[p(0.0, 0.0), p(w, 0.0), p(w, h), p(0.0, h)], true);
+            let inset = f64::min(w, h) * 0.25;
+            if geo == "x-box" {
+                segments.extend(polyline(&[p(inset, inset), p(w - inset, h - inset)], false));
+                segments.extend(polyline(&[p(w - inset, inset), p(inset, h - inset)], false));
+            } else {
+                segments.extend(polyline(
+                    &[
+                        p(inset, h * 0.55),
+                        p(w * 0.42, h - inset),
+                        p(w - inset, inset),
+                    ],
+                    false,
+                ));
+            }
+            return Some(segments);
+        }
+        _ => return None,
+    };
+    Some(polyline(&corners, true))
+}
+
+/// Vertices of a regular polygon (or a star, when `inner` < 1 and alternate
+/// vertices are pulled in) stretched to fill a `w`×`h` box, starting at the top.
+fn regular_polygon(sides: usize, w: f64, h: f64, inner: f64) -> Vec<Point> {
+    let raw: Vec<Point> = (0..sides)
+        .map(|i| {
+            let angle =
+                -std::f64::consts::FRAC_PI_2 + i as f64 * std::f64::consts::TAU / sides as f64;
+            let r = if i % 2 == 1 { inner } else { 1.0 };
+            Point::new(r * angle.cos(), r * angle.sin())
+        })
+        .collect();
+    let Some(b) = geometry::bounds_of_points(&raw) else {
+        return raw;
+    };
+    raw.iter()
+        .map(|q| Point::new((q.x - b.left) / b.width * w, (q.y - b.top) / b.height * h))
+        .collect()
+}
+
+fn terminal_point(terminal: Option<&Value>) -> Point {
+    terminal
+        .map(|t| Point::new(prop_num(t, "x", 0.0), prop_num(t, "y", 0.0)))
+        .unwrap_or(Point::new(0.0, 0.0))
+}
+
+/// Move `target` back to where the segment from `from` enters `bounds`.
+fn clip_to_bounds(from: Point, target: Point, bounds: &crate::fabric::Bounds) -> Point {
+    let inside = |p: Point| {
+        p.x >= bounds.left && p.x <= bounds.right() && p.y >= bounds.top && p.y <= bounds.bottom()
+    };
+    if inside(from) {
+        return target;
+    }
+    let (dx, dy) = (target.x - from.x, target.y - from.y);
+    let mut t_enter: f64 = 0.0;
+    for (start, delta, low, high) in [
+        (from.x, dx, bounds.left, bounds.right()),
+        (from.y, dy, bounds.top, bounds.bottom()),
+    ] {
+        if delta.abs() < 1e-9 {
+            continue;
+        }
+        let (a, b) = ((low - start) / delta, (high - start) / delta);
+        t_enter = t_enter.max(a.min(b));
+    }
+    if t_enter <= 0.0 || t_enter >= 1.0 {
+        return target;
+    }
+    Point::new(from.x + dx * t_enter, from.y + dy * t_enter)
+}
+
+/// The point the arc from `start` to `end` passes through, `bend` pixels
+/// off the chord's midpoint (tldraw's sign convention).
+fn arc_middle(start: Point, end: Point, bend: f64) -> Point {
+    let (dx, dy) = (end.x - start.x, end.y - start.y);
+    let len = dx.hypot(dy).max(1e-9);
+    let (ux, uy) = (dx / len, dy / len);
+    Point::new(
+        (start.x + end.x) / 2.0 - uy * bend,
+        (start.y + end.y) / 2.0 + ux * bend,
+    )
+}
+
+/// A bent arrow is a circular arc through both ends and its middle point.
+fn arc_between(start: Point, end: Point, bend: f64) -> Vec<Segment> {
+    let straight = || polyline(&[start, end], false);
+    if bend.abs() < 1e-3 {
+        return straight();
+    }
+    let mid = arc_middle(start, end, bend);
+    let cross = (mid.x - start.x) * (end.y - start.y) - (mid.y - start.y) * (end.x - start.x);
+    if cross.abs() < 1e-9 {
+        return straight();
+    }
+    let dist = |a: Point, b: Point| (b.x - a.x).hypot(b.y - a.y);
+    let radius = dist(start, end) * dist(end, mid) * dist(mid, start) / (2.0 * cross.abs());
+    // Each half is under 180°, so the small-arc flag is always right.
+    let sweep = if cross > 0.0 { 1 } else { 0 };
+    let d = format!(
+        "M {} {} A {r} {r} 0 0 {s} {} {} A {r} {r} 0 0 {s} {} {}",
+        start.x,
+        start.y,
+        mid.x,
+        mid.y,
+        end.x,
+        end.y,
+        r = radius,
+        s = sweep
+    );
+    path::parse(&d).unwrap_or_else(|_| straight())
+}
+
+fn line_points(props: &Value) -> Vec<Point> {
+    let mut handles: Vec<&Value> = match props.get("points").or_else(|| props.get("handles")) {
+        Some(Value::Array(list)) => list.iter().collect(),
+        Some(Value::Object(map)) => map.values().collect(),
+        _ => Vec::new(),
+    };
+    handles.sort_by(|a, b| index_of(a).cmp(index_of(b)));
+    handles
+        .iter()
+        .map(|p| Point::new(prop_num(p, "x", 0.0), prop_num(p, "y", 0.0)))
+        .collect()
+}
+
+// ── Style ───────────────────────────────────────────────────────────────────
+
+fn props(shape: &Value) -> &Value {
+    shape.get("props").unwrap_or(&Value::Null)
+}
+
+fn prop_num(props: &Value, key: &str, default: f64) -> f64 {
+    fabric::num_prop(props, key).unwrap_or(default)
+}
+
+/// Plain text of a shape: `text`, or the paragraphs of a `richText` document.
+fn text_of(props: &Value) -> String {
+    fn collect(node: &Value, out: &mut String) {
+        match fabric::str_prop(node, "type") {
+            Some("text") => out.push_str(fabric::str_prop(node, "text").unwrap_or("")),
+            Some("hardBreak") => out.push('\n'),
+            _ => {}
+        }
+        let Some(content) = node.get("content").and_then(|v| v.as_array()) else {
+            return;
+        };
+        for (i, child) in content.iter().enumerate() {
+            let is_block = fabric::str_prop(child, "type") == Some("paragraph");
+            if is_block && i > 0 {
+                out.push('\n');
+            }
+            collect(child, out);
+        }
+    }
+
+    if let Some(text) = fabric::str_prop(props, "text") {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    if let Some(rich) = props.get("richText") {
+        collect(rich, &mut out);
+    }
+    out
+}
+
+/// Labels on geo shapes, notes and arrows have their own color, black by default.
+fn label_color(props: &Value) -> &'static str {
+    let name = fabric::str_prop(props, "labelColor").unwrap_or("black");
+    PALETTE
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map(|(_, solid, _)| *solid)
+        .unwrap_or(PALETTE[0].1)
+}
+
+/// (solid, semi) for the shape's `color` prop.
+fn palette(props: &Value) -> (&'static str, &'static str) {
+    let name = fabric::str_prop(props, "color").unwrap_or("black");
+    let (_, solid, semi) = PALETTE
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .unwrap_or(&PALETTE[0]);
+    (solid, semi)
+}
+
+fn fill_paint(props: &Value) -> Value {
+    let (solid, semi) = palette(props);
+    match fabric::str_prop(props, "fill") {
+        Some("semi") => json!(SEMI_FILL),
+        Some("solid" | "pattern") => json!(semi),
+        Some("fill") => json!(solid),
+        _ => Value::Null,
+    }
+}
+
+fn stroke_width(props: &Value) -> f64 {
+    match fabric::str_prop(props, "size") {
+        Some("s") => 2.0,
+        Some("l") => 5.0,
+        Some("xl") => 10.0,
+        _ => 3.5,
+    }
+}
+
+fn line_style(props: &Value) -> LineStyle {
+    let width = stroke_width(props) * prop_num(props, "scale", 1.0);
+    LineStyle {
+        color: paint(Some(palette(props).0)),
+        width,
+        dash: match fabric::str_prop(props, "dash") {
+            Some("dashed") => Some(vec![width * 2.0, width * 2.0]),
+            Some("dotted") => Some(vec![0.0, width * 2.0]),
+            _ => None,
+        },
+        round: true,
+    }
+}
+
+fn font_size(props: &Value) -> f64 {
+    match fabric::str_prop(props, "size") {
+        Some("s") => 18.0,
+        Some("l") => 36.0,
+        Some("xl") => 44.0,
+        _ => 24.0,
+    }
+}
+
+/// Labels on geo shapes, notes and arrows use a smaller scale than text shapes.
+fn label_font_size(props: &Value) -> f64 {
+    match fabric::str_prop(props, "size") {
+        Some("s") => 18.0,
+        Some("l") => 26.0,
+        Some("xl") => 32.0,
+        _ => 22.0,
+    }
+}
+
+fn font_family(props: &Value) -> &'static str {
+    match fabric::str_prop(props, "font") {
+        Some("sans") => "IBM Plex Sans, sans-serif",
+        Some("serif") => "IBM Plex Serif, serif",
+        Some("mono") => "IBM Plex Mono, monospace",
+        _ => "Shantell Sans, cursive",
+    }
+}
+
+fn text_align(align: Option<&str>) -> &'static str {
+    match align {
+        Some(a) if a.starts_with("middle") => "center",
+        Some(a) if a.starts_with("end") => "right",
+        _ => "left",
+    }
+}
+
+fn longest_line(text: &str, size: f64) -> f64 {
+    text.split('\n')
+        .map(|line| fonts::approximate_width(line, size, 0.0))
+        .fold(0.0, f64::max)
+}
+
+fn geo_name(geo: &str) -> &'static str {
+    match geo {
+        "ellipse" | "oval" | "cloud" | "heart" => "Ellipse",
+        "triangle" => "Triangle",
+        "diamond" | "rhombus" | "rhombus-2" => "Diamond",
+        "trapezoid" => "Trapezoid",
+        "pentagon" | "hexagon" | "octagon" => "Polygon",
+        "star" => "Star",
+        "arrow-right" | "arrow-left" | "arrow-up" | "arrow-down" => "Arrow",
+        _ => "Rectangle",
+    }
+}
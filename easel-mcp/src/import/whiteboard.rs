@@ -0,0 +1,185 @@
+//! Pieces shared by the Excalidraw and tldraw importers: the scene they
+//! produce, format detection, and the curve, arrowhead and label geometry
+//! both formats need.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+
+use crate::assets::AssetStore;
+use crate::geometry::{self, Matrix, Point};
+use crate::path::Segment;
+use crate::shapes::polyline;
+
+use super::base_object;
+use super::{excalidraw, tldraw};
+
+/// Objects converted from a whiteboard file, ready for a new canvas.
+#[derive(Debug, Default)]
+pub struct ImportedScene {
+    /// Document name stored in the file, if any.
+    pub name: Option<String>,
+    pub objects: Vec<Value>,
+    pub background: Option<String>,
+    /// Elements (Excalidraw) or shapes (tldraw) converted, counting group
+    /// members and frames; `objects` only holds the top level.
+    pub elements: usize,
+    /// Elements that were skipped or only approximated.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum WhiteboardFormat {
+    Excalidraw,
+    Tldraw,
+}
+
+impl WhiteboardFormat {
+    /// Guess the format from the file extension (`.excalidraw`, `.tldr`).
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "excalidraw" => Some(Self::Excalidraw),
+            "tldr" => Some(Self::Tldraw),
+            _ => None,
+        }
+    }
+
+    /// Recognise a parsed document by its top-level keys.
+    pub fn detect(doc: &Value) -> Option<Self> {
+        let source_type = doc.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        if source_type.starts_with("excalidraw")
+            || doc.get("elements").is_some_and(|v| v.is_array())
+        {
+            Some(Self::Excalidraw)
+        } else if doc.get("tldrawFileFormatVersion").is_some()
+            || doc.get("records").is_some_and(|v| v.is_array())
+            || doc.get("store").is_some_and(|v| v.is_object())
+            || doc
+                .pointer("/document/store")
+                .is_some_and(|v| v.is_object())
+        {
+            Some(Self::Tldraw)
+        } else {
+            None
+        }
+    }
+}
+
+/// Convert an Excalidraw or tldraw JSON file. Embedded images are copied
+/// into `assets`; without a store they are skipped with a warning.
+pub fn import_whiteboard(
+    source: &str,
+    format: Option<WhiteboardFormat>,
+    assets: Option<&AssetStore>,
+) -> Result<ImportedScene, String> {
+    let doc: Value =
+        serde_json::from_str(source).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    let format = format
+        .or_else(|| WhiteboardFormat::detect(&doc))
+        .ok_or("Not an Excalidraw or tldraw file")?;
+    match format {
+        WhiteboardFormat::Excalidraw => excalidraw::convert(&doc, assets),
+        WhiteboardFormat::Tldraw => tldraw::convert(&doc, assets),
+    }
+}
+
+// ── Curves ──────────────────────────────────────────────────────────────────
+
+/// Catmull-Rom spline through every point, as cubic Béziers.
+pub(crate) fn smooth_curve(points: &[Point], closed: bool) -> Vec<Segment> {
+    if points.len() < 3 {
+        return polyline(points, closed);
+    }
+    let n = points.len();
+    let at = |i: isize| -> Point {
+        if closed {
+            points[i.rem_euclid(n as isize) as usize]
+        } else {
+            points[i.clamp(0, n as isize - 1) as usize]
+        }
+    };
+    let mut segments = vec![Segment::Move(points[0])];
+    let count = if closed { n } else { n - 1 };
+    for i in 0..count as isize {
+        let (p0, p1, p2, p3) = (at(i - 1), at(i), at(i + 1), at(i + 2));
+        let c1 = Point::new(p1.x + (p2.x - p0.x) / 6.0, p1.y + (p2.y - p0.y) / 6.0);
+        let c2 = Point::new(p2.x - (p3.x - p1.x) / 6.0, p2.y - (p3.y - p1.y) / 6.0);
+        segments.push(Segment::Cubic(c1, c2, p2));
+    }
+    if closed {
+        segments.push(Segment::Close);
+    }
+    segments
+}
+
+/// A freehand stroke, smoothed with quadratics through the midpoints of its samples.
+pub(crate) fn freehand(points: &[Point], closed: bool) -> Vec<Segment> {
+    if points.len() < 3 {
+        return polyline(points, closed);
+    }
+    let mut segments = vec![Segment::Move(points[0])];
+    for pair in points[1..].windows(2) {
+        let mid = Point::new((pair[0].x + pair[1].x) / 2.0, (pair[0].y + pair[1].y) / 2.0);
+        segments.push(Segment::Quad(pair[0], mid));
+    }
+    segments.push(Segment::Line(points[points.len() - 1]));
+    if closed {
+        segments.push(Segment::Close);
+    }
+    segments
+}
+
+// ── Labels ──────────────────────────────────────────────────────────────────
+
+pub(crate) struct Label<'a> {
+    pub text: &'a str,
+    pub font_size: f64,
+    pub font_family: &'a str,
+    pub color: &'a str,
+    /// `left`, `center` or `right`.
+    pub align: &'a str,
+    /// Line spacing as a multiple of the font size.
+    pub line_spacing: f64,
+}
+
+/// An IText whose box spans `width` from local (`x`, `y`). The alignment
+/// edge is pinned so the text stays put when Fabric re-measures it with the
+/// fonts available here.
+pub(crate) fn label_object(label: &Label, matrix: &Matrix, x: f64, y: f64, width: f64) -> Value {
+    let lines = label.text.split('\n').count().max(1);
+    let (origin_x, anchor_x) = match label.align {
+        "center" => ("center", x + width / 2.0),
+        "right" => ("right", x + width),
+        _ => ("left", x),
+    };
+    let anchor = matrix.apply(Point::new(anchor_x, y));
+    let t = matrix.decompose();
+
+    let first_line = label.text.lines().next().unwrap_or("").trim();
+    let name: String = if first_line.is_empty() {
+        "Text".to_string()
+    } else {
+        first_line.chars().take(40).collect()
+    };
+    let mut obj = base_object("IText", &name);
+    obj["text"] = json!(label.text);
+    obj["left"] = json!(geometry::round(anchor.x));
+    obj["top"] = json!(geometry::round(anchor.y));
+    obj["originX"] = json!(origin_x);
+    obj["width"] = json!(geometry::round(width));
+    obj["height"] = json!(geometry::round(
+        lines as f64 * label.font_size * label.line_spacing
+    ));
+    obj["angle"] = json!(geometry::round(t.angle));
+    obj["fontSize"] = json!(label.font_size);
+    obj["fontFamily"] = json!(label.font_family);
+    obj["textAlign"] = json!(label.align);
+    // Fabric spaces lines by fontSize × 1.13 × lineHeight.
+    obj["lineHeight"] = json!(geometry::round(
+        label.line_spacing / crate::text::FONT_SIZE_MULT
+    ));
+    obj["fill"] = json!(label.color);
+    obj["strokeWidth"] = json!(0);
+    obj
+}
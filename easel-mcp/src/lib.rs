@@ -0,0 +1,26 @@
+pub mod accessibility;
+pub mod assets;
+pub mod attribution;
+pub mod boolean;
+pub mod bundle;
+pub mod codegen;
+pub mod color;
+pub mod crdt;
+pub mod diff;
+pub mod easel;
+pub mod export;
+pub mod fabric;
+pub mod fonts;
+pub mod geometry;
+pub mod import;
+pub mod lint;
+pub mod merge;
+pub mod oplog;
+pub mod path;
+pub mod query;
+pub mod render;
+pub mod server;
+pub mod shapes;
+pub mod spatial;
+pub mod summary;
+pub mod text;
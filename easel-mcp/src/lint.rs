@@ -0,0 +1,529 @@
+//! Design lint rules for `.easel` canvases.
+//!
+//! Each rule looks at the placed objects of a [`Scene`] and reports issues
+//! against object ids. Rules and their thresholds come from a [`LintConfig`],
+//! which the MCP tool, the CLI (`easel-mcp lint --config`) and the app share.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::color::Rgba;
+use crate::fabric::{self, Bounds};
+use crate::spatial::{Placed, Scene};
+use crate::text;
+
+const DEFAULT_MIN_FONT_SIZE: f64 = 12.0;
+/// WCAG AA for body text.
+const DEFAULT_MIN_CONTRAST: f64 = 4.5;
+const DEFAULT_GRID: f64 = 8.0;
+/// Names the editor and importers give new objects, before any numbering.
+const DEFAULT_NAMES: &[&str] = &[
+    "arrow",
+    "circle",
+    "ellipse",
+    "frame",
+    "group",
+    "image",
+    "line",
+    "object",
+    "path",
+    "polygon",
+    "polyline",
+    "rect",
+    "rectangle",
+    "text",
+    "textbox",
+    "triangle",
+];
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    schemars::JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum LintRule {
+    /// Text rendered smaller than `min_font_size`.
+    MinTextSize,
+    /// Text color against its frame's fill below `min_contrast`.
+    TextContrast,
+    /// Objects that belong to no frame, or sit entirely outside their frame.
+    OutsideFrame,
+    /// Siblings that partially overlap (one fully inside the other is fine).
+    OverlappingSiblings,
+    /// Unrotated objects whose position is off the `grid`.
+    OffGrid,
+    /// Layers without a name, or still with a default one like "Rectangle 3".
+    UnnamedLayer,
+    /// Solid fills, strokes and gradient stops outside `palette`.
+    OffPalette,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 7] = [
+        LintRule::MinTextSize,
+        LintRule::TextContrast,
+        LintRule::OutsideFrame,
+        LintRule::OverlappingSiblings,
+        LintRule::OffGrid,
+        LintRule::UnnamedLayer,
+        LintRule::OffPalette,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MinTextSize => "min-text-size",
+            Self::TextContrast => "text-contrast",
+            Self::OutsideFrame => "outside-frame",
+            Self::OverlappingSiblings => "overlapping-siblings",
+            Self::OffGrid => "off-grid",
+            Self::UnnamedLayer => "unnamed-layer",
+            Self::OffPalette => "off-palette",
+        }
+    }
+
+    fn severity(self) -> Severity {
+        match self {
+            Self::TextContrast => Severity::Error,
+            Self::MinTextSize
+            | Self::OutsideFrame
+            | Self::OverlappingSiblings
+            | Self::OffPalette => Severity::Warning,
+            Self::OffGrid | Self::UnnamedLayer => Severity::Info,
+        }
+    }
+}
+
+impl FromStr for LintRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        LintRule::ALL
+            .into_iter()
+            .find(|rule| rule.as_str() == s)
+            .ok_or_else(|| {
+                let names: Vec<&str> = LintRule::ALL.iter().map(|r| r.as_str()).collect();
+                format!(
+                    "Unknown lint rule {} (expected one of {})",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+        }
+    }
+}
+
+/// Which rules to run and their thresholds. Every field is optional.
+#[derive(Debug, Clone, Default, Deserialize, schemars::JsonSchema)]
+pub struct LintConfig {
+    #[schemars(description = "Rules to run (default: all)")]
+    pub rules: Option<Vec<LintRule>>,
+    #[schemars(description = "Rules to skip")]
+    #[serde(default)]
+    pub disable: Vec<LintRule>,
+    #[schemars(description = "Smallest allowed rendered font size in px (default 12)")]
+    pub min_font_size: Option<f64>,
+    #[schemars(
+        description = "Lowest allowed contrast ratio between text and its frame fill (default 4.5)"
+    )]
+    pub min_contrast: Option<f64>,
+    #[schemars(
+        description = "Grid size in px that positions should snap to, relative to the frame (default 8)"
+    )]
+    pub grid: Option<f64>,
+    #[schemars(
+        description = "Token palette as CSS colors; off-palette runs only when this is given"
+    )]
+    pub palette: Option<Vec<String>>,
+}
+
+impl LintConfig {
+    fn enabled(&self, rule: LintRule) -> bool {
+        self.rules
+            .as_ref()
+            .is_none_or(|rules| rules.contains(&rule))
+            && !self.disable.contains(&rule)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintIssue {
+    pub rule: LintRule,
+    pub severity: Severity,
+    pub object_id: String,
+    /// The other object involved, e.g. the overlapping sibling.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related_id: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LintReport {
+    /// Objects looked at, including those nested in groups.
+    pub checked: usize,
+    pub counts: BTreeMap<LintRule, usize>,
+    pub issues: Vec<LintIssue>,
+}
+
+pub fn lint(canvas: &Value, config: &LintConfig) -> Result<LintReport, String> {
+    let palette = config
+        .palette
+        .as_ref()
+        .map(|colors| {
+            colors
+                .iter()
+                .map(|c| Rgba::parse(c).ok_or_else(|| format!("Invalid palette color: {}", c)))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+
+    let scene = Scene::new(canvas);
+    let mut linter = Linter {
+        scene: &scene,
+        canvas_background: canvas
+            .get("background")
+            .and_then(|v| v.as_str())
+            .and_then(Rgba::parse)
+            .map(|c| c.over(Rgba::WHITE))
+            .unwrap_or(Rgba::WHITE),
+        issues: Vec::new(),
+    };
+
+    let has_frames = scene.items().iter().any(|p| fabric::is_frame(p.object));
+    for item in scene.items() {
+        if config.enabled(LintRule::MinTextSize) {
+            linter.min_text_size(item, config.min_font_size.unwrap_or(DEFAULT_MIN_FONT_SIZE));
+        }
+        if config.enabled(LintRule::TextContrast) {
+            linter.text_contrast(item, config.min_contrast.unwrap_or(DEFAULT_MIN_CONTRAST));
+        }
+        if config.enabled(LintRule::OutsideFrame) && has_frames {
+            linter.outside_frame(item);
+        }
+        if config.enabled(LintRule::OffGrid) {
+            linter.off_grid(item, config.grid.unwrap_or(DEFAULT_GRID));
+        }
+        if config.enabled(LintRule::UnnamedLayer) {
+            linter.unnamed_layer(item);
+        }
+        if let (true, Some(palette)) = (config.enabled(LintRule::OffPalette), &palette) {
+            linter.off_palette(item, palette);
+        }
+    }
+    if config.enabled(LintRule::OverlappingSiblings) {
+        linter.overlapping_siblings();
+    }
+
+    let mut issues = linter.issues;
+    issues.sort_by_key(|(z, issue)| (*z, issue.rule));
+    let mut counts = BTreeMap::new();
+    for (_, issue) in &issues {
+        *counts.entry(issue.rule).or_insert(0) += 1;
+    }
+    Ok(LintReport {
+        checked: scene.items().len(),
+        counts,
+        issues: issues.into_iter().map(|(_, issue)| issue).collect(),
+    })
+}
+
+struct Linter<'s, 'a> {
+    scene: &'s Scene<'a>,
+    canvas_background: Rgba,
+    /// Issues with the paint-order index of their object, for sorting.
+    issues: Vec<(usize, LintIssue)>,
+}
+
+impl<'s, 'a> Linter<'s, 'a> {
+    fn report(&mut self, rule: LintRule, item: &Placed, related: Option<&Placed>, message: String) {
+        self.issues.push((
+            item.z,
+            LintIssue {
+                rule,
+                severity: rule.severity(),
+                object_id: item.id().unwrap_or_default().to_string(),
+                related_id: related.and_then(|r| r.id()).map(str::to_string),
+                message,
+            },
+        ));
+    }
+
+    /// The nearest frame an object is in, if any.
+    fn frame_of(&self, item: &Placed) -> Option<&'s Placed<'a>> {
+        let items = self.scene.items();
+        let mut parent = item.parent;
+        for _ in 0..items.len() {
+            let p = &items[parent?];
+            if fabric::is_frame(p.object) {
+                return Some(p);
+            }
+            parent = p.parent;
+        }
+        None
+    }
+
+    fn min_text_size(&mut self, item: &Placed, min: f64) {
+        if !fabric::is_text(item.object) {
+            return;
+        }
+        let base = fabric::num_prop(item.object, "fontSize").unwrap_or(40.0);
+        let smallest = text::char_styles(item.object)
+            .iter()
+            .flatten()
+            .filter_map(|style| style.get("fontSize").and_then(|v| v.as_f64()))
+            .fold(base, f64::min);
+        let size = smallest * item.matrix.decompose().scale_y.abs();
+        if size < min - 1e-6 {
+            self.report(
+                LintRule::MinTextSize,
+                item,
+                None,
+                format!(
+                    "Text renders at {}px, below the {}px minimum",
+                    round1(size),
+                    min
+                ),
+            );
+        }
+    }
+
+    fn text_contrast(&mut self, item: &Placed, min: f64) {
+        if !fabric::is_text(item.object) {
+            return;
+        }
+        let frame = self.frame_of(item);
+        let background = frame
+            .and_then(|f| {
+                f.object
+                    .get("fill")
+                    .and_then(|v| v.as_str())
+                    .and_then(Rgba::parse)
+            })
+            .map(|c| c.over(self.canvas_background))
+            .unwrap_or(self.canvas_background);
+
+        let mut fills: Vec<&str> = item
+            .object
+            .get("fill")
+            .and_then(|v| v.as_str())
+            .into_iter()
+            .collect();
+        let styles = text::char_styles(item.object);
+        fills.extend(
+            styles
+                .iter()
+                .flatten()
+                .filter_map(|style| style.get("fill").and_then(|v| v.as_str())),
+        );
+        let worst = fills
+            .into_iter()
+            .filter_map(Rgba::parse)
+            .filter(|c| c.a > 0.0)
+            .map(|c| c.over(background).contrast_ratio(&background))
+            .fold(f64::INFINITY, f64::min);
+        if worst < min {
+            let against = match frame {
+                Some(_) => format!("its frame's {}", background.to_hex()),
+                None => format!("the canvas {}", background.to_hex()),
+            };
+            self.report(
+                LintRule::TextContrast,
+                item,
+                frame,
+                format!(
+                    "Contrast {:.2}:1 against {} is below {}:1",
+                    worst, against, min
+                ),
+            );
+        }
+    }
+
+    fn outside_frame(&mut self, item: &Placed) {
+        if fabric::is_frame(item.object) {
+            return;
+        }
+        match item.parent.map(|p| &self.scene.items()[p]) {
+            None => self.report(
+                LintRule::OutsideFrame,
+                item,
+                None,
+                "Not inside any frame".to_string(),
+            ),
+            Some(frame)
+                if fabric::is_frame(frame.object) && disjoint(&item.bounds, &frame.bounds) =>
+            {
+                self.report(
+                    LintRule::OutsideFrame,
+                    item,
+                    Some(frame),
+                    "Belongs to a frame but lies entirely outside it".to_string(),
+                )
+            }
+            Some(_) => {}
+        }
+    }
+
+    fn overlapping_siblings(&mut self) {
+        for (a, b) in self.scene.overlaps(None) {
+            if a.parent != b.parent
+                || contains(&a.bounds, &b.bounds)
+                || contains(&b.bounds, &a.bounds)
+            {
+                continue;
+            }
+            let message = format!(
+                "Partially overlaps {}",
+                fabric::str_prop(a.object, "name").unwrap_or("a sibling")
+            );
+            self.report(LintRule::OverlappingSiblings, b, Some(a), message);
+        }
+    }
+
+    fn off_grid(&mut self, item: &Placed, grid: f64) {
+        let parent = item.parent.map(|p| &self.scene.items()[p]);
+        // Group children are laid out by their group.
+        if grid <= 0.0 || parent.is_some_and(|p| !fabric::is_frame(p.object)) {
+            return;
+        }
+        let t = item.matrix.decompose();
+        if t.angle.abs() > 1e-6 || t.skew_x.abs() > 1e-6 {
+            return;
+        }
+        // Fabric's left/top, as the editor shows them, locate the stroke box.
+        let (ox, oy) = self
+            .frame_of(item)
+            .map_or((0.0, 0.0), |frame| (frame.bounds.left, frame.bounds.top));
+        let (x, y) = (item.bounds.left - ox, item.bounds.top - oy);
+        let off = |v: f64| {
+            let r = v.rem_euclid(grid);
+            r > 0.01 && grid - r > 0.01
+        };
+        if off(x) || off(y) {
+            self.report(
+                LintRule::OffGrid,
+                item,
+                None,
+                format!(
+                    "Position {}, {} is not on the {}px grid",
+                    round1(x),
+                    round1(y),
+                    grid
+                ),
+            );
+        }
+    }
+
+    fn unnamed_layer(&mut self, item: &Placed) {
+        let name = fabric::str_prop(item.object, "name").unwrap_or("").trim();
+        if name.is_empty() {
+            self.report(
+                LintRule::UnnamedLayer,
+                item,
+                None,
+                "Layer has no name".to_string(),
+            );
+        } else if is_default_name(name) {
+            self.report(
+                LintRule::UnnamedLayer,
+                item,
+                None,
+                format!("Layer still has the default name {:?}", name),
+            );
+        }
+    }
+
+    fn off_palette(&mut self, item: &Placed, palette: &[Rgba]) {
+        let mut off = Vec::new();
+        for key in ["fill", "stroke"] {
+            if key == "stroke" && fabric::num_prop(item.object, "strokeWidth").unwrap_or(0.0) <= 0.0
+            {
+                continue;
+            }
+            let colors: Vec<&str> = match item.object.get(key) {
+                Some(Value::String(s)) => vec![s.as_str()],
+                Some(Value::Object(gradient)) => gradient
+                    .get("colorStops")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|stop| stop.get("color").and_then(|v| v.as_str()))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            for color in colors {
+                let Some(c) = Rgba::parse(color).filter(|c| c.a > 0.0) else {
+                    continue;
+                };
+                // Opacity variations of a token are still the token.
+                if !palette.iter().any(|p| (p.r, p.g, p.b) == (c.r, c.g, c.b)) {
+                    off.push(format!("{} {}", key, c.to_css()));
+                }
+            }
+        }
+        if !off.is_empty() {
+            off.dedup();
+            self.report(
+                LintRule::OffPalette,
+                item,
+                None,
+                format!("Colors not in the palette: {}", off.join(", ")),
+            );
+        }
+    }
+}
+
+/// "Rectangle 3", "Text 12 copy", "Group": names nobody chose.
+fn is_default_name(name: &str) -> bool {
+    let mut base = name.to_ascii_lowercase();
+    while let Some(stripped) = base.strip_suffix(" copy") {
+        base = stripped.to_string();
+    }
+    let base = base
+        .trim_end_matches(|c: char| c.is_ascii_digit())
+        .trim_end();
+    DEFAULT_NAMES.contains(&base)
+}
+
+fn contains(outer: &Bounds, inner: &Bounds) -> bool {
+    inner.left >= outer.left - 1e-6
+        && inner.top >= outer.top - 1e-6
+        && inner.right() <= outer.right() + 1e-6
+        && inner.bottom() <= outer.bottom() + 1e-6
+}
+
+fn disjoint(a: &Bounds, b: &Bounds) -> bool {
+    a.right() <= b.left || b.right() <= a.left || a.bottom() <= b.top || b.bottom() <= a.top
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0 + 0.0
+}
@@ -1,12 +1,20 @@
-mod easel;
-mod server;
+mod cli;
 
 use anyhow::Result;
+use clap::Parser;
+use easel_mcp::server;
 use rmcp::{ServiceExt, transport::stdio};
 use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    match cli::Cli::parse().command {
+        None | Some(cli::Command::Serve) => serve().await,
+        Some(command) => cli::run(command),
+    }
+}
+
+async fn serve() -> Result<()> {
     // Log to stderr so stdout stays clean for JSON-RPC
     tracing_subscriber::fmt()
         .with_env_filter(
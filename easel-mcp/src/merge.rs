@@ -0,0 +1,452 @@
+//! Three-way merge of canvas documents.
+//!
+//! Objects are matched by id, so two writers touching different objects, or
+//! different properties of the same object, merge cleanly. Group children
+//! (`objects` inside an object) are merged the same way, recursively. Where
+//! both sides changed the same thing differently the preferred side wins and
+//! the clash is reported, so nothing is lost silently.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+use crate::easel::EaselFile;
+use crate::fabric;
+
+/// Which side wins a conflict.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, serde::Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    #[default]
+    Ours,
+    Theirs,
+}
+
+impl Side {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Side::Ours => "ours",
+            Side::Theirs => "theirs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictKind {
+    /// Both sides set a property to different values.
+    Property,
+    /// One side deleted an object the other changed.
+    Deleted,
+    /// Both sides reordered the same objects differently.
+    Order,
+}
+
+impl ConflictKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConflictKind::Property => "property",
+            ConflictKind::Deleted => "deleted",
+            ConflictKind::Order => "order",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+    pub kind: ConflictKind,
+    /// The object concerned; for `Order`, the group whose children clash.
+    /// Absent for canvas- and document-level properties.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub object_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub property: Option<String>,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+    pub resolved: Side,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Merge<T> {
+    pub merged: T,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+struct Merger {
+    prefer: Side,
+    conflicts: Vec<MergeConflict>,
+}
+
+/// Merge two edits of the Fabric canvas JSON `base`.
+pub fn merge_canvases(base: &Value, ours: &Value, theirs: &Value, prefer: Side) -> Merge<Value> {
+    let mut merger = Merger {
+        prefer,
+        conflicts: Vec::new(),
+    };
+    let merged = merger.object(None, Some(base), ours, theirs);
+    Merge {
+        merged,
+        conflicts: merger.conflicts,
+    }
+}
+
+/// Merge whole documents: the canvas as in [`merge_canvases`], plus the name.
+/// Everything else, the viewport and revision included, comes from ours.
+pub fn merge_files(
+    base: &EaselFile,
+    ours: &EaselFile,
+    theirs: &EaselFile,
+    prefer: Side,
+) -> Merge<EaselFile> {
+    let mut merger = Merger {
+        prefer,
+        conflicts: Vec::new(),
+    };
+    let mut merged = ours.clone();
+    merged.canvas = merger.object(None, Some(&base.canvas), &ours.canvas, &theirs.canvas);
+
+    let name = |f: &EaselFile| Some(Value::String(f.name.clone()));
+    if let Some(Value::String(n)) = merger.value(
+        None,
+        "name",
+        name(base).as_ref(),
+        name(ours).as_ref(),
+        name(theirs).as_ref(),
+    ) {
+        merged.name = n;
+    }
+    merged.updated_at = ours.updated_at.max(theirs.updated_at);
+    Merge {
+        merged,
+        conflicts: merger.conflicts,
+    }
+}
+
+impl Merger {
+    fn pick(&self, ours: Option<&Value>, theirs: Option<&Value>) -> Option<Value> {
+        match self.prefer {
+            Side::Ours => ours.cloned(),
+            Side::Theirs => theirs.cloned(),
+        }
+    }
+
+    fn conflict(
+        &mut self,
+        kind: ConflictKind,
+        object_id: Option<&str>,
+        property: Option<&str>,
+        [base, ours, theirs]: [Option<&Value>; 3],
+    ) {
+        self.conflicts.push(MergeConflict {
+            kind,
+            object_id: object_id.map(String::from),
+            property: property.map(String::from),
+            base: base.cloned(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+            resolved: self.prefer,
+        });
+    }
+
+    /// Merge one property; `None` means absent (or removed).
+    fn value(
+        &mut self,
+        id: Option<&str>,
+        key: &str,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+    ) -> Option<Value> {
+        if key == "objects" {
+            if let (Some(Value::Array(o)), Some(Value::Array(t))) = (ours, theirs) {
+                if let Some(list) = self.list(id, base.and_then(|b| b.as_array()), o, t) {
+                    return Some(Value::Array(list));
+                }
+            }
+        }
+        self.three_way(id, Some(key), base, ours, theirs)
+    }
+
+    /// Whichever side changed `base`, or the preferred side if both did.
+    fn three_way(
+        &mut self,
+        id: Option<&str>,
+        key: Option<&str>,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+    ) -> Option<Value> {
+        if ours == theirs || theirs == base {
+            return ours.cloned();
+        }
+        if ours == base {
+            return theirs.cloned();
+        }
+        self.conflict(ConflictKind::Property, id, key, [base, ours, theirs]);
+        self.pick(ours, theirs)
+    }
+
+    /// Merge two edits of an object property by property.
+    fn object(
+        &mut self,
+        id: Option<&str>,
+        base: Option<&Value>,
+        ours: &Value,
+        theirs: &Value,
+    ) -> Value {
+        let (Some(o), Some(t)) = (ours.as_object(), theirs.as_object()) else {
+            return self
+                .three_way(id, None, base, Some(ours), Some(theirs))
+                .unwrap_or(Value::Null);
+        };
+        let b = base.and_then(|b| b.as_object());
+
+        let mut merged = Map::new();
+        let mut seen = HashSet::new();
+        for key in o
+            .keys()
+            .chain(t.keys())
+            .chain(b.into_iter().flat_map(|b| b.keys()))
+        {
+            if !seen.insert(key) {
+                continue;
+            }
+            let merged_value =
+                self.value(id, key, b.and_then(|b| b.get(key)), o.get(key), t.get(key));
+            if let Some(v) = merged_value {
+                merged.insert(key.clone(), v);
+            }
+        }
+        Value::Object(merged)
+    }
+
+    /// Merge object lists keyed by id, or `None` if some object has no id and
+    /// the list has to be compared whole.
+    fn list(
+        &mut self,
+        parent: Option<&str>,
+        base: Option<&Vec<Value>>,
+        ours: &[Value],
+        theirs: &[Value],
+    ) -> Option<Vec<Value>> {
+        let empty = Vec::new();
+        let base = base.unwrap_or(&empty);
+        let index = |list: &[Value]| -> Option<HashMap<String, usize>> {
+            list.iter()
+                .enumerate()
+                .map(|(i, obj)| fabric::object_id(obj).map(|id| (id.to_string(), i)))
+                .collect()
+        };
+        let (b_at, o_at, t_at) = (index(base)?, index(ours)?, index(theirs)?);
+        let ids = |list: &[Value]| -> Vec<String> {
+            list.iter()
+                .filter_map(|obj| fabric::object_id(obj).map(String::from))
+                .collect()
+        };
+        let (b_ids, o_ids, t_ids) = (ids(base), ids(ours), ids(theirs));
+
+        // Merge each object, deciding which ones survive.
+        let mut merged: HashMap<String, Value> = HashMap::new();
+        let mut seen = HashSet::new();
+        for id in o_ids.iter().chain(&t_ids).chain(&b_ids) {
+            if !seen.insert(id) {
+                continue;
+            }
+            let b = b_at.get(id).map(|&i| &base[i]);
+            let o = o_at.get(id).map(|&i| &ours[i]);
+            let t = t_at.get(id).map(|&i| &theirs[i]);
+            let survivor = match (o, t) {
+                (Some(o), Some(t)) => Some(self.object(Some(id), b, o, t)),
+                (Some(kept), None) | (None, Some(kept)) if b.is_none() => Some(kept.clone()),
+                (Some(kept), None) | (None, Some(kept)) => {
+                    if Some(kept) == b {
+                        None
+                    } else {
+                        self.conflict(ConflictKind::Deleted, Some(id), None, [b, o, t]);
+                        let kept_side = if o.is_some() {
+                            Side::Ours
+                        } else {
+                            Side::Theirs
+                        };
+                        (kept_side == self.prefer).then(|| kept.clone())
+                    }
+                }
+                (None, None) => None,
+            };
+            if let Some(obj) = survivor {
+                merged.insert(id.clone(), obj);
+            }
+        }
+
+        // Stacking order: follow the side that reordered the shared objects,
+        // then slot in what only the other side has after its predecessor.
+        let shared = |list: &[String]| -> Vec<String> {
+            list.iter()
+                .filter(|id| {
+                    b_at.contains_key(*id) && o_at.contains_key(*id) && t_at.contains_key(*id)
+                })
+                .cloned()
+                .collect()
+        };
+        let (b_order, o_order, t_order) = (shared(&b_ids), shared(&o_ids), shared(&t_ids));
+        let theirs_leads = if o_order == b_order {
+            true
+        } else if t_order == b_order || t_order == o_order {
+            false
+        } else {
+            let order = |ids: &[String]| Value::from(ids.to_vec());
+            self.conflict(
+                ConflictKind::Order,
+                parent,
+                Some("objects"),
+                [
+                    Some(&order(&b_order)),
+                    Some(&order(&o_order)),
+                    Some(&order(&t_order)),
+                ],
+            );
+            self.prefer == Side::Theirs
+        };
+        let (lead, other) = if theirs_leads {
+            (&t_ids, &o_ids)
+        } else {
+            (&o_ids, &t_ids)
+        };
+
+        let mut order: Vec<&String> = lead.iter().filter(|id| merged.contains_key(*id)).collect();
+        let mut after: Option<&String> = None;
+        for id in other {
+            if !merged.contains_key(id) {
+                continue;
+            }
+            if !order.contains(&id) {
+                let at = after
+                    .and_then(|prev| order.iter().position(|x| *x == prev))
+                    .map_or(0, |p| p + 1);
+                order.insert(at, id);
+            }
+            after = Some(id);
+        }
+        Some(
+            order
+                .into_iter()
+                .filter_map(|id| merged.remove(id))
+                .collect(),
+        )
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ids(canvas: &Value) -> Vec<&str> {
+        canvas["objects"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(fabric::object_id)
+            .collect()
+    }
+
+    #[test]
+    fn edits_to_different_properties_of_one_object_combine() {
+        let base = json!({"objects": [{"id": "a", "left": 0, "top": 0}]});
+        let ours = json!({"objects": [{"id": "a", "left": 10, "top": 0}]});
+        let theirs = json!({"objects": [{"id": "a", "left": 0, "top": 20}]});
+        let merge = merge_canvases(&base, &ours, &theirs, Side::Ours);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(
+            merge.merged["objects"][0],
+            json!({"id": "a", "left": 10, "top": 20})
+        );
+    }
+
+    #[test]
+    fn clashing_property_edits_go_to_the_preferred_side() {
+        let base = json!({"objects": [{"id": "a", "fill": "red"}]});
+        let ours = json!({"objects": [{"id": "a", "fill": "blue"}]});
+        let theirs = json!({"objects": [{"id": "a", "fill": "green"}]});
+
+        let merge = merge_canvases(&base, &ours, &theirs, Side::Theirs);
+        assert_eq!(merge.merged["objects"][0]["fill"], "green");
+        let [conflict] = merge.conflicts.as_slice() else {
+            panic!("{:?}", merge.conflicts);
+        };
+        assert_eq!(conflict.kind, ConflictKind::Property);
+        assert_eq!(conflict.object_id.as_deref(), Some("a"));
+        assert_eq!(conflict.property.as_deref(), Some("fill"));
+        assert_eq!(conflict.ours, Some(json!("blue")));
+        assert_eq!(conflict.resolved, Side::Theirs);
+    }
+
+    #[test]
+    fn delete_against_modify_is_a_conflict() {
+        let base = json!({"objects": [{"id": "a", "left": 0}, {"id": "b"}]});
+        let ours = json!({"objects": [{"id": "b"}]});
+        let theirs = json!({"objects": [{"id": "a", "left": 5}, {"id": "b"}]});
+
+        let kept = merge_canvases(&base, &ours, &theirs, Side::Theirs);
+        assert_eq!(ids(&kept.merged), ["a", "b"]);
+        assert_eq!(kept.merged["objects"][0]["left"], 5);
+        assert_eq!(kept.conflicts.len(), 1);
+        assert_eq!(kept.conflicts[0].kind, ConflictKind::Deleted);
+
+        let deleted = merge_canvases(&base, &ours, &theirs, Side::Ours);
+        assert_eq!(ids(&deleted.merged), ["b"]);
+        assert_eq!(deleted.conflicts[0].kind, ConflictKind::Deleted);
+    }
+
+    #[test]
+    fn deleting_an_unchanged_object_merges_cleanly() {
+        let base = json!({"objects": [{"id": "a"}, {"id": "b"}]});
+        let ours = json!({"objects": [{"id": "b"}]});
+        let theirs = json!({"objects": [{"id": "a"}, {"id": "b"}, {"id": "c"}]});
+        let merge = merge_canvases(&base, &ours, &theirs, Side::Ours);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(ids(&merge.merged), ["b", "c"]);
+    }
+
+    #[test]
+    fn one_sided_reorder_keeps_the_other_sides_additions_in_place() {
+        let base = json!({"objects": [{"id": "a"}, {"id": "b"}, {"id": "c"}]});
+        let ours = json!({"objects": [{"id": "c"}, {"id": "a"}, {"id": "b"}]});
+        let theirs = json!({"objects": [{"id": "a"}, {"id": "x"}, {"id": "b"}, {"id": "c"}]});
+        let merge = merge_canvases(&base, &ours, &theirs, Side::Theirs);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(ids(&merge.merged), ["c", "a", "x", "b"]);
+    }
+
+    #[test]
+    fn clashing_reorders_are_reported() {
+        let base = json!({"objects": [{"id": "a"}, {"id": "b"}, {"id": "c"}]});
+        let ours = json!({"objects": [{"id": "c"}, {"id": "b"}, {"id": "a"}]});
+        let theirs = json!({"objects": [{"id": "b"}, {"id": "a"}, {"id": "c"}]});
+        let merge = merge_canvases(&base, &ours, &theirs, Side::Ours);
+        assert_eq!(ids(&merge.merged), ["c", "b", "a"]);
+        assert_eq!(merge.conflicts.len(), 1);
+        assert_eq!(merge.conflicts[0].kind, ConflictKind::Order);
+        assert_eq!(merge.conflicts[0].object_id, None);
+    }
+
+    #[test]
+    fn merges_group_children_by_id() {
+        let base = json!({"objects": [{"id": "g", "objects": [{"id": "a", "left": 0}]}]});
+        let ours = json!({"objects": [{"id": "g", "objects": [{"id": "a", "left": 3}]}]});
+        let theirs = json!({"objects": [
+            {"id": "g", "objects": [{"id": "a", "left": 0}, {"id": "b"}]},
+        ]});
+        let merge = merge_canvases(&base, &ours, &theirs, Side::Ours);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(
+            merge.merged["objects"][0]["objects"],
+            json!([{"id": "a", "left": 3}, {"id": "b"}])
+        );
+    }
+}
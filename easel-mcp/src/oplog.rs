@@ -0,0 +1,1198 @@
+//! Append-only log of every change saved to a canvas.
+//!
+//! Each save of an `.easel` file appends a batch of operations to a SQLite
+//! database kept beside it (`<file>.ops.db`): objects created, updated or
+//! deleted, and the children of a group (or the top level) reordered, each
+//! with its values before and after. Objects are tracked by id at any depth,
+//! shallowly: a group's operations cover its own properties and each child
+//! has its own. The first batch recorded for a file that already had content
+//! is a baseline recreating it, so the log replays from an empty canvas.
+//!
+//! A batch is the difference between the file as it was on disk and as the
+//! save wrote it, after merging, so the log is the file's linear history
+//! whichever process saved it.
+//!
+//! Undo and redo are saves too: reversing or reapplying an earlier batch,
+//! recorded with the id of that batch, so the history of what is undone
+//! outlives the process that undid it.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::assets;
+use crate::attribution::{self, Actor, ActorKind, EDITED_AT, EDITED_BY};
+use crate::crdt;
+use crate::fabric;
+
+const OBJECTS: &str = "objects";
+
+/// How many of the latest edits in scope undo can step back through.
+pub const UNDO_DEPTH: usize = 100;
+
+const MIGRATIONS: &[(&str, &str)] = &[(
+    "001",
+    "CREATE TABLE batches (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        at TEXT NOT NULL,
+        revision INTEGER NOT NULL,
+        actor_kind TEXT,
+        actor_label TEXT,
+        actor_session TEXT,
+        undoes INTEGER REFERENCES batches(id),
+        redoes INTEGER REFERENCES batches(id)
+    );
+    CREATE TABLE ops (
+        batch_id INTEGER NOT NULL REFERENCES batches(id),
+        seq INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        object_id TEXT,
+        parent_id TEXT,
+        position INTEGER,
+        properties TEXT,
+        before TEXT,
+        after TEXT,
+        PRIMARY KEY (batch_id, seq)
+    );
+    CREATE INDEX idx_ops_object ON ops(object_id);
+    CREATE INDEX idx_batches_session ON batches(actor_session);",
+)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpKind {
+    Create,
+    Update,
+    Delete,
+    Reorder,
+}
+
+impl OpKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OpKind::Create => "create",
+            OpKind::Update => "update",
+            OpKind::Delete => "delete",
+            OpKind::Reorder => "reorder",
+        }
+    }
+
+    fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "create" => Some(OpKind::Create),
+            "update" => Some(OpKind::Update),
+            "delete" => Some(OpKind::Delete),
+            "reorder" => Some(OpKind::Reorder),
+            _ => None,
+        }
+    }
+}
+
+/// One change made by a save.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Op {
+    pub kind: OpKind,
+    /// The object created, updated or deleted. None for an update of the
+    /// canvas's own properties, and for reorders.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object_id: Option<String>,
+    /// The group the object was deleted from or created in, or whose
+    /// children were reordered; none at the top level.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    /// The object's index in that group.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    /// Properties whose value changed, for updates.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub properties: Vec<String>,
+    /// The object without its children (`objects` emptied), or for a
+    /// reorder the group's child ids in order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+/// The operations of one save.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Batch {
+    pub id: i64,
+    pub at: DateTime<Utc>,
+    /// File revision the save produced.
+    pub revision: u64,
+    /// Who saved; none for unattributed saves and the baseline.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<Actor>,
+    /// Set when the save undid or redid an earlier batch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<Step>,
+    pub ops: Vec<Op>,
+}
+
+/// A step back or forward through a canvas's history, naming the batch it
+/// undoes or redoes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Step {
+    Undo(i64),
+    Redo(i64),
+}
+
+/// Whose edits undo and redo step through.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryScope {
+    /// Edits saved from this process.
+    #[default]
+    Session,
+    /// Every attributed edit, whoever made it.
+    Global,
+}
+
+impl HistoryScope {
+    pub fn includes(self, actor: &Actor) -> bool {
+        match self {
+            HistoryScope::Session => actor.session == attribution::session_id(),
+            HistoryScope::Global => true,
+        }
+    }
+}
+
+/// The operation log kept beside the canvas at `path`.
+pub fn log_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".ops.db");
+    PathBuf::from(name)
+}
+
+/// Every batch in the operation log of `path`, oldest first.
+pub fn read(path: &Path) -> Result<Vec<Batch>, String> {
+    let db = log_path(path);
+    if !db.exists() {
+        return Ok(Vec::new());
+    }
+    let conn = open(path)?;
+    read_batches(&conn).map_err(|e| failed(&db, e))
+}
+
+/// The canvas as the log of `path` has it after the save that produced
+/// `revision` (the latest save without one), replayed from an empty canvas.
+pub fn replay(path: &Path, revision: Option<u64>) -> Result<Value, String> {
+    let batches = read(path)?;
+    if batches.is_empty() {
+        return Err(format!("{} has no operation log", path.display()));
+    }
+    let mut canvas = json!({ OBJECTS: [] });
+    for batch in batches
+        .iter()
+        .take_while(|b| revision.is_none_or(|r| b.revision <= r))
+    {
+        apply(&mut canvas, &batch.ops);
+    }
+    Ok(canvas)
+}
+
+/// A save on its way to the log.
+pub struct PendingSave {
+    /// The file's canvas before the save, none for a new file.
+    before: Option<Value>,
+    actor: Option<Actor>,
+    at: DateTime<Utc>,
+}
+
+/// Start a save of `canvas`, an edit made at `heads`, to the file at `path`:
+/// stamp what it changes with `actor` (see [`attribution::stamp`]) and note
+/// the file as it is now. Call with the canvas lock held, before the edit is
+/// committed to the CRDT log.
+pub fn begin(
+    path: &Path,
+    heads: &[String],
+    canvas: &mut Value,
+    actor: Option<&Actor>,
+) -> Result<PendingSave, String> {
+    let at = Utc::now();
+    let base = crdt::canvas_at(path, heads)?;
+    attribution::stamp(base.as_ref(), canvas, actor, at);
+    let before = if heads.is_empty() {
+        base
+    } else {
+        crdt::canvas_at(path, &[])?
+    };
+    Ok(PendingSave {
+        before,
+        actor: actor.cloned(),
+        at,
+    })
+}
+
+impl PendingSave {
+    /// Append what the save changed, given the canvas it wrote as `revision`,
+    /// along with the history `step` it takes, if any.
+    pub fn finish(
+        self,
+        path: &Path,
+        after: &Value,
+        revision: u64,
+        step: Option<Step>,
+    ) -> Result<(), String> {
+        let ops = diff(self.before.as_ref(), after);
+        // A step is recorded even when it changed nothing, or it would
+        // come up again
+        if ops.is_empty() && step.is_none() {
+            return Ok(());
+        }
+        let db = log_path(path);
+        let mut conn = open(path)?;
+        let record = |conn: &mut Connection| -> rusqlite::Result<()> {
+            let tx = conn.transaction()?;
+            let empty: bool =
+                tx.query_row("SELECT COUNT(*) = 0 FROM batches", [], |row| row.get(0))?;
+            if let Some(before) = self.before.as_ref().filter(|_| empty) {
+                let baseline = diff(None, before);
+                if !baseline.is_empty() {
+                    insert_batch(
+                        &tx,
+                        self.at,
+                        revision.saturating_sub(1),
+                        None,
+                        None,
+                        &baseline,
+                    )?;
+                }
+            }
+            insert_batch(&tx, self.at, revision, self.actor.as_ref(), step, &ops)?;
+            tx.commit()
+        };
+        record(&mut conn).map_err(|e| failed(&db, e))
+    }
+}
+
+/// The operations turning `before` (none for an empty file) into `after`.
+///
+/// They come in the order [`apply`] replays them: deletes and creates in
+/// document order, then updates, then reorders. An object moved to another
+/// group is deleted and created again, along with everything inside it.
+pub fn diff(before: Option<&Value>, after: &Value) -> Vec<Op> {
+    let old = before.map(nodes).unwrap_or_default();
+    let new = nodes(after);
+    let old_by: HashMap<&str, &Node> = old.iter().map(|n| (n.id.as_str(), n)).collect();
+    let new_by: HashMap<&str, &Node> = new.iter().map(|n| (n.id.as_str(), n)).collect();
+
+    // Parents come before their children, so a moved group marks its
+    // contents on the way down.
+    let mut moved: HashSet<&str> = HashSet::new();
+    for node in &new {
+        let Some(prior) = old_by.get(node.id.as_str()) else {
+            continue;
+        };
+        if prior.parent != node.parent || node.parent.as_deref().is_some_and(|p| moved.contains(p))
+        {
+            moved.insert(node.id.as_str());
+        }
+    }
+
+    let mut ops = Vec::new();
+    for node in &old {
+        if !new_by.contains_key(node.id.as_str()) || moved.contains(node.id.as_str()) {
+            ops.push(Op {
+                kind: OpKind::Delete,
+                object_id: Some(node.id.clone()),
+                parent: node.parent.clone(),
+                index: Some(node.index),
+                properties: Vec::new(),
+                before: Some(node.object.clone()),
+                after: None,
+            });
+        }
+    }
+    for node in &new {
+        if !old_by.contains_key(node.id.as_str()) || moved.contains(node.id.as_str()) {
+            ops.push(Op {
+                kind: OpKind::Create,
+                object_id: Some(node.id.clone()),
+                parent: node.parent.clone(),
+                index: Some(node.index),
+                properties: Vec::new(),
+                before: None,
+                after: Some(node.object.clone()),
+            });
+        }
+    }
+
+    let empty = Value::Object(Map::new());
+    let canvas_before = before.map(shallow);
+    let canvas_after = shallow(after);
+    let properties = changed_properties(canvas_before.as_ref().unwrap_or(&empty), &canvas_after);
+    if !properties.is_empty() {
+        ops.push(Op {
+            kind: OpKind::Update,
+            object_id: None,
+            parent: None,
+            index: None,
+            properties,
+            before: canvas_before,
+            after: Some(canvas_after),
+        });
+    }
+    for node in &new {
+        let Some(prior) = old_by.get(node.id.as_str()) else {
+            continue;
+        };
+        if moved.contains(node.id.as_str()) || same(&prior.object, &node.object) {
+            continue;
+        }
+        ops.push(Op {
+            kind: OpKind::Update,
+            object_id: Some(node.id.clone()),
+            parent: None,
+            index: None,
+            properties: changed_properties(&prior.object, &node.object),
+            before: Some(prior.object.clone()),
+            after: Some(node.object.clone()),
+        });
+    }
+
+    let old_children = children(&old);
+    let new_children = children(&new);
+    let mut parents: Vec<Option<&str>> = vec![None];
+    parents.extend(new.iter().map(|n| Some(n.id.as_str())));
+    for parent in parents {
+        if parent.is_some_and(|p| moved.contains(p) || !old_by.contains_key(p)) {
+            continue;
+        }
+        let (Some(was), Some(is)) = (old_children.get(&parent), new_children.get(&parent)) else {
+            continue;
+        };
+        let kept: HashSet<&str> = was.iter().copied().collect();
+        let stayed: HashSet<&str> = is.iter().copied().collect();
+        let was_order = was.iter().filter(|id| stayed.contains(*id));
+        let is_order = is.iter().filter(|id| kept.contains(*id));
+        if was_order.ne(is_order) {
+            ops.push(Op {
+                kind: OpKind::Reorder,
+                object_id: None,
+                parent: parent.map(str::to_string),
+                index: None,
+                properties: Vec::new(),
+                before: Some(json!(was)),
+                after: Some(json!(is)),
+            });
+        }
+    }
+    ops
+}
+
+/// Undo the latest batch in scope that is still in effect, reversing its
+/// operations on `canvas`. Returns the step to save the result with and the
+/// batch undone, or none when there is nothing left to undo. Unattributed
+/// saves, including the baseline, are never undone, and neither are edits
+/// older than the last [`UNDO_DEPTH`].
+///
+/// Fails, leaving `canvas` alone, when objects the batch changed have been
+/// changed again since.
+pub fn undo<'a>(
+    canvas: &mut Value,
+    batches: &'a [Batch],
+    in_scope: impl Fn(&Actor) -> bool,
+) -> Result<Option<(Step, &'a Batch)>, String> {
+    let (done, _) = history(batches, in_scope);
+    let Some(batch) = done
+        .last()
+        .and_then(|&id| batches.iter().find(|b| b.id == id))
+    else {
+        return Ok(None);
+    };
+    check(canvas, batch, true)?;
+    unapply(canvas, &batch.ops);
+    Ok(Some((Step::Undo(batch.id), batch)))
+}
+
+/// Redo the batch in scope most recently undone, applying its operations to
+/// `canvas` again. Like [`undo`], returns none when there is nothing to redo
+/// and fails when its objects changed since it was undone. A new edit in
+/// scope clears what there is to redo.
+pub fn redo<'a>(
+    canvas: &mut Value,
+    batches: &'a [Batch],
+    in_scope: impl Fn(&Actor) -> bool,
+) -> Result<Option<(Step, &'a Batch)>, String> {
+    let (_, undone) = history(batches, in_scope);
+    let Some(batch) = undone
+        .last()
+        .and_then(|&id| batches.iter().find(|b| b.id == id))
+    else {
+        return Ok(None);
+    };
+    check(canvas, batch, false)?;
+    apply(canvas, &batch.ops);
+    Ok(Some((Step::Redo(batch.id), batch)))
+}
+
+/// Ids of the batches in scope still in effect and of those undone, each
+/// oldest first.
+fn history(batches: &[Batch], in_scope: impl Fn(&Actor) -> bool) -> (Vec<i64>, Vec<i64>) {
+    let mut done: Vec<i64> = Vec::new();
+    let mut undone: Vec<i64> = Vec::new();
+    for batch in batches {
+        match batch.step {
+            Some(Step::Undo(target)) => {
+                if let Some(at) = done.iter().rposition(|&id| id == target) {
+                    done.remove(at);
+                    undone.push(target);
+                }
+            }
+            Some(Step::Redo(target)) => {
+                if let Some(at) = undone.iter().rposition(|&id| id == target) {
+                    undone.remove(at);
+                    done.push(target);
+                }
+            }
+            None => {
+                if batch.actor.as_ref().is_some_and(&in_scope) {
+                    done.push(batch.id);
+                    undone.clear();
+                    if done.len() > UNDO_DEPTH {
+                        done.remove(0);
+                    }
+                }
+            }
+        }
+    }
+    (done, undone)
+}
+
+/// Fail unless everything `batch` touched is as it left it (for an undo) or
+/// as it found it (for a redo). Moves and reorders aren't checked.
+fn check(canvas: &Value, batch: &Batch, undo: bool) -> Result<(), String> {
+    // An object moved between groups is deleted and created in one batch:
+    // compare with its state before the first operation and after the last
+    let mut expected: Vec<(Option<&str>, Option<&Value>)> = Vec::new();
+    for op in batch.ops.iter().filter(|op| op.kind != OpKind::Reorder) {
+        let id = op.object_id.as_deref();
+        let state = if undo {
+            op.after.as_ref()
+        } else {
+            op.before.as_ref()
+        };
+        match expected.iter_mut().find(|(seen, _)| *seen == id) {
+            Some(entry) if undo => entry.1 = state,
+            Some(_) => {}
+            None => expected.push((id, state)),
+        }
+    }
+
+    let changed: Vec<&str> = expected
+        .into_iter()
+        // A new file's canvas properties have no earlier state to check
+        .filter(|(id, state)| id.is_some() || state.is_some())
+        .filter(|(id, state)| {
+            let current = match id {
+                Some(id) => find(canvas, id),
+                None => Some(canvas),
+            };
+            !match (current, state) {
+                (Some(current), Some(state)) => same(state, current),
+                (None, None) => true,
+                _ => false,
+            }
+        })
+        .map(|(id, _)| id.unwrap_or("canvas"))
+        .collect();
+    if changed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Can't {} the save at revision {}: changed since: {}",
+            if undo { "undo" } else { "redo" },
+            batch.revision,
+            changed.join(", ")
+        ))
+    }
+}
+
+/// Redo `ops`, one batch as [`diff`] produced it, on `canvas`.
+pub fn apply(canvas: &mut Value, ops: &[Op]) {
+    for op in ops.iter().filter(|op| op.kind == OpKind::Delete) {
+        if let Some(id) = &op.object_id {
+            remove(canvas, id);
+        }
+    }
+    for op in ops.iter().filter(|op| op.kind == OpKind::Create) {
+        create(
+            canvas,
+            op.object_id.as_deref(),
+            op.parent.as_deref(),
+            op.index,
+            &op.after,
+        );
+    }
+    for op in ops.iter().filter(|op| op.kind == OpKind::Update) {
+        set(canvas, op.object_id.as_deref(), op.after.as_ref());
+    }
+    for op in ops.iter().filter(|op| op.kind == OpKind::Reorder) {
+        reorder(canvas, op.parent.as_deref(), op.after.as_ref());
+    }
+}
+
+/// Undo `ops`, one batch as [`diff`] produced it, on `canvas`.
+pub fn unapply(canvas: &mut Value, ops: &[Op]) {
+    for op in ops.iter().rev().filter(|op| op.kind == OpKind::Create) {
+        if let Some(id) = &op.object_id {
+            remove(canvas, id);
+        }
+    }
+    for op in ops.iter().filter(|op| op.kind == OpKind::Delete) {
+        create(
+            canvas,
+            op.object_id.as_deref(),
+            op.parent.as_deref(),
+            op.index,
+            &op.before,
+        );
+    }
+    for op in ops.iter().filter(|op| op.kind == OpKind::Update) {
+        set(canvas, op.object_id.as_deref(), op.before.as_ref());
+    }
+    for op in ops.iter().rev().filter(|op| op.kind == OpKind::Reorder) {
+        reorder(canvas, op.parent.as_deref(), op.before.as_ref());
+    }
+}
+
+/// Assets the objects in `batches` reference before or after their
+/// operations, for the batches undo or redo can still reach in any scope.
+/// Assets only older edits used can't come back.
+pub fn referenced_assets(batches: &[Batch]) -> HashSet<String> {
+    let mut sessions: Vec<&str> = batches
+        .iter()
+        .filter_map(|b| b.actor.as_ref())
+        .map(|actor| actor.session.as_str())
+        .collect();
+    sessions.sort_unstable();
+    sessions.dedup();
+
+    let mut reachable: HashSet<i64> = HashSet::new();
+    let (done, undone) = history(batches, |_| true);
+    reachable.extend(done.into_iter().chain(undone));
+    for session in sessions {
+        let (done, undone) = history(batches, |actor| actor.session == session);
+        reachable.extend(done.into_iter().chain(undone));
+    }
+
+    let objects: Vec<Value> = batches
+        .iter()
+        .filter(|b| reachable.contains(&b.id))
+        .flat_map(|b| &b.ops)
+        .flat_map(|op| [&op.before, &op.after])
+        .flatten()
+        .cloned()
+        .collect();
+    assets::referenced_assets(&json!({ "objects": objects }))
+}
+
+fn open(path: &Path) -> Result<Connection, String> {
+    let db = log_path(path);
+    let conn = Connection::open(&db).map_err(|e| failed(&db, e))?;
+    conn.busy_timeout(Duration::from_secs(5))
+        .and_then(|_| migrate(&conn))
+        .map_err(|e| failed(&db, e))?;
+    Ok(conn)
+}
+
+fn failed(db: &Path, e: rusqlite::Error) -> String {
+    format!("Operation log {}: {}", db.display(), e)
+}
+
+fn migrate(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version TEXT PRIMARY KEY,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );",
+    )?;
+    for (version, sql) in MIGRATIONS {
+        let applied: bool = conn.query_row(
+            "SELECT COUNT(*) > 0 FROM schema_version WHERE version = ?1",
+            [version],
+            |row| row.get(0),
+        )?;
+        if !applied {
+            conn.execute_batch(sql)?;
+            conn.execute(
+                "INSERT INTO schema_version (version) VALUES (?1)",
+                [version],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn insert_batch(
+    tx: &Transaction,
+    at: DateTime<Utc>,
+    revision: u64,
+    actor: Option<&Actor>,
+    step: Option<Step>,
+    ops: &[Op],
+) -> rusqlite::Result<i64> {
+    let (undoes, redoes) = match step {
+        Some(Step::Undo(batch)) => (Some(batch), None),
+        Some(Step::Redo(batch)) => (None, Some(batch)),
+        None => (None, None),
+    };
+    tx.execute(
+        "INSERT INTO batches (at, revision, actor_kind, actor_label, actor_session, undoes, redoes)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            at.to_rfc3339(),
+            revision as i64,
+            actor.map(|a| a.kind.as_str()),
+            actor.map(|a| a.label.as_str()),
+            actor.map(|a| a.session.as_str()),
+            undoes,
+            redoes,
+        ],
+    )?;
+    let batch = tx.last_insert_rowid();
+    let mut insert = tx.prepare(
+        "INSERT INTO ops (batch_id, seq, kind, object_id, parent_id, position, properties, before, after)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+    )?;
+    for (seq, op) in ops.iter().enumerate() {
+        let properties = (!op.properties.is_empty()).then(|| json!(op.properties).to_string());
+        insert.execute(params![
+            batch,
+            seq as i64,
+            op.kind.as_str(),
+            op.object_id,
+            op.parent,
+            op.index.map(|i| i as i64),
+            properties,
+            op.before.as_ref().map(Value::to_string),
+            op.after.as_ref().map(Value::to_string),
+        ])?;
+    }
+    Ok(batch)
+}
+
+fn read_batches(conn: &Connection) -> rusqlite::Result<Vec<Batch>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, at, revision, actor_kind, actor_label, actor_session, undoes, redoes
+         FROM batches ORDER BY id",
+    )?;
+    let mut batches: Vec<Batch> = stmt
+        .query_map([], |row| {
+            let at: String = row.get(1)?;
+            let kind: Option<String> = row.get(3)?;
+            let label: Option<String> = row.get(4)?;
+            let session: Option<String> = row.get(5)?;
+            let undoes: Option<i64> = row.get(6)?;
+            let redoes: Option<i64> = row.get(7)?;
+            let kind = kind.as_deref().and_then(|kind| match kind {
+                "user" => Some(ActorKind::User),
+                "agent" => Some(ActorKind::Agent),
+                _ => None,
+            });
+            Ok(Batch {
+                id: row.get(0)?,
+                at: DateTime::parse_from_rfc3339(&at)
+                    .map(|at| at.with_timezone(&Utc))
+                    .unwrap_or_default(),
+                revision: row.get::<_, i64>(2)? as u64,
+                actor: kind.map(|kind| Actor {
+                    kind,
+                    label: label.unwrap_or_default(),
+                    session: session.unwrap_or_default(),
+                }),
+                step: undoes.map(Step::Undo).or(redoes.map(Step::Redo)),
+                ops: Vec::new(),
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    let index: HashMap<i64, usize> = batches.iter().enumerate().map(|(i, b)| (b.id, i)).collect();
+
+    let json = |text: Option<String>| text.and_then(|t| serde_json::from_str::<Value>(&t).ok());
+    let mut stmt = conn.prepare(
+        "SELECT batch_id, kind, object_id, parent_id, position, properties, before, after
+         FROM ops ORDER BY batch_id, seq",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let batch: i64 = row.get(0)?;
+        let kind: String = row.get(1)?;
+        let (Some(&at), Some(kind)) = (index.get(&batch), OpKind::parse(&kind)) else {
+            continue;
+        };
+        let properties = json(row.get(5)?)
+            .and_then(|p| serde_json::from_value(p).ok())
+            .unwrap_or_default();
+        batches[at].ops.push(Op {
+            kind,
+            object_id: row.get(2)?,
+            parent: row.get(3)?,
+            index: row.get::<_, Option<i64>>(4)?.map(|i| i as usize),
+            properties,
+            before: json(row.get(6)?),
+            after: json(row.get(7)?),
+        });
+    }
+    Ok(batches)
+}
+
+/// An object with an id, somewhere in a canvas.
+pub(crate) struct Node {
+    pub id: String,
+    /// The object without its children.
+    pub object: Value,
+    pub parent: Option<String>,
+    pub index: usize,
+}
+
+/// Every object with an id in `canvas`, parents before their children.
+pub(crate) fn nodes(canvas: &Value) -> Vec<Node> {
+    fn walk(list: &[Value], parent: Option<&str>, out: &mut Vec<Node>) {
+        for (index, obj) in list.iter().enumerate() {
+            let Some(id) = fabric::object_id(obj) else {
+                continue;
+            };
+            out.push(Node {
+                id: id.to_string(),
+                object: shallow(obj),
+                parent: parent.map(str::to_string),
+                index,
+            });
+            if let Some(children) = obj.get(OBJECTS).and_then(Value::as_array) {
+                walk(children, Some(id), out);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    if let Some(objects) = canvas.get(OBJECTS).and_then(Value::as_array) {
+        walk(objects, None, &mut out);
+    }
+    out
+}
+
+/// Child ids of each group (and of the top level, under `None`), in order.
+fn children(nodes: &[Node]) -> HashMap<Option<&str>, Vec<&str>> {
+    let mut lists: HashMap<Option<&str>, Vec<&str>> = HashMap::new();
+    lists.entry(None).or_default();
+    for node in nodes {
+        lists
+            .entry(node.parent.as_deref())
+            .or_default()
+            .push(node.id.as_str());
+    }
+    lists
+}
+
+/// `obj` with its children emptied out, as stored in the log.
+pub(crate) fn shallow(obj: &Value) -> Value {
+    let mut obj = obj.clone();
+    if let Some(children) = obj.get_mut(OBJECTS) {
+        *children = Value::Array(Vec::new());
+    }
+    obj
+}
+
+/// Properties an edit can change: not the children, which are tracked on
+/// their own, and not the attribution stamps.
+fn tracked(key: &str) -> bool {
+    key != OBJECTS && key != EDITED_BY && key != EDITED_AT
+}
+
+pub(crate) fn same_props(a: &Value, b: &Map<String, Value>) -> bool {
+    let Some(a) = a.as_object() else {
+        return false;
+    };
+    let count = |m: &Map<String, Value>| m.keys().filter(|k| tracked(k)).count();
+    count(a) == count(b)
+        && a.iter()
+            .filter(|(k, _)| tracked(k))
+            .all(|(k, v)| b.get(k) == Some(v))
+}
+
+pub(crate) fn same(a: &Value, b: &Value) -> bool {
+    b.as_object().is_some_and(|b| same_props(a, b))
+}
+
+fn changed_properties(before: &Value, after: &Value) -> Vec<String> {
+    let empty = Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+    let mut keys: Vec<String> = before
+        .keys()
+        .chain(after.keys())
+        .filter(|k| tracked(k) && before.get(*k) != after.get(*k))
+        .cloned()
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+pub(crate) fn find<'a>(canvas: &'a Value, id: &str) -> Option<&'a Value> {
+    fn search<'a>(list: &'a [Value], id: &str) -> Option<&'a Value> {
+        list.iter().find_map(|obj| {
+            if fabric::object_id(obj) == Some(id) {
+                return Some(obj);
+            }
+            search(obj.get(OBJECTS)?.as_array()?, id)
+        })
+    }
+    search(canvas.get(OBJECTS)?.as_array()?, id)
+}
+
+/// The object `id` in `canvas`, at any depth.
+pub fn find_mut<'a>(canvas: &'a mut Value, id: &str) -> Option<&'a mut Value> {
+    fn search<'a>(list: &'a mut [Value], id: &str) -> Option<&'a mut Value> {
+        for obj in list {
+            if fabric::object_id(obj) == Some(id) {
+                return Some(obj);
+            }
+            if let Some(found) = obj
+                .get_mut(OBJECTS)
+                .and_then(Value::as_array_mut)
+                .and_then(|children| search(children, id))
+            {
+                return Some(found);
+            }
+        }
+        None
+    }
+    search(canvas.get_mut(OBJECTS)?.as_array_mut()?, id)
+}
+
+/// Remove the object `id` from `canvas`, at any depth.
+pub fn remove(canvas: &mut Value, id: &str) {
+    fn retain(list: &mut Vec<Value>, id: &str) {
+        list.retain(|obj| fabric::object_id(obj) != Some(id));
+        for obj in list {
+            if let Some(children) = obj.get_mut(OBJECTS).and_then(Value::as_array_mut) {
+                retain(children, id);
+            }
+        }
+    }
+    if let Some(objects) = canvas.get_mut(OBJECTS).and_then(Value::as_array_mut) {
+        retain(objects, id);
+    }
+}
+
+/// Insert `obj` into its group (top level if `parent` is gone) at `index`,
+/// clamped to the end.
+pub fn insert(canvas: &mut Value, parent: Option<&str>, index: Option<usize>, obj: Value) {
+    let group = parent.filter(|parent| {
+        find(canvas, parent)
+            .and_then(|group| group.get(OBJECTS))
+            .is_some_and(Value::is_array)
+    });
+    let list = match group {
+        Some(parent) => find_mut(canvas, parent)
+            .and_then(|group| group.get_mut(OBJECTS))
+            .and_then(Value::as_array_mut),
+        None => canvas.get_mut(OBJECTS).and_then(Value::as_array_mut),
+    };
+    let Some(list) = list else {
+        return;
+    };
+    let at = index.unwrap_or(list.len()).min(list.len());
+    list.insert(at, obj);
+}
+
+/// Insert `obj` unless an object with its id is already there.
+fn create(
+    canvas: &mut Value,
+    id: Option<&str>,
+    parent: Option<&str>,
+    index: Option<usize>,
+    obj: &Option<Value>,
+) {
+    let (Some(id), Some(obj)) = (id, obj) else {
+        return;
+    };
+    if find(canvas, id).is_none() {
+        insert(canvas, parent, index, obj.clone());
+    }
+}
+
+/// Give the object `id` (the canvas itself for none) the properties of
+/// `value`, keeping its children. A new file's canvas had no properties
+/// before; undoing that leaves them as they are.
+fn set(canvas: &mut Value, id: Option<&str>, value: Option<&Value>) {
+    let Some(value) = value else {
+        return;
+    };
+    let target = match id {
+        Some(id) => find_mut(canvas, id),
+        None => Some(canvas),
+    };
+    let Some(target) = target.and_then(Value::as_object_mut) else {
+        return;
+    };
+    let children = target.remove(OBJECTS);
+    *target = value.as_object().cloned().unwrap_or_default();
+    match children {
+        Some(children) => target.insert(OBJECTS.into(), children),
+        None => target.remove(OBJECTS),
+    };
+}
+
+/// Put the children of `parent` listed in `order` into that order, in the
+/// slots they occupy; the others stay where they are.
+pub fn reorder(canvas: &mut Value, parent: Option<&str>, order: Option<&Value>) {
+    let Some(order) = order.and_then(Value::as_array) else {
+        return;
+    };
+    let rank: HashMap<&str, usize> = order
+        .iter()
+        .enumerate()
+        .filter_map(|(i, id)| Some((id.as_str()?, i)))
+        .collect();
+    let list = match parent {
+        Some(parent) => find_mut(canvas, parent).and_then(|group| group.get_mut(OBJECTS)),
+        None => canvas.get_mut(OBJECTS),
+    };
+    let Some(list) = list.and_then(Value::as_array_mut) else {
+        return;
+    };
+    let rank_of = |obj: &Value| fabric::object_id(obj).and_then(|id| rank.get(id).copied());
+    let slots: Vec<usize> = (0..list.len())
+        .filter(|&i| rank_of(&list[i]).is_some())
+        .collect();
+    let mut moved: Vec<Value> = slots.iter().map(|&i| list[i].clone()).collect();
+    moved.sort_by_key(|obj| rank_of(obj));
+    for (slot, obj) in slots.into_iter().zip(moved) {
+        list[slot] = obj;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::easel::EaselFile;
+    use std::fs;
+
+    /// A batch of the ops turning `before` into `after`, as `actor` saved it.
+    fn batch(id: i64, before: &Value, after: &Value, actor: Option<&Actor>) -> Batch {
+        Batch {
+            id,
+            at: Utc::now(),
+            revision: id as u64,
+            actor: actor.cloned(),
+            step: None,
+            ops: diff(Some(before), after),
+        }
+    }
+
+    fn step(id: i64, step: Step) -> Batch {
+        Batch {
+            id,
+            at: Utc::now(),
+            revision: id as u64,
+            actor: Some(Actor::user()),
+            step: Some(step),
+            ops: Vec::new(),
+        }
+    }
+
+    fn temp_canvas() -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("easel-oplog-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("canvas.easel");
+        (dir, path)
+    }
+
+    #[test]
+    fn apply_and_unapply_round_trip() {
+        let before = json!({
+            "background": "#fff",
+            "objects": [
+                {"id": "a", "left": 0},
+                {"id": "g", "objects": [{"id": "b"}, {"id": "c"}]},
+                {"id": "d"},
+            ],
+        });
+        let after = json!({
+            "background": "#000",
+            "objects": [
+                {"id": "d"},
+                {"id": "g", "objects": [{"id": "c"}, {"id": "a", "left": 5}]},
+                {"id": "e", "fill": "red"},
+            ],
+        });
+        let ops = diff(Some(&before), &after);
+
+        let mut canvas = before.clone();
+        apply(&mut canvas, &ops);
+        assert_eq!(canvas, after);
+        unapply(&mut canvas, &ops);
+        assert_eq!(canvas, before);
+    }
+
+    #[test]
+    fn moving_into_a_group_deletes_and_recreates() {
+        let before = json!({"objects": [{"id": "a"}, {"id": "g", "objects": []}]});
+        let after = json!({"objects": [{"id": "g", "objects": [{"id": "a"}]}]});
+        let ops = diff(Some(&before), &after);
+        let kinds: Vec<(OpKind, Option<&str>)> = ops
+            .iter()
+            .map(|op| (op.kind, op.object_id.as_deref()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [(OpKind::Delete, Some("a")), (OpKind::Create, Some("a"))]
+        );
+        assert_eq!(ops[1].parent.as_deref(), Some("g"));
+    }
+
+    #[test]
+    fn reorder_is_recorded_once() {
+        let before = json!({"objects": [{"id": "a"}, {"id": "b"}, {"id": "c"}]});
+        let after = json!({"objects": [{"id": "c"}, {"id": "a"}, {"id": "b"}]});
+        let ops = diff(Some(&before), &after);
+        assert_eq!(ops.len(), 1);
+        assert_eq!(ops[0].kind, OpKind::Reorder);
+        assert_eq!(ops[0].after, Some(json!(["c", "a", "b"])));
+    }
+
+    #[test]
+    fn unchanged_canvas_has_no_ops() {
+        let canvas = json!({"objects": [{"id": "a", "objects": [{"id": "b"}]}]});
+        assert!(diff(Some(&canvas), &canvas).is_empty());
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_history_in_scope() {
+        let agent = Actor::agent("bot");
+        let v0 = json!({"objects": []});
+        let v1 = json!({"objects": [{"id": "a"}]});
+        let v2 = json!({"objects": [{"id": "a"}, {"id": "b"}]});
+        let v3 = json!({"objects": [{"id": "a"}, {"id": "b"}, {"id": "c"}]});
+        let mut batches = vec![
+            batch(1, &v0, &v1, Some(&Actor::user())),
+            batch(2, &v1, &v2, Some(&agent)),
+            batch(3, &v2, &v3, Some(&Actor::user())),
+        ];
+        let users = |a: &Actor| a.kind == ActorKind::User;
+
+        let mut canvas = v3.clone();
+        let (step_a, undone) = undo(&mut canvas, &batches, users).unwrap().unwrap();
+        assert_eq!((step_a, undone.id), (Step::Undo(3), 3));
+        assert_eq!(canvas, v2);
+        batches.push(step(4, step_a));
+
+        // The agent's batch is out of scope, so the next undo skips it
+        let (step_b, _) = undo(&mut canvas, &batches, users).unwrap().unwrap();
+        assert_eq!(step_b, Step::Undo(1));
+        assert_eq!(canvas, json!({"objects": [{"id": "b"}]}));
+        batches.push(step(5, step_b));
+        assert!(undo(&mut canvas.clone(), &batches, users)
+            .unwrap()
+            .is_none());
+
+        let (step_c, _) = redo(&mut canvas, &batches, users).unwrap().unwrap();
+        assert_eq!(step_c, Step::Redo(1));
+        batches.push(step(6, step_c));
+        assert_eq!(canvas, v2);
+
+        // A new edit in scope clears what there is to redo
+        let v4 = json!({"objects": [{"id": "a"}, {"id": "b"}, {"id": "d"}]});
+        batches.push(batch(7, &v2, &v4, Some(&Actor::user())));
+        assert!(redo(&mut v4.clone(), &batches, users).unwrap().is_none());
+    }
+
+    #[test]
+    fn undo_refuses_objects_changed_since() {
+        let v0 = json!({"objects": [{"id": "a", "left": 0}]});
+        let v1 = json!({"objects": [{"id": "a", "left": 5}]});
+        let batches = vec![batch(1, &v0, &v1, Some(&Actor::user()))];
+        let mut canvas = json!({"objects": [{"id": "a", "left": 9}]});
+        let error = undo(&mut canvas, &batches, |_| true).unwrap_err();
+        assert!(error.contains("changed since: a"), "{}", error);
+        assert_eq!(canvas["objects"][0]["left"], 9);
+    }
+
+    #[test]
+    fn referenced_assets_cover_edits_that_can_still_be_undone() {
+        let v0 = json!({"objects": [{"id": "i", "type": "Image", "assetId": "x.png"}]});
+        let v1 = json!({"objects": []});
+        let kept = HashSet::from(["x.png".to_string()]);
+
+        let mut batches = vec![batch(1, &v0, &v1, Some(&Actor::user()))];
+        assert_eq!(referenced_assets(&batches), kept);
+        batches.push(step(2, Step::Undo(1)));
+        assert_eq!(referenced_assets(&batches), kept);
+
+        // Unattributed saves can't be undone
+        assert!(referenced_assets(&[batch(1, &v0, &v1, None)]).is_empty());
+
+        // Nor can edits older than the undo depth
+        let mut batches = vec![batch(1, &v0, &v1, Some(&Actor::user()))];
+        for id in 2..=UNDO_DEPTH as i64 + 1 {
+            batches.push(batch(id, &v1, &v1, Some(&Actor::user())));
+        }
+        assert!(referenced_assets(&batches).is_empty());
+    }
+
+    #[test]
+    fn gc_keeps_a_deleted_image_until_undo_cannot_reach_it() {
+        let (dir, path) = temp_canvas();
+        let store = assets::AssetStore::for_canvas(&path);
+        let asset = store.add_bytes(b"<svg/>", Some("svg")).unwrap();
+        let user = Actor::user();
+
+        let mut easel = EaselFile::new("gc");
+        easel.objects_mut().push(json!({
+            "id": "i",
+            "type": "Image",
+            "assetId": asset.id,
+            "src": store.relative_src(&asset.id),
+        }));
+        easel.save_as(&path, &user).unwrap();
+        easel.remove_objects(&["i".to_string()]);
+        easel.save_as(&path, &user).unwrap();
+
+        let removed = store
+            .collect_garbage(&easel.canvas, &read(&path).unwrap())
+            .unwrap();
+        assert!(removed.is_empty());
+
+        for n in 0..UNDO_DEPTH {
+            easel.canvas["background"] = json!(format!("#{:06x}", n));
+            easel.save_as(&path, &user).unwrap();
+        }
+        let removed = store
+            .collect_garbage(&easel.canvas, &read(&path).unwrap())
+            .unwrap();
+        assert_eq!(removed, vec![asset.id]);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn records_a_baseline_before_the_first_save() {
+        let (dir, path) = temp_canvas();
+        let before = json!({"objects": [{"id": "a"}]});
+        let after = json!({"objects": [{"id": "a"}, {"id": "b"}]});
+        let pending = PendingSave {
+            before: Some(before.clone()),
+            actor: Some(Actor::user()),
+            at: Utc::now(),
+        };
+        pending.finish(&path, &after, 2, None).unwrap();
+
+        let batches = read(&path).unwrap();
+        assert_eq!(batches.len(), 2);
+        assert!(batches[0].actor.is_none());
+        assert_eq!(replay(&path, Some(1)).unwrap(), before);
+        assert_eq!(replay(&path, None).unwrap(), after);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,512 @@
+//! SVG path data parsing and the absolute M/L/Q/C/Z form Fabric stores in `path`.
+
+use serde_json::{json, Value};
+use std::f64::consts::PI;
+
+use crate::fabric::Bounds;
+use crate::geometry::{self, Matrix, Point};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    Move(Point),
+    Line(Point),
+    Quad(Point, Point),
+    Cubic(Point, Point, Point),
+    Close,
+}
+
+/// Parse SVG path data into absolute segments, converting H/V/S/T/A into
+/// lines, quadratics and cubics the same way Fabric's `makePathSimpler` does.
+pub fn parse(d: &str) -> Result<Vec<Segment>, String> {
+    let mut tokens = Tokenizer::new(d);
+    let mut segments = Vec::new();
+
+    let mut current = Point::new(0.0, 0.0);
+    let mut subpath_start = current;
+    let mut last_ctrl: Option<(char, Point)> = None;
+    let mut command: Option<char> = None;
+
+    loop {
+        let cmd = match tokens.next_command() {
+            Some(c) => c,
+            None => match command {
+                // Implicit repetition of the previous command
+                Some(prev) if !matches!(prev, 'Z' | 'z') && tokens.has_number() => match prev {
+                    'M' => 'L',
+                    'm' => 'l',
+                    other => other,
+                },
+                _ => break,
+            },
+        };
+        command = Some(cmd);
+        let rel = cmd.is_ascii_lowercase();
+        let base = if rel { current } else { Point::new(0.0, 0.0) };
+        let at = |x: f64, y: f64| Point::new(base.x + x, base.y + y);
+
+        match cmd.to_ascii_uppercase() {
+            'M' => {
+                let p = at(tokens.number()?, tokens.number()?);
+                segments.push(Segment::Move(p));
+                current = p;
+                subpath_start = p;
+                last_ctrl = None;
+            }
+            'L' => {
+                let p = at(tokens.number()?, tokens.number()?);
+                segments.push(Segment::Line(p));
+                current = p;
+                last_ctrl = None;
+            }
+            'H' => {
+                let x = tokens.number()?;
+                let p = Point::new(if rel { current.x + x } else { x }, current.y);
+                segments.push(Segment::Line(p));
+                current = p;
+                last_ctrl = None;
+            }
+            'V' => {
+                let y = tokens.number()?;
+                let p = Point::new(current.x, if rel { current.y + y } else { y });
+                segments.push(Segment::Line(p));
+                current = p;
+                last_ctrl = None;
+            }
+            'C' => {
+                let c1 = at(tokens.number()?, tokens.number()?);
+                let c2 = at(tokens.number()?, tokens.number()?);
+                let p = at(tokens.number()?, tokens.number()?);
+                segments.push(Segment::Cubic(c1, c2, p));
+                current = p;
+                last_ctrl = Some(('C', c2));
+            }
+            'S' => {
+                let c1 = match last_ctrl {
+                    Some(('C', c)) => reflect(c, current),
+                    _ => current,
+                };
+                let c2 = at(tokens.number()?, tokens.number()?);
+                let p = at(tokens.number()?, tokens.number()?);
+                segments.push(Segment::Cubic(c1, c2, p));
+                current = p;
+                last_ctrl = Some(('C', c2));
+            }
+            'Q' => {
+                let c = at(tokens.number()?, tokens.number()?);
+                let p = at(tokens.number()?, tokens.number()?);
+                segments.push(Segment::Quad(c, p));
+                current = p;
+                last_ctrl = Some(('Q', c));
+            }
+            'T' => {
+                let c = match last_ctrl {
+                    Some(('Q', c)) => reflect(c, current),
+                    _ => current,
+                };
+                let p = at(tokens.number()?, tokens.number()?);
+                segments.push(Segment::Quad(c, p));
+                current = p;
+                last_ctrl = Some(('Q', c));
+            }
+            'A' => {
+                let rx = tokens.number()?;
+                let ry = tokens.number()?;
+                let rotation = tokens.number()?;
+                let large_arc = tokens.flag()?;
+                let sweep = tokens.flag()?;
+                let p = at(tokens.number()?, tokens.number()?);
+                segments.extend(arc_to_cubics(
+                    current, rx, ry, rotation, large_arc, sweep, p,
+                ));
+                current = p;
+                last_ctrl = None;
+            }
+            'Z' => {
+                segments.push(Segment::Close);
+                current = subpath_start;
+                last_ctrl = None;
+            }
+            other => return Err(format!("Unsupported path command: {}", other)),
+        }
+    }
+
+    Ok(segments)
+}
+
+fn reflect(ctrl: Point, about: Point) -> Point {
+    Point::new(2.0 * about.x - ctrl.x, 2.0 * about.y - ctrl.y)
+}
+
+/// Convert an SVG elliptical arc into cubic Béziers (SVG spec F.6 conversion).
+#[allow(clippy::too_many_arguments)]
+fn arc_to_cubics(
+    from: Point,
+    rx: f64,
+    ry: f64,
+    rotation: f64,
+    large_arc: bool,
+    sweep: bool,
+    to: Point,
+) -> Vec<Segment> {
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 || (from.x == to.x && from.y == to.y) {
+        return vec![Segment::Line(to)];
+    }
+
+    let (sin_phi, cos_phi) = rotation.to_radians().sin_cos();
+    let dx = (from.x - to.x) / 2.0;
+    let dy = (from.y - to.y) / 2.0;
+    let x1 = cos_phi * dx + sin_phi * dy;
+    let y1 = -sin_phi * dx + cos_phi * dy;
+
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        let s = lambda.sqrt();
+        rx *= s;
+        ry *= s;
+    }
+
+    let num = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let den = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let mut coef = (num / den).max(0.0).sqrt();
+    if large_arc == sweep {
+        coef = -coef;
+    }
+    let cx1 = coef * rx * y1 / ry;
+    let cy1 = -coef * ry * x1 / rx;
+    let cx = cos_phi * cx1 - sin_phi * cy1 + (from.x + to.x) / 2.0;
+    let cy = sin_phi * cx1 + cos_phi * cy1 + (from.y + to.y) / 2.0;
+
+    let angle = |ux: f64, uy: f64, vx: f64, vy: f64| {
+        let a = (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+        if a.is_nan() {
+            0.0
+        } else {
+            a
+        }
+    };
+    let theta1 = angle(1.0, 0.0, (x1 - cx1) / rx, (y1 - cy1) / ry);
+    let mut delta = angle(
+        (x1 - cx1) / rx,
+        (y1 - cy1) / ry,
+        (-x1 - cx1) / rx,
+        (-y1 - cy1) / ry,
+    );
+    if !sweep && delta > 0.0 {
+        delta -= 2.0 * PI;
+    } else if sweep && delta < 0.0 {
+        delta += 2.0 * PI;
+    }
+
+    let parts = (delta.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+    let step = delta / parts as f64;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+
+    let point_at = |t: f64| {
+        let (sin_t, cos_t) = t.sin_cos();
+        Point::new(
+            cx + rx * cos_phi * cos_t - ry * sin_phi * sin_t,
+            cy + rx * sin_phi * cos_t + ry * cos_phi * sin_t,
+        )
+    };
+    let derivative = |t: f64| {
+        let (sin_t, cos_t) = t.sin_cos();
+        Point::new(
+            -rx * cos_phi * sin_t - ry * sin_phi * cos_t,
+            -rx * sin_phi * sin_t + ry * cos_phi * cos_t,
+        )
+    };
+
+    let mut out = Vec::with_capacity(parts);
+    let mut t = theta1;
+    for i in 0..parts {
+        let t2 = t + step;
+        let p1 = point_at(t);
+        let p2 = if i + 1 == parts { to } else { point_at(t2) };
+        let d1 = derivative(t);
+        let d2 = derivative(t2);
+        out.push(Segment::Cubic(
+            Point::new(p1.x + k * d1.x, p1.y + k * d1.y),
+            Point::new(p2.x - k * d2.x, p2.y - k * d2.y),
+            p2,
+        ));
+        t = t2;
+    }
+    out
+}
+
+/// Apply an affine transform to every point in the path.
+pub fn transform(segments: &[Segment], m: &Matrix) -> Vec<Segment> {
+    segments
+        .iter()
+        .map(|seg| match *seg {
+            Segment::Move(p) => Segment::Move(m.apply(p)),
+            Segment::Line(p) => Segment::Line(m.apply(p)),
+            Segment::Quad(c, p) => Segment::Quad(m.apply(c), m.apply(p)),
+            Segment::Cubic(c1, c2, p) => Segment::Cubic(m.apply(c1), m.apply(c2), m.apply(p)),
+            Segment::Close => Segment::Close,
+        })
+        .collect()
+}
+
+/// Tight bounds of the path, including curve extrema.
+pub fn bounds(segments: &[Segment]) -> Option<Bounds> {
+    let mut points = Vec::new();
+    let mut current = Point::new(0.0, 0.0);
+    let mut start = current;
+    for seg in segments {
+        match *seg {
+            Segment::Move(p) => {
+                points.push(p);
+                current = p;
+                start = p;
+            }
+            Segment::Line(p) => {
+                points.push(current);
+                points.push(p);
+                current = p;
+            }
+            Segment::Quad(c, p) => {
+                points.push(current);
+                points.push(p);
+                for t in quad_extrema(current, c, p) {
+                    points.push(quad_at(current, c, p, t));
+                }
+                current = p;
+            }
+            Segment::Cubic(c1, c2, p) => {
+                points.push(current);
+                points.push(p);
+                for t in cubic_extrema(current, c1, c2, p) {
+                    points.push(cubic_at(current, c1, c2, p, t));
+                }
+                current = p;
+            }
+            Segment::Close => current = start,
+        }
+    }
+    geometry::bounds_of_points(&points)
+}
+
+fn quad_at(p0: Point, c: Point, p: Point, t: f64) -> Point {
+    let mt = 1.0 - t;
+    Point::new(
+        mt * mt * p0.x + 2.0 * mt * t * c.x + t * t * p.x,
+        mt * mt * p0.y + 2.0 * mt * t * c.y + t * t * p.y,
+    )
+}
+
+fn cubic_at(p0: Point, c1: Point, c2: Point, p: Point, t: f64) -> Point {
+    let mt = 1.0 - t;
+    let a = mt * mt * mt;
+    let b = 3.0 * mt * mt * t;
+    let c = 3.0 * mt * t * t;
+    let d = t * t * t;
+    Point::new(
+        a * p0.x + b * c1.x + c * c2.x + d * p.x,
+        a * p0.y + b * c1.y + c * c2.y + d * p.y,
+    )
+}
+
+fn quad_extrema(p0: Point, c: Point, p: Point) -> Vec<f64> {
+    let mut out = Vec::new();
+    for (a, b, d) in [(p0.x, c.x, p.x), (p0.y, c.y, p.y)] {
+        let den = a - 2.0 * b + d;
+        if den.abs() > 1e-12 {
+            let t = (a - b) / den;
+            if t > 0.0 && t < 1.0 {
+                out.push(t);
+            }
+        }
+    }
+    out
+}
+
+fn cubic_extrema(p0: Point, c1: Point, c2: Point, p: Point) -> Vec<f64> {
+    let mut out = Vec::new();
+    for (a0, a1, a2, a3) in [(p0.x, c1.x, c2.x, p.x), (p0.y, c1.y, c2.y, p.y)] {
+        // Derivative coefficients: a*t^2 + b*t + c
+        let a = -a0 + 3.0 * a1 - 3.0 * a2 + a3;
+        let b = 2.0 * (a0 - 2.0 * a1 + a2);
+        let c = a1 - a0;
+        if a.abs() < 1e-12 {
+            if b.abs() > 1e-12 {
+                out.push(-c / b);
+            }
+        } else {
+            let disc = b * b - 4.0 * a * c;
+            if disc >= 0.0 {
+                let sq = disc.sqrt();
+                out.push((-b + sq) / (2.0 * a));
+                out.push((-b - sq) / (2.0 * a));
+            }
+        }
+    }
+    out.retain(|t| *t > 0.0 && *t < 1.0);
+    out
+}
+
+/// Serialise to Fabric's `path` array form, e.g. `[["M", 0, 0], ["L", 10, 0], ["Z"]]`.
+pub fn to_fabric(segments: &[Segment]) -> Value {
+    let r = geometry::round;
+    Value::Array(
+        segments
+            .iter()
+            .map(|seg| match *seg {
+                Segment::Move(p) => json!(["M", r(p.x), r(p.y)]),
+                Segment::Line(p) => json!(["L", r(p.x), r(p.y)]),
+                Segment::Quad(c, p) => json!(["Q", r(c.x), r(c.y), r(p.x), r(p.y)]),
+                Segment::Cubic(c1, c2, p) => {
+                    json!(["C", r(c1.x), r(c1.y), r(c2.x), r(c2.y), r(p.x), r(p.y)])
+                }
+                Segment::Close => json!(["Z"]),
+            })
+            .collect(),
+    )
+}
+
+/// Read Fabric's `path` property, accepting either the array form or an SVG string.
+pub fn from_fabric(path: &Value) -> Result<Vec<Segment>, String> {
+    if let Some(d) = path.as_str() {
+        return parse(d);
+    }
+    let commands = path
+        .as_array()
+        .ok_or_else(|| "path must be an array or SVG path string".to_string())?;
+
+    // Re-encode as path data so relative and shorthand commands are normalised too.
+    let mut d = String::new();
+    for cmd in commands {
+        let parts = cmd
+            .as_array()
+            .ok_or_else(|| "path commands must be arrays".to_string())?;
+        for part in parts {
+            match part {
+                Value::String(s) => d.push_str(s),
+                Value::Number(n) => d.push_str(&n.to_string()),
+                _ => return Err("path command arguments must be numbers".to_string()),
+            }
+            d.push(' ');
+        }
+    }
+    parse(&d)
+}
+
+/// Encode as an SVG path data string.
+pub fn to_svg(segments: &[Segment]) -> String {
+    let r = geometry::round;
+    segments
+        .iter()
+        .map(|seg| match *seg {
+            Segment::Move(p) => format!("M {} {}", r(p.x), r(p.y)),
+            Segment::Line(p) => format!("L {} {}", r(p.x), r(p.y)),
+            Segment::Quad(c, p) => format!("Q {} {} {} {}", r(c.x), r(c.y), r(p.x), r(p.y)),
+            Segment::Cubic(c1, c2, p) => format!(
+                "C {} {} {} {} {} {}",
+                r(c1.x),
+                r(c1.y),
+                r(c2.x),
+                r(c2.y),
+                r(p.x),
+                r(p.y)
+            ),
+            Segment::Close => "Z".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// ── Tokenizer ───────────────────────────────────────────────────────────────
+
+struct Tokenizer<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(s: &'a str) -> Self {
+        Self {
+            bytes: s.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self.pos < self.bytes.len()
+            && (self.bytes[self.pos].is_ascii_whitespace() || self.bytes[self.pos] == b',')
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn next_command(&mut self) -> Option<char> {
+        self.skip_separators();
+        let b = *self.bytes.get(self.pos)?;
+        if b.is_ascii_alphabetic() && b != b'e' && b != b'E' {
+            self.pos += 1;
+            Some(b as char)
+        } else {
+            None
+        }
+    }
+
+    fn has_number(&mut self) -> bool {
+        self.skip_separators();
+        matches!(self.bytes.get(self.pos), Some(b) if b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.'))
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        self.skip_separators();
+        let start = self.pos;
+        let bytes = self.bytes;
+        let mut i = self.pos;
+        if i < bytes.len() && (bytes[i] == b'-' || bytes[i] == b'+') {
+            i += 1;
+        }
+        let mut seen_dot = false;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'0'..=b'9' => i += 1,
+                b'.' if !seen_dot => {
+                    seen_dot = true;
+                    i += 1;
+                }
+                b'e' | b'E' => {
+                    i += 1;
+                    if i < bytes.len() && (bytes[i] == b'-' || bytes[i] == b'+') {
+                        i += 1;
+                    }
+                    while i < bytes.len() && bytes[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                    break;
+                }
+                _ => break,
+            }
+        }
+        self.pos = i;
+        std::str::from_utf8(&bytes[start..i])
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .ok_or_else(|| format!("Invalid number in path data at offset {}", start))
+    }
+
+    /// Arc flags may be written without separators, e.g. `a1 1 0 00 1 1`.
+    fn flag(&mut self) -> Result<bool, String> {
+        self.skip_separators();
+        match self.bytes.get(self.pos) {
+            Some(b'0') => {
+                self.pos += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.pos += 1;
+                Ok(true)
+            }
+            _ => Err(format!(
+                "Invalid arc flag in path data at offset {}",
+                self.pos
+            )),
+        }
+    }
+}
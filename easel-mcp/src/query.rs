@@ -0,0 +1,194 @@
+//! Property filters for finding objects without reading the whole canvas.
+//!
+//! Filters run over every object, including those nested in groups, and all
+//! given filters must match. Geometry ranges use canvas-space bounds (stroke
+//! included), so rotated and grouped objects are measured where they appear.
+
+use glob::{MatchOptions, Pattern};
+use serde_json::{json, Map, Value};
+
+use crate::color::Rgba;
+use crate::fabric;
+use crate::spatial::{self, Placed, Scene};
+
+/// Inclusive numeric range; either end may be left open.
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct NumberRange {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl NumberRange {
+    fn contains(&self, v: f64) -> bool {
+        self.min.is_none_or(|min| v >= min) && self.max.is_none_or(|max| v <= max)
+    }
+}
+
+#[derive(Debug, Clone, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct ObjectQuery {
+    #[schemars(
+        description = "Match any of these Fabric types, case-insensitive (e.g. rect, textbox, group, path)"
+    )]
+    pub types: Option<Vec<String>>,
+    #[schemars(
+        description = "Glob on the object name, case-insensitive (e.g. \"Button*\", \"*icon*\")"
+    )]
+    pub name: Option<String>,
+    #[schemars(
+        description = "Solid fill color; any CSS form matches the same color (\"#f00\" finds \"rgb(255,0,0)\")"
+    )]
+    pub fill: Option<String>,
+    #[schemars(description = "Solid stroke color, compared like fill")]
+    pub stroke: Option<String>,
+    #[schemars(description = "Direct children of this frame (parentId) or group")]
+    pub parent_id: Option<String>,
+    #[schemars(description = "Only frames (true) or only non-frames (false)")]
+    pub is_frame: Option<bool>,
+    #[schemars(description = "Only components (true) or only non-components (false)")]
+    pub is_component: Option<bool>,
+    #[schemars(description = "Text objects whose text contains this, case-insensitive")]
+    pub text_contains: Option<String>,
+    #[schemars(description = "Range for the left edge of the canvas-space bounds")]
+    pub left: Option<NumberRange>,
+    #[schemars(description = "Range for the top edge of the canvas-space bounds")]
+    pub top: Option<NumberRange>,
+    #[schemars(description = "Range for the canvas-space width")]
+    pub width: Option<NumberRange>,
+    #[schemars(description = "Range for the canvas-space height")]
+    pub height: Option<NumberRange>,
+}
+
+/// A query with its patterns and colors parsed once.
+struct Compiled<'q> {
+    query: &'q ObjectQuery,
+    types: Option<Vec<String>>,
+    name: Option<Pattern>,
+    fill: Option<Rgba>,
+    stroke: Option<Rgba>,
+    text: Option<String>,
+}
+
+impl<'q> Compiled<'q> {
+    fn new(query: &'q ObjectQuery) -> Result<Self, String> {
+        let color = |key: &str, value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|s| Rgba::parse(s).ok_or_else(|| format!("Invalid {} color: {}", key, s)))
+                .transpose()
+        };
+        Ok(Compiled {
+            query,
+            types: query.types.as_ref().map(|types| {
+                types
+                    .iter()
+                    .map(|t| canonical_type(&t.to_ascii_lowercase()))
+                    .collect()
+            }),
+            name: query
+                .name
+                .as_deref()
+                .map(|p| Pattern::new(p).map_err(|e| format!("Invalid name pattern {}: {}", p, e)))
+                .transpose()?,
+            fill: color("fill", &query.fill)?,
+            stroke: color("stroke", &query.stroke)?,
+            text: query.text_contains.as_ref().map(|t| t.to_lowercase()),
+        })
+    }
+
+    fn matches(&self, scene: &Scene, item: &Placed) -> bool {
+        let obj = item.object;
+        let q = self.query;
+
+        if let Some(types) = &self.types {
+            if !types.contains(&canonical_type(&fabric::object_type(obj))) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.name {
+            let options = MatchOptions {
+                case_sensitive: false,
+                ..MatchOptions::new()
+            };
+            let name = fabric::str_prop(obj, "name").unwrap_or("");
+            if !pattern.matches_with(name, options) {
+                return false;
+            }
+        }
+        if !same_color(self.fill, obj.get("fill")) || !same_color(self.stroke, obj.get("stroke")) {
+            return false;
+        }
+        if let Some(parent_id) = &q.parent_id {
+            let parent = item.parent.and_then(|p| scene.items()[p].id());
+            if parent != Some(parent_id.as_str()) {
+                return false;
+            }
+        }
+        if q.is_frame.is_some_and(|f| f != fabric::is_frame(obj))
+            || q.is_component
+                .is_some_and(|c| c != fabric::bool_prop(obj, "isComponent"))
+        {
+            return false;
+        }
+        if let Some(needle) = &self.text {
+            let text = fabric::str_prop(obj, "text").filter(|_| fabric::is_text(obj));
+            if !text.is_some_and(|t| t.to_lowercase().contains(needle.as_str())) {
+                return false;
+            }
+        }
+        let b = &item.bounds;
+        [
+            (q.left, b.left),
+            (q.top, b.top),
+            (q.width, b.width),
+            (q.height, b.height),
+        ]
+        .iter()
+        .all(|(range, v)| range.is_none_or(|r| r.contains(*v)))
+    }
+}
+
+/// Fabric v7 writes `IText`, older files `i-text`.
+fn canonical_type(kind: &str) -> String {
+    if kind == "i-text" { "itext" } else { kind }.to_string()
+}
+
+/// With no color asked for, anything matches; otherwise only a solid paint
+/// of the same color and alpha.
+fn same_color(wanted: Option<Rgba>, paint: Option<&Value>) -> bool {
+    let Some(wanted) = wanted else {
+        return true;
+    };
+    let Some(actual) = paint.and_then(|p| p.as_str()).and_then(Rgba::parse) else {
+        return false;
+    };
+    (actual.r, actual.g, actual.b) == (wanted.r, wanted.g, wanted.b)
+        && (actual.a - wanted.a).abs() < 1e-3
+}
+
+/// Objects matching every filter of `query`, in paint order.
+pub fn select<'s, 'a>(
+    scene: &'s Scene<'a>,
+    query: &ObjectQuery,
+) -> Result<Vec<&'s Placed<'a>>, String> {
+    let compiled = Compiled::new(query)?;
+    Ok(scene
+        .items()
+        .iter()
+        .filter(|item| compiled.matches(scene, item))
+        .collect())
+}
+
+/// The object's id plus the requested `fields`: stored properties by their
+/// Fabric name, or `bounds` for the canvas-space box.
+pub fn project(item: &Placed, fields: &[String]) -> Value {
+    let mut out = Map::new();
+    out.insert("id".into(), json!(item.id()));
+    for field in fields {
+        let value = match field.as_str() {
+            "bounds" => json!(spatial::rounded(&item.bounds)),
+            key => item.object.get(key).cloned().unwrap_or(Value::Null),
+        };
+        out.insert(field.clone(), value);
+    }
+    Value::Object(out)
+}
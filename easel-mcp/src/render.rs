@@ -0,0 +1,594 @@
+//! Backend-independent rendering of Fabric objects into a flat display list.
+//!
+//! Every [`DrawItem`] keeps its geometry in the object's local box (top-left
+//! at the origin, y pointing down) together with the matrix that maps that box
+//! onto the canvas, so exporters can emit real vector shapes and text.
+
+use serde_json::Value;
+use std::f64::consts::FRAC_PI_2;
+
+use crate::assets::{self, AssetStore};
+use crate::color::Rgba;
+use crate::fabric::{self, Bounds};
+use crate::fonts::FontBook;
+use crate::geometry::{self, Matrix, Point};
+use crate::path::{self, Segment};
+use crate::text::{self, TextLayout};
+
+/// Cubic Bézier control distance for a quarter circle of radius 1.
+const KAPPA: f64 = 0.552_284_749_8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind {
+    Linear,
+    Radial,
+}
+
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    /// Linear: `[x1, y1, x2, y2, 0, 0]`; radial: `[x1, y1, r1, x2, y2, r2]`.
+    pub coords: [f64; 6],
+    /// `(offset, color)` pairs sorted by offset.
+    pub stops: Vec<(f64, Rgba)>,
+    /// Maps gradient space into the object's local box.
+    pub transform: Matrix,
+}
+
+#[derive(Debug, Clone)]
+pub enum Paint {
+    Solid(Rgba),
+    Gradient(Gradient),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Debug, Clone)]
+pub struct StrokeStyle {
+    pub paint: Paint,
+    /// Width in local units (already compensated for `strokeUniform`).
+    pub width: f64,
+    pub dash: Vec<f64>,
+    pub dash_offset: f64,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    pub miter_limit: f64,
+}
+
+pub enum DrawKind {
+    Shape {
+        segments: Vec<Segment>,
+        even_odd: bool,
+    },
+    Text(TextLayout),
+    Image {
+        /// Stable key for de-duplicating identical images.
+        key: String,
+        bytes: Vec<u8>,
+        /// Size of the visible (cropped) region in local units.
+        width: f64,
+        height: f64,
+        crop_x: f64,
+        crop_y: f64,
+        natural_width: f64,
+        natural_height: f64,
+    },
+}
+
+pub struct DrawItem {
+    pub id: Option<String>,
+    pub transform: Matrix,
+    pub opacity: f64,
+    pub fill: Option<Paint>,
+    pub stroke: Option<StrokeStyle>,
+    /// Fabric `paintFirst: "stroke"`.
+    pub stroke_first: bool,
+    pub kind: DrawKind,
+}
+
+/// One page/image worth of content in canvas coordinates.
+pub struct Scene {
+    pub title: String,
+    pub bounds: Bounds,
+    pub background: Option<Rgba>,
+    pub items: Vec<DrawItem>,
+}
+
+pub struct Renderer<'a> {
+    fonts: &'a FontBook,
+    assets: Option<&'a AssetStore>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(fonts: &'a FontBook, assets: Option<&'a AssetStore>) -> Self {
+        Self {
+            fonts,
+            assets,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Objects that could not be rendered faithfully.
+    pub fn into_warnings(self) -> Vec<String> {
+        self.warnings
+    }
+
+    /// Render `objects` (in canvas order) into a scene covering `bounds`.
+    pub fn scene(&mut self, title: &str, bounds: Bounds, objects: &[&Value]) -> Scene {
+        let mut items = Vec::new();
+        for obj in objects {
+            self.object(obj, &Matrix::IDENTITY, 1.0, &mut items);
+        }
+        Scene {
+            title: title.to_string(),
+            bounds,
+            background: None,
+            items,
+        }
+    }
+
+    fn object(
+        &mut self,
+        obj: &Value,
+        parent: &Matrix,
+        parent_opacity: f64,
+        out: &mut Vec<DrawItem>,
+    ) {
+        if obj.get("visible").and_then(|v| v.as_bool()) == Some(false) {
+            return;
+        }
+        let opacity = parent_opacity * fabric::num_prop(obj, "opacity").unwrap_or(1.0);
+        let matrix = parent.multiply(&geometry::object_matrix(obj));
+        let kind = fabric::object_type(obj);
+
+        if kind == "group" {
+            // Group children are positioned relative to the group's center.
+            let inner = matrix.multiply(&Matrix::translate(
+                fabric::base_width(obj) / 2.0,
+                fabric::base_height(obj) / 2.0,
+            ));
+            for child in obj
+                .get("objects")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+            {
+                self.object(child, &inner, opacity, out);
+            }
+            return;
+        }
+
+        let id = fabric::object_id(obj).map(String::from);
+        let fill = self.paint(obj, "fill");
+        let stroke = self.stroke(obj, &matrix);
+        let stroke_first = fabric::str_prop(obj, "paintFirst") == Some("stroke");
+        let even_odd = fabric::str_prop(obj, "fillRule") == Some("evenodd");
+        let item = |kind: DrawKind, fill: Option<Paint>, stroke: Option<StrokeStyle>| DrawItem {
+            id: id.clone(),
+            transform: matrix,
+            opacity,
+            fill,
+            stroke,
+            stroke_first,
+            kind,
+        };
+
+        if fabric::is_text(obj) {
+            let layout = text::layout(obj, self.fonts);
+            if layout.font.is_none() {
+                self.warn(obj, "no font available; text skipped");
+                return;
+            }
+            let decorations = text_decorations(obj, &layout);
+            out.push(item(DrawKind::Text(layout), fill.clone(), stroke));
+            if !decorations.is_empty() {
+                out.push(item(
+                    DrawKind::Shape {
+                        segments: decorations,
+                        even_odd: false,
+                    },
+                    fill,
+                    None,
+                ));
+            }
+            return;
+        }
+
+        if kind == "image" {
+            if let Some(image) = self.image(obj) {
+                out.push(item(image, None, stroke));
+            }
+            return;
+        }
+
+        let segments = match shape_segments(obj) {
+            Ok(Some(segments)) => segments,
+            Ok(None) => {
+                self.warn(obj, &format!("unsupported object type '{}'", kind));
+                return;
+            }
+            Err(e) => {
+                self.warn(obj, &e);
+                Vec::new()
+            }
+        };
+        // Fabric only strokes lines; open paths and polylines are filled as if closed.
+        let fill = if kind == "line" { None } else { fill };
+        out.push(item(DrawKind::Shape { segments, even_odd }, fill, stroke));
+    }
+
+    fn image(&mut self, obj: &Value) -> Option<DrawKind> {
+        let (key, bytes) = match self.image_bytes(obj) {
+            Ok(found) => found,
+            Err(e) => {
+                self.warn(obj, &e);
+                return None;
+            }
+        };
+        let natural = imagesize::blob_size(&bytes).ok();
+        let width = fabric::base_width(obj);
+        let height = fabric::base_height(obj);
+        Some(DrawKind::Image {
+            key,
+            width,
+            height,
+            crop_x: fabric::num_prop(obj, "cropX").unwrap_or(0.0),
+            crop_y: fabric::num_prop(obj, "cropY").unwrap_or(0.0),
+            natural_width: natural.as_ref().map(|s| s.width as f64).unwrap_or(width),
+            natural_height: natural.as_ref().map(|s| s.height as f64).unwrap_or(height),
+            bytes,
+        })
+    }
+
+    fn image_bytes(&self, obj: &Value) -> Result<(String, Vec<u8>), String> {
+        if let (Some(asset_id), Some(store)) = (fabric::str_prop(obj, "assetId"), self.assets) {
+            return Ok((asset_id.to_string(), store.read(asset_id)?));
+        }
+        let src = fabric::str_prop(obj, "src").unwrap_or("");
+        if src.starts_with("data:") {
+            let bytes = assets::decode_data_url(src)?;
+            return Ok((format!("data:{}", sha_key(&bytes)), bytes));
+        }
+        let path = src.strip_prefix("file://").unwrap_or(src);
+        if path.is_empty() || path.contains("://") {
+            return Err("image source is not a local asset".to_string());
+        }
+        let bytes = std::fs::read(path).map_err(|e| format!("failed to read {}: {}", path, e))?;
+        Ok((path.to_string(), bytes))
+    }
+
+    fn paint(&mut self, obj: &Value, key: &str) -> Option<Paint> {
+        match obj.get(key)? {
+            Value::String(s) => {
+                let color = Rgba::parse(s)?;
+                (color.a > 0.0).then_some(Paint::Solid(color))
+            }
+            Value::Object(_) => {
+                let gradient = parse_gradient(obj, &obj[key]);
+                if gradient.is_none() {
+                    self.warn(obj, &format!("unsupported {} paint", key));
+                }
+                gradient.map(Paint::Gradient)
+            }
+            _ => None,
+        }
+    }
+
+    fn stroke(&mut self, obj: &Value, matrix: &Matrix) -> Option<StrokeStyle> {
+        let mut width = fabric::num_prop(obj, "strokeWidth").unwrap_or(1.0);
+        if width <= 0.0 {
+            return None;
+        }
+        let paint = self.paint(obj, "stroke")?;
+        if fabric::bool_prop(obj, "strokeUniform") {
+            // Keep the on-screen width constant by undoing the object's scale.
+            let [a, b, c, d, _, _] = matrix.0;
+            let scale = (a * d - b * c).abs().sqrt();
+            if scale > 0.0 {
+                width /= scale;
+            }
+        }
+        let dash = obj
+            .get("strokeDashArray")
+            .and_then(|v| v.as_array())
+            .map(|a| a.iter().filter_map(|d| d.as_f64()).collect())
+            .unwrap_or_default();
+        Some(StrokeStyle {
+            paint,
+            width,
+            dash,
+            dash_offset: fabric::num_prop(obj, "strokeDashOffset").unwrap_or(0.0),
+            cap: match fabric::str_prop(obj, "strokeLineCap") {
+                Some("round") => LineCap::Round,
+                Some("square") => LineCap::Square,
+                _ => LineCap::Butt,
+            },
+            join: match fabric::str_prop(obj, "strokeLineJoin") {
+                Some("round") => LineJoin::Round,
+                Some("bevel") => LineJoin::Bevel,
+                _ => LineJoin::Miter,
+            },
+            miter_limit: fabric::num_prop(obj, "strokeMiterLimit").unwrap_or(4.0),
+        })
+    }
+
+    fn warn(&mut self, obj: &Value, message: &str) {
+        let name = fabric::str_prop(obj, "name")
+            .or_else(|| fabric::object_id(obj))
+            .unwrap_or("object");
+        self.warnings.push(format!("{}: {}", name, message));
+    }
+}
+
+/// Axis-aligned canvas bounds of an object's box, without its stroke.
+pub fn box_bounds(obj: &Value) -> Bounds {
+    geometry::object_matrix(obj).transform_bounds(&Bounds {
+        left: 0.0,
+        top: 0.0,
+        width: fabric::base_width(obj),
+        height: fabric::base_height(obj),
+    })
+}
+
+/// Parse a Fabric gradient (`{ type, coords, colorStops, gradientUnits, ... }`).
+pub fn parse_gradient(obj: &Value, gradient: &Value) -> Option<Gradient> {
+    let kind = match fabric::str_prop(gradient, "type")? {
+        "linear" => GradientKind::Linear,
+        "radial" => GradientKind::Radial,
+        _ => return None,
+    };
+    let coords = gradient.get("coords")?;
+    let c = |key: &str| fabric::num_prop(coords, key).unwrap_or(0.0);
+    let coords = match kind {
+        GradientKind::Linear => [c("x1"), c("y1"), c("x2"), c("y2"), 0.0, 0.0],
+        GradientKind::Radial => [c("x1"), c("y1"), c("r1"), c("x2"), c("y2"), c("r2")],
+    };
+
+    let mut stops: Vec<(f64, Rgba)> = gradient
+        .get("colorStops")?
+        .as_array()?
+        .iter()
+        .filter_map(|stop| {
+            let offset = fabric::num_prop(stop, "offset")?.clamp(0.0, 1.0);
+            let color = Rgba::parse(fabric::str_prop(stop, "color")?)?;
+            let opacity = fabric::num_prop(stop, "opacity").unwrap_or(1.0);
+            Some((offset, color.with_alpha(color.a * opacity)))
+        })
+        .collect();
+    if stops.is_empty() {
+        return None;
+    }
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut transform = Matrix::translate(
+        fabric::num_prop(gradient, "offsetX").unwrap_or(0.0),
+        fabric::num_prop(gradient, "offsetY").unwrap_or(0.0),
+    );
+    if let Some(m) = gradient.get("gradientTransform").and_then(|v| v.as_array()) {
+        let values: Vec<f64> = m.iter().filter_map(|v| v.as_f64()).collect();
+        if let Ok(values) = <[f64; 6]>::try_from(values) {
+            transform = transform.multiply(&Matrix(values));
+        }
+    }
+    if fabric::str_prop(gradient, "gradientUnits") == Some("percentage") {
+        transform = transform.multiply(&Matrix::scale(
+            fabric::base_width(obj),
+            fabric::base_height(obj),
+        ));
+    }
+
+    Some(Gradient {
+        kind,
+        coords,
+        stops,
+        transform,
+    })
+}
+
+/// Outline of a shape object in its local box (top-left at the origin), as
+/// Fabric draws it. `None` for objects that are not simple shapes.
+pub fn shape_segments(obj: &Value) -> Result<Option<Vec<Segment>>, String> {
+    let kind = fabric::object_type(obj);
+    let w = fabric::base_width(obj);
+    let h = fabric::base_height(obj);
+    let segments = match kind.as_str() {
+        "rect" => {
+            let rx = fabric::num_prop(obj, "rx").unwrap_or(0.0).min(w / 2.0);
+            let ry = fabric::num_prop(obj, "ry").unwrap_or(rx).min(h / 2.0);
+            rounded_rect(w, h, rx, ry)
+        }
+        "circle" => {
+            let start = fabric::num_prop(obj, "startAngle").unwrap_or(0.0);
+            let end = fabric::num_prop(obj, "endAngle").unwrap_or(360.0);
+            let r = fabric::num_prop(obj, "radius").unwrap_or(w / 2.0);
+            if (end - start).abs() >= 360.0 {
+                ellipse(r, r, r, r)
+            } else {
+                arc(r, r, r, r, start.to_radians(), end.to_radians())
+            }
+        }
+        "ellipse" => ellipse(w / 2.0, h / 2.0, w / 2.0, h / 2.0),
+        "triangle" => vec![
+            Segment::Move(Point::new(0.0, h)),
+            Segment::Line(Point::new(w / 2.0, 0.0)),
+            Segment::Line(Point::new(w, h)),
+            Segment::Close,
+        ],
+        "line" => {
+            let x1 = fabric::num_prop(obj, "x1").unwrap_or(0.0);
+            let y1 = fabric::num_prop(obj, "y1").unwrap_or(0.0);
+            let x2 = fabric::num_prop(obj, "x2").unwrap_or(0.0);
+            let y2 = fabric::num_prop(obj, "y2").unwrap_or(0.0);
+            let (min_x, min_y) = (x1.min(x2), y1.min(y2));
+            vec![
+                Segment::Move(Point::new(x1 - min_x, y1 - min_y)),
+                Segment::Line(Point::new(x2 - min_x, y2 - min_y)),
+            ]
+        }
+        "polyline" | "polygon" => {
+            let Some(points) = obj.get("points").and_then(|v| v.as_array()) else {
+                return Ok(Some(Vec::new()));
+            };
+            let points: Vec<Point> = points
+                .iter()
+                .map(|p| {
+                    Point::new(
+                        fabric::num_prop(p, "x").unwrap_or(0.0),
+                        fabric::num_prop(p, "y").unwrap_or(0.0),
+                    )
+                })
+                .collect();
+            let Some(bounds) = geometry::bounds_of_points(&points) else {
+                return Ok(Some(Vec::new()));
+            };
+            let mut segments: Vec<Segment> = points
+                .iter()
+                .enumerate()
+                .map(|(i, p)| {
+                    let local = Point::new(p.x - bounds.left, p.y - bounds.top);
+                    if i == 0 {
+                        Segment::Move(local)
+                    } else {
+                        Segment::Line(local)
+                    }
+                })
+                .collect();
+            if kind == "polygon" {
+                segments.push(Segment::Close);
+            }
+            segments
+        }
+        "path" => {
+            let segments = match obj.get("path").map(path::from_fabric) {
+                Some(Ok(segments)) => segments,
+                Some(Err(e)) => return Err(format!("invalid path data: {}", e)),
+                None => return Ok(Some(Vec::new())),
+            };
+            // Fabric centers the path on its bounding box (`pathOffset`).
+            let Some(bounds) = path::bounds(&segments) else {
+                return Ok(Some(Vec::new()));
+            };
+            path::transform(&segments, &Matrix::translate(-bounds.left, -bounds.top))
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(segments))
+}
+
+fn rounded_rect(w: f64, h: f64, rx: f64, ry: f64) -> Vec<Segment> {
+    if rx <= 0.0 || ry <= 0.0 {
+        return vec![
+            Segment::Move(Point::new(0.0, 0.0)),
+            Segment::Line(Point::new(w, 0.0)),
+            Segment::Line(Point::new(w, h)),
+            Segment::Line(Point::new(0.0, h)),
+            Segment::Close,
+        ];
+    }
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+    let p = Point::new;
+    vec![
+        Segment::Move(p(rx, 0.0)),
+        Segment::Line(p(w - rx, 0.0)),
+        Segment::Cubic(p(w - rx + kx, 0.0), p(w, ry - ky), p(w, ry)),
+        Segment::Line(p(w, h - ry)),
+        Segment::Cubic(p(w, h - ry + ky), p(w - rx + kx, h), p(w - rx, h)),
+        Segment::Line(p(rx, h)),
+        Segment::Cubic(p(rx - kx, h), p(0.0, h - ry + ky), p(0.0, h - ry)),
+        Segment::Line(p(0.0, ry)),
+        Segment::Cubic(p(0.0, ry - ky), p(rx - kx, 0.0), p(rx, 0.0)),
+        Segment::Close,
+    ]
+}
+
+fn ellipse(cx: f64, cy: f64, rx: f64, ry: f64) -> Vec<Segment> {
+    let mut segments = arc(cx, cy, rx, ry, 0.0, std::f64::consts::TAU);
+    segments.push(Segment::Close);
+    segments
+}
+
+/// Elliptical arc from `start` to `end` (radians, clockwise in y-down space),
+/// split into cubics of at most a quarter turn.
+fn arc(cx: f64, cy: f64, rx: f64, ry: f64, start: f64, end: f64) -> Vec<Segment> {
+    let end = if end < start {
+        end + std::f64::consts::TAU
+    } else {
+        end
+    };
+    let pieces = ((end - start) / FRAC_PI_2).ceil().max(1.0) as usize;
+    let step = (end - start) / pieces as f64;
+    let k = 4.0 / 3.0 * (step / 4.0).tan();
+    let at = |t: f64| Point::new(cx + rx * t.cos(), cy + ry * t.sin());
+    let tangent = |t: f64| Point::new(-rx * t.sin(), ry * t.cos());
+
+    let mut segments = vec![Segment::Move(at(start))];
+    for i in 0..pieces {
+        let t0 = start + step * i as f64;
+        let t1 = t0 + step;
+        let (p0, p1) = (at(t0), at(t1));
+        let (d0, d1) = (tangent(t0), tangent(t1));
+        segments.push(Segment::Cubic(
+            Point::new(p0.x + k * d0.x, p0.y + k * d0.y),
+            Point::new(p1.x - k * d1.x, p1.y - k * d1.y),
+            p1,
+        ));
+    }
+    segments
+}
+
+/// Underline/overline/strikethrough bars, placed with Fabric's offsets and
+/// thickness (`fontSize / 15`) for each styled run.
+fn text_decorations(obj: &Value, layout: &TextLayout) -> Vec<Segment> {
+    let decoration = |run: &text::TextRun, key: &str| {
+        run.style
+            .get(key)
+            .and_then(|v| v.as_bool())
+            .unwrap_or_else(|| fabric::bool_prop(obj, key))
+    };
+    let mut segments = Vec::new();
+    for line in &layout.lines {
+        for run in line.runs.iter().filter(|r| r.width > 0.0) {
+            let thickness = run.font_size / 15.0;
+            let offsets = [
+                ("underline", 0.1),
+                ("linethrough", -0.315),
+                ("overline", -0.88),
+            ];
+            for (_, offset) in offsets.iter().filter(|(key, _)| decoration(run, key)) {
+                let y = line.baseline + offset * run.font_size;
+                let x0 = line.left + run.left;
+                let x1 = x0 + run.width;
+                segments.extend([
+                    Segment::Move(Point::new(x0, y)),
+                    Segment::Line(Point::new(x1, y)),
+                    Segment::Line(Point::new(x1, y + thickness)),
+                    Segment::Line(Point::new(x0, y + thickness)),
+                    Segment::Close,
+                ]);
+            }
+        }
+    }
+    segments
+}
+
+fn sha_key(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(bytes)
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
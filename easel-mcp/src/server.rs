@@ -1,18 +1,41 @@
 use chrono::Utc;
 use glob::glob;
 use rmcp::{
-    ErrorData as McpError, ServerHandler,
+    ErrorData as McpError, Peer, RoleServer, ServerHandler,
     handler::server::router::tool::ToolRouter,
     handler::server::wrapper::Parameters,
     model::*,
     schemars, tool, tool_handler, tool_router,
 };
 use serde::Serialize;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::path::PathBuf;
 use uuid::Uuid;
 
-use crate::easel::EaselFile;
+use crate::accessibility::{self, AuditOptions};
+use crate::assets::{self, AssetStore};
+use crate::attribution::{self, Actor, EditQuery};
+use crate::boolean::{self, BooleanOp};
+use crate::bundle;
+use crate::codegen::{self, CodeTarget, CodegenOptions, LayoutMode};
+use crate::diff::{self, DiffFormat};
+use crate::easel::{self, EaselFile};
+use crate::export::pdf::{self, PdfOptions};
+use crate::export::ExportScope;
+use crate::fabric::{self, Bounds};
+use crate::fonts::{self, FontBook};
+use crate::import::svg::{self, SvgImportOptions};
+use crate::color::Rgba;
+use crate::geometry::{self, Point};
+use crate::import::{self, WhiteboardFormat};
+use crate::lint::{self, LintConfig};
+use crate::oplog::{self, HistoryScope};
+use crate::query::{self, ObjectQuery};
+use crate::render;
+use crate::shapes::{self, Arrowhead, LineStyle};
+use crate::spatial::{self, RegionMode, Scene};
+use crate::summary::{self, SummaryFormat, SummaryOptions};
+use crate::text;
 
 // ── Parameter structs ───────────────────────────────────────────────────────
 
@@ -23,6 +46,8 @@ pub struct CreateEaselFileParams {
     #[schemars(description = "Name for the canvas")]
     #[serde(default = "default_canvas_name")]
     pub name: String,
+    #[schemars(description = "Label of the agent making this edit, recorded in the operation log (defaults to the MCP client's name)")]
+    pub agent: Option<String>,
 }
 
 fn default_canvas_name() -> String {
@@ -53,7 +78,7 @@ pub struct GetObjectParams {
 pub struct CreateObjectParams {
     #[schemars(description = "Path to the .easel file")]
     pub file_path: String,
-    #[schemars(description = "Object type: rect, ellipse, text, or frame")]
+    #[schemars(description = "Object type: rect, ellipse, text, textbox, frame, image, line, polyline, polygon, path, or arrow. A textbox wraps its text to a fixed width")]
     #[serde(rename = "type")]
     pub object_type: String,
     #[schemars(description = "X position (left)")]
@@ -62,22 +87,547 @@ pub struct CreateObjectParams {
     #[schemars(description = "Y position (top)")]
     #[serde(default = "default_position")]
     pub y: f64,
-    #[schemars(description = "Width of the object")]
-    #[serde(default = "default_size")]
-    pub width: f64,
-    #[schemars(description = "Height of the object")]
-    #[serde(default = "default_size")]
-    pub height: f64,
+    #[schemars(description = "Width of the object (default 200; images keep their natural size; text is measured)")]
+    pub width: Option<f64>,
+    #[schemars(description = "Height of the object (default 200; images keep their natural size; text is measured)")]
+    pub height: Option<f64>,
     #[schemars(description = "Fill color (hex)")]
     pub fill: Option<String>,
     #[schemars(description = "Stroke color (hex)")]
     pub stroke: Option<String>,
     #[schemars(description = "Optional name for the object")]
     pub name: Option<String>,
-    #[schemars(description = "Text content (only for type=text)")]
+    #[schemars(description = "Text content (only for type=text or textbox)")]
     pub text: Option<String>,
-    #[schemars(description = "Font size (only for type=text)")]
+    #[schemars(description = "Font size (only for type=text or textbox)")]
     pub font_size: Option<f64>,
+    #[schemars(description = "CSS font family list (only for type=text or textbox; default Inter, system-ui, sans-serif)")]
+    pub font_family: Option<String>,
+    #[schemars(description = "Font weight: normal, bold or 100-900 (only for type=text or textbox)")]
+    pub font_weight: Option<String>,
+    #[schemars(description = "Font style: normal or italic (only for type=text or textbox)")]
+    pub font_style: Option<String>,
+    #[schemars(description = "Alignment: left, center, right or justify (only for type=text or textbox)")]
+    pub text_align: Option<String>,
+    #[schemars(description = "Line height as a multiple of the font size (only for type=text or textbox; default 1.16)")]
+    pub line_height: Option<f64>,
+    #[schemars(description = "Extra space between characters in thousandths of an em (only for type=text or textbox)")]
+    pub char_spacing: Option<f64>,
+    #[schemars(description = "Underline the whole text (only for type=text or textbox)")]
+    pub underline: Option<bool>,
+    #[schemars(description = "Strike through the whole text (only for type=text or textbox)")]
+    pub linethrough: Option<bool>,
+    #[schemars(description = "Styles for character ranges of the text (only for type=text or textbox); later ranges win where they overlap")]
+    pub styles: Option<Vec<TextStyleParam>>,
+    #[schemars(description = "Local image file path (only for type=image); width/height scale the image, omit both to keep its natural size")]
+    pub image_path: Option<String>,
+    #[schemars(description = "Canvas points for type=line (two), polyline, polygon or arrow; x/y are ignored when given. A line or arrow without points runs from (x, y) to (x + width, y + height)")]
+    pub points: Option<Vec<PointParam>>,
+    #[schemars(description = "SVG path data in canvas coordinates (only for type=path)")]
+    pub path: Option<String>,
+    #[schemars(description = "Stroke width (default 1; 2 for lines, polylines and arrows)")]
+    pub stroke_width: Option<f64>,
+    #[schemars(description = "Head at the first point (only for type=arrow): none, arrow, triangle, triangle_outline, dot, circle_outline, diamond, diamond_outline, bar or square. Default none")]
+    pub start_arrowhead: Option<String>,
+    #[schemars(description = "Head at the last point (only for type=arrow), same options as start_arrowhead. Default arrow")]
+    pub end_arrowhead: Option<String>,
+    #[schemars(description = "Gradient fill; replaces fill")]
+    pub fill_gradient: Option<GradientParam>,
+    #[schemars(description = "Gradient stroke; replaces stroke")]
+    pub stroke_gradient: Option<GradientParam>,
+    #[schemars(description = "Stroke dash pattern: alternating dash and gap lengths, e.g. [8, 4]")]
+    pub stroke_dash_array: Option<Vec<f64>>,
+    #[schemars(description = "Stroke line cap: butt, round or square")]
+    pub stroke_line_cap: Option<String>,
+    #[schemars(description = "Stroke line join: miter, round or bevel")]
+    pub stroke_line_join: Option<String>,
+    #[schemars(description = "Object opacity from 0 to 1")]
+    pub opacity: Option<f64>,
+    #[schemars(description = "Drop shadow")]
+    pub shadow: Option<ShadowParam>,
+    #[schemars(description = "Label of the agent making this edit, recorded in the operation log (defaults to the MCP client's name)")]
+    pub agent: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GradientParam {
+    #[schemars(description = "linear or radial")]
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[schemars(description = "Color stops, at least two")]
+    pub stops: Vec<ColorStopParam>,
+    #[schemars(description = "Direction of a linear gradient in degrees: 0 runs left to right, 90 top to bottom (default 90). Coordinates given in coords take precedence")]
+    pub angle: Option<f64>,
+    #[schemars(description = "Geometry as fractions of the object's box (0-1). Linear: x1, y1 to x2, y2. Radial: focal circle x1, y1, r1 and outer circle x2, y2, r2 (default centered, r2 0.5)")]
+    pub coords: Option<GradientCoordsParam>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ColorStopParam {
+    #[schemars(description = "Position along the gradient from 0 to 1")]
+    pub offset: f64,
+    #[schemars(description = "Stop color (hex or rgba())")]
+    pub color: String,
+    #[schemars(description = "Stop opacity from 0 to 1 (default 1)")]
+    pub opacity: Option<f64>,
+}
+
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+pub struct GradientCoordsParam {
+    pub x1: Option<f64>,
+    pub y1: Option<f64>,
+    pub x2: Option<f64>,
+    pub y2: Option<f64>,
+    pub r1: Option<f64>,
+    pub r2: Option<f64>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ShadowParam {
+    #[schemars(description = "Shadow color (default rgba(0,0,0,0.3))")]
+    pub color: Option<String>,
+    #[schemars(description = "Blur radius in pixels (default 10)")]
+    pub blur: Option<f64>,
+    #[schemars(description = "Horizontal offset in pixels (default 0)")]
+    pub offset_x: Option<f64>,
+    #[schemars(description = "Vertical offset in pixels (default 4)")]
+    pub offset_y: Option<f64>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct TextStyleParam {
+    #[schemars(description = "Index of the first character, counting newlines")]
+    pub start: usize,
+    #[schemars(description = "Index after the last character")]
+    pub end: usize,
+    #[schemars(description = "Text color (hex)")]
+    pub fill: Option<String>,
+    pub font_family: Option<String>,
+    pub font_size: Option<f64>,
+    #[schemars(description = "normal, bold or 100-900")]
+    pub font_weight: Option<String>,
+    #[schemars(description = "normal or italic")]
+    pub font_style: Option<String>,
+    pub underline: Option<bool>,
+    pub linethrough: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PointParam {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// A lost save race is the caller's to retry; anything else is on us.
+fn save_error(e: String) -> McpError {
+    if easel::is_conflict(&e) {
+        McpError::invalid_request(e, None)
+    } else {
+        McpError::internal_error(e, None)
+    }
+}
+
+/// Who a tool call's edits are attributed to: the agent label the call
+/// gives, else the connected client's name.
+fn actor(peer: &Peer<RoleServer>, agent: Option<&str>) -> Actor {
+    let label = agent
+        .map(str::to_string)
+        .or_else(|| peer.peer_info().map(|info| info.client_info.name.clone()))
+        .unwrap_or_else(|| "agent".to_string());
+    Actor::agent(&label)
+}
+
+/// Undo, or with `redo` redo, one save of a file in the scope `params` gives.
+fn step_history(
+    peer: &Peer<RoleServer>,
+    params: HistoryStepParams,
+    redo: bool,
+) -> Result<CallToolResult, McpError> {
+    let path = PathBuf::from(&params.file_path);
+    let mut easel = EaselFile::load(&path)
+        .map_err(|e| McpError::internal_error(e, None))?;
+    let batches = oplog::read(&path)
+        .map_err(|e| McpError::internal_error(e, None))?;
+
+    let scope = params.scope;
+    let stepped = if redo {
+        oplog::redo(&mut easel.canvas, &batches, |a| scope.includes(a))
+    } else {
+        oplog::undo(&mut easel.canvas, &batches, |a| scope.includes(a))
+    }
+    .map_err(|e| McpError::invalid_request(e, None))?;
+    let (verb, done) = if redo { ("redo", "redone") } else { ("undo", "undone") };
+    let Some((step, batch)) = stepped else {
+        let whose = match scope {
+            HistoryScope::Session => "this session's edits",
+            HistoryScope::Global => "the file's history",
+        };
+        return Ok(CallToolResult::success(vec![Content::text(format!(
+            "Nothing to {} in {}",
+            verb, whose
+        ))]));
+    };
+
+    easel.updated_at = Utc::now();
+    easel
+        .save_step(&path, &actor(peer, params.agent.as_deref()), step)
+        .map_err(save_error)?;
+
+    let result = serde_json::json!({
+        done: {
+            "batch": batch.id,
+            "revision": batch.revision,
+            "at": batch.at,
+            "actor": batch.actor,
+            "ops": batch.ops.len(),
+        },
+        "revision": easel.revision,
+    });
+    let json = serde_json::to_string_pretty(&result)
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+    Ok(CallToolResult::success(vec![Content::text(json)]))
+}
+
+/// `points` as canvas points; lines and arrows fall back to the diagonal of
+/// the x/y/width/height box.
+fn params_points(params: &CreateObjectParams, diagonal: Option<(f64, f64)>) -> Result<Vec<Point>, McpError> {
+    match (&params.points, diagonal) {
+        (Some(points), _) => Ok(points.iter().map(|p| Point::new(p.x, p.y)).collect()),
+        (None, Some((width, height))) => Ok(vec![
+            Point::new(params.x, params.y),
+            Point::new(params.x + width, params.y + height),
+        ]),
+        (None, None) => Err(McpError::invalid_params(
+            format!("points are required for type={}", params.object_type),
+            None,
+        )),
+    }
+}
+
+fn parse_arrowhead(name: Option<&str>, default: Option<Arrowhead>) -> Result<Option<Arrowhead>, McpError> {
+    match name {
+        None => Ok(default),
+        Some("none") => Ok(None),
+        Some(name) => Arrowhead::from_name(name).map(Some).ok_or_else(|| {
+            McpError::invalid_params(format!("Unknown arrowhead: {}", name), None)
+        }),
+    }
+}
+
+/// Fabric keeps numeric weights as numbers and keywords as strings.
+fn font_weight_value(weight: &str) -> Value {
+    weight.parse::<u16>().map_or_else(|_| Value::from(weight), Value::from)
+}
+
+fn check_font_style(style: &str) -> Result<(), McpError> {
+    match style {
+        "normal" | "italic" | "oblique" => Ok(()),
+        other => Err(McpError::invalid_params(format!("Unknown font style: {}", other), None)),
+    }
+}
+
+/// Copy the typography parameters onto a text object, with `styles` ranges
+/// converted to Fabric's per-character form.
+fn apply_text_params(obj: &mut Value, params: &CreateObjectParams, text: &str) -> Result<(), McpError> {
+    if let Some(family) = &params.font_family {
+        obj["fontFamily"] = Value::from(family.as_str());
+    }
+    if let Some(weight) = &params.font_weight {
+        obj["fontWeight"] = font_weight_value(weight);
+    }
+    if let Some(style) = &params.font_style {
+        check_font_style(style)?;
+        obj["fontStyle"] = Value::from(style.as_str());
+    }
+    if let Some(align) = &params.text_align {
+        if !matches!(align.as_str(), "left" | "center" | "right" | "justify") {
+            return Err(McpError::invalid_params(format!("Unknown text alignment: {}", align), None));
+        }
+        obj["textAlign"] = Value::from(align.as_str());
+    }
+    if let Some(line_height) = params.line_height {
+        obj["lineHeight"] = Value::from(line_height);
+    }
+    if let Some(spacing) = params.char_spacing {
+        obj["charSpacing"] = Value::from(spacing);
+    }
+    if let Some(underline) = params.underline {
+        obj["underline"] = Value::from(underline);
+    }
+    if let Some(linethrough) = params.linethrough {
+        obj["linethrough"] = Value::from(linethrough);
+    }
+
+    let Some(styles) = &params.styles else {
+        return Ok(());
+    };
+    let length = text.chars().count();
+    let mut ranges = Vec::with_capacity(styles.len());
+    for range in styles {
+        if range.start >= range.end || range.end > length {
+            return Err(McpError::invalid_params(
+                format!(
+                    "Style range {}..{} is outside the text ({} characters)",
+                    range.start, range.end, length
+                ),
+                None,
+            ));
+        }
+        let mut style = Map::new();
+        if let Some(fill) = &range.fill {
+            style.insert("fill".into(), Value::from(fill.as_str()));
+        }
+        if let Some(family) = &range.font_family {
+            style.insert("fontFamily".into(), Value::from(family.as_str()));
+        }
+        if let Some(size) = range.font_size {
+            style.insert("fontSize".into(), Value::from(size));
+        }
+        if let Some(weight) = &range.font_weight {
+            style.insert("fontWeight".into(), font_weight_value(weight));
+        }
+        if let Some(font_style) = &range.font_style {
+            check_font_style(font_style)?;
+            style.insert("fontStyle".into(), Value::from(font_style.as_str()));
+        }
+        if let Some(underline) = range.underline {
+            style.insert("underline".into(), Value::from(underline));
+        }
+        if let Some(linethrough) = range.linethrough {
+            style.insert("linethrough".into(), Value::from(linethrough));
+        }
+        ranges.push((range.start, range.end, style));
+    }
+    obj["styles"] = text::styles_from_ranges(text, &ranges);
+    Ok(())
+}
+
+fn check_color(key: &str, color: &str) -> Result<(), McpError> {
+    Rgba::parse(color)
+        .map(|_| ())
+        .ok_or_else(|| McpError::invalid_params(format!("Invalid {} color: {}", key, color), None))
+}
+
+fn check_unit(key: &str, value: f64) -> Result<(), McpError> {
+    if (0.0..=1.0).contains(&value) {
+        Ok(())
+    } else {
+        Err(McpError::invalid_params(format!("{} must be between 0 and 1, got {}", key, value), None))
+    }
+}
+
+/// A Fabric `Gradient` in percentage units, so it follows the object's box.
+fn gradient_value(gradient: &GradientParam) -> Result<Value, McpError> {
+    if gradient.stops.len() < 2 {
+        return Err(McpError::invalid_params("A gradient needs at least two color stops", None));
+    }
+    let mut stops = Vec::with_capacity(gradient.stops.len());
+    for stop in &gradient.stops {
+        check_unit("Gradient stop offset", stop.offset)?;
+        check_color("gradient stop", &stop.color)?;
+        let mut value = serde_json::json!({"offset": stop.offset, "color": stop.color});
+        if let Some(opacity) = stop.opacity {
+            check_unit("Gradient stop opacity", opacity)?;
+            value["opacity"] = Value::from(opacity);
+        }
+        stops.push(value);
+    }
+    stops.sort_by(|a, b| a["offset"].as_f64().unwrap_or(0.0).total_cmp(&b["offset"].as_f64().unwrap_or(0.0)));
+
+    let default = GradientCoordsParam::default();
+    let c = gradient.coords.as_ref().unwrap_or(&default);
+    let coords = match gradient.kind.as_str() {
+        "linear" => {
+            let angle = gradient.angle.unwrap_or(90.0).to_radians();
+            let (dx, dy) = (angle.cos() / 2.0, angle.sin() / 2.0);
+            serde_json::json!({
+                "x1": c.x1.unwrap_or(0.5 - dx),
+                "y1": c.y1.unwrap_or(0.5 - dy),
+                "x2": c.x2.unwrap_or(0.5 + dx),
+                "y2": c.y2.unwrap_or(0.5 + dy),
+            })
+        }
+        "radial" => {
+            let (x2, y2) = (c.x2.unwrap_or(0.5), c.y2.unwrap_or(0.5));
+            serde_json::json!({
+                "x1": c.x1.unwrap_or(x2),
+                "y1": c.y1.unwrap_or(y2),
+                "r1": c.r1.unwrap_or(0.0),
+                "x2": x2,
+                "y2": y2,
+                "r2": c.r2.unwrap_or(0.5),
+            })
+        }
+        other => {
+            return Err(McpError::invalid_params(
+                format!("Unknown gradient type: {}. Use linear or radial.", other),
+                None,
+            ));
+        }
+    };
+    Ok(serde_json::json!({
+        "type": gradient.kind,
+        "coords": coords,
+        "colorStops": stops,
+        "gradientUnits": "percentage",
+        "offsetX": 0,
+        "offsetY": 0,
+    }))
+}
+
+/// Paint and stroke settings from the typed parameters, validated and
+/// converted before any object is built.
+struct StyleOverrides {
+    fill: Option<Value>,
+    stroke: Option<Value>,
+    dash: Option<Vec<f64>>,
+    line_cap: Option<String>,
+    line_join: Option<String>,
+    opacity: Option<f64>,
+    shadow: Option<Value>,
+}
+
+impl StyleOverrides {
+    fn from_params(params: &CreateObjectParams) -> Result<Self, McpError> {
+        if let Some(fill) = &params.fill {
+            check_color("fill", fill)?;
+        }
+        if let Some(stroke) = &params.stroke {
+            check_color("stroke", stroke)?;
+        }
+        if let Some(dash) = &params.stroke_dash_array {
+            if dash.iter().any(|d| !d.is_finite() || *d < 0.0) || dash.iter().all(|d| *d == 0.0) {
+                return Err(McpError::invalid_params(
+                    "stroke_dash_array needs non-negative lengths, not all zero",
+                    None,
+                ));
+            }
+        }
+        if let Some(cap) = &params.stroke_line_cap {
+            if !matches!(cap.as_str(), "butt" | "round" | "square") {
+                return Err(McpError::invalid_params(format!("Unknown line cap: {}", cap), None));
+            }
+        }
+        if let Some(join) = &params.stroke_line_join {
+            if !matches!(join.as_str(), "miter" | "round" | "bevel") {
+                return Err(McpError::invalid_params(format!("Unknown line join: {}", join), None));
+            }
+        }
+        if let Some(opacity) = params.opacity {
+            check_unit("opacity", opacity)?;
+        }
+        let shadow = match &params.shadow {
+            Some(shadow) => {
+                let color = shadow.color.as_deref().unwrap_or("rgba(0,0,0,0.3)");
+                check_color("shadow", color)?;
+                let blur = shadow.blur.unwrap_or(10.0);
+                if blur < 0.0 {
+                    return Err(McpError::invalid_params("Shadow blur cannot be negative", None));
+                }
+                Some(serde_json::json!({
+                    "type": "shadow",
+                    "color": color,
+                    "blur": blur,
+                    "offsetX": shadow.offset_x.unwrap_or(0.0),
+                    "offsetY": shadow.offset_y.unwrap_or(4.0),
+                    "affectStroke": false,
+                    "nonScaling": false,
+                }))
+            }
+            None => None,
+        };
+        Ok(Self {
+            fill: params.fill_gradient.as_ref().map(gradient_value).transpose()?,
+            stroke: params.stroke_gradient.as_ref().map(gradient_value).transpose()?,
+            dash: params.stroke_dash_array.clone(),
+            line_cap: params.stroke_line_cap.clone(),
+            line_join: params.stroke_line_join.clone(),
+            opacity: params.opacity,
+            shadow,
+        })
+    }
+
+    /// Apply to a new object. Arrows with solid heads are groups; their
+    /// dash was already set on the shaft, and the heads stay solid.
+    fn apply(&self, obj: &mut Value) {
+        if let Some(opacity) = self.opacity {
+            obj["opacity"] = Value::from(opacity);
+        }
+        if let Some(shadow) = &self.shadow {
+            obj["shadow"] = shadow.clone();
+        }
+        match obj.get_mut("objects").and_then(|v| v.as_array_mut()) {
+            Some(children) => children.iter_mut().for_each(|child| self.apply_paint(child, false)),
+            None => self.apply_paint(obj, true),
+        }
+    }
+
+    fn apply_paint(&self, obj: &mut Value, dash: bool) {
+        let painted = |key: &str| obj.get(key).is_some_and(|v| !v.is_null());
+        let (has_fill, has_stroke) = (painted("fill"), painted("stroke"));
+        if let (Some(gradient), true) = (&self.fill, has_fill) {
+            obj["fill"] = gradient.clone();
+        }
+        if let (Some(gradient), true) = (&self.stroke, has_stroke) {
+            obj["stroke"] = gradient.clone();
+        }
+        if let (Some(pattern), true) = (&self.dash, dash) {
+            obj["strokeDashArray"] = serde_json::json!(pattern);
+        }
+        if let Some(cap) = &self.line_cap {
+            obj["strokeLineCap"] = Value::from(cap.as_str());
+        }
+        if let Some(join) = &self.line_join {
+            obj["strokeLineJoin"] = Value::from(join.as_str());
+        }
+    }
+}
+
+/// Check paint and stroke values passed to `update_object` in Fabric's own
+/// JSON shape, so a malformed gradient or shadow never reaches the file.
+fn validate_style_properties(properties: &Map<String, Value>) -> Result<(), McpError> {
+    let invalid = |key: &str, value: &Value| {
+        McpError::invalid_params(format!("Invalid {}: {}", key, value), None)
+    };
+    for (key, value) in properties {
+        match (key.as_str(), value) {
+            (_, Value::Null) => {}
+            ("fill" | "stroke", Value::String(color)) => check_color(key, color)?,
+            ("fill" | "stroke", Value::Object(_)) => {
+                let stops = value.get("colorStops").and_then(|v| v.as_array());
+                let valid_stops = stops.is_some_and(|stops| {
+                    !stops.is_empty()
+                        && stops.iter().all(|stop| {
+                            fabric::num_prop(stop, "offset").is_some_and(|o| (0.0..=1.0).contains(&o))
+                                && fabric::str_prop(stop, "color").is_some_and(|c| Rgba::parse(c).is_some())
+                        })
+                });
+                if !valid_stops || render::parse_gradient(&Value::Null, value).is_none() {
+                    return Err(invalid(key, value));
+                }
+            }
+            ("opacity", Value::Number(n)) => check_unit("opacity", n.as_f64().unwrap_or(-1.0))?,
+            ("strokeDashArray", Value::Array(dash))
+                if dash.iter().all(|d| d.as_f64().is_some_and(|d| d >= 0.0)) => {}
+            ("strokeLineCap", Value::String(cap)) if matches!(cap.as_str(), "butt" | "round" | "square") => {}
+            ("strokeLineJoin", Value::String(join)) if matches!(join.as_str(), "miter" | "round" | "bevel") => {}
+            ("shadow", Value::Object(shadow)) => {
+                if let Some(color) = shadow.get("color") {
+                    check_color("shadow", color.as_str().ok_or_else(|| invalid(key, value))?)?;
+                }
+                if shadow.get("blur").and_then(|b| b.as_f64()).is_some_and(|b| b < 0.0) {
+                    return Err(invalid(key, value));
+                }
+            }
+            ("shadow", Value::String(_)) => {}
+            ("fill" | "stroke" | "opacity" | "strokeDashArray" | "strokeLineCap" | "strokeLineJoin" | "shadow", _) => {
+                return Err(invalid(key, value));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
 }
 
 fn default_position() -> f64 {
@@ -93,8 +643,177 @@ pub struct UpdateObjectParams {
     pub file_path: String,
     #[schemars(description = "Object ID to update")]
     pub id: String,
-    #[schemars(description = "Properties to update (e.g. left, top, width, height, fill, stroke, name, text, fontSize)")]
+    #[schemars(description = "Properties to update (e.g. left, top, width, height, fill, stroke, name, text, fontSize, fontFamily, styles). Text width and height are re-measured; fill, stroke, opacity, shadow and stroke styles are validated")]
     pub properties: Value,
+    #[schemars(description = "Label of the agent making this edit, recorded in the operation log (defaults to the MCP client's name)")]
+    pub agent: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct BooleanObjectsParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "IDs of two or more top-level shapes (rect, ellipse, circle, triangle, polygon, polyline, path or groups of them)")]
+    pub ids: Vec<String>,
+    #[schemars(description = "union, subtract (the bottom-most shape minus the others), intersect, or exclude")]
+    pub operation: BooleanOp,
+    #[schemars(description = "Name for the resulting path (defaults to the operation)")]
+    pub name: Option<String>,
+    #[schemars(description = "Keep the original shapes instead of replacing them (default false)")]
+    #[serde(default)]
+    pub keep_originals: bool,
+    #[schemars(description = "Label of the agent making this edit, recorded in the operation log (defaults to the MCP client's name)")]
+    pub agent: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct LintCanvasParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[serde(flatten)]
+    pub config: LintConfig,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct AuditAccessibilityParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[serde(flatten)]
+    pub options: AuditOptions,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct SummarizeCanvasParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Levels of nesting to expand below the top level (0 = top-level objects only); deeper objects are counted, not listed. Unlimited by default")]
+    pub depth: Option<usize>,
+    #[schemars(description = "Only outline this frame or group and its contents")]
+    pub frame_id: Option<String>,
+    #[schemars(description = "text (default): indented one-line-per-object outline; json: the same outline as nested objects")]
+    #[serde(default)]
+    pub format: SummaryFormat,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct DiffCanvasesParams {
+    #[schemars(description = "Path to the earlier version of the .easel file")]
+    pub before_path: String,
+    #[schemars(description = "Path to the later version of the .easel file")]
+    pub after_path: String,
+    #[schemars(description = "text (default): one line per change; json: added/removed/moved/changed/reordered lists")]
+    #[serde(default)]
+    pub format: DiffFormat,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct QueryObjectsParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[serde(flatten)]
+    pub query: ObjectQuery,
+    #[schemars(description = "Properties to return for each match besides id, e.g. [\"name\", \"fill\", \"text\", \"bounds\"] (bounds is the canvas-space box). Defaults to name and type")]
+    pub fields: Option<Vec<String>>,
+    #[schemars(description = "Number of matches to skip (default 0)")]
+    #[serde(default)]
+    pub offset: usize,
+    #[schemars(description = "Maximum matches to return (default 50, at most 500)")]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ObjectsAtPointParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "X coordinate in canvas space")]
+    pub x: f64,
+    #[schemars(description = "Y coordinate in canvas space")]
+    pub y: f64,
+    #[schemars(description = "Also match objects within this distance of the point (default 0)")]
+    pub tolerance: Option<f64>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ObjectsInRectParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Region left edge in canvas space")]
+    pub left: Option<f64>,
+    #[schemars(description = "Region top edge in canvas space")]
+    pub top: Option<f64>,
+    #[schemars(description = "Region width")]
+    pub width: Option<f64>,
+    #[schemars(description = "Region height")]
+    pub height: Option<f64>,
+    #[schemars(description = "Use this object's bounds (e.g. a frame) as the region instead of left/top/width/height; the object and its own children are left out")]
+    pub object_id: Option<String>,
+    #[schemars(description = "intersects (default): anything touching the region; contains: only objects entirely inside it")]
+    #[serde(default)]
+    pub mode: RegionMode,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct FindOverlapsParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Only report overlaps involving these object IDs (default: every object)")]
+    pub ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct BoundingBoxParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Object IDs to measure, including objects nested in groups")]
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ListEditsParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Only edits by this actor: an agent or user label, a session id, or a full actor id (kind:label@session)")]
+    pub actor: Option<String>,
+    #[schemars(description = "Only changes to this object ID")]
+    pub object_id: Option<String>,
+    #[schemars(description = "Only edits saved at or after this RFC 3339 time")]
+    pub since: Option<String>,
+    #[schemars(description = "Only the most recent N matching edits")]
+    pub limit: Option<usize>,
+    #[schemars(description = "Include each changed object as it was before and after (default false: only which properties changed)")]
+    #[serde(default)]
+    pub include_values: bool,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct HistoryStepParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Whose edits to step through: session (edits made through this connection, the default) or global (every attributed edit, the app user's included)")]
+    #[serde(default)]
+    pub scope: HistoryScope,
+    #[schemars(description = "Label of the agent making this edit, recorded in the operation log (defaults to the MCP client's name)")]
+    pub agent: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ReplayCanvasParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Replay up to and including the save that produced this revision (default: every save)")]
+    pub revision: Option<u64>,
+    #[schemars(description = "Write the replayed canvas to a new .easel file here instead of returning it")]
+    pub output_path: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct RevertEditsParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Whose edits to revert: an agent or user label, a session id, or a full actor id (kind:label@session)")]
+    pub actor: String,
+    #[schemars(description = "Label of the agent making this edit, recorded in the operation log (defaults to the MCP client's name)")]
+    pub agent: Option<String>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -103,6 +822,112 @@ pub struct DeleteObjectsParams {
     pub file_path: String,
     #[schemars(description = "Array of object IDs to delete")]
     pub ids: Vec<String>,
+    #[schemars(description = "Label of the agent making this edit, recorded in the operation log (defaults to the MCP client's name)")]
+    pub agent: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct GenerateCodeParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "ID of the frame to generate code for")]
+    pub frame_id: String,
+    #[schemars(description = "Output target: html (HTML + CSS) or react (JSX component)")]
+    #[serde(default)]
+    pub target: CodeTarget,
+    #[schemars(description = "Child layout: absolute (mirror canvas positions) or flex (infer rows/columns)")]
+    #[serde(default)]
+    pub layout: LayoutMode,
+    #[schemars(description = "Emit Tailwind utility classes instead of a stylesheet")]
+    #[serde(default)]
+    pub tailwind: bool,
+    #[schemars(description = "Component name for react output (defaults to the frame name)")]
+    pub component_name: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ExportPdfParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Where to write the PDF")]
+    pub output_path: String,
+    #[schemars(description = "What to export: canvas (one page), frames (one page per frame) or selection (the given objects on one page)")]
+    #[serde(default)]
+    pub scope: ExportScope,
+    #[schemars(description = "Frame IDs for scope=frames (all top-level frames when omitted) or object IDs for scope=selection")]
+    #[serde(default)]
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ImportSvgParams {
+    #[schemars(description = "Path to the .easel file to append the imported objects to")]
+    pub file_path: String,
+    #[schemars(description = "Path to the .svg file to import")]
+    pub svg_path: String,
+    #[schemars(description = "Horizontal offset applied to the imported artwork")]
+    #[serde(default)]
+    pub x: f64,
+    #[schemars(description = "Vertical offset applied to the imported artwork")]
+    #[serde(default)]
+    pub y: f64,
+    #[schemars(description = "Optional frame ID to parent the imported top-level objects to")]
+    pub parent_id: Option<String>,
+    #[schemars(description = "Label of the agent making this edit, recorded in the operation log (defaults to the MCP client's name)")]
+    pub agent: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ImportWhiteboardParams {
+    #[schemars(description = "Path to the Excalidraw (.excalidraw) or tldraw (.tldr / JSON) file to import")]
+    pub source_path: String,
+    #[schemars(description = "Path where the new .easel file should be created")]
+    pub file_path: String,
+    #[schemars(description = "Source format; detected from the file when omitted")]
+    pub format: Option<WhiteboardFormat>,
+    #[schemars(description = "Name for the canvas; defaults to the name stored in the file or the file name")]
+    pub name: Option<String>,
+    #[schemars(description = "Label of the agent making this edit, recorded in the operation log (defaults to the MCP client's name)")]
+    pub agent: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct AssetsParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ListFontsParams {
+    #[schemars(description = "Optional .easel file whose bundled fonts should be included")]
+    pub file_path: Option<String>,
+    #[schemars(description = "Only list families whose name contains this text (case-insensitive)")]
+    pub query: Option<String>,
+    #[schemars(description = "A CSS font-family list, e.g. \"Inter, system-ui, sans-serif\", to resolve to the face exports will use")]
+    pub resolve: Option<String>,
+    #[schemars(description = "Weight for resolve: normal, bold or 100-900 (default normal)")]
+    pub font_weight: Option<String>,
+    #[schemars(description = "Style for resolve: normal or italic (default normal)")]
+    pub font_style: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct ExportAssetsParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Directory to copy the assets into")]
+    pub destination: String,
+    #[schemars(description = "Asset IDs to export (all assets when empty)")]
+    #[serde(default)]
+    pub ids: Vec<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+pub struct PackEaselFileParams {
+    #[schemars(description = "Path to the .easel file")]
+    pub file_path: String,
+    #[schemars(description = "Where to write the result (defaults to converting file_path in place)")]
+    pub destination: Option<String>,
 }
 
 // ── Response structs ────────────────────────────────────────────────────────
@@ -122,6 +947,12 @@ pub struct EaselMcpServer {
     tool_router: ToolRouter<Self>,
 }
 
+impl Default for EaselMcpServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[tool_router]
 impl EaselMcpServer {
     pub fn new() -> Self {
@@ -134,6 +965,7 @@ impl EaselMcpServer {
     #[tool(name = "create_easel_file", description = "Create a new empty .easel canvas file at the given path")]
     fn create_easel_file(
         &self,
+        peer: Peer<RoleServer>,
         Parameters(params): Parameters<CreateEaselFileParams>,
     ) -> Result<CallToolResult, McpError> {
         let path = PathBuf::from(&params.file_path);
@@ -145,8 +977,10 @@ impl EaselMcpServer {
             ));
         }
 
-        let easel = EaselFile::new(&params.name);
-        easel.save(&path).map_err(|e| McpError::internal_error(e, None))?;
+        let mut easel = EaselFile::new(&params.name);
+        easel
+            .save_as(&path, &actor(&peer, params.agent.as_deref()))
+            .map_err(save_error)?;
 
         let result = serde_json::json!({
             "filePath": path.display().to_string(),
@@ -228,10 +1062,11 @@ impl EaselMcpServer {
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
-    /// Create a new object (rect, ellipse, text, or frame) in an .easel file.
-    #[tool(name = "create_object", description = "Create a new canvas object (rect, ellipse, text, or frame) in an .easel file")]
+    /// Create a new object (shape, text, frame, image, line or path) in an .easel file.
+    #[tool(name = "create_object", description = "Create a new canvas object (rect, ellipse, text, textbox, frame, image, line, polyline, polygon, path, or arrow) in an .easel file. Text supports fonts, alignment, spacing and per-range styles and is measured server-side. Fills and strokes can be linear or radial gradients, with dashes, caps, joins, opacity and a drop shadow. Images are copied into the canvas asset store.")]
     fn create_object(
         &self,
+        peer: Peer<RoleServer>,
         Parameters(params): Parameters<CreateObjectParams>,
     ) -> Result<CallToolResult, McpError> {
         let path = PathBuf::from(&params.file_path);
@@ -242,8 +1077,11 @@ impl EaselMcpServer {
         };
 
         let id = Uuid::new_v4().to_string();
+        let width = params.width.unwrap_or_else(default_size);
+        let height = params.height.unwrap_or_else(default_size);
 
-        let obj = match params.object_type.as_str() {
+        let overrides = StyleOverrides::from_params(&params)?;
+        let mut obj = match params.object_type.as_str() {
             "rect" => {
                 let fill = params.fill.unwrap_or_else(|| "#d9d9d9".to_string());
                 let stroke = params.stroke.unwrap_or_else(|| "#b3b3b3".to_string());
@@ -254,8 +1092,8 @@ impl EaselMcpServer {
                     "name": name,
                     "left": params.x,
                     "top": params.y,
-                    "width": params.width,
-                    "height": params.height,
+                    "width": width,
+                    "height": height,
                     "fill": fill,
                     "stroke": stroke,
                     "strokeWidth": 1,
@@ -275,8 +1113,8 @@ impl EaselMcpServer {
                     "name": name,
                     "left": params.x,
                     "top": params.y,
-                    "rx": params.width / 2.0,
-                    "ry": params.height / 2.0,
+                    "rx": width / 2.0,
+                    "ry": height / 2.0,
                     "fill": fill,
                     "stroke": stroke,
                     "strokeWidth": 1,
@@ -286,13 +1124,14 @@ impl EaselMcpServer {
                     "version": "7.0.0"
                 })
             }
-            "text" => {
-                let fill = params.fill.unwrap_or_else(|| "#ffffff".to_string());
-                let text_content = params.text.unwrap_or_else(|| "Text".to_string());
+            "text" | "textbox" => {
+                let fill = params.fill.clone().unwrap_or_else(|| "#ffffff".to_string());
+                let text_content = params.text.clone().unwrap_or_else(|| "Text".to_string());
                 let font_size = params.font_size.unwrap_or(16.0);
-                let name = params.name.unwrap_or_else(|| "Text".to_string());
-                serde_json::json!({
-                    "type": "IText",
+                let name = params.name.clone().unwrap_or_else(|| "Text".to_string());
+                let textbox = params.object_type == "textbox";
+                let mut obj = serde_json::json!({
+                    "type": if textbox { "Textbox" } else { "IText" },
                     "id": id,
                     "name": name,
                     "text": text_content,
@@ -304,7 +1143,14 @@ impl EaselMcpServer {
                     "originX": "left",
                     "originY": "top",
                     "version": "7.0.0"
-                })
+                });
+                if textbox {
+                    obj["width"] = Value::from(width);
+                }
+                apply_text_params(&mut obj, &params, &text_content)?;
+                let bundled = FontBook::with_bundled(&AssetStore::for_canvas(&path));
+                text::fit_to_content(&mut obj, bundled.as_ref().unwrap_or_else(|| FontBook::system()));
+                obj
             }
             "frame" => {
                 let name = params.name.unwrap_or_else(|| "Frame".to_string());
@@ -315,8 +1161,8 @@ impl EaselMcpServer {
                     "isFrame": true,
                     "left": params.x,
                     "top": params.y,
-                    "width": params.width,
-                    "height": params.height,
+                    "width": width,
+                    "height": height,
                     "fill": "#ffffff",
                     "stroke": "#e0e0e0",
                     "strokeWidth": 1,
@@ -326,17 +1172,110 @@ impl EaselMcpServer {
                     "version": "7.0.0"
                 })
             }
+            "image" => {
+                let image_path = params.image_path.ok_or_else(|| {
+                    McpError::invalid_params("image_path is required for type=image", None)
+                })?;
+                let store = AssetStore::for_canvas(&path);
+                let asset = store
+                    .add_file(&PathBuf::from(&image_path))
+                    .map_err(|e| McpError::invalid_params(e, None))?;
+                let mut obj = assets::image_object(
+                    &store,
+                    &asset,
+                    params.x,
+                    params.y,
+                    params.width,
+                    params.height,
+                    params.name,
+                );
+                obj["id"] = Value::String(id.clone());
+                obj
+            }
+            kind @ ("line" | "arrow") => {
+                let points = params_points(&params, Some((width, height)))?;
+                let style = LineStyle {
+                    color: shapes::paint(Some(params.stroke.as_deref().unwrap_or("#1e1e1e"))),
+                    width: params.stroke_width.unwrap_or(2.0),
+                    dash: overrides.dash.clone(),
+                    round: true,
+                };
+                let mut obj = if kind == "line" {
+                    let [start, end] = points[..] else {
+                        return Err(McpError::invalid_params("A line needs exactly two points", None));
+                    };
+                    shapes::line_object(start, end, &style, params.name.as_deref().unwrap_or("Line"))
+                } else {
+                    let start = parse_arrowhead(params.start_arrowhead.as_deref(), None)?;
+                    let end = parse_arrowhead(params.end_arrowhead.as_deref(), Some(Arrowhead::Arrow))?;
+                    let name = params.name.as_deref().unwrap_or("Arrow");
+                    shapes::arrow_object(&points, start, end, &style, name)
+                        .ok_or_else(|| McpError::invalid_params("An arrow needs at least two points", None))?
+                };
+                obj["id"] = Value::String(id.clone());
+                obj
+            }
+            kind @ ("polyline" | "polygon") => {
+                let points = params_points(&params, None)?;
+                let closed = kind == "polygon";
+                if points.len() < if closed { 3 } else { 2 } {
+                    return Err(McpError::invalid_params(
+                        format!("A {} needs at least {} points", kind, if closed { 3 } else { 2 }),
+                        None,
+                    ));
+                }
+                let style = LineStyle {
+                    color: shapes::paint(Some(
+                        params.stroke.as_deref().unwrap_or(if closed { "#b3b3b3" } else { "#1e1e1e" }),
+                    )),
+                    width: params.stroke_width.unwrap_or(if closed { 1.0 } else { 2.0 }),
+                    dash: overrides.dash.clone(),
+                    round: !closed,
+                };
+                let fill = shapes::paint(Some(params.fill.as_deref().unwrap_or("#d9d9d9")));
+                let name = params
+                    .name
+                    .as_deref()
+                    .unwrap_or(if closed { "Polygon" } else { "Polyline" });
+                let mut obj = shapes::poly_object(&points, closed, &style, fill, name)
+                    .ok_or_else(|| McpError::invalid_params("Points are required", None))?;
+                obj["id"] = Value::String(id.clone());
+                obj
+            }
+            "path" => {
+                let d = params.path.as_deref().ok_or_else(|| {
+                    McpError::invalid_params("path is required for type=path", None)
+                })?;
+                let style = LineStyle {
+                    color: shapes::paint(Some(params.stroke.as_deref().unwrap_or("#b3b3b3"))),
+                    width: params.stroke_width.unwrap_or(1.0),
+                    dash: overrides.dash.clone(),
+                    round: false,
+                };
+                let fill = shapes::paint(Some(params.fill.as_deref().unwrap_or("#d9d9d9")));
+                let name = params.name.as_deref().unwrap_or("Path");
+                let mut obj = shapes::svg_path_object(d, &style, fill, name)
+                    .map_err(|e| McpError::invalid_params(e, None))?;
+                obj["id"] = Value::String(id.clone());
+                obj
+            }
             other => {
                 return Err(McpError::invalid_params(
-                    format!("Unknown object type: {}. Use rect, ellipse, text, or frame.", other),
+                    format!(
+                        "Unknown object type: {}. Use rect, ellipse, text, textbox, frame, image, line, polyline, polygon, path, or arrow.",
+                        other
+                    ),
                     None,
                 ));
             }
         };
+        overrides.apply(&mut obj);
 
         easel.objects_mut().push(obj.clone());
         easel.updated_at = Utc::now();
-        easel.save(&path).map_err(|e| McpError::internal_error(e, None))?;
+        easel
+            .save_as(&path, &actor(&peer, params.agent.as_deref()))
+            .map_err(save_error)?;
 
         let json = serde_json::to_string_pretty(&obj)
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -348,12 +1287,16 @@ impl EaselMcpServer {
     #[tool(name = "update_object", description = "Update properties of an existing canvas object by its ID")]
     fn update_object(
         &self,
+        peer: Peer<RoleServer>,
         Parameters(params): Parameters<UpdateObjectParams>,
     ) -> Result<CallToolResult, McpError> {
         let path = PathBuf::from(&params.file_path);
         let mut easel = EaselFile::load(&path)
             .map_err(|e| McpError::internal_error(e, None))?;
 
+        if let Some(properties) = params.properties.as_object() {
+            validate_style_properties(properties)?;
+        }
         let obj = easel.find_object_mut(&params.id).ok_or_else(|| {
             McpError::internal_error(format!("Object not found: {}", params.id), None)
         })?;
@@ -367,10 +1310,17 @@ impl EaselMcpServer {
                 target.insert(key.clone(), value.clone());
             }
         }
+        // Text boxes follow their content, as Fabric re-measures them on load.
+        if fabric::is_text(obj) {
+            let bundled = FontBook::with_bundled(&AssetStore::for_canvas(&path));
+            text::fit_to_content(obj, bundled.as_ref().unwrap_or_else(|| FontBook::system()));
+        }
 
         let updated = obj.clone();
         easel.updated_at = Utc::now();
-        easel.save(&path).map_err(|e| McpError::internal_error(e, None))?;
+        easel
+            .save_as(&path, &actor(&peer, params.agent.as_deref()))
+            .map_err(save_error)?;
 
         let json = serde_json::to_string_pretty(&updated)
             .map_err(|e| McpError::internal_error(e.to_string(), None))?;
@@ -382,6 +1332,7 @@ impl EaselMcpServer {
     #[tool(name = "delete_objects", description = "Delete one or more canvas objects by their IDs from an .easel file")]
     fn delete_objects(
         &self,
+        peer: Peer<RoleServer>,
         Parameters(params): Parameters<DeleteObjectsParams>,
     ) -> Result<CallToolResult, McpError> {
         let path = PathBuf::from(&params.file_path);
@@ -397,7 +1348,9 @@ impl EaselMcpServer {
             .collect();
 
         easel.updated_at = Utc::now();
-        easel.save(&path).map_err(|e| McpError::internal_error(e, None))?;
+        easel
+            .save_as(&path, &actor(&peer, params.agent.as_deref()))
+            .map_err(save_error)?;
 
         let result = serde_json::json!({
             "deleted": removed,
@@ -409,6 +1362,766 @@ impl EaselMcpServer {
 
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
+
+    /// List saves from a canvas's operation log.
+    #[tool(name = "list_edits", description = "Who changed what: the operation log of an .easel file, one batch per save, oldest first. Each batch has its time, revision, actor (kind user/agent, label, session; absent for unattributed saves) and its operations: create, update (with the properties changed), delete and reorder. Filter by actor, object ID or time; also lists every actor with their edit counts")]
+    fn list_edits(
+        &self,
+        Parameters(params): Parameters<ListEditsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let since = params
+            .since
+            .as_deref()
+            .map(|since| {
+                chrono::DateTime::parse_from_rfc3339(since)
+                    .map(|t| t.with_timezone(&Utc))
+                    .map_err(|e| McpError::invalid_params(format!("Invalid since: {}", e), None))
+            })
+            .transpose()?;
+        let batches = oplog::read(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let query = EditQuery {
+            actor: params.actor,
+            object_id: params.object_id,
+            since,
+        };
+        let mut edits = attribution::query(&batches, &query, params.include_values);
+        if let Some(limit) = params.limit {
+            edits.drain(..edits.len().saturating_sub(limit));
+        }
+
+        let result = serde_json::json!({
+            "edits": edits,
+            "actors": attribution::actors(&batches),
+        });
+
+        let json = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Undo one actor's edits, leaving everyone else's in place.
+    #[tool(name = "revert_edits", description = "Revert one actor's edits to an .easel file: every object they added, changed or removed goes back to how it was before their edits. Objects someone else changed afterwards are skipped and listed. The revert is itself recorded as an edit")]
+    fn revert_edits(
+        &self,
+        peer: Peer<RoleServer>,
+        Parameters(params): Parameters<RevertEditsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let mut easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+        let batches = oplog::read(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+        if !batches.iter().any(|b| b.actor.as_ref().is_some_and(|a| a.matches(&params.actor))) {
+            return Err(McpError::invalid_params(
+                format!("No edits by {} in the operation log", params.actor),
+                None,
+            ));
+        }
+
+        let report = attribution::revert(&mut easel.canvas, &batches, &params.actor);
+        if !report.reverted.is_empty() {
+            easel.updated_at = Utc::now();
+            easel
+                .save_as(&path, &actor(&peer, params.agent.as_deref()))
+                .map_err(save_error)?;
+        }
+
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Undo the latest edit in scope.
+    #[tool(name = "undo", description = "Undo the latest edit to an .easel file that is still in effect: by default the latest save made through this connection, with scope=global the latest attributed save by anyone. Repeat to step further back; redo steps forward again. The history is the file's operation log, so it survives restarts. Fails without changing anything if the edit's objects were changed again since")]
+    fn undo(
+        &self,
+        peer: Peer<RoleServer>,
+        Parameters(params): Parameters<HistoryStepParams>,
+    ) -> Result<CallToolResult, McpError> {
+        step_history(&peer, params, false)
+    }
+
+    /// Redo the latest undone edit in scope.
+    #[tool(name = "redo", description = "Redo the edit to an .easel file most recently undone in the same scope (session by default, or global). A new edit in that scope clears what there is to redo. Fails without changing anything if the edit's objects were changed since the undo")]
+    fn redo(
+        &self,
+        peer: Peer<RoleServer>,
+        Parameters(params): Parameters<HistoryStepParams>,
+    ) -> Result<CallToolResult, McpError> {
+        step_history(&peer, params, true)
+    }
+
+    /// Rebuild a canvas from its operation log.
+    #[tool(name = "replay_canvas", description = "Rebuild an .easel canvas by replaying its operation log from an empty canvas, up to a given revision or to the latest save. Returns the canvas JSON, or writes it to a new .easel file at output_path. Use list_edits to find the revision to go back to")]
+    fn replay_canvas(
+        &self,
+        Parameters(params): Parameters<ReplayCanvasParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let canvas = oplog::replay(&path, params.revision)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let Some(output_path) = params.output_path else {
+            let json = serde_json::to_string_pretty(&canvas)
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+            return Ok(CallToolResult::success(vec![Content::text(json)]));
+        };
+        let output = PathBuf::from(&output_path);
+        if output.exists() {
+            return Err(McpError::invalid_params(
+                format!("{} already exists", output.display()),
+                None,
+            ));
+        }
+        let name = EaselFile::load(&path)
+            .map(|easel| easel.name)
+            .unwrap_or_else(|_| "Untitled".to_string());
+        let mut easel = EaselFile::new(&name);
+        easel.canvas = canvas;
+        easel.save(&output).map_err(save_error)?;
+
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Replayed {} to {} ({} objects)",
+            params.file_path,
+            output.display(),
+            easel.objects().len()
+        ))]))
+    }
+
+    /// Combine shapes with a boolean path operation.
+    #[tool(name = "boolean_objects", description = "Combine two or more top-level shapes into one Path object with a boolean operation (union, subtract, intersect, exclude), styled like the bottom-most shape. The originals are replaced unless keep_originals is set")]
+    fn boolean_objects(
+        &self,
+        peer: Peer<RoleServer>,
+        Parameters(params): Parameters<BooleanObjectsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let mut easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let result = boolean::apply_to_canvas(
+            &mut easel.canvas,
+            &params.ids,
+            params.operation,
+            params.name.as_deref(),
+            params.keep_originals,
+        )
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+        easel.updated_at = Utc::now();
+        easel
+            .save_as(&path, &actor(&peer, params.agent.as_deref()))
+            .map_err(save_error)?;
+
+        let json = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Outline the canvas compactly for an agent's context window.
+    #[tool(name = "summarize_canvas", description = "Compact outline of a canvas: statistics (object counts by type, bounds, fonts, most used fills) and frames with their children, one line per object with id, type, name, rounded canvas box and key styles. Use depth and frame_id to keep large canvases small")]
+    fn summarize_canvas(
+        &self,
+        Parameters(params): Parameters<SummarizeCanvasParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let options = SummaryOptions {
+            depth: params.depth,
+            root_id: params.frame_id,
+            format: params.format,
+        };
+        let summary = summary::summarize(&easel.canvas, &easel.name, &options)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let text = match summary {
+            Value::String(outline) => outline,
+            json => serde_json::to_string(&json)
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?,
+        };
+
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    /// Semantic diff of two versions of a canvas.
+    #[tool(name = "diff_canvases", description = "Compare two versions of a canvas by object id: added, removed and moved objects (position or frame/group), changed properties per object, stacking-order changes, canvas-level properties, name and viewport. Use it to review edits")]
+    fn diff_canvases(
+        &self,
+        Parameters(params): Parameters<DiffCanvasesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let load = |path: &str| {
+            EaselFile::load(&PathBuf::from(path)).map_err(|e| McpError::invalid_params(e, None))
+        };
+        let diff = diff::diff_files(&load(&params.before_path)?, &load(&params.after_path)?);
+
+        let text = match params.format {
+            DiffFormat::Text => diff::report(&diff),
+            DiffFormat::Json => serde_json::to_string_pretty(&diff)
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?,
+        };
+
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    /// Check a canvas against the design lint rules.
+    #[tool(name = "lint_canvas", description = "Lint a canvas for design issues: text below a minimum size, low text contrast against its frame, objects outside any frame, partially overlapping siblings, off-grid positions, unnamed layers and colors outside a token palette. Rules and thresholds are configurable; every issue names its object id")]
+    fn lint_canvas(
+        &self,
+        Parameters(params): Parameters<LintCanvasParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let report = lint::lint(&easel.canvas, &params.config)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Audit frames against WCAG contrast, touch target and reading order checks.
+    #[tool(name = "audit_accessibility", description = "WCAG audit of frames: contrast of every text object against its effective background (the shapes beneath it composited together), minimum touch-target sizes in mobile frames, and visual versus layer reading order. Returns a summary, per-frame reading order and findings keyed by object id")]
+    fn audit_accessibility(
+        &self,
+        Parameters(params): Parameters<AuditAccessibilityParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let report = accessibility::audit(&easel.canvas, &params.options)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let json = serde_json::to_string_pretty(&report)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Find objects by property and return only the requested fields.
+    #[tool(name = "query_objects", description = "Find objects by type, name glob, fill or stroke color, parent frame or group, frame/component flags, text content and canvas-space position or size ranges. Returns ids with only the requested fields, paginated, instead of the whole tree")]
+    fn query_objects(
+        &self,
+        Parameters(params): Parameters<QueryObjectsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+        let scene = Scene::new(&easel.canvas);
+
+        let matches = query::select(&scene, &params.query)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+        let fields = params
+            .fields
+            .unwrap_or_else(|| vec!["name".to_string(), "type".to_string()]);
+        let limit = params.limit.unwrap_or(50).clamp(1, 500);
+        let objects: Vec<Value> = matches
+            .iter()
+            .skip(params.offset)
+            .take(limit)
+            .map(|p| query::project(p, &fields))
+            .collect();
+        let next = params.offset + objects.len();
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "total": matches.len(),
+            "objects": objects,
+            "nextOffset": (next < matches.len()).then_some(next),
+        }))
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Find the objects painted under a canvas point.
+    #[tool(name = "objects_at_point", description = "Hit-test a canvas point: list the objects whose fill or stroke covers it (text, images and frames by their rotated box), top-most first, accounting for origin, scale, angle and group transforms")]
+    fn objects_at_point(
+        &self,
+        Parameters(params): Parameters<ObjectsAtPointParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let tolerance = params.tolerance.unwrap_or(0.0).max(0.0);
+        let scene = Scene::new(&easel.canvas);
+        let hits: Vec<Value> = scene
+            .at_point(Point::new(params.x, params.y), tolerance)
+            .into_iter()
+            .map(|p| p.summary())
+            .collect();
+
+        let json = serde_json::to_string_pretty(&hits)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Find the objects touching or inside a region.
+    #[tool(name = "objects_in_rect", description = "List objects that intersect or lie entirely inside a canvas region, given as left/top/width/height or as another object's bounds (e.g. what overlaps this frame), in paint order")]
+    fn objects_in_rect(
+        &self,
+        Parameters(params): Parameters<ObjectsInRectParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+        let scene = Scene::new(&easel.canvas);
+
+        let (region, exclude) = match &params.object_id {
+            Some(id) => {
+                let target = scene.find(id).ok_or_else(|| {
+                    McpError::invalid_params(format!("Object not found: {}", id), None)
+                })?;
+                let mut exclude = scene.descendants(target.z);
+                exclude.push(target.z);
+                (target.bounds, exclude)
+            }
+            None => match (params.left, params.top, params.width, params.height) {
+                (Some(left), Some(top), Some(width), Some(height)) if width >= 0.0 && height >= 0.0 => (
+                    Bounds {
+                        left,
+                        top,
+                        width,
+                        height,
+                    },
+                    Vec::new(),
+                ),
+                _ => {
+                    return Err(McpError::invalid_params(
+                        "Give either object_id or left, top and a non-negative width and height",
+                        None,
+                    ));
+                }
+            },
+        };
+
+        let objects: Vec<Value> = scene
+            .in_rect(&region, params.mode)
+            .into_iter()
+            .filter(|p| !exclude.contains(&p.z))
+            .map(|p| p.summary())
+            .collect();
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "region": spatial::rounded(&region),
+            "objects": objects,
+        }))
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Find pairs of overlapping objects.
+    #[tool(name = "find_overlaps", description = "Find pairs of objects whose rotated boxes overlap (not just touch), ignoring frames and groups paired with their own children; optionally only pairs involving given IDs")]
+    fn find_overlaps(
+        &self,
+        Parameters(params): Parameters<FindOverlapsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+        let scene = Scene::new(&easel.canvas);
+
+        let only = match &params.ids {
+            Some(ids) => Some(
+                ids.iter()
+                    .map(|id| {
+                        scene.find(id).map(|p| p.z).ok_or_else(|| {
+                            McpError::invalid_params(format!("Object not found: {}", id), None)
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            None => None,
+        };
+
+        let pairs: Vec<Value> = scene
+            .overlaps(only.as_deref())
+            .into_iter()
+            .map(|(a, b)| serde_json::json!([a.summary(), b.summary()]))
+            .collect();
+
+        let json = serde_json::to_string_pretty(&pairs)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Measure objects in canvas space.
+    #[tool(name = "bounding_box", description = "Get the canvas-space bounds (stroke included) of one or more objects, each with its rotated corner points, and the box around all of them")]
+    fn bounding_box(
+        &self,
+        Parameters(params): Parameters<BoundingBoxParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if params.ids.is_empty() {
+            return Err(McpError::invalid_params("ids must not be empty", None));
+        }
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+        let scene = Scene::new(&easel.canvas);
+
+        let mut found = Vec::new();
+        let mut not_found = Vec::new();
+        for id in &params.ids {
+            match scene.find(id) {
+                Some(p) => found.push(p),
+                None => not_found.push(id.clone()),
+            }
+        }
+
+        let objects: Vec<Value> = found
+            .iter()
+            .map(|p| {
+                let mut summary = p.summary();
+                summary["corners"] = p
+                    .corners
+                    .iter()
+                    .map(|c| serde_json::json!([geometry::round(c.x), geometry::round(c.y)]))
+                    .collect();
+                summary
+            })
+            .collect();
+        let bounds = Bounds::union_all(found.iter().map(|p| p.bounds)).map(|b| spatial::rounded(&b));
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "bounds": bounds,
+            "objects": objects,
+            "notFound": not_found,
+        }))
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Generate HTML/CSS or a React component from a frame's object tree.
+    #[tool(name = "generate_code", description = "Generate HTML/CSS or a React (optionally Tailwind) component from a frame and its children")]
+    fn generate_code(
+        &self,
+        Parameters(params): Parameters<GenerateCodeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let options = CodegenOptions {
+            target: params.target,
+            layout: params.layout,
+            tailwind: params.tailwind,
+            component_name: params.component_name,
+        };
+        let code = codegen::generate(&easel.canvas, &params.frame_id, &options)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let json = serde_json::to_string_pretty(&code)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Render the canvas, its frames or a selection to a PDF file.
+    #[tool(name = "export_pdf", description = "Export an .easel canvas to PDF with vector shapes and embedded fonts: the whole canvas, one page per frame, or a selection")]
+    fn export_pdf(
+        &self,
+        Parameters(params): Parameters<ExportPdfParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let options = PdfOptions {
+            scope: params.scope,
+            ids: params.ids,
+        };
+        let summary = pdf::write_pdf(
+            &easel.canvas,
+            &options,
+            Some(&AssetStore::for_canvas(&path)),
+            &PathBuf::from(&params.output_path),
+        )
+        .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let json = serde_json::to_string_pretty(&summary)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Import an SVG file as Fabric objects appended to an .easel file.
+    #[tool(name = "import_svg", description = "Import an SVG file (shapes, paths, text, groups, transforms, gradients) as canvas objects appended to an .easel file")]
+    fn import_svg(
+        &self,
+        peer: Peer<RoleServer>,
+        Parameters(params): Parameters<ImportSvgParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let mut easel = if path.exists() {
+            EaselFile::load(&path).map_err(|e| McpError::internal_error(e, None))?
+        } else {
+            EaselFile::new("Untitled")
+        };
+
+        let source = std::fs::read_to_string(&params.svg_path).map_err(|e| {
+            McpError::invalid_params(format!("Failed to read {}: {}", params.svg_path, e), None)
+        })?;
+        let options = SvgImportOptions {
+            offset_x: params.x,
+            offset_y: params.y,
+        };
+        let mut imported = svg::import_svg(&source, &options)
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        if let Some(parent_id) = &params.parent_id {
+            for obj in &mut imported.objects {
+                obj["parentId"] = Value::String(parent_id.clone());
+            }
+        }
+
+        let ids: Vec<Value> = imported
+            .objects
+            .iter()
+            .filter_map(|o| o.get("id").cloned())
+            .collect();
+        easel.objects_mut().extend(imported.objects);
+        easel.updated_at = Utc::now();
+        easel
+            .save_as(&path, &actor(&peer, params.agent.as_deref()))
+            .map_err(save_error)?;
+
+        let result = serde_json::json!({
+            "imported": ids.len(),
+            "ids": ids,
+            "warnings": imported.warnings,
+        });
+
+        let json = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Convert an Excalidraw or tldraw scene into a new .easel file.
+    #[tool(name = "import_whiteboard", description = "Create a new .easel file from an Excalidraw or tldraw scene, converting rectangles, ellipses, diamonds, text, arrows, lines, freehand strokes, images, groups and frames")]
+    fn import_whiteboard(
+        &self,
+        peer: Peer<RoleServer>,
+        Parameters(params): Parameters<ImportWhiteboardParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        if path.exists() {
+            return Err(McpError::invalid_params(
+                format!("File already exists: {}", path.display()),
+                None,
+            ));
+        }
+
+        let source_path = PathBuf::from(&params.source_path);
+        let source = std::fs::read_to_string(&source_path).map_err(|e| {
+            McpError::invalid_params(format!("Failed to read {}: {}", params.source_path, e), None)
+        })?;
+        let format = params.format.or_else(|| WhiteboardFormat::from_path(&source_path));
+        let scene = import::import_whiteboard(&source, format, Some(&AssetStore::for_canvas(&path)))
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let name = params
+            .name
+            .or(scene.name)
+            .or_else(|| source_path.file_stem().and_then(|s| s.to_str()).map(String::from))
+            .unwrap_or_else(default_canvas_name);
+        let mut easel = EaselFile::new(&name);
+        if let Some(background) = scene.background {
+            easel.canvas["background"] = Value::String(background);
+        }
+        let imported = scene.elements;
+        easel.objects_mut().extend(scene.objects);
+        easel
+            .save_as(&path, &actor(&peer, params.agent.as_deref()))
+            .map_err(save_error)?;
+
+        let result = serde_json::json!({
+            "filePath": path.display().to_string(),
+            "name": name,
+            "imported": imported,
+            "warnings": scene.warnings,
+        });
+
+        let json = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// List binary assets stored for an .easel file.
+    #[tool(name = "list_assets", description = "List the image assets stored alongside an .easel file, with which are still referenced")]
+    fn list_assets(
+        &self,
+        Parameters(params): Parameters<AssetsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let referenced = assets::referenced_assets(&easel.canvas);
+        let list: Vec<Value> = AssetStore::for_canvas(&path)
+            .list()
+            .map_err(|e| McpError::internal_error(e, None))?
+            .into_iter()
+            .map(|asset| {
+                let used = referenced.contains(&asset.id);
+                let mut v = serde_json::to_value(asset).unwrap_or_default();
+                v["referenced"] = Value::Bool(used);
+                v
+            })
+            .collect();
+
+        let json = serde_json::to_string_pretty(&list)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// List installed fonts and resolve font-family lists.
+    #[tool(name = "list_fonts", description = "List the font families available for text measurement and export (system fonts plus any bundled with the canvas), optionally filtered, and resolve a CSS font-family list to the face that will actually be used")]
+    fn list_fonts(
+        &self,
+        Parameters(params): Parameters<ListFontsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let bundled = params
+            .file_path
+            .as_ref()
+            .and_then(|p| FontBook::with_bundled(&AssetStore::for_canvas(&PathBuf::from(p))));
+        let book = bundled.as_ref().unwrap_or_else(|| FontBook::system());
+
+        let query = params.query.as_deref().map(str::to_lowercase);
+        let families: Vec<_> = book
+            .families()
+            .into_iter()
+            .filter(|f| query.as_ref().is_none_or(|q| f.family.to_lowercase().contains(q)))
+            .collect();
+
+        let mut result = serde_json::json!({ "families": families });
+        if let Some(family) = &params.resolve {
+            let weight = fonts::parse_weight(params.font_weight.as_deref().unwrap_or("normal"));
+            let italic = matches!(params.font_style.as_deref(), Some("italic" | "oblique"));
+            result["resolved"] = serde_json::to_value(book.describe(family, weight, italic))
+                .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+        }
+
+        let json = serde_json::to_string_pretty(&result)
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Delete assets that neither the canvas nor an edit undo or redo can
+    /// still reach references.
+    #[tool(name = "gc_assets", description = "Garbage-collect image assets no longer referenced by any object in an .easel file or by an edit that can still be undone or redone")]
+    fn gc_assets(
+        &self,
+        Parameters(params): Parameters<AssetsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let easel = EaselFile::load(&path)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let history = oplog::read(&path).map_err(|e| McpError::internal_error(e, None))?;
+        let removed = AssetStore::for_canvas(&path)
+            .collect_garbage(&easel.canvas, &history)
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({ "removed": removed }))
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Copy assets out of the store into a directory.
+    #[tool(name = "export_assets", description = "Copy image assets of an .easel file into a destination directory")]
+    fn export_assets(
+        &self,
+        Parameters(params): Parameters<ExportAssetsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let path = PathBuf::from(&params.file_path);
+        let written = AssetStore::for_canvas(&path)
+            .export(&params.ids, &PathBuf::from(&params.destination))
+            .map_err(|e| McpError::invalid_params(e, None))?;
+
+        let files: Vec<String> = written.iter().map(|p| p.display().to_string()).collect();
+        let json = serde_json::to_string_pretty(&serde_json::json!({ "exported": files }))
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Package an .easel file and its assets into a single zipped bundle.
+    #[tool(name = "pack_easel_file", description = "Convert an .easel file into a self-contained bundle (zip with document, manifest and embedded image assets)")]
+    fn pack_easel_file(
+        &self,
+        Parameters(params): Parameters<PackEaselFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let source = PathBuf::from(&params.file_path);
+        let dest = params.destination.map(PathBuf::from).unwrap_or_else(|| source.clone());
+
+        let mut easel = EaselFile::load(&source).map_err(|e| McpError::invalid_params(e, None))?;
+        let thumbnail = bundle::read_thumbnail(&source)
+            .map_err(|e| McpError::internal_error(e, None))?;
+        easel
+            .save_bundle(&dest, &AssetStore::for_canvas(&source), thumbnail.as_deref())
+            .map_err(|e| McpError::internal_error(e, None))?;
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "path": dest.display().to_string(),
+            "assets": assets::referenced_assets(&easel.canvas).len(),
+        }))
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Turn a bundle back into plain JSON with a sibling asset directory.
+    #[tool(name = "unpack_easel_file", description = "Convert a bundled .easel file into plain JSON, extracting its assets into the adjacent .assets directory")]
+    fn unpack_easel_file(
+        &self,
+        Parameters(params): Parameters<PackEaselFileParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let source = PathBuf::from(&params.file_path);
+        let dest = params.destination.map(PathBuf::from).unwrap_or_else(|| source.clone());
+
+        let mut easel = EaselFile::load(&source).map_err(|e| McpError::invalid_params(e, None))?;
+        let ids: Vec<String> = assets::referenced_assets(&easel.canvas).into_iter().collect();
+        let dest_store = AssetStore::for_canvas(&dest);
+        if bundle::is_bundle(&source) {
+            bundle::unpack_assets(&source, &dest_store)
+                .map_err(|e| McpError::internal_error(e, None))?;
+        } else if dest_store.dir() != AssetStore::for_canvas(&source).dir() && !ids.is_empty() {
+            AssetStore::for_canvas(&source)
+                .export(&ids, dest_store.dir())
+                .map_err(|e| McpError::internal_error(e, None))?;
+        }
+
+        easel.save_json(&dest).map_err(|e| McpError::internal_error(e, None))?;
+
+        let json = serde_json::to_string_pretty(&serde_json::json!({
+            "path": dest.display().to_string(),
+            "assetDirectory": dest_store.dir().display().to_string(),
+            "assets": ids.len(),
+        }))
+        .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
 }
 
 #[tool_handler]
@@ -417,8 +2130,17 @@ impl ServerHandler for EaselMcpServer {
         ServerInfo {
             instructions: Some(
                 "Easel MCP server: read and write Fabric.js canvas objects in .easel files. \
-                 Use list_easel_files to discover files, get_canvas_state to read the full object tree, \
-                 get_object for individual object details, and create_object/update_object/delete_objects to modify."
+                 Use list_easel_files to discover files, summarize_canvas for a compact outline, get_canvas_state to read the full object tree (or query_objects to fetch just the matching ids and fields), \
+                 diff_canvases to review what changed between two versions of a file, \
+                 get_object for individual object details, and create_object/update_object/delete_objects to modify. \
+                 Edits are attributed to the calling agent (pass agent to label them): every save is recorded in the file's operation log: list_edits shows who changed what, undo/redo step back and forth through your own saves (or everyone's, with scope=global), revert_edits undoes one actor's edits, and replay_canvas rebuilds the canvas as of any revision. \
+                 boolean_objects merges shapes into one path (union, subtract, intersect, exclude). \
+                 For geometry questions use objects_at_point, objects_in_rect, find_overlaps and bounding_box instead of reading the whole tree. \
+                 Use generate_code to turn a frame into HTML/CSS or a React component, export_pdf for PDF hand-off, import_svg to bring in SVG artwork, and import_whiteboard to turn Excalidraw or tldraw sketches into a new canvas. \
+                 lint_canvas checks a design against configurable rules (text size, contrast, framing, overlaps, grid, naming, palette), and audit_accessibility runs WCAG contrast, touch-target and reading-order checks per frame. \
+                 Use list_fonts to see which font families text can use and how a fontFamily list resolves. \
+                 pack_easel_file/unpack_easel_file convert between plain JSON and self-contained zipped bundles (with images and the fonts the text names). \
+                 Saves are locked and merged property by property with concurrent edits from the Easel app and other agents, so work on different objects or properties converges; a \"Conflict\" error means a file without merge history changed underneath the edit, so retry it."
                     .to_string(),
             ),
             capabilities: ServerCapabilities::builder()
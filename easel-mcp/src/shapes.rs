@@ -0,0 +1,373 @@
+//! Stroked geometry shared by object creation and the importers: lines,
+//! polylines, polygons, SVG paths and arrows, with Fabric bounding boxes
+//! computed from their points.
+
+use serde_json::{json, Value};
+
+use crate::geometry::{self, Matrix, Point};
+use crate::import::{base_object, group_objects, path_object, place};
+use crate::path::{self, Segment};
+
+/// Bézier handle length for a quarter circle, as a fraction of the radius.
+const KAPPA: f64 = 0.552_284_75;
+
+// ── Styling ─────────────────────────────────────────────────────────────────
+
+/// A color string as Fabric paint; empty and `transparent` mean no paint.
+pub fn paint(color: Option<&str>) -> Value {
+    match color.map(str::trim) {
+        None | Some("" | "transparent" | "none") => Value::Null,
+        Some(c) => json!(c),
+    }
+}
+
+pub struct LineStyle {
+    pub color: Value,
+    pub width: f64,
+    pub dash: Option<Vec<f64>>,
+    /// Round caps and joins, as hand-drawn strokes use.
+    pub round: bool,
+}
+
+impl LineStyle {
+    pub fn apply(&self, obj: &mut Value, fill: Value) {
+        obj["fill"] = fill;
+        obj["stroke"] = self.color.clone();
+        obj["strokeWidth"] = json!(if self.color.is_null() {
+            0.0
+        } else {
+            self.width
+        });
+        if let Some(dash) = &self.dash {
+            obj["strokeDashArray"] = json!(dash);
+        }
+        if self.round {
+            obj["strokeLineCap"] = json!("round");
+            obj["strokeLineJoin"] = json!("round");
+        }
+    }
+}
+
+// ── Segments ────────────────────────────────────────────────────────────────
+pub fn polyline(points: &[Point], closed: bool) -> Vec<Segment> {
+    let mut segments = Vec::with_capacity(points.len() + 1);
+    for (i, p) in points.iter().enumerate() {
+        segments.push(if i == 0 {
+            Segment::Move(*p)
+        } else {
+            Segment::Line(*p)
+        });
+    }
+    if closed && points.len() > 2 {
+        segments.push(Segment::Close);
+    }
+    segments
+}
+
+pub fn circle(center: Point, r: f64) -> Vec<Segment> {
+    let k = r * KAPPA;
+    let (cx, cy) = (center.x, center.y);
+    vec![
+        Segment::Move(Point::new(cx + r, cy)),
+        Segment::Cubic(
+            Point::new(cx + r, cy + k),
+            Point::new(cx + k, cy + r),
+            Point::new(cx, cy + r),
+        ),
+        Segment::Cubic(
+            Point::new(cx - k, cy + r),
+            Point::new(cx - r, cy + k),
+            Point::new(cx - r, cy),
+        ),
+        Segment::Cubic(
+            Point::new(cx - r, cy - k),
+            Point::new(cx - k, cy - r),
+            Point::new(cx, cy - r),
+        ),
+        Segment::Cubic(
+            Point::new(cx + k, cy - r),
+            Point::new(cx + r, cy - k),
+            Point::new(cx + r, cy),
+        ),
+        Segment::Close,
+    ]
+}
+
+fn end_point(seg: &Segment) -> Option<Point> {
+    match *seg {
+        Segment::Move(p) | Segment::Line(p) | Segment::Quad(_, p) | Segment::Cubic(_, _, p) => {
+            Some(p)
+        }
+        Segment::Close => None,
+    }
+}
+
+/// The first point of an open path and the point its tangent comes from.
+pub fn start_tangent(segments: &[Segment]) -> Option<(Point, Point)> {
+    let tip = end_point(segments.first()?)?;
+    let from = match *segments.get(1)? {
+        Segment::Line(p) => p,
+        Segment::Quad(c, _) | Segment::Cubic(c, _, _) => c,
+        _ => return None,
+    };
+    Some((tip, from))
+}
+
+/// The last point of an open path and the point its tangent comes from.
+pub fn end_tangent(segments: &[Segment]) -> Option<(Point, Point)> {
+    let n = segments.len();
+    if n < 2 {
+        return None;
+    }
+    let (tip, from) = match segments[n - 1] {
+        Segment::Line(p) => (p, end_point(&segments[n - 2])?),
+        Segment::Quad(c, p) | Segment::Cubic(_, c, p) => (p, c),
+        _ => return None,
+    };
+    Some((tip, from))
+}
+
+/// Length of a polyline through `points`.
+pub fn polyline_length(points: &[Point]) -> f64 {
+    points
+        .windows(2)
+        .map(|w| (w[1].x - w[0].x).hypot(w[1].y - w[0].y))
+        .sum()
+}
+
+// ── Arrows ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arrowhead {
+    Arrow,
+    Bar,
+    Triangle,
+    TriangleOutline,
+    Dot,
+    Circle,
+    Diamond,
+    DiamondOutline,
+    Square,
+}
+
+impl Arrowhead {
+    /// Head names accepted by `create_object`; Excalidraw uses the same ones.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "arrow" => Self::Arrow,
+            "bar" => Self::Bar,
+            "triangle" => Self::Triangle,
+            "triangle_outline" => Self::TriangleOutline,
+            "dot" | "circle" => Self::Dot,
+            "circle_outline" => Self::Circle,
+            "diamond" => Self::Diamond,
+            "diamond_outline" => Self::DiamondOutline,
+            "square" => Self::Square,
+            _ => return None,
+        })
+    }
+
+    /// Heads drawn as a separate shape filled with the stroke color.
+    fn filled(self) -> bool {
+        matches!(
+            self,
+            Self::Triangle | Self::Dot | Self::Diamond | Self::Square
+        )
+    }
+
+    /// Outline of the head at `tip`, pointing away from `from`.
+    fn segments(self, tip: Point, from: Point, size: f64) -> Vec<Segment> {
+        let len = (tip.x - from.x).hypot(tip.y - from.y);
+        let (ux, uy) = if len > 1e-9 {
+            ((tip.x - from.x) / len, (tip.y - from.y) / len)
+        } else {
+            (1.0, 0.0)
+        };
+        // Back along the shaft by `along`, then sideways by `across`.
+        let at = |along: f64, across: f64| {
+            Point::new(
+                tip.x - ux * along - uy * across,
+                tip.y - uy * along + ux * across,
+            )
+        };
+        let wing = 25f64.to_radians();
+        let (along, across) = (size * wing.cos(), size * wing.sin());
+        match self {
+            Self::Arrow => vec![
+                Segment::Move(at(along, across)),
+                Segment::Line(tip),
+                Segment::Line(at(along, -across)),
+            ],
+            Self::Bar => vec![
+                Segment::Move(at(0.0, size / 2.0)),
+                Segment::Line(at(0.0, -size / 2.0)),
+            ],
+            Self::Triangle | Self::TriangleOutline => vec![
+                Segment::Move(tip),
+                Segment::Line(at(along, across)),
+                Segment::Line(at(along, -across)),
+                Segment::Close,
+            ],
+            Self::Dot | Self::Circle => circle(at(size * 0.3, 0.0), size * 0.3),
+            Self::Diamond | Self::DiamondOutline => vec![
+                Segment::Move(tip),
+                Segment::Line(at(size / 2.0, size / 4.0)),
+                Segment::Line(at(size, 0.0)),
+                Segment::Line(at(size / 2.0, -size / 4.0)),
+                Segment::Close,
+            ],
+            Self::Square => {
+                let h = size * 0.3;
+                vec![
+                    Segment::Move(at(0.0, h)),
+                    Segment::Line(at(2.0 * h, h)),
+                    Segment::Line(at(2.0 * h, -h)),
+                    Segment::Line(at(0.0, -h)),
+                    Segment::Close,
+                ]
+            }
+        }
+    }
+}
+
+pub struct ArrowEnd {
+    pub head: Arrowhead,
+    pub tip: Point,
+    pub from: Point,
+    pub size: f64,
+}
+
+/// A line or arrow: the shaft as a Path, with outline heads added to it and
+/// filled heads as separate paths grouped with the shaft.
+pub fn linear_object(
+    mut shaft: Vec<Segment>,
+    ends: &[ArrowEnd],
+    style: &LineStyle,
+    fill: Value,
+    matrix: &Matrix,
+    name: &str,
+) -> Option<Value> {
+    let mut filled_heads = Vec::new();
+    for end in ends {
+        let segments = end.head.segments(end.tip, end.from, end.size);
+        if end.head.filled() {
+            filled_heads.push(segments);
+        } else {
+            shaft.extend(segments);
+        }
+    }
+
+    let (mut obj, origin) = path_object(&shaft, name)?;
+    style.apply(&mut obj, fill);
+    place(&mut obj, matrix, origin);
+    if filled_heads.is_empty() {
+        return Some(obj);
+    }
+
+    let solid = LineStyle {
+        color: style.color.clone(),
+        width: style.width,
+        dash: None,
+        round: style.round,
+    };
+    let mut parts = vec![obj];
+    for segments in filled_heads {
+        if let Some((mut head, origin)) = path_object(&segments, "Arrowhead") {
+            solid.apply(&mut head, style.color.clone());
+            place(&mut head, matrix, origin);
+            parts.push(head);
+        }
+    }
+    Some(group_objects(parts, name))
+}
+
+// ── Canvas objects ──────────────────────────────────────────────────────────
+
+/// A Fabric Line between two canvas points.
+pub fn line_object(start: Point, end: Point, style: &LineStyle, name: &str) -> Value {
+    let mut obj = base_object("Line", name);
+    obj["x1"] = json!(start.x);
+    obj["y1"] = json!(start.y);
+    obj["x2"] = json!(end.x);
+    obj["y2"] = json!(end.y);
+    obj["width"] = json!((end.x - start.x).abs());
+    obj["height"] = json!((end.y - start.y).abs());
+    style.apply(&mut obj, Value::Null);
+    place(
+        &mut obj,
+        &Matrix::IDENTITY,
+        Point::new(start.x.min(end.x), start.y.min(end.y)),
+    );
+    obj
+}
+
+/// A Fabric Polyline, or Polygon when `closed`, through canvas points.
+pub fn poly_object(
+    points: &[Point],
+    closed: bool,
+    style: &LineStyle,
+    fill: Value,
+    name: &str,
+) -> Option<Value> {
+    let bbox = geometry::bounds_of_points(points)?;
+    let mut obj = base_object(if closed { "Polygon" } else { "Polyline" }, name);
+    obj["points"] = Value::Array(
+        points
+            .iter()
+            .map(|p| json!({"x": geometry::round(p.x), "y": geometry::round(p.y)}))
+            .collect(),
+    );
+    obj["width"] = json!(bbox.width);
+    obj["height"] = json!(bbox.height);
+    // Fabric draws points relative to pathOffset, the center of their bounds.
+    obj["pathOffset"] = json!({"x": bbox.center_x(), "y": bbox.center_y()});
+    style.apply(&mut obj, if closed { fill } else { Value::Null });
+    place(&mut obj, &Matrix::IDENTITY, Point::new(bbox.left, bbox.top));
+    Some(obj)
+}
+
+/// A Fabric Path from SVG path data in canvas coordinates.
+pub fn svg_path_object(
+    d: &str,
+    style: &LineStyle,
+    fill: Value,
+    name: &str,
+) -> Result<Value, String> {
+    let segments = path::parse(d)?;
+    let (mut obj, origin) =
+        path_object(&segments, name).ok_or("Path data has no drawable segments")?;
+    style.apply(&mut obj, fill);
+    place(&mut obj, &Matrix::IDENTITY, origin);
+    Ok(obj)
+}
+
+/// An arrow along canvas points, with heads sized from the stroke width.
+pub fn arrow_object(
+    points: &[Point],
+    start: Option<Arrowhead>,
+    end: Option<Arrowhead>,
+    style: &LineStyle,
+    name: &str,
+) -> Option<Value> {
+    let shaft = polyline(points, false);
+    let n = points.len();
+    if n < 2 {
+        return None;
+    }
+    let base_size = 10.0 + style.width * 3.0;
+    let mut ends = Vec::new();
+    for (head, tangent, segment) in [
+        (start, start_tangent(&shaft), polyline_length(&points[..2])),
+        (end, end_tangent(&shaft), polyline_length(&points[n - 2..])),
+    ] {
+        if let (Some(head), Some((tip, from))) = (head, tangent) {
+            ends.push(ArrowEnd {
+                head,
+                tip,
+                from,
+                size: base_size.min(segment / 2.0),
+            });
+        }
+    }
+    linear_object(shaft, &ends, style, Value::Null, &Matrix::IDENTITY, name)
+}
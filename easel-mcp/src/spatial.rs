@@ -0,0 +1,558 @@
+//! Geometry-aware lookups over a canvas: what is under a point, what lies in
+//! a region, which objects overlap.
+//!
+//! Every object, including those nested in groups, is placed in canvas space
+//! through its full transform (origin, scale, angle, skew, flips and the
+//! enclosing groups). Candidates come from a packed R-tree over the placed
+//! bounds and are then checked against the object's own geometry: shape
+//! outlines for hit-testing, the rotated box for regions and overlaps.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+use crate::boolean;
+use crate::color::Rgba;
+use crate::fabric::{self, Bounds};
+use crate::geometry::{self, Matrix, Point};
+use crate::path;
+use crate::render;
+
+/// Children per R-tree node.
+const NODE_SIZE: usize = 16;
+/// Slack for comparisons between computed coordinates.
+const EPSILON: f64 = 1e-6;
+
+/// How [`Scene::in_rect`] matches objects against the region.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionMode {
+    /// The object's rotated box touches the region.
+    #[default]
+    Intersects,
+    /// The object lies entirely inside the region.
+    Contains,
+}
+
+/// An object placed in canvas space.
+pub struct Placed<'a> {
+    pub object: &'a Value,
+    /// Position in paint order, bottom-most first.
+    pub z: usize,
+    /// The frame (`parentId`) or group the object belongs to.
+    pub parent: Option<usize>,
+    /// Maps the object's unscaled local box into canvas space.
+    pub matrix: Matrix,
+    /// Corners of the stroke-inclusive box in canvas space, clockwise from top-left.
+    pub corners: [Point; 4],
+    /// Axis-aligned canvas bounds, stroke included.
+    pub bounds: Bounds,
+}
+
+impl Placed<'_> {
+    pub fn id(&self) -> Option<&str> {
+        fabric::object_id(self.object)
+    }
+
+    fn is_group(&self) -> bool {
+        fabric::object_type(self.object) == "group"
+    }
+
+    /// Compact description for tool output.
+    pub fn summary(&self) -> Value {
+        json!({
+            "id": self.id(),
+            "name": fabric::str_prop(self.object, "name"),
+            "type": fabric::str_prop(self.object, "type"),
+            "parentId": fabric::str_prop(self.object, "parentId"),
+            "bounds": rounded(&self.bounds),
+        })
+    }
+}
+
+/// Every object of a canvas with its placement and a spatial index over them.
+pub struct Scene<'a> {
+    items: Vec<Placed<'a>>,
+    index: SpatialIndex,
+}
+
+impl<'a> Scene<'a> {
+    pub fn new(canvas: &'a Value) -> Scene<'a> {
+        let objects = canvas
+            .get("objects")
+            .and_then(|v| v.as_array())
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let mut items = Vec::new();
+        for obj in objects {
+            place(obj, &Matrix::IDENTITY, None, &mut items);
+        }
+
+        // Frame membership is by id; resolve it once everything is placed.
+        let by_id: HashMap<&str, usize> = items
+            .iter()
+            .filter_map(|p| Some((fabric::object_id(p.object)?, p.z)))
+            .collect();
+        for item in items.iter_mut().filter(|p| p.parent.is_none()) {
+            item.parent = fabric::str_prop(item.object, "parentId")
+                .and_then(|id| by_id.get(id).copied())
+                .filter(|&p| p != item.z);
+        }
+
+        let boxes: Vec<Bounds> = items.iter().map(|p| p.bounds).collect();
+        Scene {
+            index: SpatialIndex::build(&boxes),
+            items,
+        }
+    }
+
+    pub fn items(&self) -> &[Placed<'a>] {
+        &self.items
+    }
+
+    pub fn find(&self, id: &str) -> Option<&Placed<'a>> {
+        self.items.iter().find(|p| p.id() == Some(id))
+    }
+
+    /// Objects whose painted geometry lies within `tolerance` of `point`,
+    /// top-most first. A group is hit when any of its children is.
+    pub fn at_point(&self, point: Point, tolerance: f64) -> Vec<&Placed<'a>> {
+        let area = Bounds {
+            left: point.x - tolerance,
+            top: point.y - tolerance,
+            width: tolerance * 2.0,
+            height: tolerance * 2.0,
+        };
+        let mut hit = vec![false; self.items.len()];
+        for i in self.index.search(&area) {
+            let item = &self.items[i];
+            if !item.is_group() && hit_test(item, point, tolerance) {
+                hit[i] = true;
+                let mut parent = item.parent;
+                while let Some(p) = parent.filter(|&p| self.items[p].is_group()) {
+                    hit[p] = true;
+                    parent = self.items[p].parent;
+                }
+            }
+        }
+        let mut found: Vec<&Placed> = self.items.iter().filter(|p| hit[p.z]).collect();
+        found.reverse();
+        found
+    }
+
+    /// Objects whose fill covers `point`, bottom-most first. Text and groups
+    /// are left out; images count by their box.
+    pub fn fills_at(&self, point: Point) -> Vec<&Placed<'a>> {
+        let area = Bounds {
+            left: point.x,
+            top: point.y,
+            width: 0.0,
+            height: 0.0,
+        };
+        self.index
+            .search(&area)
+            .into_iter()
+            .map(|i| &self.items[i])
+            .filter(|item| fill_test(item, point))
+            .collect()
+    }
+
+    /// Objects in paint order that touch or lie inside `area`.
+    pub fn in_rect(&self, area: &Bounds, mode: RegionMode) -> Vec<&Placed<'a>> {
+        let mut found: Vec<usize> = self
+            .index
+            .search(area)
+            .into_iter()
+            .filter(|&i| {
+                let item = &self.items[i];
+                match mode {
+                    RegionMode::Intersects => quads_overlap(&item.corners, &corners(area), false),
+                    RegionMode::Contains => contains(area, &item.bounds),
+                }
+            })
+            .collect();
+        found.sort_unstable();
+        found.into_iter().map(|i| &self.items[i]).collect()
+    }
+
+    /// Pairs of objects whose rotated boxes overlap by more than an edge,
+    /// skipping frames or groups paired with their own descendants. With
+    /// `only`, at least one object of each pair is in that set.
+    pub fn overlaps(&self, only: Option<&[usize]>) -> Vec<(&Placed<'a>, &Placed<'a>)> {
+        let mut pairs = Vec::new();
+        for a in &self.items {
+            if only.is_some_and(|only| !only.contains(&a.z)) {
+                continue;
+            }
+            for b in self.index.search(&a.bounds) {
+                let b = &self.items[b];
+                // Report each pair once, unless only one side was asked about.
+                if b.z == a.z || (b.z < a.z && only.is_none_or(|only| only.contains(&b.z))) {
+                    continue;
+                }
+                if self.is_ancestor(a.z, b.z) || self.is_ancestor(b.z, a.z) {
+                    continue;
+                }
+                if quads_overlap(&a.corners, &b.corners, true) {
+                    pairs.push(if a.z < b.z { (a, b) } else { (b, a) });
+                }
+            }
+        }
+        pairs.sort_by_key(|(a, b)| (a.z, b.z));
+        pairs
+    }
+
+    /// Every descendant of an item: frame members and group children, recursively.
+    pub fn descendants(&self, z: usize) -> Vec<usize> {
+        (0..self.items.len())
+            .filter(|&i| self.is_ancestor(z, i))
+            .collect()
+    }
+
+    /// Whether `ancestor` is a frame or group that `z` belongs to, at any depth.
+    pub fn is_ancestor(&self, ancestor: usize, mut z: usize) -> bool {
+        // Bounded walk: a parentId cycle in a hand-edited file must not hang.
+        for _ in 0..self.items.len() {
+            match self.items[z].parent {
+                Some(p) if p == ancestor => return true,
+                Some(p) => z = p,
+                None => return false,
+            }
+        }
+        false
+    }
+}
+
+fn place<'a>(
+    obj: &'a Value,
+    parent_matrix: &Matrix,
+    parent: Option<usize>,
+    items: &mut Vec<Placed<'a>>,
+) {
+    let matrix = parent_matrix.multiply(&geometry::object_matrix(obj));
+    let corners = stroke_corners(obj, &matrix);
+    let z = items.len();
+    items.push(Placed {
+        object: obj,
+        z,
+        parent,
+        matrix,
+        corners,
+        bounds: geometry::object_bounds_with(obj, parent_matrix),
+    });
+
+    if let Some(children) = obj.get("objects").and_then(|v| v.as_array()) {
+        // Group children are positioned relative to the group's centre.
+        let inner = matrix.multiply(&Matrix::translate(
+            fabric::base_width(obj) / 2.0,
+            fabric::base_height(obj) / 2.0,
+        ));
+        for child in children {
+            place(child, &inner, Some(z), items);
+        }
+    }
+}
+
+/// Canvas-space corners of the object's box grown by its stroke.
+fn stroke_corners(obj: &Value, matrix: &Matrix) -> [Point; 4] {
+    let stroke_width = fabric::num_prop(obj, "strokeWidth").unwrap_or(0.0);
+    let (w, h) = (fabric::base_width(obj), fabric::base_height(obj));
+    if fabric::bool_prop(obj, "strokeUniform") {
+        // The stroke keeps its width on screen: grow the box after transforming.
+        let c = [
+            Point::new(0.0, 0.0),
+            Point::new(w, 0.0),
+            Point::new(w, h),
+            Point::new(0.0, h),
+        ]
+        .map(|p| matrix.apply(p));
+        grow(c, stroke_width / 2.0)
+    } else {
+        let s = stroke_width / 2.0;
+        [
+            Point::new(-s, -s),
+            Point::new(w + s, -s),
+            Point::new(w + s, h + s),
+            Point::new(-s, h + s),
+        ]
+        .map(|p| matrix.apply(p))
+    }
+}
+
+/// Push each corner of a parallelogram out along both of its edges.
+fn grow(c: [Point; 4], by: f64) -> [Point; 4] {
+    let unit = |a: Point, b: Point| {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let len = dx.hypot(dy);
+        if len < EPSILON {
+            Point::new(0.0, 0.0)
+        } else {
+            Point::new(dx / len * by, dy / len * by)
+        }
+    };
+    let u = unit(c[0], c[1]);
+    let v = unit(c[0], c[3]);
+    [
+        Point::new(c[0].x - u.x - v.x, c[0].y - u.y - v.y),
+        Point::new(c[1].x + u.x - v.x, c[1].y + u.y - v.y),
+        Point::new(c[2].x + u.x + v.x, c[2].y + u.y + v.y),
+        Point::new(c[3].x - u.x + v.x, c[3].y - u.y + v.y),
+    ]
+}
+
+fn corners(b: &Bounds) -> [Point; 4] {
+    [
+        Point::new(b.left, b.top),
+        Point::new(b.right(), b.top),
+        Point::new(b.right(), b.bottom()),
+        Point::new(b.left, b.bottom()),
+    ]
+}
+
+fn contains(outer: &Bounds, inner: &Bounds) -> bool {
+    inner.left >= outer.left - EPSILON
+        && inner.top >= outer.top - EPSILON
+        && inner.right() <= outer.right() + EPSILON
+        && inner.bottom() <= outer.bottom() + EPSILON
+}
+
+fn intersects(a: &Bounds, b: &Bounds) -> bool {
+    a.left <= b.right() + EPSILON
+        && b.left <= a.right() + EPSILON
+        && a.top <= b.bottom() + EPSILON
+        && b.top <= a.bottom() + EPSILON
+}
+
+/// Separating-axis test between two convex quads. With `strict`, quads that
+/// only share an edge or a corner do not count as overlapping.
+fn quads_overlap(a: &[Point; 4], b: &[Point; 4], strict: bool) -> bool {
+    let slack = if strict { -EPSILON } else { EPSILON };
+    for quad in [a, b] {
+        for i in 0..2 {
+            let (p, q) = (quad[i], quad[i + 1]);
+            let axis = Point::new(p.y - q.y, q.x - p.x);
+            if axis.x.abs() < EPSILON && axis.y.abs() < EPSILON {
+                continue;
+            }
+            let project = |quad: &[Point; 4]| {
+                quad.iter()
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), p| {
+                        let d = p.x * axis.x + p.y * axis.y;
+                        (lo.min(d), hi.max(d))
+                    })
+            };
+            let (a_lo, a_hi) = project(a);
+            let (b_lo, b_hi) = project(b);
+            let scale = axis.x.hypot(axis.y);
+            if a_hi < b_lo - slack * scale || b_hi < a_lo - slack * scale {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Whether `point` falls on the painted geometry of a single object: inside
+/// its fill or on its stroke for shapes and paths, inside its rotated box for
+/// everything else (text, images, frames without a shape).
+fn hit_test(item: &Placed, point: Point, tolerance: f64) -> bool {
+    let obj = item.object;
+    let segments = match render::shape_segments(obj) {
+        Ok(Some(segments)) if !segments.is_empty() => segments,
+        _ => return in_quad(&item.corners, point, tolerance),
+    };
+    let segments = path::transform(&segments, &item.matrix);
+
+    if has_paint(obj.get("fill")) && fill_contains(obj, &segments, point) {
+        return true;
+    }
+
+    let stroke_width = fabric::num_prop(obj, "strokeWidth").unwrap_or(0.0);
+    let half = if has_paint(obj.get("stroke")) && stroke_width > 0.0 {
+        if fabric::bool_prop(obj, "strokeUniform") {
+            stroke_width / 2.0
+        } else {
+            let [a, b, c, d, _, _] = item.matrix.0;
+            stroke_width / 2.0 * (a * d - b * c).abs().sqrt()
+        }
+    } else {
+        0.0
+    };
+    let reach = half + tolerance;
+    if reach <= 0.0 {
+        return false;
+    }
+    boolean::polylines(&segments).iter().any(|(line, closed)| {
+        let n = line.len();
+        let edges = if *closed { n } else { n - 1 };
+        (0..edges).any(|i| distance_to_segment(point, line[i], line[(i + 1) % n]) <= reach)
+    })
+}
+
+/// Whether `point` is inside the filled area of canvas-space `segments`.
+fn fill_contains(obj: &Value, segments: &[path::Segment], point: Point) -> bool {
+    let winding: i32 = boolean::flatten(segments)
+        .iter()
+        .map(|c| boolean::winding(c, point))
+        .sum();
+    if fabric::str_prop(obj, "fillRule") == Some("evenodd") {
+        winding % 2 != 0
+    } else {
+        winding != 0
+    }
+}
+
+/// Whether an object's fill covers `point`: shapes by their outline, images
+/// by their box. Text and groups have no fill of their own to speak of.
+fn fill_test(item: &Placed, point: Point) -> bool {
+    let obj = item.object;
+    if item.is_group() || fabric::is_text(obj) {
+        return false;
+    }
+    if fabric::object_type(obj) == "image" {
+        return in_quad(&item.corners, point, 0.0);
+    }
+    match render::shape_segments(obj) {
+        Ok(Some(segments)) if has_paint(obj.get("fill")) => {
+            fill_contains(obj, &path::transform(&segments, &item.matrix), point)
+        }
+        _ => false,
+    }
+}
+
+fn has_paint(paint: Option<&Value>) -> bool {
+    match paint {
+        Some(Value::String(s)) => Rgba::parse(s).is_none_or(|c| c.a > 0.0),
+        Some(Value::Object(_)) => true,
+        _ => false,
+    }
+}
+
+fn in_quad(quad: &[Point; 4], p: Point, tolerance: f64) -> bool {
+    // A collapsed box (a horizontal line, say) has no inside; only `tolerance` reaches it.
+    let mut sign = 0.0;
+    let mut inside = true;
+    for i in 0..4 {
+        let (a, b) = (quad[i], quad[(i + 1) % 4]);
+        let cross = (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x);
+        if cross.abs() < EPSILON {
+            continue;
+        }
+        if sign == 0.0 {
+            sign = cross.signum();
+        } else if cross.signum() != sign {
+            inside = false;
+            break;
+        }
+    }
+    (inside && sign != 0.0)
+        || (0..4).any(|i| distance_to_segment(p, quad[i], quad[(i + 1) % 4]) <= tolerance)
+}
+
+fn distance_to_segment(p: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    let t = if len2 < EPSILON {
+        0.0
+    } else {
+        (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0)
+    };
+    (p.x - a.x - t * dx).hypot(p.y - a.y - t * dy)
+}
+
+pub fn rounded(b: &Bounds) -> Bounds {
+    Bounds {
+        left: geometry::round(b.left),
+        top: geometry::round(b.top),
+        width: geometry::round(b.width),
+        height: geometry::round(b.height),
+    }
+}
+
+// ── R-tree ──────────────────────────────────────────────────────────────────
+
+struct Node {
+    bounds: Bounds,
+    /// Item indices in a leaf, node indices otherwise.
+    children: Vec<usize>,
+    leaf: bool,
+}
+
+/// Static R-tree bulk-loaded with Sort-Tile-Recursive packing. Canvases are
+/// rebuilt from the file on every call, so there is no incremental insert.
+struct SpatialIndex {
+    boxes: Vec<Bounds>,
+    nodes: Vec<Node>,
+    root: Option<usize>,
+}
+
+impl SpatialIndex {
+    fn build(boxes: &[Bounds]) -> SpatialIndex {
+        let mut nodes = Vec::new();
+        let mut level: Vec<(usize, Bounds)> = boxes.iter().copied().enumerate().collect();
+        let mut leaf = true;
+        while !level.is_empty() {
+            let mut next = Vec::new();
+            for chunk in str_order(&level).chunks(NODE_SIZE) {
+                let bounds = Bounds::union_all(chunk.iter().map(|&i| level[i].1))
+                    .expect("chunks are never empty");
+                nodes.push(Node {
+                    bounds,
+                    children: chunk.iter().map(|&i| level[i].0).collect(),
+                    leaf,
+                });
+                next.push((nodes.len() - 1, bounds));
+            }
+            if next.len() == 1 {
+                return SpatialIndex {
+                    boxes: boxes.to_vec(),
+                    nodes,
+                    root: Some(next[0].0),
+                };
+            }
+            level = next;
+            leaf = false;
+        }
+        SpatialIndex {
+            boxes: Vec::new(),
+            nodes,
+            root: None,
+        }
+    }
+
+    /// Indices of every box that touches `area`.
+    fn search(&self, area: &Bounds) -> Vec<usize> {
+        let mut found = Vec::new();
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            if !intersects(&node.bounds, area) {
+                continue;
+            }
+            if node.leaf {
+                found.extend(
+                    node.children
+                        .iter()
+                        .filter(|&&i| intersects(&self.boxes[i], area)),
+                );
+            } else {
+                stack.extend_from_slice(&node.children);
+            }
+        }
+        found.sort_unstable();
+        found
+    }
+}
+
+/// Entry order for packing: vertical slices by centre x, each sorted by centre y.
+fn str_order(entries: &[(usize, Bounds)]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..entries.len()).collect();
+    order.sort_by(|&a, &b| entries[a].1.center_x().total_cmp(&entries[b].1.center_x()));
+    let leaves = entries.len().div_ceil(NODE_SIZE);
+    let slices = (leaves as f64).sqrt().ceil().max(1.0) as usize;
+    let per_slice = leaves.div_ceil(slices) * NODE_SIZE;
+    for slice in order.chunks_mut(per_slice.max(1)) {
+        slice.sort_by(|&a, &b| entries[a].1.center_y().total_cmp(&entries[b].1.center_y()));
+    }
+    order
+}
@@ -0,0 +1,358 @@
+//! Compact canvas outlines for agents.
+//!
+//! Frames list their members and groups their children, each object on one
+//! line with its id, type, name, rounded canvas-space box and the styles that
+//! matter when reasoning about a design. Statistics come first so a model can
+//! decide whether to drill down with `query_objects` or `get_object`.
+
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::color::Rgba;
+use crate::fabric;
+use crate::spatial::{Placed, Scene};
+
+/// Longest text excerpt shown per text object, in characters.
+const TEXT_EXCERPT: usize = 60;
+/// Most frequent fill colors listed in the statistics.
+const TOP_COLORS: usize = 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryFormat {
+    /// Indented one-line-per-object outline.
+    #[default]
+    Text,
+    /// The same outline as nested JSON.
+    Json,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SummaryOptions {
+    /// Levels of nesting to expand below the roots; deeper objects are counted.
+    pub depth: Option<usize>,
+    /// Outline only this frame (or group) and its contents.
+    pub root_id: Option<String>,
+    pub format: SummaryFormat,
+}
+
+struct Node {
+    item: usize,
+    children: Vec<Node>,
+    /// Descendants left out by the depth limit.
+    hidden: usize,
+}
+
+pub fn summarize(canvas: &Value, name: &str, options: &SummaryOptions) -> Result<Value, String> {
+    let scene = Scene::new(canvas);
+    let items = scene.items();
+
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); items.len()];
+    let mut roots = Vec::new();
+    for item in items {
+        match item.parent {
+            Some(p) => children[p].push(item.z),
+            None => roots.push(item.z),
+        }
+    }
+    if let Some(id) = &options.root_id {
+        let root = scene
+            .find(id)
+            .ok_or_else(|| format!("Object not found: {}", id))?;
+        roots = vec![root.z];
+    }
+
+    let depth = options.depth.unwrap_or(usize::MAX);
+    let outline: Vec<Node> = roots.iter().map(|&r| build(r, &children, depth)).collect();
+    let stats = statistics(&scene, &outline, &children);
+
+    Ok(match options.format {
+        SummaryFormat::Json => json!({
+            "name": name,
+            "stats": stats,
+            "outline": outline.iter().map(|n| node_json(items, n)).collect::<Vec<_>>(),
+        }),
+        SummaryFormat::Text => {
+            let mut out = String::new();
+            write_header(&mut out, name, &stats);
+            for node in &outline {
+                write_node(&mut out, items, node, 0);
+            }
+            Value::String(out)
+        }
+    })
+}
+
+fn build(item: usize, children: &[Vec<usize>], depth: usize) -> Node {
+    if depth == 0 {
+        return Node {
+            item,
+            children: Vec::new(),
+            hidden: count(item, children),
+        };
+    }
+    Node {
+        item,
+        children: children[item]
+            .iter()
+            .map(|&c| build(c, children, depth - 1))
+            .collect(),
+        hidden: 0,
+    }
+}
+
+fn count(item: usize, children: &[Vec<usize>]) -> usize {
+    children[item].iter().map(|&c| 1 + count(c, children)).sum()
+}
+
+fn statistics(scene: &Scene, outline: &[Node], children: &[Vec<usize>]) -> Value {
+    let items: Vec<&Placed> = outline
+        .iter()
+        .flat_map(|n| subtree(n.item, children))
+        .map(|i| &scene.items()[i])
+        .collect();
+
+    let mut by_type: BTreeMap<String, usize> = BTreeMap::new();
+    let mut fonts: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut colors: BTreeMap<String, usize> = BTreeMap::new();
+    for item in &items {
+        *by_type.entry(kind(item.object)).or_default() += 1;
+        if fabric::is_text(item.object) {
+            if let Some(family) = fabric::str_prop(item.object, "fontFamily") {
+                *fonts.entry(family).or_default() += 1;
+            }
+        }
+        if let Some(color) = solid(item.object.get("fill")) {
+            *colors.entry(color).or_default() += 1;
+        }
+    }
+    let mut colors: Vec<(String, usize)> = colors.into_iter().collect();
+    colors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    colors.truncate(TOP_COLORS);
+
+    let bounds = fabric::Bounds::union_all(outline.iter().map(|n| scene.items()[n.item].bounds));
+    json!({
+        "objects": items.len(),
+        "byType": by_type,
+        "bounds": bounds.map(|b| rounded_box(&b)),
+        "fonts": fonts.keys().collect::<Vec<_>>(),
+        "topFills": colors.iter().map(|(c, n)| json!([c, n])).collect::<Vec<_>>(),
+    })
+}
+
+fn subtree(item: usize, children: &[Vec<usize>]) -> Vec<usize> {
+    let mut out = vec![item];
+    for &c in &children[item] {
+        out.extend(subtree(c, children));
+    }
+    out
+}
+
+/// `Frame` and `Component` stand in for the Fabric type of frame rects.
+pub(crate) fn kind(obj: &Value) -> String {
+    if fabric::is_frame(obj) && fabric::bool_prop(obj, "isComponent") {
+        "Component".to_string()
+    } else if fabric::is_frame(obj) {
+        "Frame".to_string()
+    } else {
+        fabric::str_prop(obj, "type")
+            .unwrap_or("Object")
+            .to_string()
+    }
+}
+
+fn rounded_box(b: &fabric::Bounds) -> [i64; 4] {
+    [b.left, b.top, b.width, b.height].map(|v| v.round() as i64)
+}
+
+/// A solid paint as CSS, or `None` for gradients, patterns and no paint.
+fn solid(paint: Option<&Value>) -> Option<String> {
+    let color = Rgba::parse(paint?.as_str()?)?;
+    (color.a > 0.0).then(|| color.to_css())
+}
+
+fn paint(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::Object(g) => Some(format!(
+            "{} gradient",
+            g.get("type").and_then(|t| t.as_str()).unwrap_or("linear")
+        )),
+        other => solid(Some(other)),
+    }
+}
+
+/// The styles worth showing, as ordered key/value pairs.
+fn styles(obj: &Value) -> Vec<(&'static str, Value)> {
+    let mut out = Vec::new();
+    if fabric::bool_prop(obj, "isComponent") && !fabric::is_frame(obj) {
+        out.push(("component", json!(true)));
+    }
+    if let Some(fill) = paint(obj.get("fill")) {
+        if !fabric::is_text(obj) || fill != "#000000" {
+            out.push(("fill", json!(fill)));
+        }
+    }
+    let stroke_width = fabric::num_prop(obj, "strokeWidth").unwrap_or(0.0);
+    if let Some(stroke) = paint(obj.get("stroke")).filter(|_| stroke_width > 0.0) {
+        out.push((
+            "stroke",
+            json!(format!("{} {}px", stroke, round1(stroke_width))),
+        ));
+    }
+    if let Some(rx) = fabric::num_prop(obj, "rx").filter(|&rx| rx > 0.0) {
+        if fabric::object_type(obj) == "rect" {
+            out.push(("radius", json!(round1(rx))));
+        }
+    }
+    if let Some(opacity) = fabric::num_prop(obj, "opacity").filter(|&o| o < 1.0) {
+        out.push(("opacity", json!(round1(opacity))));
+    }
+    if let Some(angle) = fabric::num_prop(obj, "angle").filter(|a| a.abs() > 0.05) {
+        out.push(("angle", json!(round1(angle))));
+    }
+    if fabric::is_text(obj) {
+        let mut font = format!(
+            "{}px {}",
+            round1(fabric::num_prop(obj, "fontSize").unwrap_or(40.0)),
+            fabric::str_prop(obj, "fontFamily").unwrap_or("Times New Roman")
+        );
+        match obj.get("fontWeight") {
+            Some(Value::String(w)) if w != "normal" && w != "400" => {
+                let _ = write!(font, " {}", w);
+            }
+            Some(Value::Number(w)) if w.as_f64() != Some(400.0) => {
+                let _ = write!(font, " {}", w);
+            }
+            _ => {}
+        }
+        if fabric::str_prop(obj, "fontStyle") == Some("italic") {
+            font.push_str(" italic");
+        }
+        out.push(("font", json!(font)));
+        if let Some(text) = fabric::str_prop(obj, "text") {
+            out.push(("text", json!(excerpt(text))));
+        }
+    }
+    out
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0 + 0.0
+}
+
+fn excerpt(text: &str) -> String {
+    let flat = text.replace('\n', "⏎");
+    if flat.chars().count() > TEXT_EXCERPT {
+        format!("{}…", flat.chars().take(TEXT_EXCERPT).collect::<String>())
+    } else {
+        flat
+    }
+}
+
+fn node_json(items: &[Placed], node: &Node) -> Value {
+    let item = &items[node.item];
+    let mut out = Map::new();
+    out.insert("id".into(), json!(item.id()));
+    out.insert("type".into(), json!(kind(item.object)));
+    if let Some(name) = fabric::str_prop(item.object, "name") {
+        out.insert("name".into(), json!(name));
+    }
+    out.insert("box".into(), json!(rounded_box(&item.bounds)));
+    for (key, value) in styles(item.object) {
+        out.insert(key.into(), value);
+    }
+    if !node.children.is_empty() {
+        out.insert(
+            "children".into(),
+            node.children.iter().map(|c| node_json(items, c)).collect(),
+        );
+    }
+    if node.hidden > 0 {
+        out.insert("hidden".into(), json!(node.hidden));
+    }
+    Value::Object(out)
+}
+
+fn write_header(out: &mut String, name: &str, stats: &Value) {
+    let by_type = stats["byType"]
+        .as_object()
+        .map(|types| {
+            types
+                .iter()
+                .map(|(t, n)| format!("{} {}", n, t))
+                .collect::<Vec<_>>()
+                .join(", ")
+        })
+        .unwrap_or_default();
+    let _ = write!(
+        out,
+        "{:?}: {} objects ({})",
+        name, stats["objects"], by_type
+    );
+    if let Some([x, y, w, h]) = serde_json::from_value::<Option<[i64; 4]>>(stats["bounds"].clone())
+        .ok()
+        .flatten()
+    {
+        let _ = write!(out, ", bounds [{},{} {}x{}]", x, y, w, h);
+    }
+    out.push('\n');
+    let fonts: Vec<&str> = stats["fonts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|f| f.as_str())
+        .collect();
+    if !fonts.is_empty() {
+        let _ = writeln!(out, "fonts: {}", fonts.join(", "));
+    }
+    let fills: Vec<String> = stats["topFills"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|pair| format!("{}×{}", pair[0].as_str().unwrap_or(""), pair[1]))
+        .collect();
+    if !fills.is_empty() {
+        let _ = writeln!(out, "fills: {}", fills.join(", "));
+    }
+}
+
+fn write_node(out: &mut String, items: &[Placed], node: &Node, indent: usize) {
+    let item = &items[node.item];
+    let [x, y, w, h] = rounded_box(&item.bounds);
+    let _ = write!(
+        out,
+        "{:indent$}{}",
+        "",
+        kind(item.object),
+        indent = indent * 2
+    );
+    if let Some(name) = fabric::str_prop(item.object, "name") {
+        let _ = write!(out, " {:?}", name);
+    }
+    let _ = write!(
+        out,
+        " #{} [{},{} {}x{}]",
+        item.id().unwrap_or("?"),
+        x,
+        y,
+        w,
+        h
+    );
+    for (key, value) in styles(item.object) {
+        let _ = match value {
+            Value::String(s) if key == "text" => write!(out, " {:?}", s),
+            Value::Bool(true) => write!(out, " {}", key),
+            Value::String(s) if s.contains(' ') => write!(out, " {}={:?}", key, s),
+            Value::String(s) => write!(out, " {}={}", key, s),
+            other => write!(out, " {}={}", key, other),
+        };
+    }
+    if node.hidden > 0 {
+        let _ = write!(out, " (+{} nested)", node.hidden);
+    }
+    out.push('\n');
+    for child in &node.children {
+        write_node(out, items, child, indent + 1);
+    }
+}
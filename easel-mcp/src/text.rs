@@ -0,0 +1,358 @@
+//! Text layout matching Fabric's line metrics for `IText`/`Textbox` objects,
+//! including per-character `styles`.
+
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+
+use crate::fabric;
+use crate::fonts::{self, FontBook, FontData};
+use crate::geometry;
+
+/// Fabric's `_fontSizeMult`: line box height per unit of font size.
+pub const FONT_SIZE_MULT: f64 = 1.13;
+/// Fabric's `_fontSizeFraction`: part of the line box below the baseline.
+pub const FONT_SIZE_FRACTION: f64 = 0.222;
+pub const DEFAULT_LINE_HEIGHT: f64 = 1.16;
+
+/// A stretch of one line drawn with a single font and style.
+#[derive(Clone)]
+pub struct TextRun {
+    pub text: String,
+    /// Offset of the run from the start of its line.
+    pub left: f64,
+    pub width: f64,
+    pub font: Option<Arc<FontData>>,
+    pub font_size: f64,
+    /// This run's `styles` overrides; empty when it uses the object's own style.
+    pub style: Map<String, Value>,
+}
+
+#[derive(Clone)]
+pub struct TextLine {
+    pub text: String,
+    /// Offset of the line start from the left edge of the text box.
+    pub left: f64,
+    /// Baseline position from the top of the text box.
+    pub baseline: f64,
+    pub width: f64,
+    pub runs: Vec<TextRun>,
+}
+
+pub struct TextLayout {
+    pub font: Option<Arc<FontData>>,
+    pub font_size: f64,
+    /// Extra space after each character, in pixels.
+    pub char_spacing: f64,
+    pub lines: Vec<TextLine>,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// A resolved character style, shared by every character that uses it.
+struct Span {
+    style: Map<String, Value>,
+    font: Option<Arc<FontData>>,
+    font_size: f64,
+}
+
+#[derive(Clone)]
+struct Glyph {
+    ch: char,
+    span: usize,
+    width: f64,
+}
+
+/// Lay out a Fabric text object: split (and for `Textbox`, wrap) lines, then
+/// position each one the way Fabric's `_renderTextCommon` does.
+pub fn layout(obj: &Value, fonts: &FontBook) -> TextLayout {
+    let font_size = fabric::num_prop(obj, "fontSize").unwrap_or(40.0);
+    let line_height = fabric::num_prop(obj, "lineHeight").unwrap_or(DEFAULT_LINE_HEIGHT);
+    // charSpacing is in thousandths of an em of the object's font size.
+    let char_spacing = fabric::num_prop(obj, "charSpacing").unwrap_or(0.0) * font_size / 1000.0;
+    let font = fonts.resolve_for(obj).and_then(|id| fonts.load(id));
+
+    let text = fabric::str_prop(obj, "text").unwrap_or("");
+    let styles = char_styles(obj);
+    let mut spans = vec![Span {
+        style: Map::new(),
+        font: font.clone(),
+        font_size,
+    }];
+
+    let wrap_width = match fabric::object_type(obj).as_str() {
+        "textbox" => fabric::num_prop(obj, "width"),
+        _ => None,
+    };
+    let mut lines: Vec<Vec<Glyph>> = Vec::new();
+    for (index, line) in text.split('\n').enumerate() {
+        let line_styles = styles.get(index);
+        let glyphs: Vec<Glyph> = line
+            .chars()
+            .enumerate()
+            .map(|(i, ch)| {
+                let style = line_styles.and_then(|s| s.get(i));
+                let span = match style {
+                    Some(style) if !style.is_empty() => span_for(obj, style, &mut spans, fonts),
+                    _ => 0,
+                };
+                let (s, glyph) = (&spans[span], ch.to_string());
+                let width = match &s.font {
+                    Some(font) => font.measure(&glyph, s.font_size, char_spacing),
+                    None => fonts::approximate_width(&glyph, s.font_size, char_spacing),
+                };
+                Glyph { ch, span, width }
+            })
+            .collect();
+        match wrap_width {
+            Some(width) => lines.extend(wrap_line(&glyphs, width)),
+            None => lines.push(glyphs),
+        }
+    }
+
+    // Fabric does not count the spacing after a line's last character.
+    let widths: Vec<f64> = lines
+        .iter()
+        .map(|glyphs| match glyphs.len() {
+            0 => 0.0,
+            _ => glyphs.iter().map(|g| g.width).sum::<f64>() - char_spacing,
+        })
+        .collect();
+    let content_width = widths.iter().cloned().fold(0.0, f64::max);
+    let box_width = fabric::num_prop(obj, "width").unwrap_or(content_width);
+    let align = fabric::str_prop(obj, "textAlign").unwrap_or("left");
+
+    let mut layout = TextLayout {
+        font,
+        font_size,
+        char_spacing,
+        lines: Vec::new(),
+        width: content_width,
+        height: 0.0,
+    };
+    let mut top = 0.0;
+    let mut last_box = 0.0;
+    for (glyphs, width) in lines.into_iter().zip(widths) {
+        let left = match align {
+            "center" => (box_width - width) / 2.0,
+            "right" => box_width - width,
+            _ => 0.0,
+        };
+        // The tallest character sets the line box; empty lines use the object's size.
+        let max_size = glyphs
+            .iter()
+            .map(|g| spans[g.span].font_size)
+            .reduce(f64::max)
+            .unwrap_or(font_size);
+        let line_box = max_size * FONT_SIZE_MULT;
+        layout.lines.push(TextLine {
+            text: glyphs.iter().map(|g| g.ch).collect(),
+            left,
+            baseline: top + line_box * (1.0 - FONT_SIZE_FRACTION),
+            width,
+            runs: runs(&glyphs, &spans),
+        });
+        top += line_box * line_height;
+        last_box = line_box;
+    }
+
+    // The last line does not get the extra line-height spacing.
+    layout.height = if layout.lines.is_empty() {
+        0.0
+    } else {
+        top - last_box * line_height + last_box
+    };
+    layout
+}
+
+/// Set `width` and `height` to the measured text so the file matches what
+/// Fabric computes on load. A Textbox keeps its width unless a single word
+/// is wider, as Fabric's `dynamicMinWidth` does.
+pub fn fit_to_content(obj: &mut Value, fonts: &FontBook) {
+    let layout = layout(obj, fonts);
+    let width = match fabric::object_type(obj).as_str() {
+        "textbox" => fabric::num_prop(obj, "width")
+            .unwrap_or(0.0)
+            .max(layout.width),
+        _ => layout.width,
+    };
+    obj["width"] = json!(geometry::round(width));
+    obj["height"] = json!(geometry::round(layout.height));
+}
+
+/// Index of the span for a character's style overrides, adding it if new.
+fn span_for(
+    obj: &Value,
+    style: &Map<String, Value>,
+    spans: &mut Vec<Span>,
+    fonts: &FontBook,
+) -> usize {
+    if let Some(index) = spans.iter().position(|s| &s.style == style) {
+        return index;
+    }
+    let mut merged = obj.clone();
+    for (key, value) in style {
+        merged[key.as_str()] = value.clone();
+    }
+    spans.push(Span {
+        style: style.clone(),
+        font: fonts.resolve_for(&merged).and_then(|id| fonts.load(id)),
+        font_size: fabric::num_prop(&merged, "fontSize").unwrap_or(40.0),
+    });
+    spans.len() - 1
+}
+
+/// Merge consecutive characters of the same span into runs.
+fn runs(glyphs: &[Glyph], spans: &[Span]) -> Vec<TextRun> {
+    let mut runs: Vec<TextRun> = Vec::new();
+    let mut left = 0.0;
+    let mut current = None;
+    for glyph in glyphs {
+        match runs.last_mut() {
+            Some(run) if current == Some(glyph.span) => {
+                run.text.push(glyph.ch);
+                run.width += glyph.width;
+            }
+            _ => {
+                let span = &spans[glyph.span];
+                runs.push(TextRun {
+                    text: glyph.ch.to_string(),
+                    left,
+                    width: glyph.width,
+                    font: span.font.clone(),
+                    font_size: span.font_size,
+                    style: span.style.clone(),
+                });
+                current = Some(glyph.span);
+            }
+        }
+        left += glyph.width;
+    }
+    runs
+}
+
+/// Greedy word wrap, as Fabric's `Textbox._wrapLine` does without
+/// `splitByGrapheme`: words longer than the box stay on their own line.
+fn wrap_line(glyphs: &[Glyph], width: f64) -> Vec<Vec<Glyph>> {
+    let sum = |glyphs: &[Glyph]| glyphs.iter().map(|g| g.width).sum::<f64>();
+    let mut lines = Vec::new();
+    let mut current: Vec<Glyph> = Vec::new();
+    let mut start = 0;
+    while start <= glyphs.len() {
+        let end = glyphs[start..]
+            .iter()
+            .position(|g| g.ch == ' ')
+            .map_or(glyphs.len(), |i| start + i);
+        let word = &glyphs[start..end];
+        if !current.is_empty() {
+            // The space before this word.
+            let space = &glyphs[start - 1];
+            if sum(&current) + space.width + sum(word) > width {
+                lines.push(std::mem::take(&mut current));
+            } else {
+                current.push(space.clone());
+            }
+        }
+        current.extend_from_slice(word);
+        start = end + 1;
+    }
+    lines.push(current);
+    lines
+}
+
+// ── Styles ──────────────────────────────────────────────────────────────────
+
+/// Per-character style overrides from Fabric's `styles`, indexed by source
+/// line (the text split on `\n`; wrapped Textbox lines share theirs) and
+/// character. Reads both the v6+ array form, whose offsets skip newlines,
+/// and the older `{line: {char: style}}` map.
+pub fn char_styles(obj: &Value) -> Vec<Vec<Map<String, Value>>> {
+    let text = fabric::str_prop(obj, "text").unwrap_or("");
+    let mut lines: Vec<Vec<Map<String, Value>>> = text
+        .split('\n')
+        .map(|line| vec![Map::new(); line.chars().count()])
+        .collect();
+
+    match obj.get("styles") {
+        Some(Value::Array(ranges)) => {
+            // Flat character offset at which each line starts.
+            let mut starts = Vec::with_capacity(lines.len());
+            let mut offset = 0;
+            for line in &lines {
+                starts.push(offset);
+                offset += line.len();
+            }
+            for range in ranges {
+                let start = range.get("start").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+                let end = range.get("end").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+                let Some(style) = range.get("style").and_then(|v| v.as_object()) else {
+                    continue;
+                };
+                for index in start..end.min(offset) {
+                    let line = starts.partition_point(|&s| s <= index) - 1;
+                    lines[line][index - starts[line]].extend(style.clone());
+                }
+            }
+        }
+        Some(Value::Object(by_line)) => {
+            for (line, chars) in by_line {
+                let Some(line) = line.parse::<usize>().ok().and_then(|l| lines.get_mut(l)) else {
+                    continue;
+                };
+                for (index, style) in chars.as_object().into_iter().flatten() {
+                    let slot = index.parse::<usize>().ok().and_then(|i| line.get_mut(i));
+                    if let (Some(slot), Some(style)) = (slot, style.as_object()) {
+                        slot.extend(style.clone());
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+    lines
+}
+
+/// Fabric's `stylesToArray`: per-character styles as sorted `{start, end,
+/// style}` ranges over the text with newlines skipped.
+pub fn styles_to_array(lines: &[Vec<Map<String, Value>>]) -> Value {
+    let mut ranges: Vec<Value> = Vec::new();
+    let mut offset = 0;
+    let mut previous: Option<&Map<String, Value>> = None;
+    for line in lines {
+        for style in line {
+            if style.is_empty() {
+                previous = None;
+            } else if previous == Some(style) {
+                if let Some(last) = ranges.last_mut() {
+                    last["end"] = json!(offset + 1);
+                }
+            } else {
+                ranges.push(json!({"start": offset, "end": offset + 1, "style": style}));
+                previous = Some(style);
+            }
+            offset += 1;
+        }
+    }
+    Value::Array(ranges)
+}
+
+/// Build Fabric's `styles` from ranges whose offsets count every character
+/// of `text`, newlines included. Later ranges win where they overlap.
+pub fn styles_from_ranges(text: &str, ranges: &[(usize, usize, Map<String, Value>)]) -> Value {
+    let mut lines: Vec<Vec<Map<String, Value>>> = vec![Vec::new()];
+    for (index, ch) in text.chars().enumerate() {
+        if ch == '\n' {
+            lines.push(Vec::new());
+            continue;
+        }
+        let mut style = Map::new();
+        for (start, end, overrides) in ranges {
+            if (*start..*end).contains(&index) {
+                style.extend(overrides.clone());
+            }
+        }
+        if let Some(line) = lines.last_mut() {
+            line.push(style);
+        }
+    }
+    styles_to_array(&lines)
+}
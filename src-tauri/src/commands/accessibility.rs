@@ -0,0 +1,17 @@
+use easel_mcp::accessibility::{self, AccessibilityReport, AuditOptions};
+use tauri::State;
+
+use crate::easel;
+use crate::state::AppState;
+
+#[tauri::command]
+pub fn audit_accessibility(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    options: Option<AuditOptions>,
+) -> Result<AccessibilityReport, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let easel_file = easel::EaselFile::load(&path)?;
+
+    accessibility::audit(&easel_file.canvas, &options.unwrap_or_default())
+}
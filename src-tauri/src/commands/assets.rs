@@ -0,0 +1,79 @@
+use easel_mcp::assets::{self, Asset, AssetStore};
+use easel_mcp::attribution::Actor;
+use easel_mcp::oplog;
+use serde_json::Value;
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::db;
+use crate::easel;
+use crate::state::AppState;
+
+fn store_for(state: &AppState, canvas_id: &str) -> AssetStore {
+    AssetStore::for_canvas(&easel::canvas_easel_path(&state.app_data_dir, canvas_id))
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn add_image(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    image_path: String,
+    x: f64,
+    y: f64,
+    width: Option<f64>,
+    height: Option<f64>,
+    name: Option<String>,
+) -> Result<Value, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let store = AssetStore::for_canvas(&path);
+    let asset = store.add_file(&PathBuf::from(&image_path))?;
+    let obj = assets::image_object(&store, &asset, x, y, width, height, name);
+
+    let mut easel_file = easel::EaselFile::load(&path)?;
+    easel_file.objects_mut().push(obj.clone());
+    easel_file.touch();
+    state
+        .watcher
+        .own_write(&path, || easel_file.save_as(&path, &Actor::user()))?;
+
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::canvas::update_canvas_timestamp(&conn, &canvas_id).map_err(|e| e.to_string())?;
+
+    Ok(obj)
+}
+
+#[tauri::command]
+pub fn list_assets(state: State<'_, AppState>, canvas_id: String) -> Result<Vec<Asset>, String> {
+    store_for(&state, &canvas_id).list()
+}
+
+#[tauri::command]
+pub fn get_asset_path(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    asset_id: String,
+) -> Result<String, String> {
+    let path = store_for(&state, &canvas_id).path_of(&asset_id)?;
+    Ok(path.display().to_string())
+}
+
+#[tauri::command]
+pub fn gc_assets(state: State<'_, AppState>, canvas_id: String) -> Result<Vec<String>, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let easel_file = easel::EaselFile::load(&path)?;
+    let history = oplog::read(&path)?;
+    AssetStore::for_canvas(&path).collect_garbage(&easel_file.canvas, &history)
+}
+
+#[tauri::command]
+pub fn export_assets(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    destination: String,
+    ids: Option<Vec<String>>,
+) -> Result<Vec<String>, String> {
+    let written = store_for(&state, &canvas_id)
+        .export(&ids.unwrap_or_default(), &PathBuf::from(destination))?;
+    Ok(written.iter().map(|p| p.display().to_string()).collect())
+}
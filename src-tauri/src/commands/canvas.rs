@@ -1,3 +1,9 @@
+use easel_mcp::assets::AssetStore;
+use easel_mcp::attribution::{self, Actor};
+use easel_mcp::boolean::{self, BooleanOp};
+use easel_mcp::import::svg::{self, SvgImportOptions};
+use easel_mcp::import::{self, WhiteboardFormat};
+use serde::Serialize;
 use tauri::State;
 use uuid::Uuid;
 
@@ -44,8 +50,8 @@ pub fn list_canvases(state: State<'_, AppState>) -> Result<Vec<db::canvas::Canva
                         &id,
                         &easel_file.name,
                         count + offset,
-                        &easel_file.created_at,
-                        &easel_file.updated_at,
+                        &easel::timestamp(&easel_file.created_at),
+                        &easel::timestamp(&easel_file.updated_at),
                     );
                     offset += 1;
                 }
@@ -68,9 +74,9 @@ pub fn create_canvas(state: State<'_, AppState>, name: String) -> Result<db::can
     let meta = db::canvas::create_canvas(&conn, &id, &name, count).map_err(|e| e.to_string())?;
 
     // Create an empty .easel file
-    let easel = easel::EaselFile::new(&name);
+    let mut easel = easel::EaselFile::new(&name);
     let path = easel::canvas_easel_path(&state.app_data_dir, &id);
-    easel.save(&path)?;
+    state.watcher.own_write(&path, || easel.save(&path))?;
 
     Ok(meta)
 }
@@ -85,7 +91,7 @@ pub fn rename_canvas(state: State<'_, AppState>, id: String, name: String) -> Re
     if path.exists() {
         let mut easel_file = easel::EaselFile::load(&path)?;
         easel_file.name = name;
-        easel_file.save(&path)?;
+        state.watcher.own_write(&path, || easel_file.save(&path))?;
     }
 
     Ok(())
@@ -93,14 +99,19 @@ pub fn rename_canvas(state: State<'_, AppState>, id: String, name: String) -> Re
 
 #[tauri::command]
 pub fn delete_canvas(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    state.sync_server.unshare(&id)?;
     let conn = state.db.lock().map_err(|e| e.to_string())?;
     db::canvas::delete_canvas(&conn, &id).map_err(|e| e.to_string())?;
 
-    // Also delete the .easel file
+    // Also delete the .easel file and its assets
     let path = easel::canvas_easel_path(&state.app_data_dir, &id);
     if path.exists() {
-        std::fs::remove_file(&path).map_err(|e| e.to_string())?;
+        state
+            .watcher
+            .own_write(&path, || std::fs::remove_file(&path).map_err(|e| e.to_string()))?;
     }
+    state.watcher.unwatch_canvas(&id);
+    easel_mcp::easel::remove_sidecars(&path)?;
 
     Ok(())
 }
@@ -112,7 +123,7 @@ pub fn import_easel_file(state: State<'_, AppState>, file_path: String) -> Resul
         return Err(format!("File not found: {}", file_path));
     }
 
-    let easel_file = easel::EaselFile::load(&source)?;
+    let mut easel_file = easel::EaselFile::load(&source)?;
 
     let conn = state.db.lock().map_err(|e| e.to_string())?;
     let id = Uuid::new_v4().to_string();
@@ -123,13 +134,145 @@ pub fn import_easel_file(state: State<'_, AppState>, file_path: String) -> Resul
     let meta = db::canvas::create_canvas(&conn, &id, &easel_file.name, count)
         .map_err(|e| e.to_string())?;
 
-    // Copy the .easel file to the canvases directory
+    // Copy the .easel file to the canvases directory, and keep following the
+    // source so edits made to it there show up in the app.
     let dest = easel::canvas_easel_path(&state.app_data_dir, &id);
-    easel_file.save(&dest)?;
+    easel::copy_assets(&source, &dest, &easel_file.canvas)?;
+    state.watcher.own_write(&dest, || easel_file.save(&dest))?;
+
+    let source = source.canonicalize().unwrap_or(source);
+    db::canvas::set_canvas_source(&conn, &id, source.to_str()).map_err(|e| e.to_string())?;
+    state.watcher.watch_source(source, id);
 
     Ok(meta)
 }
 
+#[tauri::command]
+pub fn import_whiteboard_file(
+    state: State<'_, AppState>,
+    file_path: String,
+    format: Option<WhiteboardFormat>,
+) -> Result<db::canvas::CanvasMeta, String> {
+    let source_path = std::path::PathBuf::from(&file_path);
+    let source = std::fs::read_to_string(&source_path)
+        .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+
+    let id = Uuid::new_v4().to_string();
+    let dest = easel::canvas_easel_path(&state.app_data_dir, &id);
+    let format = format.or_else(|| WhiteboardFormat::from_path(&source_path));
+    let scene = import::import_whiteboard(&source, format, Some(&AssetStore::for_canvas(&dest)))?;
+
+    let name = scene
+        .name
+        .or_else(|| source_path.file_stem().and_then(|s| s.to_str()).map(String::from))
+        .unwrap_or_else(|| "Untitled".to_string());
+    let mut easel_file = easel::EaselFile::new(&name);
+    if let Some(background) = scene.background {
+        easel_file.canvas["background"] = serde_json::Value::String(background);
+    }
+    easel_file.objects_mut().extend(scene.objects);
+    state.watcher.own_write(&dest, || easel_file.save(&dest))?;
+
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    let count: i32 = conn
+        .query_row("SELECT COUNT(*) FROM canvases", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    db::canvas::create_canvas(&conn, &id, &name, count).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn export_canvas_bundle(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    destination: String,
+    thumbnail: Option<Vec<u8>>,
+) -> Result<(), String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let mut easel_file = easel::EaselFile::load(&path)?;
+    easel_file.save_bundle(
+        &std::path::PathBuf::from(destination),
+        &AssetStore::for_canvas(&path),
+        thumbnail.as_deref(),
+    )
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SvgImport {
+    pub ids: Vec<String>,
+    /// Elements that were skipped, such as `<use>` of a missing id.
+    pub warnings: Vec<String>,
+}
+
+#[tauri::command]
+pub fn import_svg_file(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    file_path: String,
+    x: Option<f64>,
+    y: Option<f64>,
+) -> Result<SvgImport, String> {
+    let source = std::fs::read_to_string(&file_path)
+        .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+    let options = SvgImportOptions {
+        offset_x: x.unwrap_or(0.0),
+        offset_y: y.unwrap_or(0.0),
+    };
+    let imported = svg::import_svg(&source, &options)?;
+    let ids: Vec<String> = imported
+        .objects
+        .iter()
+        .filter_map(|o| o.get("id").and_then(|v| v.as_str()).map(String::from))
+        .collect();
+
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let mut easel_file = easel::EaselFile::load(&path)?;
+    easel_file.objects_mut().extend(imported.objects);
+    easel_file.touch();
+    state
+        .watcher
+        .own_write(&path, || easel_file.save_as(&path, &Actor::user()))?;
+
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::canvas::update_canvas_timestamp(&conn, &canvas_id).map_err(|e| e.to_string())?;
+
+    Ok(SvgImport {
+        ids,
+        warnings: imported.warnings,
+    })
+}
+
+/// Combine top-level shapes into one Path (union, subtract, intersect or
+/// exclude) and return the new object.
+#[tauri::command]
+pub fn boolean_objects(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    ids: Vec<String>,
+    operation: BooleanOp,
+    name: Option<String>,
+    keep_originals: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let mut easel_file = easel::EaselFile::load(&path)?;
+    let result = boolean::apply_to_canvas(
+        &mut easel_file.canvas,
+        &ids,
+        operation,
+        name.as_deref(),
+        keep_originals.unwrap_or(false),
+    )?;
+    easel_file.touch();
+    state
+        .watcher
+        .own_write(&path, || easel_file.save_as(&path, &Actor::user()))?;
+
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::canvas::update_canvas_timestamp(&conn, &canvas_id).map_err(|e| e.to_string())?;
+
+    Ok(result)
+}
+
 #[tauri::command]
 pub fn get_canvas_state(state: State<'_, AppState>, canvas_id: String) -> Result<Option<db::canvas::CanvasState>, String> {
     let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
@@ -149,7 +292,9 @@ pub fn get_canvas_state(state: State<'_, AppState>, canvas_id: String) -> Result
         canvas_json,
         zoom: easel_file.viewport.zoom,
         viewport_transform,
-        updated_at: easel_file.updated_at,
+        updated_at: easel::timestamp(&easel_file.updated_at),
+        revision: easel_file.revision,
+        heads: easel_file.heads,
     }))
 }
 
@@ -160,7 +305,9 @@ pub fn save_canvas_state(
     canvas_json: String,
     zoom: f64,
     viewport_transform: String,
-) -> Result<(), String> {
+    revision: Option<u64>,
+    heads: Option<Vec<String>>,
+) -> Result<db::canvas::SavedCanvas, String> {
     let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
 
     let canvas: serde_json::Value = serde_json::from_str(&canvas_json)
@@ -180,14 +327,38 @@ pub fn save_canvas_state(
         easel::EaselFile::new(&name)
     };
 
-    easel_file.canvas = canvas;
+    // Merge with agent edits made since the state the frontend loaded. Without
+    // heads, save against the loaded revision so such an edit is reported as
+    // a conflict instead of being overwritten
+    match heads {
+        Some(heads) if !heads.is_empty() => easel_file.heads = heads,
+        _ => {
+            easel_file.heads.clear();
+            if let Some(revision) = revision {
+                easel_file.revision = revision;
+            }
+        }
+    }
+    easel_file.canvas = canvas.clone();
     easel_file.viewport.zoom = zoom;
     easel_file.viewport.transform = transform;
-    easel_file.save(&path)?;
+    state
+        .watcher
+        .own_write(&path, || easel_file.save_as(&path, &Actor::user()))?;
 
     // Update timestamp in canvases table
     let conn = state.db.lock().map_err(|e| e.to_string())?;
     db::canvas::update_canvas_timestamp(&conn, &canvas_id).map_err(|e| e.to_string())?;
 
-    Ok(())
+    // The app doesn't keep the attribution stamps, so they don't count
+    let canvas_json = if attribution::without_stamps(&easel_file.canvas) == canvas {
+        None
+    } else {
+        Some(serde_json::to_string(&easel_file.canvas).map_err(|e| e.to_string())?)
+    };
+    Ok(db::canvas::SavedCanvas {
+        revision: easel_file.revision,
+        heads: easel_file.heads,
+        canvas_json,
+    })
 }
@@ -0,0 +1,27 @@
+use easel_mcp::codegen::{self, CodeTarget, CodegenOptions, GeneratedCode, LayoutMode};
+use tauri::State;
+
+use crate::easel;
+use crate::state::AppState;
+
+#[tauri::command]
+pub fn generate_code(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    frame_id: String,
+    target: Option<CodeTarget>,
+    layout: Option<LayoutMode>,
+    tailwind: Option<bool>,
+    component_name: Option<String>,
+) -> Result<GeneratedCode, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let easel_file = easel::EaselFile::load(&path)?;
+
+    let options = CodegenOptions {
+        target: target.unwrap_or_default(),
+        layout: layout.unwrap_or_default(),
+        tailwind: tailwind.unwrap_or(false),
+        component_name,
+    };
+    codegen::generate(&easel_file.canvas, &frame_id, &options)
+}
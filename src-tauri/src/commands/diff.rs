@@ -0,0 +1,42 @@
+use easel_mcp::diff::{self, CanvasDiff};
+use easel_mcp::easel::EaselFile;
+use serde::Serialize;
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::easel;
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffResult {
+    #[serde(flatten)]
+    pub diff: CanvasDiff,
+    /// The same diff as a human-readable report.
+    pub report: String,
+}
+
+/// A canvas id from the canvases directory, or else a path to a `.easel` file.
+fn resolve(state: &AppState, canvas: &str) -> PathBuf {
+    let path = easel::canvas_easel_path(&state.app_data_dir, canvas);
+    if path.exists() {
+        path
+    } else {
+        PathBuf::from(canvas)
+    }
+}
+
+/// Compare two versions of a canvas, each given as a canvas id or a file path.
+#[tauri::command]
+pub fn diff_canvases(
+    state: State<'_, AppState>,
+    before: String,
+    after: String,
+) -> Result<DiffResult, String> {
+    let before = EaselFile::load(&resolve(&state, &before))?;
+    let after = EaselFile::load(&resolve(&state, &after))?;
+
+    let diff = diff::diff_files(&before, &after);
+    let report = diff::report(&diff);
+    Ok(DiffResult { diff, report })
+}
@@ -0,0 +1,151 @@
+use easel_mcp::attribution::{self, Actor, ActorKind, ActorSummary, EditQuery, RevertReport};
+use easel_mcp::oplog::{self, Batch};
+use serde::Serialize;
+use tauri::State;
+
+use crate::db;
+use crate::easel;
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasEdits {
+    /// Batches from the canvas's operation log, one per save.
+    pub edits: Vec<Batch>,
+    /// Everyone who has edited the canvas.
+    pub actors: Vec<ActorSummary>,
+}
+
+/// Who changed what in a canvas, optionally only one actor's edits or one
+/// object's changes.
+#[tauri::command]
+pub fn list_canvas_edits(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    actor: Option<String>,
+    object_id: Option<String>,
+) -> Result<CanvasEdits, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let batches = oplog::read(&path)?;
+    let query = EditQuery {
+        actor,
+        object_id,
+        since: None,
+    };
+    Ok(CanvasEdits {
+        edits: attribution::query(&batches, &query, false),
+        actors: attribution::actors(&batches),
+    })
+}
+
+/// Revert one actor's edits to a canvas, leaving objects others changed
+/// since. The caller reloads the canvas to show the result.
+#[tauri::command]
+pub fn revert_canvas_edits(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    actor: String,
+) -> Result<RevertReport, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let mut easel_file = easel::EaselFile::load(&path)?;
+    let batches = oplog::read(&path)?;
+    if !batches
+        .iter()
+        .any(|b| b.actor.as_ref().is_some_and(|a| a.matches(&actor)))
+    {
+        return Err(format!("No edits by {} in the operation log", actor));
+    }
+
+    let report = attribution::revert(&mut easel_file.canvas, &batches, &actor);
+    if !report.reverted.is_empty() {
+        easel_file.touch();
+        state
+            .watcher
+            .own_write(&path, || easel_file.save_as(&path, &Actor::user()))?;
+
+        let conn = state.db.lock().map_err(|e| e.to_string())?;
+        db::canvas::update_canvas_timestamp(&conn, &canvas_id).map_err(|e| e.to_string())?;
+    }
+    Ok(report)
+}
+
+/// The canvas as it was after the save that produced `revision` (the latest
+/// without one), replayed from its operation log.
+#[tauri::command]
+pub fn replay_canvas(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    revision: Option<u64>,
+) -> Result<serde_json::Value, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    oplog::replay(&path, revision)
+}
+
+/// Undo the user's latest saved edit to a canvas, whoever's session saved it.
+/// Saves after `revision` are left out: the caller's in-memory history
+/// already stepped back through them. Returns the canvas to show, or none
+/// when there is nothing to undo.
+#[tauri::command]
+pub fn undo_canvas_edit(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    revision: Option<u64>,
+) -> Result<Option<db::canvas::CanvasState>, String> {
+    step_history(&state, canvas_id, revision, false)
+}
+
+/// Redo the user's edit most recently undone by `undo_canvas_edit`.
+#[tauri::command]
+pub fn redo_canvas_edit(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    revision: Option<u64>,
+) -> Result<Option<db::canvas::CanvasState>, String> {
+    step_history(&state, canvas_id, revision, true)
+}
+
+fn step_history(
+    state: &AppState,
+    canvas_id: String,
+    revision: Option<u64>,
+    redo: bool,
+) -> Result<Option<db::canvas::CanvasState>, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut easel_file = easel::EaselFile::load(&path)?;
+    let batches: Vec<Batch> = oplog::read(&path)?
+        .into_iter()
+        .filter(|b| revision.is_none_or(|r| b.revision <= r))
+        .collect();
+
+    let by_user = |a: &Actor| a.kind == ActorKind::User;
+    let stepped = if redo {
+        oplog::redo(&mut easel_file.canvas, &batches, by_user)?
+    } else {
+        oplog::undo(&mut easel_file.canvas, &batches, by_user)?
+    };
+    let Some((step, _)) = stepped else {
+        return Ok(None);
+    };
+    easel_file.touch();
+    // The caller shows the result, so the watcher needn't reload it
+    state
+        .watcher
+        .own_write(&path, || easel_file.save_step(&path, &Actor::user(), step))?;
+
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::canvas::update_canvas_timestamp(&conn, &canvas_id).map_err(|e| e.to_string())?;
+
+    Ok(Some(db::canvas::CanvasState {
+        canvas_json: serde_json::to_string(&easel_file.canvas).map_err(|e| e.to_string())?,
+        zoom: easel_file.viewport.zoom,
+        viewport_transform: serde_json::to_string(&easel_file.viewport.transform)
+            .map_err(|e| e.to_string())?,
+        updated_at: easel::timestamp(&easel_file.updated_at),
+        revision: easel_file.revision,
+        heads: easel_file.heads,
+        canvas_id,
+    }))
+}
@@ -0,0 +1,31 @@
+use easel_mcp::assets::AssetStore;
+use easel_mcp::export::pdf::{self, PdfOptions, PdfSummary};
+use easel_mcp::export::ExportScope;
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::easel;
+use crate::state::AppState;
+
+#[tauri::command]
+pub fn export_pdf(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    output_path: String,
+    scope: Option<ExportScope>,
+    ids: Option<Vec<String>>,
+) -> Result<PdfSummary, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let easel_file = easel::EaselFile::load(&path)?;
+
+    let options = PdfOptions {
+        scope: scope.unwrap_or_default(),
+        ids: ids.unwrap_or_default(),
+    };
+    pdf::write_pdf(
+        &easel_file.canvas,
+        &options,
+        Some(&AssetStore::for_canvas(&path)),
+        &PathBuf::from(output_path),
+    )
+}
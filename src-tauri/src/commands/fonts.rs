@@ -0,0 +1,21 @@
+use easel_mcp::assets::AssetStore;
+use easel_mcp::fonts::{FontBook, FontFamily};
+use tauri::State;
+
+use crate::easel;
+use crate::state::AppState;
+
+/// Font families available to the backend for measurement and export; with a
+/// canvas, fonts its bundle carries are included.
+#[tauri::command]
+pub fn list_fonts(
+    state: State<'_, AppState>,
+    canvas_id: Option<String>,
+) -> Result<Vec<FontFamily>, String> {
+    let bundled = canvas_id.and_then(|id| {
+        let path = easel::canvas_easel_path(&state.app_data_dir, &id);
+        FontBook::with_bundled(&AssetStore::for_canvas(&path))
+    });
+    let book = bundled.as_ref().unwrap_or_else(|| FontBook::system());
+    Ok(book.families())
+}
@@ -0,0 +1,17 @@
+use easel_mcp::lint::{self, LintConfig, LintReport};
+use tauri::State;
+
+use crate::easel;
+use crate::state::AppState;
+
+#[tauri::command]
+pub fn lint_canvas(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    config: Option<LintConfig>,
+) -> Result<LintReport, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let easel_file = easel::EaselFile::load(&path)?;
+
+    lint::lint(&easel_file.canvas, &config.unwrap_or_default())
+}
@@ -0,0 +1,52 @@
+use easel_mcp::attribution::Actor;
+use easel_mcp::merge::{self, MergeConflict, Side};
+use serde::Serialize;
+use tauri::State;
+
+use crate::db;
+use crate::easel;
+use crate::state::AppState;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasMerge {
+    pub canvas_json: String,
+    pub revision: u64,
+    pub heads: Vec<String>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Merge the frontend's unsaved edits (`canvas_json`, made on top of
+/// `base_json`) with the canvas file as it is now, and save the result.
+#[tauri::command]
+pub fn merge_canvas_state(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    base_json: String,
+    canvas_json: String,
+    prefer: Option<Side>,
+) -> Result<CanvasMerge, String> {
+    let path = easel::canvas_easel_path(&state.app_data_dir, &canvas_id);
+    let base: serde_json::Value =
+        serde_json::from_str(&base_json).map_err(|e| format!("Invalid base JSON: {}", e))?;
+    let ours: serde_json::Value =
+        serde_json::from_str(&canvas_json).map_err(|e| format!("Invalid canvas JSON: {}", e))?;
+
+    let mut easel_file = easel::EaselFile::load(&path)?;
+    let merge = merge::merge_canvases(&base, &ours, &easel_file.canvas, prefer.unwrap_or_default());
+    easel_file.canvas = merge.merged;
+    easel_file.touch();
+    state
+        .watcher
+        .own_write(&path, || easel_file.save_as(&path, &Actor::user()))?;
+
+    let conn = state.db.lock().map_err(|e| e.to_string())?;
+    db::canvas::update_canvas_timestamp(&conn, &canvas_id).map_err(|e| e.to_string())?;
+
+    Ok(CanvasMerge {
+        canvas_json: serde_json::to_string(&easel_file.canvas).map_err(|e| e.to_string())?,
+        revision: easel_file.revision,
+        heads: easel_file.heads,
+        conflicts: merge.conflicts,
+    })
+}
@@ -1,2 +1,12 @@
+pub mod accessibility;
+pub mod assets;
 pub mod canvas;
 pub mod chat;
+pub mod codegen;
+pub mod diff;
+pub mod edits;
+pub mod export;
+pub mod fonts;
+pub mod lint;
+pub mod merge;
+pub mod sync;
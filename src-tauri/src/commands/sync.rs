@@ -0,0 +1,30 @@
+use tauri::State;
+
+use crate::state::AppState;
+use crate::sync_server::SharedCanvas;
+
+/// Share a canvas with other Easel instances on the local network, starting
+/// the sync server on `port` (any free port by default) if needed.
+#[tauri::command]
+pub fn share_canvas(
+    state: State<'_, AppState>,
+    canvas_id: String,
+    port: Option<u16>,
+) -> Result<SharedCanvas, String> {
+    state
+        .sync_server
+        .share(&state.app_data_dir, &state.watcher, &canvas_id, port)
+}
+
+#[tauri::command]
+pub fn stop_sharing_canvas(state: State<'_, AppState>, canvas_id: String) -> Result<(), String> {
+    state.sync_server.unshare(&canvas_id)
+}
+
+#[tauri::command]
+pub fn get_shared_canvas(
+    state: State<'_, AppState>,
+    canvas_id: String,
+) -> Result<Option<SharedCanvas>, String> {
+    state.sync_server.shared_canvas(&canvas_id)
+}
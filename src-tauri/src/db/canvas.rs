@@ -20,6 +20,18 @@ pub struct CanvasState {
     pub zoom: f64,
     pub viewport_transform: String,
     pub updated_at: String,
+    pub revision: u64,
+    pub heads: Vec<String>,
+}
+
+/// Return type for save_canvas_state command
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedCanvas {
+    pub revision: u64,
+    pub heads: Vec<String>,
+    /// The canvas after merging with concurrent edits, if that changed it.
+    pub canvas_json: Option<String>,
 }
 
 pub fn list_canvases(conn: &Connection) -> Result<Vec<CanvasMeta>, rusqlite::Error> {
@@ -118,3 +130,19 @@ pub fn update_canvas_timestamp(conn: &Connection, id: &str) -> Result<(), rusqli
     )?;
     Ok(())
 }
+
+/// Remember the file a canvas was imported from, so edits to it are picked up.
+pub fn set_canvas_source(conn: &Connection, id: &str, source_path: Option<&str>) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "UPDATE canvases SET source_path = ?1 WHERE id = ?2",
+        params![source_path, id],
+    )?;
+    Ok(())
+}
+
+/// (canvas id, source path) for every canvas imported from a file.
+pub fn list_canvas_sources(conn: &Connection) -> Result<Vec<(String, String)>, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT id, source_path FROM canvases WHERE source_path IS NOT NULL")?;
+    let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect()
+}
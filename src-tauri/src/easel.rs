@@ -1,120 +1,46 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use easel_mcp::assets::{self, AssetStore};
+use easel_mcp::easel::{parse_timestamp, ViewportState};
 use rusqlite::Connection;
-use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct EaselFile {
-    pub format_version: u32,
-    pub name: String,
-    pub canvas: Value,
-    pub viewport: ViewportState,
-    pub created_at: String,
-    pub updated_at: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct ViewportState {
-    pub zoom: f64,
-    pub transform: Vec<f64>,
-}
-
-impl EaselFile {
-    pub fn new(name: &str) -> Self {
-        let now = chrono_now();
-        Self {
-            format_version: 1,
-            name: name.to_string(),
-            canvas: serde_json::json!({"version": "7.0.0", "objects": []}),
-            viewport: ViewportState {
-                zoom: 1.0,
-                transform: vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
-            },
-            created_at: now.clone(),
-            updated_at: now,
-        }
-    }
-
-    pub fn load(path: &Path) -> Result<Self, String> {
-        let content = fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
-        serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
-    }
-
-    pub fn save(&self, path: &Path) -> Result<(), String> {
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create directory: {}", e))?;
-        }
-        let json = serde_json::to_string_pretty(self)
-            .map_err(|e| format!("Failed to serialize: {}", e))?;
-
-        // Atomic write: write to .tmp then rename
-        let tmp_path = path.with_extension("easel.tmp");
-        fs::write(&tmp_path, &json)
-            .map_err(|e| format!("Failed to write temp file: {}", e))?;
-        fs::rename(&tmp_path, path)
-            .map_err(|e| format!("Failed to rename temp file: {}", e))?;
-        Ok(())
+pub use easel_mcp::easel::EaselFile;
+
+/// Copy the assets `canvas` references from `source`, next to it or embedded
+/// in its bundle, to next to `dest`.
+pub fn copy_assets(source: &Path, dest: &Path, canvas: &Value) -> Result<(), String> {
+    let source_store = AssetStore::for_canvas(source);
+    let asset_ids: Vec<String> = assets::referenced_assets(canvas)
+        .into_iter()
+        .filter(|asset_id| source_store.contains(asset_id))
+        .collect();
+    if !asset_ids.is_empty() {
+        source_store.export(&asset_ids, AssetStore::for_canvas(dest).dir())?;
     }
+    Ok(())
 }
 
-fn chrono_now() -> String {
-    // ISO 8601 timestamp without external chrono dependency
-    // We use the same format as SQLite's datetime('now')
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-    // Format as UTC datetime string
-    let secs_per_day = 86400u64;
-    let days = now / secs_per_day;
-    let rem = now % secs_per_day;
-    let hours = rem / 3600;
-    let minutes = (rem % 3600) / 60;
-    let seconds = rem % 60;
-
-    // Days since 1970-01-01
-    let mut y = 1970i64;
-    let mut remaining_days = days as i64;
-    loop {
-        let days_in_year = if is_leap(y) { 366 } else { 365 };
-        if remaining_days < days_in_year {
-            break;
-        }
-        remaining_days -= days_in_year;
-        y += 1;
-    }
-    let month_days: &[i64] = if is_leap(y) {
-        &[31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
+/// Replace the document at `dest` with the one at `source`, keeping the
+/// viewport and creation time of the existing copy.
+pub fn sync_from_source(source: &Path, dest: &Path) -> Result<(), String> {
+    let mut easel_file = EaselFile::load(source)?;
+    if let Ok(existing) = EaselFile::load(dest) {
+        easel_file.viewport = existing.viewport;
+        easel_file.created_at = existing.created_at;
+        easel_file.revision = existing.revision;
+        easel_file.heads = existing.heads;
     } else {
-        &[31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
-    };
-    let mut m = 0usize;
-    for &md in month_days {
-        if remaining_days < md {
-            break;
-        }
-        remaining_days -= md;
-        m += 1;
+        easel_file.heads.clear();
     }
-    format!(
-        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
-        y,
-        m + 1,
-        remaining_days + 1,
-        hours,
-        minutes,
-        seconds
-    )
+    copy_assets(source, dest, &easel_file.canvas)?;
+    easel_file.save(dest)
 }
 
-fn is_leap(y: i64) -> bool {
-    (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
+/// A file timestamp as the app shows and stores it: UTC, to the second.
+pub fn timestamp(at: &DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
 }
 
 /// Path to the canvases directory
@@ -194,14 +120,11 @@ pub fn migrate_canvas_states_to_files(conn: &Connection, app_data_dir: &Path) {
         let transform: Vec<f64> = serde_json::from_str(&viewport_transform)
             .unwrap_or_else(|_| vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
 
-        let easel = EaselFile {
-            format_version: 1,
-            name,
-            canvas,
-            viewport: ViewportState { zoom, transform },
-            created_at,
-            updated_at,
-        };
+        let mut easel = EaselFile::new(&name);
+        easel.canvas = canvas;
+        easel.viewport = ViewportState { zoom, transform };
+        easel.created_at = parse_timestamp(&created_at).unwrap_or(easel.created_at);
+        easel.updated_at = parse_timestamp(&updated_at).unwrap_or(easel.updated_at);
 
         if let Err(e) = easel.save(&easel_path) {
             eprintln!("Warning: failed to migrate canvas {}: {}", canvas_id, e);
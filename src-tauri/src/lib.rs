@@ -3,9 +3,11 @@ mod db;
 mod easel;
 mod migrations;
 mod state;
+mod sync_server;
+mod watcher;
 
 use state::AppState;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -30,9 +32,18 @@ pub fn run() {
             migrations::run_migrations(&conn)
                 .expect("failed to run migrations");
 
+            // Reload open canvases when easel-mcp (or anything else) edits them
+            let watcher = Arc::new(watcher::CanvasWatcher::default());
+            for (canvas_id, source) in db::canvas::list_canvas_sources(&conn).unwrap_or_default() {
+                watcher.watch_source(source.into(), canvas_id);
+            }
+            watcher.clone().spawn(app.handle().clone(), app_data_dir.clone());
+
             app.manage(AppState {
                 db: Mutex::new(conn),
                 app_data_dir,
+                watcher,
+                sync_server: sync_server::SyncServer::default(),
             });
 
             Ok(())
@@ -44,13 +55,37 @@ pub fn run() {
             commands::canvas::delete_canvas,
             commands::canvas::get_canvas_state,
             commands::canvas::save_canvas_state,
+            commands::merge::merge_canvas_state,
+            commands::diff::diff_canvases,
+            commands::edits::list_canvas_edits,
+            commands::edits::revert_canvas_edits,
+            commands::edits::replay_canvas,
+            commands::edits::undo_canvas_edit,
+            commands::edits::redo_canvas_edit,
+            commands::sync::share_canvas,
+            commands::sync::stop_sharing_canvas,
+            commands::sync::get_shared_canvas,
             commands::canvas::import_easel_file,
+            commands::canvas::import_svg_file,
+            commands::canvas::import_whiteboard_file,
+            commands::canvas::export_canvas_bundle,
+            commands::canvas::boolean_objects,
             commands::chat::create_chat_session,
             commands::chat::list_chat_sessions,
             commands::chat::delete_chat_session,
             commands::chat::save_chat_message,
             commands::chat::get_chat_messages,
             commands::chat::clear_chat_messages,
+            commands::codegen::generate_code,
+            commands::export::export_pdf,
+            commands::fonts::list_fonts,
+            commands::lint::lint_canvas,
+            commands::accessibility::audit_accessibility,
+            commands::assets::add_image,
+            commands::assets::list_assets,
+            commands::assets::get_asset_path,
+            commands::assets::gc_assets,
+            commands::assets::export_assets,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1 @@
+ALTER TABLE canvases ADD COLUMN source_path TEXT;
@@ -1,9 +1,14 @@
 use std::path::PathBuf;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 use rusqlite::Connection;
 
+use crate::sync_server::SyncServer;
+use crate::watcher::CanvasWatcher;
+
 pub struct AppState {
     pub db: Mutex<Connection>,
     pub app_data_dir: PathBuf,
+    pub watcher: Arc<CanvasWatcher>,
+    pub sync_server: SyncServer,
 }
@@ -0,0 +1,636 @@
+//! Shares canvases with other Easel instances on the local network.
+//!
+//! The owner shares a canvas, which starts a WebSocket server if none is
+//! running. Peers, the owner's own window included, connect to
+//! `ws://<host>:<port>/<canvas id>?token=<token>`, say `hello`, and get the
+//! canvas and everyone's presence back. From then on each peer sends
+//! object-level ops and its presence (cursor, selection); the server applies
+//! the ops to its copy, relays both to the other peers and saves the copy to
+//! the owner's `.easel` file. Edits that reach the file some other way, from
+//! an agent say, go out to the peers as a snapshot. No cloud service is
+//! involved, so this works on an offline network.
+
+use easel_mcp::attribution::{self, Actor};
+use easel_mcp::fabric;
+use easel_mcp::oplog;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::net::{IpAddr, TcpListener, TcpStream, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tungstenite::http::StatusCode;
+use tungstenite::Message;
+use uuid::Uuid;
+
+use crate::easel::{self, EaselFile};
+use crate::watcher::{self, CanvasWatcher, Stamp};
+
+/// How long a connection waits for its peer before passing on relayed messages.
+const READ_TIMEOUT: Duration = Duration::from_millis(20);
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+const PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An edit to one object, in Fabric JSON terms.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum ObjectOp {
+    /// Replace the object with this one's id wherever it is, or add it at
+    /// `index` in the top-level stack (on top when absent).
+    Upsert {
+        object: Value,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<usize>,
+    },
+    /// Remove the object, at any depth.
+    Remove { id: String },
+    /// Restack the listed top-level objects into this order, within the
+    /// slots they already occupy.
+    Reorder { ids: Vec<String> },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Presence {
+    pub peer_id: String,
+    pub name: String,
+    pub color: String,
+    /// Scene coordinates.
+    pub cursor: Option<Point>,
+    pub selection: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum ClientMessage {
+    Hello {
+        name: String,
+        #[serde(default)]
+        color: String,
+    },
+    Ops {
+        ops: Vec<ObjectOp>,
+    },
+    Presence {
+        #[serde(default)]
+        cursor: Option<Point>,
+        #[serde(default)]
+        selection: Vec<String>,
+    },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum ServerMessage<'a> {
+    #[serde(rename_all = "camelCase")]
+    Welcome {
+        peer_id: &'a str,
+        name: &'a str,
+        canvas: &'a Value,
+        peers: Vec<&'a Presence>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Ops {
+        peer_id: &'a str,
+        ops: &'a [ObjectOp],
+    },
+    Presence(&'a Presence),
+    #[serde(rename_all = "camelCase")]
+    Leave {
+        peer_id: &'a str,
+    },
+    Snapshot {
+        canvas: &'a Value,
+    },
+    Error {
+        message: &'a str,
+    },
+}
+
+fn encode(message: &ServerMessage) -> String {
+    serde_json::to_string(message).unwrap_or_default()
+}
+
+/// A shared canvas as peers see it.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedCanvas {
+    pub canvas_id: String,
+    pub port: u16,
+    /// Addresses to join at: the LAN one first, when there is one.
+    pub urls: Vec<String>,
+    pub peers: usize,
+}
+
+struct Peer {
+    outbox: Sender<String>,
+    presence: Presence,
+}
+
+struct Room {
+    path: PathBuf,
+    file: EaselFile,
+    peers: HashMap<String, Peer>,
+    /// Names of the peers whose ops were applied since the last save; the
+    /// save is attributed to them.
+    editors: Vec<String>,
+    /// Stamp of the file as last saved or loaded here.
+    stamp: Option<Stamp>,
+    /// Ops messages applied so far.
+    edits: u64,
+}
+
+impl Room {
+    fn broadcast(&self, except: Option<&str>, message: &ServerMessage) {
+        let text = encode(message);
+        for (peer_id, peer) in &self.peers {
+            if Some(peer_id.as_str()) != except {
+                let _ = peer.outbox.send(text.clone());
+            }
+        }
+    }
+
+    /// What the persist loop has to do for this room: save the ops applied
+    /// since the last save, or check the file for edits made some other way.
+    fn pending_sync(&self, canvas_id: &str) -> PendingSync {
+        let save = (!self.editors.is_empty()).then(|| {
+            let actor = Actor {
+                label: self.editors.join(", "),
+                ..Actor::user()
+            };
+            (self.file.clone(), actor)
+        });
+        PendingSync {
+            canvas_id: canvas_id.to_string(),
+            path: self.path.clone(),
+            save,
+            stamp: self.stamp,
+            edits: self.edits,
+        }
+    }
+
+    /// Take on the file a sync saved or reloaded, unless ops came in while
+    /// it ran; the next sync saves those, merging with the file. The peers
+    /// get a snapshot if that leaves the canvas different.
+    fn settle(&mut self, edits: u64, file: EaselFile, stamp: Option<Stamp>) {
+        if self.edits != edits {
+            return;
+        }
+        let before = std::mem::replace(&mut self.file, file);
+        self.editors.clear();
+        self.stamp = stamp;
+        // Stamping the save's authors alone is no news to the peers
+        let after = attribution::without_stamps(&self.file.canvas);
+        if after != attribution::without_stamps(&before.canvas) {
+            self.broadcast(
+                None,
+                &ServerMessage::Snapshot {
+                    canvas: &self.file.canvas,
+                },
+            );
+        }
+    }
+}
+
+/// A room's sync with its file, worked out under the rooms lock and run
+/// without it.
+struct PendingSync {
+    canvas_id: String,
+    path: PathBuf,
+    /// A copy of the canvas to save, with the peers to attribute it to.
+    save: Option<(EaselFile, Actor)>,
+    stamp: Option<Stamp>,
+    edits: u64,
+}
+
+impl PendingSync {
+    /// Save the copy, or reload the file if it changed on disk. Returns the
+    /// file as it now is, or none when there was nothing to do.
+    fn run(self, watcher: &CanvasWatcher) -> Option<EaselFile> {
+        let path = &self.path;
+        match self.save {
+            Some((mut file, actor)) => {
+                file.touch();
+                if let Err(e) = watcher.own_write(path, || file.save_as(path, &actor)) {
+                    eprintln!(
+                        "Warning: failed to save shared canvas {}: {}",
+                        path.display(),
+                        e
+                    );
+                    return None;
+                }
+                Some(file)
+            }
+            None if watcher::stamp(path) == self.stamp => None,
+            None => EaselFile::load(path)
+                .map_err(|e| eprintln!("Warning: failed to reload shared canvas: {}", e))
+                .ok(),
+        }
+    }
+}
+
+struct Shared {
+    token: String,
+    watcher: Arc<CanvasWatcher>,
+    rooms: Mutex<HashMap<String, Room>>,
+    stop: AtomicBool,
+}
+
+struct Running {
+    port: u16,
+    shared: Arc<Shared>,
+}
+
+/// The app's sync server; it runs while at least one canvas is shared.
+#[derive(Default)]
+pub struct SyncServer {
+    running: Mutex<Option<Running>>,
+}
+
+impl SyncServer {
+    /// Share `canvas_id`, first starting the server on `port` (any free port
+    /// when `None`) if it isn't running.
+    pub fn share(
+        &self,
+        app_data_dir: &Path,
+        watcher: &Arc<CanvasWatcher>,
+        canvas_id: &str,
+        port: Option<u16>,
+    ) -> Result<SharedCanvas, String> {
+        let mut running = self.running.lock().map_err(|e| e.to_string())?;
+        let already_shared = running.as_ref().is_some_and(|server| {
+            server
+                .shared
+                .rooms
+                .lock()
+                .is_ok_and(|rooms| rooms.contains_key(canvas_id))
+        });
+        let room = if already_shared {
+            None
+        } else {
+            Some(open_room(app_data_dir, watcher, canvas_id)?)
+        };
+
+        if running.is_none() {
+            *running = Some(start(watcher.clone(), port)?);
+        }
+        let server = running.as_ref().expect("sync server just started");
+        let mut rooms = server.shared.rooms.lock().map_err(|e| e.to_string())?;
+        if let Some(room) = room {
+            rooms.entry(canvas_id.to_string()).or_insert(room);
+        }
+        Ok(server.describe(canvas_id, rooms[canvas_id].peers.len()))
+    }
+
+    /// Stop sharing `canvas_id`, disconnecting its peers, and stop the
+    /// server if nothing else is shared.
+    pub fn unshare(&self, canvas_id: &str) -> Result<(), String> {
+        let mut running = self.running.lock().map_err(|e| e.to_string())?;
+        let Some(server) = running.as_ref() else {
+            return Ok(());
+        };
+        let mut rooms = server.shared.rooms.lock().map_err(|e| e.to_string())?;
+        let room = rooms.remove(canvas_id);
+        let empty = rooms.is_empty();
+        drop(rooms);
+        if let Some(room) = room {
+            room.pending_sync(canvas_id).run(&server.shared.watcher);
+        }
+        if empty {
+            server.shared.stop.store(true, Ordering::Relaxed);
+            *running = None;
+        }
+        Ok(())
+    }
+
+    pub fn shared_canvas(&self, canvas_id: &str) -> Result<Option<SharedCanvas>, String> {
+        let running = self.running.lock().map_err(|e| e.to_string())?;
+        let Some(server) = running.as_ref() else {
+            return Ok(None);
+        };
+        let rooms = server.shared.rooms.lock().map_err(|e| e.to_string())?;
+        Ok(rooms
+            .get(canvas_id)
+            .map(|room| server.describe(canvas_id, room.peers.len())))
+    }
+}
+
+fn open_room(
+    app_data_dir: &Path,
+    watcher: &CanvasWatcher,
+    canvas_id: &str,
+) -> Result<Room, String> {
+    let path = easel::canvas_easel_path(app_data_dir, canvas_id);
+    let mut file = EaselFile::load(&path)?;
+    // Start the file's merge history so the room's saves never conflict
+    if file.heads.is_empty() {
+        watcher.own_write(&path, || file.save(&path))?;
+    }
+    Ok(Room {
+        stamp: watcher::stamp(&path),
+        path,
+        file,
+        peers: HashMap::new(),
+        editors: Vec::new(),
+        edits: 0,
+    })
+}
+
+impl Running {
+    fn describe(&self, canvas_id: &str, peers: usize) -> SharedCanvas {
+        let target = format!("{}/{}?token={}", self.port, canvas_id, self.shared.token);
+        let mut urls: Vec<String> = lan_address()
+            .into_iter()
+            .map(|ip| format!("ws://{}:{}", ip, target))
+            .collect();
+        urls.push(format!("ws://127.0.0.1:{}", target));
+        SharedCanvas {
+            canvas_id: canvas_id.to_string(),
+            port: self.port,
+            urls,
+            peers,
+        }
+    }
+}
+
+fn start(watcher: Arc<CanvasWatcher>, port: Option<u16>) -> Result<Running, String> {
+    let listener = TcpListener::bind(("0.0.0.0", port.unwrap_or(0)))
+        .map_err(|e| format!("Failed to start sync server: {}", e))?;
+    let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to start sync server: {}", e))?;
+
+    let shared = Arc::new(Shared {
+        token: Uuid::new_v4().simple().to_string(),
+        watcher,
+        rooms: Mutex::new(HashMap::new()),
+        stop: AtomicBool::new(false),
+    });
+    let accepting = shared.clone();
+    thread::spawn(move || accept_loop(listener, accepting));
+    let persisting = shared.clone();
+    thread::spawn(move || persist_loop(persisting));
+    Ok(Running { port, shared })
+}
+
+fn accept_loop(listener: TcpListener, shared: Arc<Shared>) {
+    while !shared.stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let shared = shared.clone();
+                thread::spawn(move || serve(stream, &shared));
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_INTERVAL),
+            Err(e) => {
+                eprintln!("Warning: sync server stopped accepting: {}", e);
+                return;
+            }
+        }
+    }
+}
+
+fn persist_loop(shared: Arc<Shared>) {
+    while !shared.stop.load(Ordering::Relaxed) {
+        thread::sleep(PERSIST_INTERVAL);
+        let Ok(rooms) = shared.rooms.lock() else {
+            return;
+        };
+        let pending: Vec<PendingSync> = rooms
+            .iter()
+            .map(|(canvas_id, room)| room.pending_sync(canvas_id))
+            .collect();
+        drop(rooms);
+
+        // Peers keep editing while the files are written
+        for sync in pending {
+            let (canvas_id, path, edits) = (sync.canvas_id.clone(), sync.path.clone(), sync.edits);
+            let Some(file) = sync.run(&shared.watcher) else {
+                continue;
+            };
+            let stamp = watcher::stamp(&path);
+            let Ok(mut rooms) = shared.rooms.lock() else {
+                return;
+            };
+            if let Some(room) = rooms.get_mut(&canvas_id) {
+                room.settle(edits, file, stamp);
+            }
+        }
+    }
+}
+
+/// Run one peer's connection until either side closes it.
+fn serve(stream: TcpStream, shared: &Shared) {
+    if stream.set_nonblocking(false).is_err() {
+        return;
+    }
+    let mut canvas_id = None;
+    // tungstenite's callback type fixes the error
+    #[allow(clippy::result_large_err)]
+    let handshake = tungstenite::accept_hdr(stream, |request: &Request, response: Response| {
+        let id = shared
+            .authorize(request)
+            .map_err(|(status, reason)| reject(status, reason))?;
+        canvas_id = Some(id);
+        Ok(response)
+    });
+    // The handshake error keeps the callback, so settle it before reading canvas_id
+    let Ok(mut ws) = handshake else {
+        return;
+    };
+    let Some(canvas_id) = canvas_id else {
+        return;
+    };
+    if ws.get_ref().set_read_timeout(Some(READ_TIMEOUT)).is_err() {
+        return;
+    }
+
+    let peer_id = Uuid::new_v4().to_string();
+    let (outbox, inbox) = mpsc::channel();
+    // Handed to the room on hello; once the room drops it, the canvas is no
+    // longer shared and the connection closes.
+    let mut outbox = Some(outbox);
+    'session: loop {
+        match ws.read() {
+            Ok(Message::Text(text)) => {
+                let reply = shared
+                    .handle(&canvas_id, &peer_id, &mut outbox, &text)
+                    .unwrap_or_else(|e| Some(encode(&ServerMessage::Error { message: &e })));
+                if let Some(reply) = reply {
+                    if ws.send(Message::Text(reply)).is_err() {
+                        break;
+                    }
+                }
+            }
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => break,
+        }
+        loop {
+            match inbox.try_recv() {
+                Ok(text) => {
+                    if ws.send(Message::Text(text)).is_err() {
+                        break 'session;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    let _ = ws.close(None);
+                    let _ = ws.flush();
+                    break 'session;
+                }
+            }
+        }
+    }
+    shared.leave(&canvas_id, &peer_id);
+}
+
+impl Shared {
+    /// Check the join token and that the requested canvas is shared, giving
+    /// the status and reason to reject the request with otherwise.
+    fn authorize(&self, request: &Request) -> Result<String, (StatusCode, &'static str)> {
+        let uri = request.uri();
+        let token = uri
+            .query()
+            .into_iter()
+            .flat_map(|query| query.split('&'))
+            .find_map(|pair| pair.strip_prefix("token="));
+        if token != Some(self.token.as_str()) {
+            return Err((StatusCode::FORBIDDEN, "Invalid sync token"));
+        }
+        let canvas_id = uri.path().trim_start_matches('/');
+        let shared = self
+            .rooms
+            .lock()
+            .is_ok_and(|rooms| rooms.contains_key(canvas_id));
+        if !shared {
+            return Err((StatusCode::NOT_FOUND, "Canvas is not shared"));
+        }
+        Ok(canvas_id.to_string())
+    }
+
+    /// Act on one message from `peer_id`; what comes back goes to that peer only.
+    fn handle(
+        &self,
+        canvas_id: &str,
+        peer_id: &str,
+        outbox: &mut Option<Sender<String>>,
+        text: &str,
+    ) -> Result<Option<String>, String> {
+        let message: ClientMessage =
+            serde_json::from_str(text).map_err(|e| format!("Invalid message: {}", e))?;
+        let mut rooms = self.rooms.lock().map_err(|e| e.to_string())?;
+        let room = rooms
+            .get_mut(canvas_id)
+            .ok_or_else(|| "Canvas is no longer shared".to_string())?;
+
+        match message {
+            ClientMessage::Hello { name, color } => {
+                let Some(outbox) = outbox.take() else {
+                    return Err("Already joined".to_string());
+                };
+                let presence = Presence {
+                    peer_id: peer_id.to_string(),
+                    name,
+                    color,
+                    cursor: None,
+                    selection: Vec::new(),
+                };
+                room.broadcast(None, &ServerMessage::Presence(&presence));
+                let welcome = encode(&ServerMessage::Welcome {
+                    peer_id,
+                    name: &room.file.name,
+                    canvas: &room.file.canvas,
+                    peers: room.peers.values().map(|p| &p.presence).collect(),
+                });
+                room.peers
+                    .insert(peer_id.to_string(), Peer { outbox, presence });
+                Ok(Some(welcome))
+            }
+            ClientMessage::Ops { ops } => {
+                let Some(peer) = room.peers.get(peer_id) else {
+                    return Err("Send hello first".to_string());
+                };
+                if !room.editors.contains(&peer.presence.name) {
+                    room.editors.push(peer.presence.name.clone());
+                }
+                for op in &ops {
+                    apply_op(&mut room.file.canvas, op);
+                }
+                room.edits += 1;
+                room.broadcast(Some(peer_id), &ServerMessage::Ops { peer_id, ops: &ops });
+                Ok(None)
+            }
+            ClientMessage::Presence { cursor, selection } => {
+                let peer = room
+                    .peers
+                    .get_mut(peer_id)
+                    .ok_or_else(|| "Send hello first".to_string())?;
+                peer.presence.cursor = cursor;
+                peer.presence.selection = selection;
+                let presence = peer.presence.clone();
+                room.broadcast(Some(peer_id), &ServerMessage::Presence(&presence));
+                Ok(None)
+            }
+        }
+    }
+
+    fn leave(&self, canvas_id: &str, peer_id: &str) {
+        let Ok(mut rooms) = self.rooms.lock() else {
+            return;
+        };
+        if let Some(room) = rooms.get_mut(canvas_id) {
+            if room.peers.remove(peer_id).is_some() {
+                room.broadcast(None, &ServerMessage::Leave { peer_id });
+            }
+        }
+    }
+}
+
+fn reject(status: StatusCode, reason: &str) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(reason.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+/// This machine's address on the local network, if it has one. Connecting
+/// a UDP socket sends nothing; it only picks the interface a packet would
+/// leave by.
+fn lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.168.0.1:9").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+/// Apply `op` to a Fabric canvas JSON.
+pub fn apply_op(canvas: &mut Value, op: &ObjectOp) {
+    match op {
+        ObjectOp::Upsert { object, index } => {
+            let Some(id) = fabric::object_id(object) else {
+                return;
+            };
+            if let Some(existing) = oplog::find_mut(canvas, id) {
+                *existing = object.clone();
+            } else {
+                oplog::insert(canvas, None, *index, object.clone());
+            }
+        }
+        ObjectOp::Remove { id } => oplog::remove(canvas, id),
+        ObjectOp::Reorder { ids } => oplog::reorder(canvas, None, Some(&json!(ids))),
+    }
+}
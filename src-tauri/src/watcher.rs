@@ -0,0 +1,150 @@
+//! Notices canvas files written outside the app, usually by `easel-mcp`.
+//!
+//! A background thread polls the canvases directory and the source files of
+//! imported canvases, and emits `canvas-changed` with the canvas id so the
+//! frontend can reload the open tab. Writes made by the app itself go through
+//! [`CanvasWatcher::own_write`] and are not reported back.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+use crate::easel;
+
+pub const CANVAS_CHANGED: &str = "canvas-changed";
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasChanged {
+    pub canvas_id: String,
+    /// The file was deleted rather than written.
+    pub removed: bool,
+}
+
+/// Modification time and length; either changing counts as a write.
+pub(crate) type Stamp = (SystemTime, u64);
+
+#[derive(Default)]
+struct Watched {
+    /// Last seen stamp of each `.easel` file in the canvases directory.
+    files: HashMap<PathBuf, Stamp>,
+    /// Imported source file -> (canvas id, last seen stamp).
+    sources: HashMap<PathBuf, (String, Option<Stamp>)>,
+    primed: bool,
+}
+
+#[derive(Default)]
+pub struct CanvasWatcher {
+    watched: Mutex<Watched>,
+}
+
+impl CanvasWatcher {
+    /// Run `write` on `path` without the change being reported. The lock is
+    /// held across the write so a poll can't observe it half way.
+    pub fn own_write<T>(
+        &self,
+        path: &Path,
+        write: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut watched = self.watched.lock().map_err(|e| e.to_string())?;
+        let result = write();
+        match stamp(path) {
+            Some(s) => watched.files.insert(path.to_path_buf(), s),
+            None => watched.files.remove(path),
+        };
+        result
+    }
+
+    /// Keep `canvas_id` in step with the file it was imported from.
+    pub fn watch_source(&self, source: PathBuf, canvas_id: String) {
+        if let Ok(mut watched) = self.watched.lock() {
+            let current = stamp(&source);
+            watched.sources.insert(source, (canvas_id, current));
+        }
+    }
+
+    pub fn unwatch_canvas(&self, canvas_id: &str) {
+        if let Ok(mut watched) = self.watched.lock() {
+            watched.sources.retain(|_, (id, _)| id != canvas_id);
+        }
+    }
+
+    /// Poll `app_data_dir` until the app exits.
+    pub fn spawn(self: Arc<Self>, app: AppHandle, app_data_dir: PathBuf) {
+        thread::spawn(move || loop {
+            for event in self.poll(&app_data_dir) {
+                if let Err(e) = app.emit(CANVAS_CHANGED, &event) {
+                    eprintln!("Warning: failed to emit {}: {}", CANVAS_CHANGED, e);
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        });
+    }
+
+    fn poll(&self, app_data_dir: &Path) -> Vec<CanvasChanged> {
+        let Ok(mut watched) = self.watched.lock() else {
+            return Vec::new();
+        };
+        let mut events = Vec::new();
+
+        // Copy changed sources first so the scan below reports their canvases.
+        let mut synced = Vec::new();
+        for (source, (canvas_id, last)) in watched.sources.iter_mut() {
+            let current = stamp(source);
+            if current.is_none() || current == *last {
+                continue;
+            }
+            *last = current;
+            let dest = easel::canvas_easel_path(app_data_dir, canvas_id);
+            match easel::sync_from_source(source, &dest) {
+                Ok(()) => synced.push(dest),
+                Err(e) => eprintln!("Warning: failed to reload {}: {}", source.display(), e),
+            }
+        }
+
+        let mut seen = HashMap::new();
+        if let Ok(entries) = fs::read_dir(easel::canvases_dir(app_data_dir)) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("easel") {
+                    continue;
+                }
+                if let Some(s) = stamp(&path) {
+                    seen.insert(path, s);
+                }
+            }
+        }
+
+        if watched.primed {
+            for (path, s) in &seen {
+                if watched.files.get(path) != Some(s) || synced.contains(path) {
+                    events.extend(changed(path, false));
+                }
+            }
+            for path in watched.files.keys() {
+                if !seen.contains_key(path) {
+                    events.extend(changed(path, true));
+                }
+            }
+        }
+        watched.files = seen;
+        watched.primed = true;
+        events
+    }
+}
+
+pub(crate) fn stamp(path: &Path) -> Option<Stamp> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+fn changed(path: &Path, removed: bool) -> Option<CanvasChanged> {
+    let canvas_id = path.file_stem()?.to_str()?.to_string();
+    Some(CanvasChanged { canvas_id, removed })
+}
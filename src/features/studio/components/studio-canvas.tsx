@@ -11,11 +11,15 @@ import {
 import { AligningGuidelines } from "fabric/extensions";
 import { useCallback, useEffect, useRef, useState } from "react";
 
+import { useCanvasSync } from "../hooks/use-canvas-sync";
 import { getFabricCanvas, setFabricCanvasRef, useStudioStore } from "../store/studio-store";
 import { useTabStore } from "../store/tab-store";
+import { CUSTOM_PROPS, loadCanvasJson, toSavedObject } from "../utils/canvas-json";
 import { findParentFrame, moveFrameChildren, setParentId } from "../utils/frame-helpers";
 
 import * as canvasApi from "@/lib/api/canvas";
+import * as editsApi from "@/lib/api/edits";
+import * as mergeApi from "@/lib/api/merge";
 import { cn } from "@/lib/utils";
 
 let objectCounter = 0;
@@ -149,6 +153,7 @@ interface CanvasContextMenu {
 export function StudioCanvas({ className }: { className?: string }) {
   const canvasRef = useRef<HTMLCanvasElement>(null);
   const containerRef = useRef<HTMLDivElement>(null);
+  const activeTabId = useTabStore((s) => s.activeTabId);
   const [contextMenu, setContextMenu] = useState<CanvasContextMenu | null>(null);
   const contextMenuRef = useRef<HTMLDivElement>(null);
   const fabricRef = useRef<Canvas | null>(null);
@@ -160,9 +165,15 @@ export function StudioCanvas({ className }: { className?: string }) {
   const spaceHeldRef = useRef(false);
   const isRestoringRef = useRef(false);
   const framePosRef = useRef<Map<string, { left: number; top: number }>>(new Map());
-  const historyRef = useRef<string[]>([]);
+  // Canvas snapshots, each with the revision saved when it was taken
+  const historyRef = useRef<{ json: string; revision?: number }[]>([]);
   const historyIndexRef = useRef(-1);
   const isUndoRedoRef = useRef(false);
+  const revisionRef = useRef<number | undefined>(undefined);
+  // CRDT state the canvas was loaded or saved at; saves merge from here
+  const headsRef = useRef<string[] | undefined>(undefined);
+  // The canvas as last loaded or saved: the common base when merging with disk
+  const baseJsonRef = useRef<string | undefined>(undefined);
   const isTextEditingRef = useRef(false);
   const clipboardRef = useRef<FabricObjectType | null>(null);
 
@@ -222,16 +233,23 @@ export function StudioCanvas({ className }: { className?: string }) {
     void canvasApi.getCanvasState(tabId).then((saved) => {
       if (!fabricRef.current) return;
 
+      revisionRef.current = saved?.revision;
+      headsRef.current = saved?.heads;
       if (saved && saved.canvasJson !== "{}") {
         const viewportTransform = JSON.parse(saved.viewportTransform) as number[];
-        void canvas.loadFromJSON(saved.canvasJson).then(() => {
+        void loadCanvasJson(canvas, tabId, saved.canvasJson).then(() => {
           if (!fabricRef.current) return;
           isRestoringRef.current = false;
+          baseJsonRef.current = JSON.stringify(toSavedObject(canvas));
           canvas.setViewportTransform(
             viewportTransform as [number, number, number, number, number, number],
           );
           canvas.setZoom(saved.zoom);
           canvas.requestRenderAll();
+          // History starts at the loaded canvas, not at each object as it was added
+          historyRef.current = [];
+          historyIndexRef.current = -1;
+          saveHistory();
           actions.syncObjectsFromCanvas();
           actions.setZoom(Math.round(saved.zoom * 100) / 100);
         });
@@ -585,12 +603,10 @@ export function StudioCanvas({ className }: { className?: string }) {
     // History: save canvas state for undo/redo
     function saveHistory() {
       if (isUndoRedoRef.current || isTextEditingRef.current) return;
-      const json = JSON.stringify(
-        canvas.toObject(["id", "name", "isFrame", "isComponent", "parentId"]),
-      );
+      const json = JSON.stringify(toSavedObject(canvas));
       // Trim future states if we're not at the end
       historyRef.current = historyRef.current.slice(0, historyIndexRef.current + 1);
-      historyRef.current.push(json);
+      historyRef.current.push({ json, revision: revisionRef.current });
       // Cap at 50 states
       if (historyRef.current.length > 50) {
         historyRef.current.shift();
@@ -628,11 +644,14 @@ export function StudioCanvas({ className }: { className?: string }) {
     canvas.on("object:removed", () => saveHistory());
 
     function performUndo() {
-      if (historyIndexRef.current <= 0) return;
+      if (historyIndexRef.current <= 0) {
+        stepSavedHistory(false);
+        return;
+      }
       historyIndexRef.current -= 1;
-      const json = historyRef.current[historyIndexRef.current];
+      const { json } = historyRef.current[historyIndexRef.current];
       isUndoRedoRef.current = true;
-      void canvas.loadFromJSON(json).then(() => {
+      void loadCanvasJson(canvas, tabId, json).then(() => {
         isUndoRedoRef.current = false;
         canvas.requestRenderAll();
         actions.syncObjectsFromCanvas();
@@ -640,17 +659,61 @@ export function StudioCanvas({ className }: { className?: string }) {
     }
 
     function performRedo() {
-      if (historyIndexRef.current >= historyRef.current.length - 1) return;
+      if (historyIndexRef.current >= historyRef.current.length - 1) {
+        // Only straight after stepping through saved history; a new edit
+        // since leaves nothing to redo
+        if (historyRef.current.length === 1) stepSavedHistory(true);
+        return;
+      }
       historyIndexRef.current += 1;
-      const json = historyRef.current[historyIndexRef.current];
+      const { json } = historyRef.current[historyIndexRef.current];
       isUndoRedoRef.current = true;
-      void canvas.loadFromJSON(json).then(() => {
+      void loadCanvasJson(canvas, tabId, json).then(() => {
         isUndoRedoRef.current = false;
         canvas.requestRenderAll();
         actions.syncObjectsFromCanvas();
       });
     }
 
+    // Past the in-memory history, step through the user's saved edits from
+    // the canvas's operation log, leaving out those saved since the oldest
+    // snapshot. Unsaved changes are saved first so the file matches what is
+    // on screen.
+    function stepSavedHistory(redo: boolean) {
+      const since = historyRef.current[0]?.revision ?? 0;
+      if (autoSaveTimer) clearTimeout(autoSaveTimer);
+      autoSaveTimer = null;
+      const json = JSON.stringify(toSavedObject(canvas));
+      const flushed =
+        json === baseJsonRef.current
+          ? Promise.resolve()
+          : canvasApi
+              .saveCanvasState(
+                tabId,
+                json,
+                canvas.getZoom(),
+                JSON.stringify([...canvas.viewportTransform]),
+                revisionRef.current,
+                headsRef.current,
+              )
+              .then((saved) => {
+                revisionRef.current = saved.revision;
+                headsRef.current = saved.heads;
+                baseJsonRef.current = json;
+              });
+      void flushed
+        .then(() =>
+          redo ? editsApi.redoCanvasEdit(tabId, since) : editsApi.undoCanvasEdit(tabId, since),
+        )
+        .then((saved) => {
+          if (!saved || !fabricRef.current) return;
+          applyFromDisk(saved.canvasJson, saved.revision, saved.heads, true);
+        })
+        .catch((error: unknown) => {
+          console.warn("Canvas history:", error);
+        });
+    }
+
     const resizeObserver = new ResizeObserver(handleResize);
     resizeObserver.observe(container);
 
@@ -673,7 +736,7 @@ export function StudioCanvas({ className }: { className?: string }) {
           const obj = canvas.getActiveObject();
           if (obj) {
             void obj
-              .clone(["id", "name", "isFrame", "isComponent", "parentId"])
+              .clone(CUSTOM_PROPS)
               .then((cloned: FabricObjectType) => {
                 clipboardRef.current = cloned;
               });
@@ -686,7 +749,7 @@ export function StudioCanvas({ className }: { className?: string }) {
           const obj = canvas.getActiveObject();
           if (obj) {
             void obj
-              .clone(["id", "name", "isFrame", "isComponent", "parentId"])
+              .clone(CUSTOM_PROPS)
               .then((cloned: FabricObjectType) => {
                 clipboardRef.current = cloned;
                 canvas.remove(obj);
@@ -703,7 +766,7 @@ export function StudioCanvas({ className }: { className?: string }) {
           const clip = clipboardRef.current;
           if (clip) {
             void clip
-              .clone(["id", "name", "isFrame", "isComponent", "parentId"])
+              .clone(CUSTOM_PROPS)
               .then((cloned: FabricObjectType) => {
                 cloned.set({
                   left: (cloned.left ?? 0) + 20,
@@ -747,24 +810,104 @@ export function StudioCanvas({ className }: { className?: string }) {
     function scheduleAutoSave() {
       if (isRestoringRef.current || isUndoRedoRef.current) return;
       if (autoSaveTimer) clearTimeout(autoSaveTimer);
-      autoSaveTimer = setTimeout(() => {
-        if (!fabricRef.current) return;
-        const json = JSON.stringify(
-          canvas.toObject(["id", "name", "isFrame", "isComponent", "parentId"]),
-        );
-        const z = canvas.getZoom();
-        const vt = JSON.stringify([...canvas.viewportTransform]);
-        void canvasApi.saveCanvasState(tabId, json, z, vt);
-      }, 2000);
+      autoSaveTimer = setTimeout(saveNow, 2000);
+    }
+    function saveNow() {
+      if (autoSaveTimer) clearTimeout(autoSaveTimer);
+      autoSaveTimer = null;
+      if (!fabricRef.current) return;
+      const json = JSON.stringify(toSavedObject(canvas));
+      const z = canvas.getZoom();
+      const vt = JSON.stringify([...canvas.viewportTransform]);
+      canvasApi
+        .saveCanvasState(tabId, json, z, vt, revisionRef.current, headsRef.current)
+        .then((saved) => {
+          if (!fabricRef.current) return;
+          revisionRef.current = saved.revision;
+          if (!saved.canvasJson) {
+            headsRef.current = saved.heads;
+            baseJsonRef.current = json;
+          } else if (!autoSaveTimer) {
+            // Merged with someone else's edits: show the result. If newer
+            // local edits are pending, the next save merges them from the
+            // old heads instead.
+            applyFromDisk(saved.canvasJson, saved.revision, saved.heads);
+          }
+        })
+        .catch((error: unknown) => {
+          // Someone else saved first: fold their changes into ours
+          if (canvasApi.isConflict(error)) mergeWithDisk();
+        });
     }
 
     canvas.on("object:modified", () => scheduleAutoSave());
     canvas.on("object:added", () => scheduleAutoSave());
     canvas.on("object:removed", () => scheduleAutoSave());
 
+    // Live reload: pick up edits written to this canvas's file by an agent.
+    // Unsaved local edits are saved right away, which merges them with the
+    // file; otherwise the file is simply reloaded. The viewport stays put.
+    function applyFromDisk(json: string, revision: number, heads: string[], restart = false) {
+      revisionRef.current = revision;
+      headsRef.current = heads;
+      isRestoringRef.current = true;
+      isUndoRedoRef.current = true;
+      void loadCanvasJson(canvas, tabId, json).then(() => {
+        isRestoringRef.current = false;
+        isUndoRedoRef.current = false;
+        if (!fabricRef.current) return;
+        baseJsonRef.current = JSON.stringify(toSavedObject(canvas));
+        canvas.requestRenderAll();
+        // A step through saved history starts the in-memory history over
+        if (restart) {
+          historyRef.current = [];
+          historyIndexRef.current = -1;
+        }
+        saveHistory();
+        actions.syncObjectsFromCanvas();
+      });
+    }
+    function reloadFromDisk() {
+      void canvasApi.getCanvasState(tabId).then((saved) => {
+        if (!saved || !fabricRef.current) return;
+        if (autoSaveTimer) clearTimeout(autoSaveTimer);
+        applyFromDisk(saved.canvasJson, saved.revision, saved.heads);
+      });
+    }
+    function mergeWithDisk() {
+      const base = baseJsonRef.current;
+      if (base === undefined) {
+        reloadFromDisk();
+        return;
+      }
+      if (autoSaveTimer) clearTimeout(autoSaveTimer);
+      autoSaveTimer = null;
+      const json = JSON.stringify(toSavedObject(canvas));
+      mergeApi
+        .mergeCanvasState(tabId, base, json)
+        .then((result) => {
+          if (!fabricRef.current) return;
+          if (result.conflicts.length > 0) {
+            console.warn("Kept local values for conflicting edits", result.conflicts);
+          }
+          applyFromDisk(result.canvasJson, result.revision, result.heads);
+        })
+        .catch(() => {
+          reloadFromDisk();
+        });
+    }
+    const stopWatching = canvasApi.onCanvasChanged((event) => {
+      if (event.canvasId !== tabId || event.removed) return;
+      if (autoSaveTimer) saveNow();
+      else reloadFromDisk();
+    });
+
     return () => {
       window.removeEventListener("keydown", onKeyDown);
       window.removeEventListener("keyup", onKeyUp);
+      void stopWatching.then((unlisten) => {
+        unlisten();
+      });
       resizeObserver.disconnect();
       aligningGuidelines.dispose();
 
@@ -772,12 +915,17 @@ export function StudioCanvas({ className }: { className?: string }) {
 
       // Save canvas state on unmount (tab switch or close) unless mid-restore
       if (!isRestoringRef.current) {
-        const json = JSON.stringify(
-          canvas.toObject(["id", "name", "isFrame", "isComponent", "parentId"]),
-        );
+        const json = JSON.stringify(toSavedObject(canvas));
         const z = canvas.getZoom();
         const vt = JSON.stringify([...canvas.viewportTransform]);
-        void canvasApi.saveCanvasState(tabId, json, z, vt);
+        void canvasApi.saveCanvasState(
+          tabId,
+          json,
+          z,
+          vt,
+          revisionRef.current,
+          headsRef.current,
+        );
       }
 
       fabricRef.current = null;
@@ -786,6 +934,9 @@ export function StudioCanvas({ className }: { className?: string }) {
     };
   }, [handleResize]);
 
+  // Live collaboration when this tab is shared or joined
+  useCanvasSync(activeTabId);
+
   // Close context menu on click-outside or Escape
   useEffect(() => {
     if (!contextMenu) return;
@@ -818,7 +969,7 @@ export function StudioCanvas({ className }: { className?: string }) {
       case "duplicate": {
         if (!target) break;
         void target
-          .clone(["id", "name", "isFrame", "isComponent", "parentId"])
+          .clone(CUSTOM_PROPS)
           .then((cloned: FabricObjectType) => {
             cloned.set({ left: (cloned.left ?? 0) + 20, top: (cloned.top ?? 0) + 20 });
             (cloned as unknown as { id: string }).id = generateId();
@@ -127,6 +127,19 @@ export function StudioGalleryDialog({ open, onOpenChange }: StudioGalleryDialogP
     });
   }
 
+  function handleImportWhiteboard() {
+    void openFileDialog({
+      multiple: false,
+      filters: [{ name: "Excalidraw / tldraw", extensions: ["excalidraw", "tldr", "json"] }],
+    }).then((selected: string | null) => {
+      if (!selected) return;
+      void canvasApi.importWhiteboardFile(selected).then((meta) => {
+        setActiveTab(meta.id);
+        onOpenChange(false);
+      });
+    });
+  }
+
   return (
     <Dialog open={open} onOpenChange={onOpenChange}>
       <DialogContent
@@ -169,6 +182,13 @@ export function StudioGalleryDialog({ open, onOpenChange }: StudioGalleryDialogP
               <Import className="size-3.5" />
               Import .easel
             </button>
+            <button
+              className="flex w-full items-center justify-center gap-2 rounded-md py-1.5 text-[11px] text-[#888] transition-colors hover:bg-[#222] hover:text-[#ccc]"
+              onClick={handleImportWhiteboard}
+            >
+              <Import className="size-3.5" />
+              Import sketch
+            </button>
           </div>
         </div>
 
@@ -43,6 +43,8 @@ const TYPE_ICONS: Record<string, ReactNode> = {
   i_text: <Type className="size-3" />,
   itext: <Type className="size-3" />,
   IText: <Type className="size-3" />,
+  textbox: <Type className="size-3" />,
+  Textbox: <Type className="size-3" />,
 };
 
 export function StudioLayerItem({
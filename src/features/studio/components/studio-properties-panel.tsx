@@ -37,6 +37,7 @@ import type { ObjectProperties } from "../types";
 import { Collapsible, CollapsibleContent, CollapsibleTrigger } from "@/components/ui/collapsible";
 import { ScrollArea } from "@/components/ui/scroll-area";
 import { Tooltip, TooltipContent, TooltipTrigger } from "@/components/ui/tooltip";
+import { listFonts } from "@/lib/api/fonts";
 import { cn } from "@/lib/utils";
 
 const FALLBACK_FONTS = [
@@ -83,19 +84,29 @@ function useSystemFonts(): string[] {
     if (fetched.current) return;
     fetched.current = true;
 
-    const w = window as unknown as {
-      queryLocalFonts?: () => Promise<{ family: string }[]>;
-    };
-    if (typeof w.queryLocalFonts === "function") {
-      void w.queryLocalFonts().then((fontData: { family: string }[]) => {
-        const families = [...new Set(fontData.map((f) => f.family))].sort((a, b) =>
-          a.localeCompare(b),
-        );
+    // Prefer the backend's list: those are the fonts exports can embed.
+    void listFonts()
+      .then((families) => families.map((f) => f.family))
+      .catch(() => [] as string[])
+      .then((families) => {
         if (families.length > 0) {
           setFonts(families);
+          return;
+        }
+        const w = window as unknown as {
+          queryLocalFonts?: () => Promise<{ family: string }[]>;
+        };
+        if (typeof w.queryLocalFonts === "function") {
+          void w.queryLocalFonts().then((fontData: { family: string }[]) => {
+            const local = [...new Set(fontData.map((f) => f.family))].sort((a, b) =>
+              a.localeCompare(b),
+            );
+            if (local.length > 0) {
+              setFonts(local);
+            }
+          });
         }
       });
-    }
   }, []);
 
   return fonts;
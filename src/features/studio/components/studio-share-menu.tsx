@@ -0,0 +1,154 @@
+import { Users } from "lucide-react";
+import { useEffect, useRef, useState } from "react";
+
+import { useSyncStore } from "../store/sync-store";
+import { useTabStore } from "../store/tab-store";
+
+import { Tooltip, TooltipContent, TooltipTrigger } from "@/components/ui/tooltip";
+import { cn } from "@/lib/utils";
+
+const ITEM_CLASS =
+  "flex w-full items-center px-3 py-1.5 text-left text-[12px] text-[#999] transition-colors hover:bg-[#252525] hover:text-[#e0e0e0]";
+
+export function StudioShareMenu() {
+  const activeTabId = useTabStore((s) => s.activeTabId);
+  const session = useSyncStore((s) => (activeTabId ? s.sessions[activeTabId] : undefined));
+  const { share, stopSharing, join, leave } = useSyncStore((s) => s.actions);
+
+  const [open, setOpen] = useState(false);
+  const [joinUrl, setJoinUrl] = useState("");
+  const [error, setError] = useState<string | null>(null);
+  const menuRef = useRef<HTMLDivElement>(null);
+
+  // Close menu on outside click
+  useEffect(() => {
+    if (!open) return;
+    const handleClick = (e: MouseEvent) => {
+      if (menuRef.current && !menuRef.current.contains(e.target as Node)) {
+        setOpen(false);
+      }
+    };
+    document.addEventListener("mousedown", handleClick);
+    return () => document.removeEventListener("mousedown", handleClick);
+  }, [open]);
+
+  function run(action: () => Promise<void>) {
+    setError(null);
+    action().catch((err: unknown) => setError(String(err)));
+  }
+
+  function handleShare() {
+    if (!activeTabId) return;
+    run(async () => {
+      const shared = await share(activeTabId);
+      await navigator.clipboard.writeText(shared.urls[0]);
+    });
+  }
+
+  function handleJoin() {
+    const url = joinUrl.trim();
+    if (!url) return;
+    run(async () => {
+      await join(url);
+      setJoinUrl("");
+      setOpen(false);
+    });
+  }
+
+  const shareUrl = session?.shared?.urls[0];
+
+  return (
+    <div ref={menuRef} className="relative">
+      <Tooltip>
+        <TooltipTrigger asChild>
+          <button
+            className={cn(
+              "flex h-6 items-center gap-1.5 rounded-md px-2 text-[11px] transition-colors hover:bg-[#252525] hover:text-[#ccc]",
+              session ? "bg-[#4f8ef7]/20 text-[#8ab4f8]" : "text-[#999]",
+            )}
+            onClick={() => setOpen(!open)}
+          >
+            <Users className="size-[14px]" />
+            <span>{session ? session.peers.length + 1 : "Share"}</span>
+          </button>
+        </TooltipTrigger>
+        {!open && (
+          <TooltipContent side="bottom" sideOffset={4}>
+            Share on local network
+          </TooltipContent>
+        )}
+      </Tooltip>
+
+      {open && (
+        <div className="absolute right-0 top-full z-50 mt-1 w-[260px] rounded-lg border border-[#222] bg-[#191919] py-1 shadow-lg shadow-black/40">
+          {session ? (
+            <>
+              {shareUrl && (
+                <div className="truncate px-3 py-1.5 font-mono text-[10px] text-[#666]">
+                  {shareUrl}
+                </div>
+              )}
+              {session.peers.map((peer) => (
+                <div
+                  key={peer.peerId}
+                  className="flex items-center gap-2 px-3 py-1 text-[12px] text-[#ccc]"
+                >
+                  <span className="size-2 rounded-full" style={{ backgroundColor: peer.color }} />
+                  <span className="truncate">{peer.name}</span>
+                </div>
+              ))}
+              {session.peers.length === 0 && (
+                <div className="px-3 py-1 text-[12px] text-[#555]">Nobody else here yet</div>
+              )}
+              <div className="my-1 h-px bg-[#222]" />
+              {shareUrl && (
+                <button
+                  className={ITEM_CLASS}
+                  onClick={() => run(() => navigator.clipboard.writeText(shareUrl))}
+                >
+                  Copy link
+                </button>
+              )}
+              <button
+                className={cn(ITEM_CLASS, "text-red-400 hover:text-red-300")}
+                onClick={() => {
+                  if (!activeTabId) return;
+                  if (session.shared) run(() => stopSharing(activeTabId));
+                  else leave(activeTabId);
+                }}
+              >
+                {session.shared ? "Stop sharing" : "Leave"}
+              </button>
+            </>
+          ) : (
+            <>
+              <button className={ITEM_CLASS} onClick={handleShare}>
+                Share on local network
+              </button>
+              <div className="my-1 h-px bg-[#222]" />
+              <div className="flex items-center gap-1 px-2 py-1">
+                <input
+                  className="min-w-0 flex-1 rounded border border-[#333] bg-[#1a1a1a] px-1.5 py-1 text-[11px] text-[#d4d4d4] outline-none focus:border-[#4f8ef7]/50"
+                  placeholder="ws://…"
+                  value={joinUrl}
+                  onChange={(e) => setJoinUrl(e.target.value)}
+                  onKeyDown={(e) => {
+                    if (e.key === "Enter") handleJoin();
+                    e.stopPropagation();
+                  }}
+                />
+                <button
+                  className="rounded px-2 py-1 text-[11px] text-[#8ab4f8] transition-colors hover:bg-[#252525]"
+                  onClick={handleJoin}
+                >
+                  Join
+                </button>
+              </div>
+            </>
+          )}
+          {error && <div className="px-3 py-1 text-[11px] text-red-400">{error}</div>}
+        </div>
+      )}
+    </div>
+  );
+}
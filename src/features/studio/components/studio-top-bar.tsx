@@ -4,6 +4,8 @@ import { useCallback, useEffect, useRef, useState } from "react";
 import { useStudioStore } from "../store/studio-store";
 import { useTabStore } from "../store/tab-store";
 
+import { StudioShareMenu } from "./studio-share-menu";
+
 import {
   ContextMenu,
   ContextMenuContent,
@@ -228,6 +230,7 @@ export function StudioTopBar({ className, onOpenSettings, onOpenGallery }: Studi
 
         {/* Right: action buttons */}
         <div className="flex flex-shrink-0 items-center gap-[2px] px-2">
+          <StudioShareMenu />
           <Tooltip>
             <TooltipTrigger asChild>
               <button
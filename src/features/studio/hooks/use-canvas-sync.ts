@@ -0,0 +1,323 @@
+import { type FabricObject, Point as FabricPoint, type TPointerEventInfo, util } from "fabric";
+import { useEffect } from "react";
+
+import { getFabricCanvas, useStudioStore } from "../store/studio-store";
+import { useSyncStore } from "../store/sync-store";
+import { loadCanvasJson, resolveAssets, toSavedObject } from "../utils/canvas-json";
+
+import type { ObjectOp, Point, Presence, SyncConnection, SyncMessage } from "@/lib/api/sync";
+
+import { getPreference } from "@/lib/api/preferences";
+import * as syncApi from "@/lib/api/sync";
+
+/** Local edits and pointer moves go out at most this often (ms). */
+const SEND_INTERVAL = 50;
+const PEER_COLORS = ["#f87171", "#fb923c", "#facc15", "#4ade80", "#22d3ee", "#a78bfa", "#f472b6"];
+
+function getId(obj: FabricObject): string {
+  return (obj as unknown as { id?: string }).id ?? "";
+}
+
+/** Fabric JSON for `obj` in scene coordinates, even while it is part of a multi-selection. */
+function serialize(obj: FabricObject): Record<string, unknown> {
+  const json = toSavedObject(obj);
+  if (obj.group) {
+    const { translateX, translateY, angle, scaleX, scaleY, skewX, skewY } = util.qrDecompose(
+      obj.calcTransformMatrix(),
+    );
+    const origin = obj.translateToGivenOrigin(
+      new FabricPoint(translateX, translateY),
+      "center",
+      "center",
+      obj.originX,
+      obj.originY,
+    );
+    Object.assign(json, { left: origin.x, top: origin.y, angle, scaleX, scaleY, skewX, skewY });
+  }
+  return json;
+}
+
+/**
+ * Keep the tab's canvas in step with the shared canvas it is joined to:
+ * local edits go out as object ops, other peers' ops are applied here, and
+ * their cursors and selections are drawn over the canvas.
+ */
+export function useCanvasSync(tabId: string) {
+  const url = useSyncStore((s) => s.sessions[tabId]?.url);
+  const owner = useSyncStore((s) => s.sessions[tabId]?.shared != null);
+
+  useEffect(() => {
+    const canvas = getFabricCanvas();
+    if (!url || !canvas) return;
+
+    const { leave, setPeers } = useSyncStore.getState().actions;
+    const { syncObjectsFromCanvas } = useStudioStore.getState().actions;
+    const peers = new Map<string, Presence>();
+    // Each object as last sent or received, to tell local edits apart
+    const synced = new Map<string, string>();
+    let order: string[] = [];
+    let joined = false;
+    let applying = false;
+    let closed = false;
+    let connection: SyncConnection | null = null;
+    let pushTimer: ReturnType<typeof setTimeout> | null = null;
+    let presenceTimer: ReturnType<typeof setTimeout> | null = null;
+    let cursor: Point | null = null;
+    // Remote changes are applied one after another
+    let remote = Promise.resolve();
+
+    function remember() {
+      synced.clear();
+      order = [];
+      for (const obj of canvas.getObjects()) {
+        const id = getId(obj);
+        if (!id) continue;
+        synced.set(id, JSON.stringify(serialize(obj)));
+        order.push(id);
+      }
+    }
+
+    function pushLocalChanges() {
+      pushTimer = null;
+      if (!joined || !connection) return;
+      if (applying) {
+        schedulePush();
+        return;
+      }
+      const ops: ObjectOp[] = [];
+      const ids: string[] = [];
+      canvas.getObjects().forEach((obj, index) => {
+        const id = getId(obj);
+        if (!id) return;
+        ids.push(id);
+        const object = serialize(obj);
+        const text = JSON.stringify(object);
+        if (synced.get(id) === text) return;
+        ops.push(synced.has(id) ? { op: "upsert", object } : { op: "upsert", object, index });
+        synced.set(id, text);
+      });
+      const present = new Set(ids);
+      for (const id of [...synced.keys()]) {
+        if (present.has(id)) continue;
+        ops.push({ op: "remove", id });
+        synced.delete(id);
+      }
+      const known = new Set(order);
+      const before = order.filter((id) => present.has(id));
+      const after = ids.filter((id) => known.has(id));
+      if (before.some((id, i) => id !== after[i])) ops.push({ op: "reorder", ids });
+      order = ids;
+      connection.sendOps(ops);
+    }
+    function schedulePush() {
+      pushTimer ??= setTimeout(pushLocalChanges, SEND_INTERVAL);
+    }
+
+    function sendPresence() {
+      presenceTimer = null;
+      connection?.sendPresence(cursor, useStudioStore.getState().selectedObjectIds);
+    }
+    function schedulePresence() {
+      presenceTimer ??= setTimeout(sendPresence, SEND_INTERVAL);
+    }
+
+    async function applyOps(ops: ObjectOp[]) {
+      for (const op of ops) {
+        const objects = canvas.getObjects();
+        switch (op.op) {
+          case "upsert": {
+            const [obj] = await util.enlivenObjects<FabricObject>([
+              await resolveAssets(tabId, op.object),
+            ]);
+            const id = getId(obj);
+            const existing = objects.find((o) => getId(o) === id);
+            if (existing) {
+              const at = objects.indexOf(existing);
+              canvas.remove(existing);
+              canvas.insertAt(at, obj);
+            } else {
+              canvas.insertAt(Math.min(op.index ?? objects.length, objects.length), obj);
+            }
+            synced.set(id, JSON.stringify(serialize(obj)));
+            break;
+          }
+          case "remove": {
+            const existing = objects.find((o) => getId(o) === op.id);
+            if (existing) canvas.remove(existing);
+            synced.delete(op.id);
+            break;
+          }
+          case "reorder": {
+            // Same rule as the server: the listed objects swap into each other's slots
+            const rank = new Map(op.ids.map((id, i) => [id, i]));
+            const slots = objects.flatMap((o, i) => (rank.has(getId(o)) ? [i] : []));
+            const moved = slots
+              .map((i) => objects[i])
+              .sort((a, b) => (rank.get(getId(a)) ?? 0) - (rank.get(getId(b)) ?? 0));
+            moved.forEach((obj, i) => canvas.moveObjectTo(obj, slots[i]));
+            break;
+          }
+        }
+      }
+    }
+
+    function applyRemote(change: () => Promise<void>) {
+      remote = remote.then(async () => {
+        applying = true;
+        try {
+          await change();
+        } catch (error) {
+          console.error("Failed to apply shared canvas change:", error);
+        } finally {
+          applying = false;
+        }
+        order = canvas.getObjects().map(getId).filter(Boolean);
+        canvas.requestRenderAll();
+        syncObjectsFromCanvas();
+      });
+    }
+
+    function loadShared(json: Record<string, unknown>) {
+      applyRemote(async () => {
+        await loadCanvasJson(canvas, tabId, json);
+        remember();
+      });
+    }
+
+    function publishPeers() {
+      setPeers(tabId, [...peers.values()]);
+      canvas.requestRenderAll();
+    }
+
+    function onMessage(message: SyncMessage) {
+      switch (message.type) {
+        case "welcome": {
+          joined = true;
+          for (const peer of message.peers) peers.set(peer.peerId, peer);
+          publishPeers();
+          if (owner) {
+            // The owner's canvas stays as it is; whatever differs from the
+            // shared copy goes out as ops
+            const objects = (message.canvas.objects ?? []) as Record<string, unknown>[];
+            synced.clear();
+            order = [];
+            for (const object of objects) {
+              if (typeof object.id !== "string") continue;
+              synced.set(object.id, JSON.stringify(object));
+              order.push(object.id);
+            }
+            schedulePush();
+          } else {
+            loadShared(message.canvas);
+          }
+          break;
+        }
+        case "ops":
+          applyRemote(() => applyOps(message.ops));
+          break;
+        case "presence": {
+          const { type: _type, ...presence } = message;
+          const isNew = !peers.has(presence.peerId);
+          peers.set(presence.peerId, presence);
+          if (isNew) publishPeers();
+          else canvas.requestRenderAll();
+          break;
+        }
+        case "leave":
+          peers.delete(message.peerId);
+          publishPeers();
+          break;
+        case "snapshot":
+          // The owner picks up file changes through the canvas watcher
+          if (!owner) loadShared(message.canvas);
+          break;
+        case "error":
+          console.warn("Canvas sync:", message.message);
+          break;
+      }
+    }
+
+    // Other people's selections and cursors, in their colours
+    function drawPeers() {
+      if (peers.size === 0) return;
+      const ctx = canvas.getContext();
+      const vpt = canvas.viewportTransform;
+      const toScreen = (x: number, y: number) => ({
+        x: x * vpt[0] + vpt[4],
+        y: y * vpt[3] + vpt[5],
+      });
+      ctx.save();
+      ctx.font = "11px Inter, system-ui, sans-serif";
+      ctx.lineWidth = 1.5;
+      for (const peer of peers.values()) {
+        ctx.strokeStyle = peer.color;
+        for (const id of peer.selection) {
+          const obj = canvas.getObjects().find((o) => getId(o) === id);
+          if (!obj) continue;
+          const box = obj.getBoundingRect();
+          const topLeft = toScreen(box.left, box.top);
+          ctx.strokeRect(topLeft.x, topLeft.y, box.width * vpt[0], box.height * vpt[3]);
+        }
+        if (!peer.cursor) continue;
+        const { x, y } = toScreen(peer.cursor.x, peer.cursor.y);
+        ctx.fillStyle = peer.color;
+        ctx.beginPath();
+        ctx.moveTo(x, y);
+        ctx.lineTo(x, y + 14);
+        ctx.lineTo(x + 4, y + 10);
+        ctx.lineTo(x + 10, y + 10);
+        ctx.closePath();
+        ctx.fill();
+        const labelWidth = ctx.measureText(peer.name).width + 8;
+        ctx.fillRect(x + 10, y + 12, labelWidth, 16);
+        ctx.fillStyle = "#111";
+        ctx.fillText(peer.name, x + 14, y + 24);
+      }
+      ctx.restore();
+    }
+
+    const onMouseMove = (e: TPointerEventInfo) => {
+      const point = canvas.getScenePoint(e.e);
+      cursor = { x: Math.round(point.x), y: Math.round(point.y) };
+      schedulePresence();
+    };
+    const onMouseLeave = () => {
+      cursor = null;
+      schedulePresence();
+    };
+    canvas.on("object:added", schedulePush);
+    canvas.on("object:modified", schedulePush);
+    canvas.on("object:removed", schedulePush);
+    canvas.on("mouse:move", onMouseMove);
+    canvas.on("after:render", drawPeers);
+    canvas.upperCanvasEl.addEventListener("mouseleave", onMouseLeave);
+    const unsubscribe = useStudioStore.subscribe((state, prev) => {
+      if (state.objects !== prev.objects) schedulePush();
+      if (state.selectedObjectIds !== prev.selectedObjectIds) schedulePresence();
+    });
+
+    const color = PEER_COLORS[Math.floor(Math.random() * PEER_COLORS.length)];
+    void getPreference("displayName", "Guest").then((name) => {
+      if (closed) return;
+      connection = syncApi.connectCanvasSync(url, { name, color }, onMessage, () => {
+        // Dropped by the server or the network: end the session
+        if (!closed) leave(tabId);
+      });
+    });
+
+    return () => {
+      closed = true;
+      connection?.close();
+      if (pushTimer) clearTimeout(pushTimer);
+      if (presenceTimer) clearTimeout(presenceTimer);
+      unsubscribe();
+      canvas.off("object:added", schedulePush);
+      canvas.off("object:modified", schedulePush);
+      canvas.off("object:removed", schedulePush);
+      canvas.off("mouse:move", onMouseMove);
+      canvas.off("after:render", drawPeers);
+      canvas.upperCanvasEl.removeEventListener("mouseleave", onMouseLeave);
+      if (getFabricCanvas() === canvas) canvas.requestRenderAll();
+    };
+  }, [tabId, url, owner]);
+}
@@ -1,6 +1,7 @@
 import { useCallback } from "react";
 
 import { getFabricCanvas, useStudioStore } from "../store/studio-store";
+import { CUSTOM_PROPS } from "../utils/canvas-json";
 import { setParentId } from "../utils/frame-helpers";
 
 import type { LayerTreeNode, StudioObject } from "../types";
@@ -110,7 +111,7 @@ export function useLayers() {
       if (!canvas || !obj) return;
 
       void obj
-        .clone(["id", "name", "isFrame", "isComponent", "parentId"])
+        .clone(CUSTOM_PROPS)
         .then((cloned: FabricObjectType) => {
           cloned.set({ left: (cloned.left ?? 0) + 20, top: (cloned.top ?? 0) + 20 });
           const newId = `obj-dup-${String(Date.now())}`;
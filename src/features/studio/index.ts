@@ -2,6 +2,7 @@ export { Studio } from "./components/studio";
 export { StudioCanvas } from "./components/studio-canvas";
 export { StudioDeleteButton } from "./components/studio-delete-button";
 export { StudioShapeMenu } from "./components/studio-shape-menu";
+export { StudioShareMenu } from "./components/studio-share-menu";
 export { StudioToolbar } from "./components/studio-toolbar";
 export { StudioToolbarButton } from "./components/studio-toolbar-button";
 export { StudioTopBar } from "./components/studio-top-bar";
@@ -0,0 +1,63 @@
+import { create } from "zustand";
+
+import { useTabStore } from "./tab-store";
+
+import type { Presence, SharedCanvas } from "@/lib/api/sync";
+
+import * as syncApi from "@/lib/api/sync";
+
+export interface SyncSession {
+  /** Where the tab's canvas is joined. */
+  url: string;
+  /** Set when this instance owns the canvas and serves it. */
+  shared: SharedCanvas | null;
+  /** Everyone else on the canvas. */
+  peers: Presence[];
+}
+
+interface SyncState {
+  /** Keyed by tab id. */
+  sessions: Record<string, SyncSession>;
+  actions: {
+    share: (tabId: string) => Promise<SharedCanvas>;
+    stopSharing: (tabId: string) => Promise<void>;
+    /** Open a new tab joined to the canvas shared at `url`. */
+    join: (url: string) => Promise<void>;
+    leave: (tabId: string) => void;
+    setPeers: (tabId: string, peers: Presence[]) => void;
+  };
+}
+
+export const useSyncStore = create<SyncState>()((set, get) => ({
+  sessions: {},
+  actions: {
+    share: async (tabId: string) => {
+      const shared = await syncApi.shareCanvas(tabId);
+      // The owner joins over loopback like any other peer
+      const url = shared.urls[shared.urls.length - 1];
+      set((s) => ({ sessions: { ...s.sessions, [tabId]: { url, shared, peers: [] } } }));
+      return shared;
+    },
+    stopSharing: async (tabId: string) => {
+      await syncApi.stopSharingCanvas(tabId);
+      get().actions.leave(tabId);
+    },
+    join: async (url: string) => {
+      const tabId = await useTabStore.getState().actions.addTab("Shared canvas");
+      set((s) => ({ sessions: { ...s.sessions, [tabId]: { url, shared: null, peers: [] } } }));
+    },
+    leave: (tabId: string) => {
+      set((s) => {
+        const { [tabId]: _left, ...sessions } = s.sessions;
+        return { sessions };
+      });
+    },
+    setPeers: (tabId: string, peers: Presence[]) => {
+      set((s) => {
+        const session = s.sessions[tabId];
+        if (!session) return s;
+        return { sessions: { ...s.sessions, [tabId]: { ...session, peers } } };
+      });
+    },
+  },
+}));
@@ -29,7 +29,8 @@ interface TabState {
   initialized: boolean;
   actions: {
     initialize: (lastActiveId?: string) => Promise<void>;
-    addTab: () => Promise<void>;
+    /** Create a canvas and open it; resolves to its id. */
+    addTab: (name?: string) => Promise<string>;
     closeTab: (id: string) => Promise<void>;
     duplicateTab: (id: string) => Promise<void>;
     setActiveTab: (id: string) => void;
@@ -71,9 +72,11 @@ export const useTabStore = create<TabState>()((set, get) => ({
       set({ tabs, activeTabId: activeId, initialized: true });
     },
 
-    addTab: async () => {
-      const name = `Untitled ${String(tabNameCounter)}`;
-      tabNameCounter += 1;
+    addTab: async (name?: string) => {
+      if (!name) {
+        name = `Untitled ${String(tabNameCounter)}`;
+        tabNameCounter += 1;
+      }
       const canvas = await canvasApi.createCanvas(name);
       const tab = toTab(canvas);
       set((s) => ({
@@ -81,6 +84,7 @@ export const useTabStore = create<TabState>()((set, get) => ({
         activeTabId: tab.id,
       }));
       void setPreference("lastActiveCanvasId", tab.id);
+      return tab.id;
     },
 
     closeTab: async (id: string) => {
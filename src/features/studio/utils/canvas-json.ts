@@ -0,0 +1,64 @@
+import { convertFileSrc } from "@tauri-apps/api/core";
+
+import type { Canvas, FabricObject } from "fabric";
+
+import { getAssetPath } from "@/lib/api/assets";
+
+/** Custom properties kept whenever objects are serialized or cloned. */
+export const CUSTOM_PROPS = ["id", "name", "isFrame", "isComponent", "parentId", "assetId"];
+
+type JsonObject = Record<string, unknown>;
+
+// The `src` saved in the file for each asset URL handed to the webview
+const storedSrc = new Map<string, string>();
+
+/** Images backed by the canvas's asset store, searching into groups. */
+function assetImages(objects: unknown): JsonObject[] {
+  if (!Array.isArray(objects)) return [];
+  return (objects as JsonObject[]).flatMap((obj) => [
+    ...(typeof obj.assetId === "string" ? [obj] : []),
+    ...assetImages(obj.objects),
+  ]);
+}
+
+/**
+ * A copy of saved canvas (or object) JSON whose asset-backed images point at
+ * URLs the webview can load. The file keeps their paths relative to the canvas.
+ */
+export async function resolveAssets(
+  canvasId: string,
+  json: string | JsonObject,
+): Promise<JsonObject> {
+  const root = (typeof json === "string" ? JSON.parse(json) : structuredClone(json)) as JsonObject;
+  await Promise.all(
+    assetImages([root]).map(async (image) => {
+      try {
+        const url = convertFileSrc(await getAssetPath(canvasId, image.assetId as string));
+        if (typeof image.src === "string") storedSrc.set(url, image.src);
+        image.src = url;
+      } catch (error) {
+        console.warn("Failed to resolve image asset:", error);
+      }
+    }),
+  );
+  return root;
+}
+
+/** Load saved canvas JSON, resolving its images first. */
+export async function loadCanvasJson(
+  canvas: Canvas,
+  canvasId: string,
+  json: string | JsonObject,
+): Promise<Canvas> {
+  return canvas.loadFromJSON(await resolveAssets(canvasId, json));
+}
+
+/** Serialize a canvas or object as it is saved: custom properties kept, image paths restored. */
+export function toSavedObject(target: Canvas | FabricObject): JsonObject {
+  const json = target.toObject(CUSTOM_PROPS) as JsonObject;
+  for (const image of assetImages([json])) {
+    const stored = storedSrc.get(image.src as string);
+    if (stored) image.src = stored;
+  }
+  return json;
+}
@@ -0,0 +1,66 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type WcagLevel = "AA" | "AAA";
+
+/** Field names match the MCP tool's parameters. */
+export interface AuditOptions {
+  frame_ids?: string[];
+  level?: WcagLevel;
+  min_target_size?: number;
+}
+
+export interface ContrastCheck {
+  foreground: string;
+  background: string;
+  ratio: number;
+  largeText: boolean;
+  required: number;
+  achieved: "AAA" | "AA" | "fail";
+  passes: boolean;
+  /** Set when an image or gradient lies beneath the text. */
+  approximate?: boolean;
+}
+
+export interface TargetCheck {
+  width: number;
+  height: number;
+  minimum: number;
+  passes: boolean;
+}
+
+export interface ObjectAudit {
+  frameId: string | null;
+  name: string | null;
+  contrast?: ContrastCheck;
+  touchTarget?: TargetCheck;
+  readingOrder?: { visual: number; layer: number };
+}
+
+export interface FrameAudit {
+  frameId: string | null;
+  name: string | null;
+  mobile: boolean;
+  readingOrder: string[];
+  layerOrderMatches: boolean;
+}
+
+export interface AccessibilityReport {
+  level: WcagLevel;
+  summary: {
+    frames: number;
+    texts: number;
+    contrastFailures: number;
+    touchTargets: number;
+    touchTargetFailures: number;
+    outOfOrder: number;
+  };
+  frames: FrameAudit[];
+  objects: Record<string, ObjectAudit>;
+}
+
+export function auditAccessibility(
+  canvasId: string,
+  options?: AuditOptions,
+): Promise<AccessibilityReport> {
+  return invoke<AccessibilityReport>("audit_accessibility", { canvasId, options });
+}
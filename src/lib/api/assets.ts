@@ -0,0 +1,45 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface Asset {
+  id: string;
+  mimeType: string;
+  size: number;
+  width: number | null;
+  height: number | null;
+}
+
+export interface AddImageOptions {
+  width?: number;
+  height?: number;
+  name?: string;
+}
+
+export function addImage(
+  canvasId: string,
+  imagePath: string,
+  x: number,
+  y: number,
+  options: AddImageOptions = {},
+): Promise<Record<string, unknown>> {
+  return invoke("add_image", { canvasId, imagePath, x, y, ...options });
+}
+
+export function listAssets(canvasId: string): Promise<Asset[]> {
+  return invoke<Asset[]>("list_assets", { canvasId });
+}
+
+export function getAssetPath(canvasId: string, assetId: string): Promise<string> {
+  return invoke<string>("get_asset_path", { canvasId, assetId });
+}
+
+export function gcAssets(canvasId: string): Promise<string[]> {
+  return invoke<string[]>("gc_assets", { canvasId });
+}
+
+export function exportAssets(
+  canvasId: string,
+  destination: string,
+  ids?: string[],
+): Promise<string[]> {
+  return invoke<string[]>("export_assets", { canvasId, destination, ids });
+}
@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 
 export interface CanvasMeta {
   id: string;
@@ -14,6 +15,17 @@ export interface CanvasState {
   zoom: number;
   viewportTransform: string;
   updatedAt: string;
+  /** Save counter; pass it back to `saveCanvasState` to detect conflicting writes. */
+  revision: number;
+  /** CRDT state of the saved canvas; pass it back to have saves merged instead. */
+  heads: string[];
+}
+
+export interface SavedCanvas {
+  revision: number;
+  heads: string[];
+  /** The canvas after merging with concurrent edits, when that changed it. */
+  canvasJson: string | null;
 }
 
 export function listCanvases(): Promise<CanvasMeta[]> {
@@ -36,20 +48,108 @@ export function getCanvasState(canvasId: string): Promise<CanvasState | null> {
   return invoke<CanvasState | null>("get_canvas_state", { canvasId });
 }
 
+export interface CanvasChangedEvent {
+  canvasId: string;
+  /** The file was deleted rather than written. */
+  removed: boolean;
+}
+
+/**
+ * Subscribe to canvas files changing outside the app, e.g. an agent editing
+ * through easel-mcp or an edit to the file a canvas was imported from.
+ */
+export function onCanvasChanged(
+  handler: (event: CanvasChangedEvent) => void,
+): Promise<UnlistenFn> {
+  return listen<CanvasChangedEvent>("canvas-changed", (event) => {
+    handler(event.payload);
+  });
+}
+
 export function importEaselFile(filePath: string): Promise<CanvasMeta> {
   return invoke<CanvasMeta>("import_easel_file", { filePath });
 }
 
+export type WhiteboardFormat = "excalidraw" | "tldraw";
+
+/** Create a canvas from an Excalidraw or tldraw file; the format is detected when omitted. */
+export function importWhiteboardFile(
+  filePath: string,
+  format?: WhiteboardFormat,
+): Promise<CanvasMeta> {
+  return invoke<CanvasMeta>("import_whiteboard_file", { filePath, format });
+}
+
+/** Save a canvas as a self-contained bundle; `thumbnail` is PNG bytes. */
+export function exportCanvasBundle(
+  canvasId: string,
+  destination: string,
+  thumbnail?: Uint8Array,
+): Promise<void> {
+  return invoke("export_canvas_bundle", {
+    canvasId,
+    destination,
+    thumbnail: thumbnail ? Array.from(thumbnail) : undefined,
+  });
+}
+
+export interface SvgImport {
+  ids: string[];
+  /** Elements that were skipped, such as `<use>` of a missing id. */
+  warnings: string[];
+}
+
+export function importSvgFile(
+  canvasId: string,
+  filePath: string,
+  x?: number,
+  y?: number,
+): Promise<SvgImport> {
+  return invoke<SvgImport>("import_svg_file", { canvasId, filePath, x, y });
+}
+
+export type BooleanOperation = "union" | "subtract" | "intersect" | "exclude";
+
+/**
+ * Combine top-level shapes into a single Path object. Subtract removes the
+ * others from the bottom-most shape. The operands are removed unless
+ * `keepOriginals` is set.
+ */
+export function booleanObjects(
+  canvasId: string,
+  ids: string[],
+  operation: BooleanOperation,
+  name?: string,
+  keepOriginals?: boolean,
+): Promise<Record<string, unknown>> {
+  return invoke("boolean_objects", { canvasId, ids, operation, name, keepOriginals });
+}
+
+/**
+ * Save the canvas. With `heads` the CRDT log records, the edit is merged with
+ * whatever was saved since that state, and the merged canvas comes back if it
+ * differs. Otherwise, with `revision` set, the save is rejected (see
+ * `isConflict`) if the file was written since then.
+ */
 export function saveCanvasState(
   canvasId: string,
   canvasJson: string,
   zoom: number,
   viewportTransform: string,
-): Promise<void> {
-  return invoke("save_canvas_state", {
+  revision?: number,
+  heads?: string[],
+): Promise<SavedCanvas> {
+  return invoke<SavedCanvas>("save_canvas_state", {
     canvasId,
     canvasJson,
     zoom,
     viewportTransform,
+    revision,
+    heads,
   });
 }
+
+/** Whether a rejected save lost a race with another writer, e.g. an agent. */
+export function isConflict(error: unknown): boolean {
+  return typeof error === "string" && error.startsWith("Conflict");
+}
@@ -0,0 +1,29 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type CodeTarget = "html" | "react";
+export type LayoutMode = "absolute" | "flex";
+
+export interface GeneratedFile {
+  fileName: string;
+  contents: string;
+}
+
+export interface GeneratedCode {
+  frameId: string;
+  files: GeneratedFile[];
+}
+
+export interface GenerateCodeOptions {
+  target?: CodeTarget;
+  layout?: LayoutMode;
+  tailwind?: boolean;
+  componentName?: string;
+}
+
+export function generateCode(
+  canvasId: string,
+  frameId: string,
+  options: GenerateCodeOptions = {},
+): Promise<GeneratedCode> {
+  return invoke<GeneratedCode>("generate_code", { canvasId, frameId, ...options });
+}
@@ -0,0 +1,57 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface Change<T> {
+  from: T;
+  to: T;
+}
+
+export interface PropertyChange {
+  property: string;
+  /** Absent when the property was added. */
+  from?: unknown;
+  /** Absent when the property was removed. */
+  to?: unknown;
+}
+
+export interface ObjectRef {
+  id: string;
+  type: string;
+  name?: string;
+  /** Frame or group the object belongs to. */
+  parentId?: string;
+}
+
+export interface ObjectMove extends ObjectRef {
+  from: [number, number];
+  to: [number, number];
+  /** Set when the object changed frame or group. */
+  parent?: Change<string | null>;
+}
+
+export interface ObjectChange extends ObjectRef {
+  properties: PropertyChange[];
+}
+
+export interface Reordering extends ObjectRef {
+  /** Stacking index within its parent list (0 = bottom). */
+  from: number;
+  to: number;
+}
+
+export interface CanvasDiff {
+  name?: Change<string>;
+  viewport?: Change<{ zoom: number; transform: number[] }>;
+  canvas: PropertyChange[];
+  added: ObjectRef[];
+  removed: ObjectRef[];
+  moved: ObjectMove[];
+  changed: ObjectChange[];
+  reordered: Reordering[];
+  /** The same diff as a human-readable report. */
+  report: string;
+}
+
+/** Compare two versions of a canvas; each side is a canvas id or a `.easel` file path. */
+export function diffCanvases(before: string, after: string): Promise<CanvasDiff> {
+  return invoke<CanvasDiff>("diff_canvases", { before, after });
+}
@@ -0,0 +1,99 @@
+import { invoke } from "@tauri-apps/api/core";
+
+import type { CanvasState } from "./canvas";
+
+export interface Actor {
+  kind: "user" | "agent";
+  label: string;
+  /** Process the edit came from, telling apart agents with the same label. */
+  session: string;
+}
+
+export interface Op {
+  kind: "create" | "update" | "delete" | "reorder";
+  /** Absent for updates of the canvas's own properties, and for reorders. */
+  objectId?: string;
+  /** Group the object was created in or deleted from, or whose children were reordered. */
+  parent?: string;
+  index?: number;
+  /** Properties whose value changed, for updates. */
+  properties?: string[];
+}
+
+/** One save, from the canvas's operation log. */
+export interface Batch {
+  id: number;
+  at: string;
+  /** File revision the save produced. */
+  revision: number;
+  /** Absent for unattributed saves and the baseline. */
+  actor?: Actor;
+  /** Set when the save undid or redid the batch with this id. */
+  step?: { undo: number } | { redo: number };
+  ops: Op[];
+}
+
+export interface ActorSummary extends Actor {
+  /** `kind:label@session`, as stamped on objects in `editedBy`. */
+  id: string;
+  edits: number;
+  objects: number;
+  lastAt: string;
+}
+
+export interface CanvasEdits {
+  edits: Batch[];
+  actors: ActorSummary[];
+}
+
+export interface RevertReport {
+  reverted: string[];
+  /** Objects someone else changed since; left as they are. */
+  skipped: string[];
+}
+
+/**
+ * Who changed what in a canvas, from its operation log. `actor` is a label,
+ * session id or actor id.
+ */
+export function listCanvasEdits(
+  canvasId: string,
+  actor?: string,
+  objectId?: string,
+): Promise<CanvasEdits> {
+  return invoke<CanvasEdits>("list_canvas_edits", { canvasId, actor, objectId });
+}
+
+/** Revert one actor's edits. Reload the canvas (`getCanvasState`) to show the result. */
+export function revertCanvasEdits(canvasId: string, actor: string): Promise<RevertReport> {
+  return invoke<RevertReport>("revert_canvas_edits", { canvasId, actor });
+}
+
+/** The canvas as of `revision` (the latest save without one), replayed from its operation log. */
+export function replayCanvas(
+  canvasId: string,
+  revision?: number,
+): Promise<Record<string, unknown>> {
+  return invoke<Record<string, unknown>>("replay_canvas", { canvasId, revision });
+}
+
+/**
+ * Undo the user's latest saved edit, for when the canvas's in-memory history
+ * runs out. Saves after `revision`, which that history already covered, are
+ * skipped. Resolves to the canvas to show, or null when there is nothing to
+ * undo; rejects if the edit's objects changed since.
+ */
+export function undoCanvasEdit(
+  canvasId: string,
+  revision?: number,
+): Promise<CanvasState | null> {
+  return invoke<CanvasState | null>("undo_canvas_edit", { canvasId, revision });
+}
+
+/** Redo the edit most recently undone by `undoCanvasEdit`. */
+export function redoCanvasEdit(
+  canvasId: string,
+  revision?: number,
+): Promise<CanvasState | null> {
+  return invoke<CanvasState | null>("redo_canvas_edit", { canvasId, revision });
+}
@@ -0,0 +1,22 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type ExportScope = "canvas" | "frames" | "selection";
+
+export interface PdfSummary {
+  pages: number;
+  bytes: number;
+  warnings: string[];
+}
+
+/**
+ * Export a canvas to PDF. `ids` are frame IDs for the "frames" scope (all
+ * top-level frames when omitted) or object IDs for "selection".
+ */
+export function exportPdf(
+  canvasId: string,
+  outputPath: string,
+  scope: ExportScope = "canvas",
+  ids?: string[],
+): Promise<PdfSummary> {
+  return invoke<PdfSummary>("export_pdf", { canvasId, outputPath, scope, ids });
+}
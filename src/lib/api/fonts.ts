@@ -0,0 +1,23 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface FontFace {
+  family: string;
+  postScriptName: string;
+  weight: number;
+  italic: boolean;
+  monospaced: boolean;
+  path: string | null;
+}
+
+export interface FontFamily {
+  family: string;
+  faces: FontFace[];
+}
+
+/**
+ * Font families the backend can measure and export with. Passing a canvas
+ * includes the fonts carried by its bundle.
+ */
+export function listFonts(canvasId?: string): Promise<FontFamily[]> {
+  return invoke<FontFamily[]>("list_fonts", { canvasId });
+}
@@ -0,0 +1,40 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type LintRule =
+  | "min-text-size"
+  | "text-contrast"
+  | "outside-frame"
+  | "overlapping-siblings"
+  | "off-grid"
+  | "unnamed-layer"
+  | "off-palette";
+
+export type LintSeverity = "error" | "warning" | "info";
+
+/** Field names match the MCP tool and the CLI `--config` file. */
+export interface LintConfig {
+  rules?: LintRule[];
+  disable?: LintRule[];
+  min_font_size?: number;
+  min_contrast?: number;
+  grid?: number;
+  palette?: string[];
+}
+
+export interface LintIssue {
+  rule: LintRule;
+  severity: LintSeverity;
+  objectId: string;
+  relatedId?: string;
+  message: string;
+}
+
+export interface LintReport {
+  checked: number;
+  counts: Partial<Record<LintRule, number>>;
+  issues: LintIssue[];
+}
+
+export function lintCanvas(canvasId: string, config?: LintConfig): Promise<LintReport> {
+  return invoke<LintReport>("lint_canvas", { canvasId, config });
+}
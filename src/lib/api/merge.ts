@@ -0,0 +1,35 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type MergeSide = "ours" | "theirs";
+
+export interface MergeConflict {
+  kind: "property" | "deleted" | "order";
+  /** Absent for canvas-level properties. */
+  objectId?: string;
+  property?: string;
+  base: unknown;
+  ours: unknown;
+  theirs: unknown;
+  resolved: MergeSide;
+}
+
+export interface CanvasMerge {
+  canvasJson: string;
+  revision: number;
+  heads: string[];
+  conflicts: MergeConflict[];
+}
+
+/**
+ * Merge unsaved edits (`canvasJson`, made on top of `baseJson`) with the
+ * canvas file as it is now, save the result and return it. Clashing edits
+ * keep the `prefer` side, ours by default, and are listed in `conflicts`.
+ */
+export function mergeCanvasState(
+  canvasId: string,
+  baseJson: string,
+  canvasJson: string,
+  prefer?: MergeSide,
+): Promise<CanvasMerge> {
+  return invoke<CanvasMerge>("merge_canvas_state", { canvasId, baseJson, canvasJson, prefer });
+}
@@ -0,0 +1,100 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface SharedCanvas {
+  canvasId: string;
+  port: number;
+  /** Addresses other instances can join at, the LAN one first when there is one. */
+  urls: string[];
+  peers: number;
+}
+
+export type ObjectOp =
+  | { op: "upsert"; object: Record<string, unknown>; index?: number }
+  | { op: "remove"; id: string }
+  | { op: "reorder"; ids: string[] };
+
+export interface Point {
+  x: number;
+  y: number;
+}
+
+export interface Presence {
+  peerId: string;
+  name: string;
+  color: string;
+  /** Scene coordinates. */
+  cursor: Point | null;
+  selection: string[];
+}
+
+export type SyncMessage =
+  | {
+      type: "welcome";
+      peerId: string;
+      name: string;
+      canvas: Record<string, unknown>;
+      peers: Presence[];
+    }
+  | { type: "ops"; peerId: string; ops: ObjectOp[] }
+  | ({ type: "presence" } & Presence)
+  | { type: "leave"; peerId: string }
+  | { type: "snapshot"; canvas: Record<string, unknown> }
+  | { type: "error"; message: string };
+
+/**
+ * Share a canvas with other Easel instances on the local network. The sync
+ * server starts on `port` (any free port by default) if it isn't running.
+ */
+export function shareCanvas(canvasId: string, port?: number): Promise<SharedCanvas> {
+  return invoke<SharedCanvas>("share_canvas", { canvasId, port });
+}
+
+export function stopSharingCanvas(canvasId: string): Promise<void> {
+  return invoke("stop_sharing_canvas", { canvasId });
+}
+
+export function getSharedCanvas(canvasId: string): Promise<SharedCanvas | null> {
+  return invoke<SharedCanvas | null>("get_shared_canvas", { canvasId });
+}
+
+export interface SyncConnection {
+  sendOps: (ops: ObjectOp[]) => void;
+  sendPresence: (cursor: Point | null, selection: string[]) => void;
+  close: () => void;
+}
+
+/**
+ * Join the shared canvas at `url` (one of `SharedCanvas.urls`). Messages
+ * from the server, starting with `welcome`, go to `onMessage`; `onClose`
+ * runs once the connection ends, from either side.
+ */
+export function connectCanvasSync(
+  url: string,
+  peer: { name: string; color: string },
+  onMessage: (message: SyncMessage) => void,
+  onClose?: () => void,
+): SyncConnection {
+  const socket = new WebSocket(url);
+  socket.addEventListener("open", () => {
+    socket.send(JSON.stringify({ type: "hello", ...peer }));
+  });
+  socket.addEventListener("message", (event: MessageEvent) => {
+    if (typeof event.data === "string") onMessage(JSON.parse(event.data) as SyncMessage);
+  });
+  socket.addEventListener("close", () => onClose?.());
+
+  function send(message: Record<string, unknown>) {
+    if (socket.readyState === WebSocket.OPEN) socket.send(JSON.stringify(message));
+  }
+  return {
+    sendOps: (ops) => {
+      if (ops.length > 0) send({ type: "ops", ops });
+    },
+    sendPresence: (cursor, selection) => {
+      send({ type: "presence", cursor, selection });
+    },
+    close: () => {
+      socket.close();
+    },
+  };
+}